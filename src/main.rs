@@ -4,55 +4,337 @@ use dotenv::dotenv;
 use serenity::{
     prelude::*,
     async_trait,
+    http::Http,
+    builder::CreateEmbed,
+    client::bridge::gateway::{ShardId, ShardManager},
     model::{
-        prelude::UserId,
+        prelude::{ChannelId, GuildId, UserId, AttachmentType, Permissions},
+        application::interaction::{
+            Interaction,
+            InteractionResponseType,
+            application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+        },
+        oauth2::OAuth2Scope,
         gateway::Ready,
-        channel::Message,
+        channel::{GuildChannel, Message},
+        user::User,
+        guild::{Guild, Member, Role, UnavailableGuild},
     },
+    utils::{ArgumentConvert, Colour},
     framework::standard::{
         HelpOptions,
         help_commands,
-        macros::{hook, help, command, group},
-        buckets::LimitedFor,
+        macros::{hook, help, command, group, check},
         StandardFramework,
         CommandGroup,
         CommandResult,
+        CommandOptions,
+        DispatchError,
+        Reason,
         Args,
     },
 };
 
-use std::collections::HashSet;
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+    thread,
+};
 
 #[allow(clippy::wildcard_imports)]
 use crate::utils::{
     functions::*,
-    imaging::ImageExecutor,
-    helpers::{resolve_extra_arg, resolve_arg},
-    resolver::ImageResolver,
+    imaging::{ImageArguments, ImageExecutor, Output, Rng, inspect, inspect_first, send_output, decode_frames, compress_to_fit, encode_sticker, encode_ico, DEFAULT_MAX_FRAMES, ICO_SIZES, MAX_ARCHIVE_SIZE, MAX_ATTACHMENTS},
+    helpers::{resolve_extra_arg, resolve_extra_arg_or_reply, resolve_arg, validate_name, to_data_uri, avatar_url, humanize_bytes, humanize_duration, process_memory},
+    resolver::{AttachmentRef, ImageResolver, DEFAULT_MAX_SIZE},
+    flags::Flags,
+    config::{Config, CONFIG_PATH},
+    registry,
+    hashing::{self, REPOST_THRESHOLD},
+    codes::{render_qr, scan_codes},
+    templates::Template,
+    ocr::{self, DEFAULT_LANGUAGE},
+    database::Database,
+    entitlements::{GuildLimits, Tier, cooldown_factor},
+    cooldowns::{self, Cooldown, CooldownManager},
+    abuse::{self, AbuseMonitor},
+    pool::{self, WorkerPool, run_job, estimate_cost},
+    memory,
+    jobs::JobTracker,
+    metrics::METRICS,
+    audit,
+    presence,
+    welcome,
+    daily,
+    avatars::{self, AvatarEntry, AVATAR_HISTORY},
+    pipeline::{Pipeline, Stage},
+    script::{self, Script, MAX_SAVED, MAX_WEIGHT},
+    bench,
+    assets::ASSETS,
+    slash,
+    vision,
+    policy::{self, FetchPolicy},
+    options::{
+        Align,
+        Anchor,
+        CaptionOptions,
+        CaptionPosition,
+        Color,
+        Accessory,
+        ChannelMask,
+        CropOptions,
+        OverlayOptions,
+        MosaicOptions,
+        StackOptions,
+        CompareOptions,
+        IntroOptions,
+        TiltOptions,
+        AnaglyphOptions,
+        BannerPreset,
+        Cut,
+        DelayPolicy,
+        FrameRange,
+        Timestamp,
+        FlagStyle,
+        FlagifyOptions,
+        UpscaleFilter,
+        CensorOptions,
+        CensorStyle,
+        Region,
+        GradientKind,
+        GradientOptions,
+        Size,
+        TextOptions,
+        PosterOptions,
+        QuoteOptions,
+        RankOptions,
+        GridOptions,
+        ChartKind,
+        ChartData,
+        ChartOptions,
+        OutputFormat,
+    },
 };
+use ril::Rgba;
+use rand::SeedableRng;
+
+use utils::{Error, error::ErrorCategory};
 
 mod utils;
 
+/// counts the memory allocated by each thread, so that jobs and benchmarks can measure their own
+#[global_allocator]
+static ALLOCATOR: memory::CountingAllocator = memory::CountingAllocator;
 
 #[group]
+#[checks(Nsfw, GuildSettings)]
 #[commands(
     invert,
+    invertalpha,
     huerotate,
     caption,
+    overlay,
+    mosaic,
+    flagify,
+    noise,
+    random,
+    pipe,
+    custom,
+    enlarge,
+    frames,
+    split,
+    emojipack,
+    trimgif,
+    stackgif,
+    compare,
+    trim,
+    eyes,
+    glasses,
+    clown,
+    facecrop,
+    bannerize,
+    censor,
+    scan,
+    tilt,
+    anaglyph,
+    wiggle,
+    upscale,
+    neon,
+    diff,
+    phash,
+    blurhash,
+    qr,
+    qrscan,
+    readtext,
+    color,
+    gradient,
+    text,
+    posterfy,
+    dance,
+    party,
+    vibe,
+    intro,
+    quote,
+    rankcard,
+    chart,
+    steal,
+    addsticker,
+    avatar,
+    avatarhistory,
+    favicon,
 )]
 struct Imaging;
 
+#[group]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+#[commands(
+    toggle,
+    restrict,
+    cooldown,
+    cooldownrole,
+    limits,
+    reposts,
+    welcome,
+    daily,
+)]
+struct Settings;
+
+#[group]
+#[commands(
+    cancel,
+    queue,
+    mylimits,
+)]
+struct General;
+
+#[group]
+#[commands(
+    ping,
+    uptime,
+    about,
+    invite,
+)]
+struct Meta;
+
+#[group]
+#[owners_only]
+#[commands(
+    premium,
+    bench,
+    reload,
+)]
+struct Owner;
+
 struct Handler;
 
 struct ClientData;
 
+struct ConfigData;
+
+struct DatabaseData;
+
+struct CooldownData;
+
+struct AbuseData;
+
+struct PoolData;
+
+struct JobData;
+
+struct ShardManagerData;
+
+struct OwnersData;
+
+struct StartData;
+
 #[async_trait]
 impl EventHandler for Handler {
-    async fn ready(&self, _ctx: Context, data: Ready) {
+    async fn ready(&self, ctx: Context, data: Ready) {
         println!("Bot is ready!\nLogged in as {} ({})",
             data.user.tag(),
             data.user.id,
         );
+
+        let slash_only = ctx.data.read()
+            .await
+            .get::<ConfigData>()
+            .map_or(false, |config| config.slash_only);
+
+        if slash_only {
+            if let Err(err) = slash::register(&ctx).await {
+                tracing::error!("failed to register the slash commands: {err}");
+            }
+        }
+    }
+
+    async fn cache_ready(&self, ctx: Context, _guilds: Vec<GuildId>) {
+        // the guild count is only known once every guild has been received
+        presence::update(&ctx)
+            .await;
+        presence::start_poster(&ctx)
+            .await;
+        daily::start_scheduler(&ctx);
+    }
+
+    async fn guild_create(&self, ctx: Context, _guild: Guild, is_new: bool) {
+        // guilds are also created for each guild the bot is already in as the shards connect
+        if is_new {
+            presence::update(&ctx)
+                .await;
+        }
+    }
+
+    async fn guild_delete(&self, ctx: Context, incomplete: UnavailableGuild, _full: Option<Guild>) {
+        // unavailable guilds are merely going through an outage, rather than having removed the bot
+        if !incomplete.unavailable {
+            presence::update(&ctx)
+                .await;
+        }
+    }
+
+    async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        if let Err(err) = welcome::greet(&ctx, &new_member).await {
+            tracing::warn!("failed to welcome member {} of guild {}: {}", new_member.user.id, new_member.guild_id, err.report());
+        }
+    }
+
+    async fn guild_member_update(&self, ctx: Context, _old_if_available: Option<Member>, new: Member) {
+        if let Err(err) = avatars::track(&ctx, &new).await {
+            tracing::warn!("failed to track the avatar of user {}: {}", new.user.id, err.report());
+        }
+    }
+
+    async fn message(&self, ctx: Context, message: Message) {
+        if let Err(err) = detect_repost(&ctx, &message).await {
+            tracing::warn!("failed to check message {} for reposts: {err}", message.id);
+        }
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let interaction = match interaction {
+            Interaction::ApplicationCommand(interaction) => interaction,
+            Interaction::Autocomplete(interaction) => {
+                if let Err(err) = slash::autocomplete(&ctx, &interaction).await {
+                    tracing::warn!("failed to respond to autocomplete for `{}`: {err}", interaction.data.name);
+                }
+                return;
+            },
+            _ => return,
+        };
+
+        if let Err(err) = run_slash_command(&ctx, &interaction).await {
+            let embed = error_embed(&ctx, interaction.user.id, interaction.data.name.as_str(), err.as_ref())
+                .await;
+
+            interaction.edit_original_interaction_response(&ctx, |response|
+                response.content("")
+                    .set_embed(embed)
+            )
+                .await
+                .ok();
+        }
     }
 }
 
@@ -60,60 +342,803 @@ impl TypeMapKey for ClientData {
     type Value = reqwest::Client;
 }
 
-/// an "after" callback hook on commands to handle `Err` CommandResults and send the error message
+impl TypeMapKey for ConfigData {
+    type Value = Arc<Config>;
+}
+
+impl TypeMapKey for DatabaseData {
+    type Value = Database;
+}
+
+impl TypeMapKey for CooldownData {
+    type Value = Arc<CooldownManager>;
+}
+
+impl TypeMapKey for AbuseData {
+    type Value = Arc<AbuseMonitor>;
+}
+
+impl TypeMapKey for PoolData {
+    type Value = Arc<WorkerPool>;
+}
+
+impl TypeMapKey for JobData {
+    type Value = Arc<JobTracker>;
+}
+
+impl TypeMapKey for ShardManagerData {
+    type Value = Arc<Mutex<ShardManager>>;
+}
+
+impl TypeMapKey for OwnersData {
+    type Value = Arc<HashSet<UserId>>;
+}
+
+impl TypeMapKey for StartData {
+    type Value = Instant;
+}
+
+/// a "before" callback hook on commands that refuses commands used in direct messages if they are disabled
+/// and applies the cooldown of imaging commands, recording when they were invoked
 #[hook]
-async fn error_handler(ctx: &Context, message: &Message, _cmd_name: &str, result: CommandResult) {
-    if let Err(err) = result {
-        message.reply(ctx, err.to_string())
+async fn before_hook(ctx: &Context, message: &Message, cmd_name: &str) -> bool {
+    if message.guild_id.is_none() {
+        let allow_dms = ctx.data.read()
             .await
-            .ok();
+            .get::<ConfigData>()
+            .map_or(true, |config| config.allow_dms);
+
+        if !allow_dms {
+            message.reply(ctx, "Commands cannot be used in direct messages")
+                .await
+                .ok();
+
+            return false;
+        }
+    }
+
+    if find_command(cmd_name).is_some() {
+        if let Err(remaining) = apply_cooldown(ctx, message).await {
+            message.reply(ctx,
+                format!("⏲️ You are still on cooldown! Try again in `{:.1}s`", remaining.as_secs_f64())
+            )
+                .await
+                .ok();
+
+            return false;
+        }
+
+        if let Some(tracker) = JobTracker::get(ctx).await {
+            tracker.invoked(message.id);
+        }
+    }
+
+    true
+}
+
+/// returns the cooldown of imaging commands the provided user is subject to within the provided guild,
+/// which is that configured by the guild or otherwise that of their tier, scaled by their [`cooldown_factor`],
+/// or `None` if they are exempt from it
+async fn effective_cooldown(ctx: &Context, user: &User, tier: Tier, guild_id: Option<GuildId>) -> Option<Cooldown> {
+    let factor = cooldown_factor(ctx, user, tier, guild_id)
+        .await;
+
+    if factor <= 0.0 {
+        return None;
+    }
+
+    let mut cooldown = Cooldown {
+        delay: tier.limits()
+            .cooldown,
+        ..Cooldown::default()
+    };
+
+    if let (Some(guild_id), Ok(database)) = (guild_id, Database::get(ctx).await) {
+        if let Ok(Some(configured)) = database.cooldown(guild_id).await {
+            cooldown = configured;
+        }
+    }
+    cooldown.delay = cooldown.delay.mul_f64(factor.min(1.0));
+
+    Some(cooldown)
+}
+
+/// applies the cooldown of imaging commands to the author of the message,
+/// using the cooldown configured for the guild if any, or the default of their tier otherwise,
+/// scaled by the author's [`cooldown_factor`] so that owners, premium users and trusted roles may skip or shorten it,
+/// followed by the penalty of the [`AbuseMonitor`] if they have been escalated, which no one is exempt from
+async fn apply_cooldown(ctx: &Context, message: &Message) -> Result<(), Duration> {
+    let tier = Tier::lookup(ctx, &message.author)
+        .await;
+    let cooldown = effective_cooldown(ctx, &message.author, tier, message.guild_id)
+        .await;
+
+    let data = ctx.data.read()
+        .await;
+
+    if let (Some(cooldown), Some(manager)) = (cooldown, data.get::<CooldownData>()) {
+        manager.hit(message.author.id, cooldown)?;
+    }
+
+    // on top of the static cooldown, users detected abusing the bot serve an escalating penalty,
+    // which applies even to those exempt from the static cooldown
+    data.get::<AbuseData>()
+        .map_or(Ok(()), |monitor| monitor.hit(message.author.id, message.channel_id))
+}
+
+/// summarizes the job of the command invoked by the provided message in the audit log,
+/// if its invocation was recorded, which is only the case for imaging commands
+async fn audit_invocation(ctx: &Context, message: &Message, cmd_name: &str, slash: bool, result: &CommandResult) {
+    let Some(tracker) = JobTracker::get(ctx).await else {
+        return;
+    };
+    let Some(invoked) = tracker.take_invocation(message.id) else {
+        return;
+    };
+
+    let report = tracker.take_report(message.id);
+    let summary = audit::JobSummary {
+        user_id: message.author.id,
+        user_tag: message.author.tag(),
+        guild_id: message.guild_id,
+        command: cmd_name.to_string(),
+        slash,
+        source: report.and_then(|report| report.source),
+        duration: invoked.elapsed(),
+        output_size: report.map(|report| report.output_size),
+        // the audit log is read by the bot's owner, so errors are written with their causes
+        error: result.as_ref()
+            .err()
+            .map(|err| err.downcast_ref::<Error>()
+                .map_or_else(|| err.to_string(), |err| err.report().to_string())
+            ),
+    };
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        if let Err(err) = audit::log(&ctx, summary).await {
+            tracing::warn!("failed to post to the audit log: {}", err.report());
+        }
+    });
+}
+
+/// the most characters of the cause of an error shown in the error embed
+const EMBED_CAUSE_LENGTH: usize = 256;
+
+/// an "after" callback hook on commands to handle `Err` CommandResults and send the error message
+/// in the [`error_embed`], and to clean up the invocation recorded by [`before_hook`]
+#[hook]
+async fn error_handler(ctx: &Context, message: &Message, cmd_name: &str, result: CommandResult) {
+    audit_invocation(ctx, message, cmd_name, false, &result)
+        .await;
+
+    let Err(err) = result else {
+        return;
+    };
+
+    let embed = error_embed(ctx, message.author.id, cmd_name, err.as_ref())
+        .await;
+
+    message.channel_id.send_message(ctx, |msg|
+        msg.reference_message(message)
+            .allowed_mentions(|am| am.empty_parse())
+            .set_embed(embed)
+    )
+        .await
+        .ok();
+}
+
+/// builds the embed describing an error a command failed with, along with the error's code and a hint for errors raised by the bot itself,
+/// noting oversized inputs with the abuse monitor and logging network and internal errors on the way,
+/// shared by the [`error_handler`] and slash commands
+async fn error_embed(ctx: &Context, user_id: UserId, cmd_name: &str, err: &(dyn std::error::Error + Send + Sync)) -> CreateEmbed {
+    if let Some(Error::ImageTooLarge(..) | Error::TooManyFrames(..) | Error::MemoryLimit(..)) = err.downcast_ref::<Error>().map(Error::root) {
+        if let Some(monitor) = ctx.data.read().await.get::<AbuseData>() {
+            monitor.oversized(user_id);
+        }
+    }
+
+    if let Some(err) = err.downcast_ref::<Error>()
+        .filter(|err| matches!(err.category(), ErrorCategory::Network | ErrorCategory::Internal))
+    {
+        tracing::warn!(command = cmd_name, code = err.code(), "command failed: {}", err.report());
+    }
+
+    let support_url = ctx.data.read()
+        .await
+        .get::<ConfigData>()
+        .and_then(|config| config.support_url.clone());
+
+    let mut embed = CreateEmbed::default();
+    embed.description(err.to_string());
+
+    if let Some(err) = err.downcast_ref::<Error>() {
+        let category = err.category();
+
+        embed.title(category.title())
+            .colour(category.colour());
+
+        // the causes of internal errors are only logged, as they may reveal the bot's internals
+        if let Some(cause) = err.cause().filter(|_| category != ErrorCategory::Internal) {
+            embed.field("Cause", format!("`{}`", cause.chars().take(EMBED_CAUSE_LENGTH).collect::<String>()), false);
+        }
+        if let Some(hint) = err.hint() {
+            embed.field("Hint", hint, false);
+        }
+        embed.footer(|footer| footer.text(
+            support_url.as_ref()
+                .map_or_else(
+                    || format!("Error {}", err.code()),
+                    |url| format!("Error {} • Need help? {url}", err.code()),
+                )
+        ));
+    } else {
+        embed.title("Command Error")
+            .colour(Colour::RED);
+
+        if let Some(url) = &support_url {
+            embed.footer(|footer| footer.text(format!("Need help? {url}")));
+        }
     }
+
+    embed
 }
 
-/// a callback for when the user is still on cooldown when invoking a command
+/// a callback for when a command fails to dispatch, such as from a failed check or missing permissions
 #[hook]
-async fn delay_action(ctx: &Context, message: &Message) {
-    message.reply(ctx, "⏲️ You are still on cooldown!")
+async fn dispatch_error(ctx: &Context, message: &Message, error: DispatchError, _cmd_name: &str) {
+    let content = match error {
+        DispatchError::CheckFailed(_, Reason::User(reason) | Reason::UserAndLog { user: reason, .. }) => reason,
+        DispatchError::LackingPermissions(permissions) =>
+            format!("You are lacking the required permissions to use this command: `{}`",
+                permissions.get_permission_names().join(", "),
+            ),
+        DispatchError::OnlyForGuilds =>
+            String::from("This command can only be used in a server"),
+        _ => return,
+    };
+
+    message.reply(ctx, content)
         .await
         .ok();
 }
 
+/// a check that refuses effects marked as NSFW in the registry outside of age-restricted channels
+#[check]
+#[name = "Nsfw"]
+async fn nsfw_check(
+    ctx: &Context,
+    message: &Message,
+    _args: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    enforce_nsfw(ctx, message.guild_id, message.channel_id, options.names)
+        .await
+}
+
+/// a check that enforces the per-guild settings configured with `toggle` and `restrict`
+#[check]
+#[name = "GuildSettings"]
+async fn guild_settings_check(
+    ctx: &Context,
+    message: &Message,
+    _args: &mut Args,
+    options: &CommandOptions,
+) -> Result<(), Reason> {
+    let Some(command) = options.names.first() else {
+        return Ok(());
+    };
+
+    enforce_guild_settings(ctx, message.guild_id, message.author.id, command)
+        .await
+}
+
+/// refuses any of the commands named `names` that apply an effect marked as NSFW in the registry
+/// outside of age-restricted channels, shared by the [`nsfw_check`] and slash commands
+async fn enforce_nsfw(ctx: &Context, guild_id: Option<GuildId>, channel_id: ChannelId, names: &[&str]) -> Result<(), Reason> {
+    let is_nsfw = names
+        .iter()
+        .any(|name| registry::find(name).map_or(false, |effect| effect.nsfw));
+
+    if !is_nsfw || guild_id.is_none() {
+        return Ok(());
+    }
+
+    let age_restricted = channel_id.to_channel(ctx)
+        .await
+        .ok()
+        .and_then(|channel| channel.guild())
+        .map_or(false, |channel| channel.is_nsfw());
+
+    if age_restricted {
+        Ok(())
+    } else {
+        Err(Reason::User(String::from("🔞 This effect can only be used in age-restricted channels")))
+    }
+}
+
+/// refuses the command named `command` if it has been disabled in the guild, or is restricted to a role the user lacks,
+/// shared by the [`guild_settings_check`] and slash commands
+async fn enforce_guild_settings(ctx: &Context, guild_id: Option<GuildId>, user_id: UserId, command: &str) -> Result<(), Reason> {
+    let Some(guild_id) = guild_id else {
+        return Ok(());
+    };
+    let Ok(database) = Database::get(ctx).await else {
+        return Ok(());
+    };
+
+    if database.is_command_disabled(guild_id, command)
+        .await
+        .unwrap_or(false)
+    {
+        return Err(Reason::User(String::from("This command has been disabled in this server")));
+    }
+
+    if let Ok(Some(role_id)) = database.command_restriction(guild_id, command).await {
+        let member = guild_id.member(ctx, user_id)
+            .await
+            .map_err(|err| Reason::Log(err.to_string()))?;
+
+        let is_admin = member.permissions(ctx)
+            .map_or(false, |permissions| permissions.manage_guild());
+
+        if !is_admin && !member.roles.contains(&role_id) {
+            return Err(Reason::User(format!("This command is restricted to members with the <@&{role_id}> role")));
+        }
+    }
+
+    Ok(())
+}
+
+/// finds the primary name of an imaging command by any of its names or aliases
+fn find_command(name: &str) -> Option<&'static str> {
+    IMAGING_GROUP.options.commands
+        .iter()
+        .find(|command|
+            command.options.names
+                .iter()
+                .any(|alias| alias.eq_ignore_ascii_case(name))
+        )
+        .map(|command| command.options.names[0])
+}
+
+/// replies to a message with a link to an earlier one if any of its attached images is a near-duplicate of a recent image,
+/// when repost detection is enabled in its guild, recording the hashes of its images either way
+async fn detect_repost(ctx: &Context, message: &Message) -> Result<(), Error> {
+    let Some(guild_id) = message.guild_id else {
+        return Ok(());
+    };
+    if message.author.bot || message.attachments.is_empty() {
+        return Ok(());
+    }
+
+    let database = Database::get(ctx)
+        .await?;
+    if !database.is_repost_detection_enabled(guild_id).await? {
+        return Ok(());
+    }
+
+    for attachment in &message.attachments {
+        let is_image = attachment.content_type
+            .as_deref()
+            .map_or(false, |content_type| content_type.starts_with("image/"));
+        if !is_image || attachment.size > DEFAULT_MAX_SIZE {
+            continue;
+        }
+
+        let bytes = attachment.download()
+            .await?;
+        let Some(hash) = run_job(ctx, estimate_cost(&bytes),
+            move || decode_frames(&bytes, DEFAULT_MAX_FRAMES)
+                .map(|frames| hashing::phash(&frames))
+        )
+            .await??
+        else {
+            continue;
+        };
+
+        if let Some((channel_id, message_id)) = database.find_similar_hash(guild_id, hash, REPOST_THRESHOLD).await? {
+            message.reply(ctx,
+                format!("🔁 This image looks like a repost of {}", message_id.link(channel_id, Some(guild_id)))
+            )
+                .await?;
+        }
+
+        database.record_hash(guild_id, hash, message.channel_id, message.id)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// re-reads the configuration file and swaps it into the client's data, along with the fetch policy and memory ceiling derived from it
+///
+/// settings only read on startup, such as the database url, the worker threads and `slash_only`, require a restart to apply
+async fn reload_config(data: &RwLock<TypeMap>) -> Result<(), String> {
+    let config = Config::try_load(CONFIG_PATH)?;
+
+    FetchPolicy::set(config.fetch_policy());
+    memory::set_ceiling(config.memory_ceiling());
+    data.write()
+        .await
+        .insert::<ConfigData>(Arc::new(config));
+
+    tracing::info!("reloaded the configuration");
+    Ok(())
+}
+
+/// runs a slash or context menu command applying an effect, or one of [`slash::SLASH_COMMANDS`], used when the bot runs in slash-only mode
+///
+/// the response to the interaction stands in for the invocation message,
+/// so that the image pipeline can reply to it just as it would for a prefix command
+async fn run_slash_command(ctx: &Context, interaction: &ApplicationCommandInteraction) -> CommandResult {
+    let name = interaction.data.name.as_str();
+    if slash::slash_effects().all(|effect| effect.name != name) && !slash::SLASH_COMMANDS.contains(&name) {
+        return Ok(());
+    }
+
+    interaction.create_interaction_response(ctx, |response|
+        response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+    )
+        .await?;
+
+    // the same checks as the imaging group's are run, as the framework does not dispatch interactions
+    let checked = match enforce_nsfw(ctx, interaction.guild_id, interaction.channel_id, &[name]).await {
+        Ok(()) => enforce_guild_settings(ctx, interaction.guild_id, interaction.user.id, name)
+            .await,
+        failed => failed,
+    };
+    if let Err(reason) = checked {
+        return Err(match reason {
+            Reason::User(reason) | Reason::UserAndLog { user: reason, .. } => reason,
+            _ => String::from("You can not use this command here"),
+        }
+            .into());
+    }
+
+    // text is rendered from scratch, so it has no source image to resolve
+    let sources = if name == "text" {
+        Vec::new()
+    } else {
+        vec![
+            ImageResolver::new()
+                .resolve_interaction(ctx, interaction)
+                .await?
+        ]
+    };
+
+    let mut message = interaction.edit_original_interaction_response(ctx, |response|
+        response.content(format!("Rendering `{name}`..."))
+    )
+        .await?;
+
+    // the response is authored by the bot, but the limits, cooldown and jobs belong to the invoking user
+    message.author = interaction.user.clone();
+    message.guild_id = interaction.guild_id;
+
+    if let Err(remaining) = apply_cooldown(ctx, &message).await {
+        return Err(format!("⏲️ You are still on cooldown! Try again in `{:.1}s`", remaining.as_secs_f64()).into());
+    }
+
+    // slash commands are audited as prefix commands are, once they pass their checks and cooldown
+    if let Some(tracker) = JobTracker::get(ctx).await {
+        tracker.invoked(message.id);
+    }
+
+    let result = dispatch_slash_command(ctx, interaction, &message, sources)
+        .await;
+    audit_invocation(ctx, &message, name, true, &result)
+        .await;
+
+    result
+}
+
+/// runs the slash command of the provided interaction on its resolved `sources`,
+/// with `message` being the deferred response, which stands in for the invoking message
+async fn dispatch_slash_command(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    message: &Message,
+    sources: Vec<Vec<u8>>,
+) -> CommandResult {
+    let name = interaction.data.name.as_str();
+    let option = |name: &str| interaction.data.options
+        .iter()
+        .find(|option| option.name == name)
+        .and_then(|option| option.resolved.clone());
+    let number = |name: &str| match option(name) {
+        Some(CommandDataOptionValue::Number(value)) => Some(value),
+        _ => None,
+    };
+
+    let string = |name: &str| match option(name) {
+        Some(CommandDataOptionValue::String(value)) => Some(value),
+        _ => None,
+    };
+
+    match name {
+        // the chainable effects are run as a single stage, taking their only parameter if they have one
+        name if Stage::EFFECTS.contains(&name) => {
+            let value = registry::find(name)
+                .and_then(|effect| effect.params.first())
+                .and_then(|param| number(param.name));
+
+            ImageExecutor::new(ctx, message)
+                .function(Stage::run)
+                .arguments(vec![Stage::new(name, value)?])
+                .seed(None)
+                .delay(DelayPolicy::Preserve)
+                .run_batch(sources)
+                .await
+        },
+        "caption" => {
+            let text = string("text")
+                .ok_or(Error::MissingArgument("text"))?;
+
+            #[allow(clippy::cast_possible_truncation)]
+            let options = CaptionOptions {
+                text,
+                color: Rgba::black(),
+                stroke: None,
+                size: number("size").unwrap_or(30.0) as f32,
+                align: Align::Center,
+                position: CaptionPosition::Top,
+                transparent: false,
+            };
+
+            ImageExecutor::new(ctx, message)
+                .function(caption_func)
+                .arguments(vec![options])
+                .run_batch(sources)
+                .await
+        },
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        "frames" => ImageExecutor::new(ctx, message)
+            .function(frames_func)
+            .arguments(vec![number("count").unwrap_or(10.0) as usize])
+            .run_batch(sources)
+            .await,
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        "trim" => ImageExecutor::new(ctx, message)
+            .function(trim_func)
+            .arguments(vec![number("tolerance").unwrap_or(0.0) as u8])
+            .run_batch(sources)
+            .await,
+        "facecrop" => {
+            let options = CropOptions {
+                smart: matches!(option("smart"), Some(CommandDataOptionValue::Boolean(true))),
+                circle: matches!(option("circle"), Some(CommandDataOptionValue::Boolean(true))),
+            };
+
+            ImageExecutor::new(ctx, message)
+                .function(facecrop_func)
+                .arguments(vec![options])
+                .run_batch(sources)
+                .await
+        },
+        "upscale" => {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let factor = number("factor").unwrap_or(4.0) as u32;
+
+            ImageExecutor::new(ctx, message)
+                .function(upscale_func)
+                .arguments(vec![(factor, UpscaleFilter::Nearest)])
+                .weight(u64::from(factor * factor))
+                .run_batch(sources)
+                .await
+        },
+        "scan" => ImageExecutor::new(ctx, message)
+            .function(scan_func)
+            .arguments(vec![true])
+            .run_batch(sources)
+            .await,
+        "pipe" => {
+            let pipeline = string("effects")
+                .ok_or(Error::MissingArgument("effects"))?
+                .parse::<Pipeline>()?;
+            let note = format!("**Pipeline:** `{pipeline}`");
+            let script = Script::try_from(pipeline)?;
+
+            ImageExecutor::new(ctx, message)
+                .function(script::run)
+                .note(format!("{note} (cost `{}`)", script.weight()))
+                .weight(script.weight())
+                .arguments(vec![script])
+                .seed(None)
+                .delay(DelayPolicy::Preserve)
+                .run_batch(sources)
+                .await
+        },
+        "template" => {
+            let name = string("name")
+                .ok_or(Error::MissingArgument("name"))?
+                .to_lowercase();
+            // the name is typed freely rather than picked, so it is checked against the templates that exist
+            if !Template::names().contains(&name) {
+                return Err(Error::MissingTemplate(name).into());
+            }
+            let template = Template::read(name.as_str())?;
+
+            ImageExecutor::new(ctx, message)
+                .function(template_func)
+                .arguments(vec![template])
+                .run_batch(sources)
+                .await
+        },
+        "text" => {
+            let options = TextOptions {
+                text: string("text")
+                    .ok_or(Error::MissingArgument("text"))?,
+                font: string("font"),
+                color: Rgba::black(),
+                stroke: None,
+                background: Rgba::transparent(),
+                size: 64.0,
+                align: Align::Left,
+                max_width: 1024,
+            };
+            let (width, height) = options.estimated_size();
+
+            ImageExecutor::new(ctx, message)
+                .function(text_func)
+                .arguments(vec![options])
+                .generate(width, height)
+                .await
+        },
+        name => Err(format!("The effect `{name}` is not available as a slash command").into()),
+    }
+}
+
 #[tokio::main]
 async fn main() {
     dotenv().ok();
+    tracing_subscriber::fmt::init();
+    pool::install_panic_hook();
+
+    let token = env::var("TOKEN")
+        .unwrap();
+
+    let owners = Http::new(token.as_str())
+        .get_current_application_info()
+        .await
+        .map(|info| HashSet::from([info.owner.id]))
+        .unwrap_or_default();
 
     let framework = StandardFramework::new()
         .configure(
             |conf| conf.prefix("r!")
                 .with_whitespace(true)
+                .owners(owners.clone())
         )
+        .before(before_hook)
         .after(error_handler)
+        .on_dispatch_error(dispatch_error)
         .group(&IMAGING_GROUP)
+        .group(&SETTINGS_GROUP)
+        .group(&GENERAL_GROUP)
+        .group(&META_GROUP)
+        .group(&OWNER_GROUP)
         .help(&HELP_COMMAND)
-        .bucket("imaging",
-            |bucket|
-                bucket.delay(5)
-                    .limit_for(LimitedFor::User)
-                    .await_ratelimits(5)
-                    .delay_action(delay_action)
-        )
         .await;
 
-    let token = env::var("TOKEN")
+    let config = Config::load(CONFIG_PATH);
+    FetchPolicy::set(config.fetch_policy());
+    memory::set_ceiling(config.memory_ceiling());
+
+    let database = Database::connect(config.database_url.as_str())
+        .await
         .unwrap();
+    if let Err(err) = METRICS.load(&database).await {
+        tracing::warn!("failed to restore the metrics: {err}");
+    }
+    if let Err(err) = Tier::load(&database).await {
+        tracing::warn!("failed to restore the cached tiers: {err}");
+    }
+    if !vision::is_available() {
+        tracing::warn!("the face detection model `{}` is not installed, faces will not be detected", vision::FACE_MODEL);
+    }
+
+    let workers = config.worker_threads
+        .or_else(|| thread::available_parallelism().ok().map(usize::from))
+        .unwrap_or(4);
+
+    let mut intents = GatewayIntents::non_privileged()
+        | GatewayIntents::GUILD_MEMBERS;
+    if !config.slash_only {
+        intents |= GatewayIntents::MESSAGE_CONTENT;
+    }
 
-    let intents = GatewayIntents::non_privileged()
-        | GatewayIntents::GUILD_MEMBERS
-        | GatewayIntents::MESSAGE_CONTENT;
+    // without the content of messages, prefix commands can not be parsed, so only slash commands are available
+    let mut builder = Client::builder(token, intents)
+        .event_handler(Handler);
+    if !config.slash_only {
+        builder = builder.framework(framework);
+    }
 
-    let mut client = Client::builder(token, intents)
-        .event_handler(Handler)
-        .framework(framework)
+    let mut client = builder
         .await
         .unwrap();
+    let monitor = Arc::new(AbuseMonitor::new());
+    let cooldown_manager = Arc::new(CooldownManager::new());
+    if let Err(err) = cooldown_manager.load(&database).await {
+        tracing::warn!("failed to restore the cooldowns: {err}");
+    }
     {
         let mut data = client.data.write().await;
-        data.insert::<ClientData>(reqwest::Client::new());
+        data.insert::<ClientData>(policy::build_client());
+        data.insert::<ConfigData>(Arc::new(config));
+        data.insert::<DatabaseData>(database.clone());
+        data.insert::<CooldownData>(cooldown_manager.clone());
+        data.insert::<AbuseData>(monitor.clone());
+        data.insert::<PoolData>(Arc::new(WorkerPool::new(workers)));
+        data.insert::<JobData>(Arc::new(JobTracker::new()));
+        data.insert::<ShardManagerData>(client.shard_manager.clone());
+        data.insert::<StartData>(Instant::now());
+        data.insert::<OwnersData>(Arc::new(owners));
+    }
+
+    tokio::spawn(ASSETS.watch());
+
+    // users who have calmed down are forgotten periodically
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(abuse::SWEEP_INTERVAL);
+
+        loop {
+            interval.tick()
+                .await;
+            monitor.sweep();
+        }
+    });
+
+    // as are users whose cooldowns have long expired
+    let sweeper = cooldown_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(cooldowns::SWEEP_INTERVAL);
+
+        loop {
+            interval.tick()
+                .await;
+            sweeper.sweep();
+        }
+    });
+
+    // the metrics, cached tiers and cooldowns are saved on a graceful shutdown, so that they persist across restarts
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        if shutdown_signal().await {
+            if let Err(err) = METRICS.save(&database).await {
+                tracing::error!("failed to save the metrics: {err}");
+            }
+            if let Err(err) = Tier::save(&database).await {
+                tracing::error!("failed to save the cached tiers: {err}");
+            }
+            if let Err(err) = cooldown_manager.save(&database).await {
+                tracing::error!("failed to save the cooldowns: {err}");
+            }
+
+            shard_manager.lock()
+                .await
+                .shutdown_all()
+                .await;
+        }
+    });
+
+    // the configuration is reloaded on `SIGHUP`, as with the `reload` command
+    #[cfg(unix)]
+    {
+        let data = client.data.clone();
+
+        tokio::spawn(async move {
+            let Ok(mut hangup) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) else {
+                return;
+            };
+
+            while hangup.recv().await.is_some() {
+                if let Err(err) = reload_config(&data).await {
+                    tracing::error!("failed to reload the configuration: {err}");
+                }
+            }
+        });
     }
 
     client.start()
@@ -122,6 +1147,30 @@ async fn main() {
 }
 
 
+/// waits for the bot to be asked to shut down, either by `ctrl-c` or by `SIGTERM` as sent by service managers and containers,
+/// returning whether a signal was received at all
+async fn shutdown_signal() -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(mut terminate) = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) else {
+            return tokio::signal::ctrl_c()
+                .await
+                .is_ok();
+        };
+
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.is_ok(),
+            received = terminate.recv() => received.is_some(),
+        }
+    }
+
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c()
+        .await
+        .is_ok()
+}
+
+
 #[help]
 async fn help_command(
     context: &Context,
@@ -146,45 +1195,2214 @@ async fn help_command(
 
 
 #[command]
-#[bucket = "imaging"]
-async fn invert(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
-    let resolved = ImageResolver::new()
-        .resolve(ctx, message, resolve_arg(&mut args))
+async fn invert(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
         .await?;
 
+    let mut mask = flags.get_or("channels", ChannelMask::default())?;
+    if flags.has("alpha") {
+        mask = mask.with_alpha();
+    }
+
     ImageExecutor::new(ctx, message)
         .function(invert_func)
-        .run(resolved)
+        .arguments(vec![mask])
+        .run_batch(sources)
         .await
 }
 
 #[command]
-#[bucket = "imaging"]
-async fn huerotate(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
-    let resolved = ImageResolver::new()
-        .resolve(ctx, message, resolve_arg(&mut args))
+#[aliases("invertopacity")]
+async fn invertalpha(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(invert_func)
+        .arguments(vec![ChannelMask { r: false, g: false, b: false, a: true }])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+async fn huerotate(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
         .await?;
 
     ImageExecutor::new(ctx, message)
         .function(huerotate_func)
-        .run(resolved)
+        .delay(DelayPolicy::Preserve)
+        .preview(flags.has("preview"))
+        .run_batch(sources)
         .await
 }
 
 #[command]
-#[bucket = "imaging"]
-async fn caption(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+async fn caption(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
     let mut resolver = ImageResolver::new();
-    let resolved = resolver
-        .resolve(ctx, message, resolve_arg(&mut args))
+    let sources = resolver
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
         .await?;
-    let arg = resolve_extra_arg(
-        resolver.arg_resolved, &mut args
+    let text = resolve_extra_arg_or_reply(
+        resolver.arg_resolved, &mut args, message
     );
 
+    let options = CaptionOptions {
+        text,
+        color: flags.get_or("color", Color(Rgba::black()))?.0,
+        stroke: flags.get::<Color>("stroke")?
+            .map(|color| color.0),
+        size: flags.get_or("size", 30.0)?
+            .clamp(8.0, 120.0),
+        align: flags.get_or("align", Align::Center)?,
+        position: flags.get_or("position", CaptionPosition::Top)?,
+        transparent: flags.has("transparent"),
+    };
+
     ImageExecutor::new(ctx, message)
         .function(caption_func)
-        .arguments(vec![arg])
+        .arguments(vec![options])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("watermark")]
+async fn overlay(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let (source, overlay) = ImageResolver::new()
+        .resolve_pair(ctx, message, resolve_arg(&mut args), resolve_arg(&mut args))
+        .await?;
+
+    let options = OverlayOptions {
+        overlay,
+        anchor: flags.get_or("position", Anchor::BottomRight)?,
+        scale: flags.get_or("scale", 0.25)?
+            .clamp(0.01, 1.0),
+        opacity: flags.get_or("opacity", 1.0)?
+            .clamp(0.0, 1.0),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(overlay_func)
+        .arguments(vec![options])
+        .preview(flags.has("preview"))
+        .run(source)
+        .await
+}
+
+#[command]
+#[aliases("flag")]
+#[usage("<country code or flag emoji> [image] [--style=<ring|wash>] [--opacity=<0-1>]")]
+async fn flagify(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let country = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("country"))?;
+
+    let mut resolver = ImageResolver::new();
+    let flag = resolver.resolve_flag(ctx, &country)
+        .await?;
+    let sources = resolver
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let options = FlagifyOptions {
+        flag,
+        style: flags.get_or("style", FlagStyle::Ring)?,
+        opacity: flags.get_or("opacity", 0.45)?
+            .clamp(0.0, 1.0),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(flagify_func)
+        .arguments(vec![options])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("tiles")]
+#[usage("[image] [tile image] [--tiles=<count>]")]
+async fn mosaic(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let (source, tile) = ImageResolver::new()
+        .resolve_pair(ctx, message, resolve_arg(&mut args), resolve_arg(&mut args))
+        .await?;
+
+    let options = MosaicOptions {
+        tile,
+        tiles: flags.get_or("tiles", 32_u32)?
+            .clamp(4, 128),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(mosaic_func)
+        .arguments(vec![options])
+        .preview(flags.has("preview"))
+        .run(source)
+        .await
+}
+
+#[command]
+#[aliases("grain")]
+async fn noise(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let intensity = flags.get_or("intensity", 0.25)?
+        .clamp(0.0, 1.0);
+
+    ImageExecutor::new(ctx, message)
+        .function(noise_func)
+        .arguments(vec![intensity])
+        .seed(flags.get("seed")?)
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("surprise")]
+async fn random(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    // the seed determines the effect and its parameters as well, so that a surprise can be reproduced
+    let seed = flags.get("seed")?
+        .unwrap_or_else(rand::random);
+    let mut rng = Rng::seed_from_u64(seed);
+
+    let stage = Stage::random(&mut rng)
+        .ok_or("There are no effects to pick from")?;
+
+    let mut note = format!("**Effect:** `{}`", stage.effect.name);
+    if let (Some(param), Some(value)) = (stage.effect.params.first(), stage.value) {
+        note.push_str(format!(" `--{}={value:.2}`", param.name).as_str());
+    }
+
+    ImageExecutor::new(ctx, message)
+        .function(Stage::run)
+        .arguments(vec![stage])
+        .note(note)
+        .seed(Some(seed))
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("chain")]
+#[usage("[image] <effect[:value]> | <effect[:value]> ...")]
+async fn pipe(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+
+    // the image is optional, so the first argument is only taken as one if the rest does not parse without it
+    let (argument, pipeline) = match args.rest().parse::<Pipeline>() {
+        Ok(pipeline) => (None, pipeline),
+        Err(err) => {
+            let argument = resolve_arg(&mut args);
+            let pipeline = args.rest()
+                .parse::<Pipeline>()
+                .map_err(|_| err)?;
+
+            (argument, pipeline)
+        },
+    };
+
+    let note = format!("**Pipeline:** `{pipeline}`");
+    let script = Script::try_from(pipeline)?;
+
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, argument)
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(script::run)
+        .note(format!("{note} (cost `{}`)", script.weight()))
+        .weight(script.weight())
+        .arguments(vec![script])
+        .seed(flags.get("seed")?)
+        .delay(DelayPolicy::Preserve)
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("script")]
+#[usage("[image] \"<op(value)> | <op(value)> ...\" | <name> [image] | save <name> \"<script>\" | delete <name> | list")]
+async fn custom(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    // scripts may be wrapped in double quotes as a whole, which do not belong to the text they contain
+    fn unquote(script: &str) -> &str {
+        let script = script.trim();
+
+        script.strip_prefix('"')
+            .and_then(|script| script.strip_suffix('"'))
+            .unwrap_or(script)
+    }
+
+    let (flags, mut args) = Flags::extract(&args);
+    let database = Database::get(ctx)
+        .await?;
+
+    let first = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("script"))?;
+    match first.to_lowercase().as_str() {
+        "save" => {
+            let name = resolve_arg(&mut args)
+                .ok_or(Error::MissingArgument("name"))?
+                .to_lowercase();
+            script::validate_name(&name)?;
+            let script = unquote(args.rest())
+                .parse::<Script>()?;
+
+            let saved = database.custom_effects(message.author.id)
+                .await?;
+            if saved.len() >= MAX_SAVED && !saved.iter().any(|(saved, _)| *saved == name) {
+                return Err(format!("You can save at most {MAX_SAVED} custom effects, delete one with `custom delete <name>` first").into());
+            }
+
+            database.save_custom_effect(message.author.id, &name, &script.to_string())
+                .await?;
+            message.reply(ctx, format!(
+                "Saved `{name}` as `{script}`, with a cost of `{}` out of `{MAX_WEIGHT}`, use it with `custom {name} [image]`",
+                script.weight(),
+            ))
+                .await?;
+
+            return Ok(());
+        },
+        "delete" | "remove" => {
+            let name = resolve_arg(&mut args)
+                .ok_or(Error::MissingArgument("name"))?
+                .to_lowercase();
+
+            if !database.delete_custom_effect(message.author.id, &name).await? {
+                return Err(format!("You have no custom effect named `{name}`").into());
+            }
+            message.reply(ctx, format!("Deleted the custom effect `{name}`"))
+                .await?;
+
+            return Ok(());
+        },
+        "list" => {
+            let saved = database.custom_effects(message.author.id)
+                .await?;
+            if saved.is_empty() {
+                return Err("You have not saved any custom effects yet, save one with `custom save <name> \"<script>\"`".into());
+            }
+
+            let list = saved.iter()
+                .map(|(name, script)| format!("`{name}`: `{script}`"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            message.reply(ctx, format!("**Your custom effects ({}/{MAX_SAVED}):**\n{list}", saved.len()))
+                .await?;
+
+            return Ok(());
+        },
+        _ => (),
+    }
+
+    // the first argument is either the name of a saved effect, followed by the image, or the start of a script,
+    // which as with `pipe` is only preceded by the image if the script does not parse without it
+    let (argument, script) = if let Some(saved) = database.custom_effect(message.author.id, &first.to_lowercase()).await? {
+        (resolve_arg(&mut args), saved.parse::<Script>()?)
+    } else {
+        args.restore();
+
+        match unquote(args.rest()).parse::<Script>() {
+            Ok(script) => (None, script),
+            Err(err) => {
+                let argument = resolve_arg(&mut args);
+                let rest = unquote(args.rest());
+                let script = if rest.is_empty() { Err(err) } else { rest.parse::<Script>() }?;
+
+                (argument, script)
+            },
+        }
+    };
+
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, argument)
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(script::run)
+        .note(format!("**Script:** `{script}` (cost `{}`)", script.weight()))
+        .weight(script.weight())
+        .arguments(vec![script])
+        .seed(flags.get("seed")?)
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("emoji", "e")]
+async fn enlarge(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let argument = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("emoji"))?;
+
+    let resolved = ImageResolver::new()
+        .resolve_emoji(ctx, message, argument.as_str())
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(enlarge_func)
+        .arguments(vec![4])
+        .max_height(128)
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("explode")]
+async fn frames(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    // archives are not bound by the attachment limit, so every frame is exported by default
+    let archive = flags.has("zip");
+    let count = if archive {
+        flags.get_or("count", usize::MAX)?
+    } else {
+        flags.get_or("count", MAX_ATTACHMENTS)?
+            .clamp(1, MAX_ATTACHMENTS)
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(frames_func)
+        .arguments(vec![count])
+        .archive(archive)
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("emotepack", "emotes")]
+#[usage("[image] [--grid=<columns>x<rows>]")]
+async fn emojipack(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let Size(columns, rows) = flags.get_or("grid", Size(3, 3))?;
+    let grid = Size(columns.clamp(1, 8), rows.clamp(1, 8));
+
+    // each tile is compressed to fit discord's limit on the size of emojis, as `steal` does, so that every one can be uploaded
+    let archive = inspect(ctx, message, vec![resolved], move |mut images| {
+        let tiles = emojipack_func(ImageArguments { frames: images.swap_remove(0), arguments: vec![grid], seed: 0 })?;
+        let outputs = tiles.into_iter()
+            .map(|tile| {
+                let (bytes, is_gif) = compress_to_fit(tile, EMOJI_TILE_SIZE, MAX_EMOJI_SIZE)?;
+
+                Ok(Output { bytes, extension: if is_gif { "gif" } else { "png" } })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Output::archive(outputs, MAX_ARCHIVE_SIZE)
+    })
+        .await?;
+
+    send_output(
+        ctx,
+        message,
+        vec![archive],
+        format!("**Grid:** {}x{} tiles of {EMOJI_TILE_SIZE}px, numbered from left to right, top to bottom", grid.0, grid.1),
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("cut", "clip")]
+#[usage("[image] [--start=<time>] [--end=<time>] | [--frames=<first>..<last>]")]
+async fn trimgif(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let cut = if let Some(range) = flags.get::<FrameRange>("frames")? {
+        Cut::Frames(range)
+    } else {
+        let start = flags.get::<Timestamp>("start")?
+            .map_or(Duration::ZERO, |start| start.0);
+        let end = flags.get::<Timestamp>("end")?
+            .map(|end| end.0);
+
+        if end.is_some_and(|end| end <= start) {
+            return Err("The end of the cut must be after its start".into());
+        }
+        Cut::Time(start, end)
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(trimgif_func)
+        .arguments(vec![cut])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("concat", "append")]
+#[usage("[image] [image]")]
+async fn stackgif(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (_, mut args) = Flags::extract(&args);
+    let (source, next) = ImageResolver::new()
+        .resolve_pair(ctx, message, resolve_arg(&mut args), resolve_arg(&mut args))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(stack_func)
+        .arguments(vec![StackOptions { next }])
+        .run(source)
+        .await
+}
+
+#[command]
+#[aliases("beforeafter", "sidebyside")]
+#[usage("[image] [image] [--slider]")]
+async fn compare(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let (before, after) = ImageResolver::new()
+        .resolve_pair(ctx, message, resolve_arg(&mut args), resolve_arg(&mut args))
+        .await?;
+
+    let options = CompareOptions {
+        after,
+        slider: flags.has("slider"),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(compare_func)
+        .arguments(vec![options])
+        .run(before)
+        .await
+}
+
+#[command]
+#[aliases("channels", "rgba")]
+async fn split(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (_, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(split_func)
+        .note("**Channels:** red, green, blue, alpha")
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("crop", "autocrop")]
+async fn trim(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let tolerance = flags.get_or("tolerance", 0_u8)?
+        .min(64);
+
+    ImageExecutor::new(ctx, message)
+        .function(trim_func)
+        .arguments(vec![tolerance])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("lasers", "lasereyes")]
+async fn eyes(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(accessory_func)
+        .arguments(vec![Accessory::LaserEyes])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("dealwithit", "sunglasses")]
+async fn glasses(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(accessory_func)
+        .arguments(vec![Accessory::Glasses])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("clownnose")]
+async fn clown(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(accessory_func)
+        .arguments(vec![Accessory::ClownNose])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("squarecrop")]
+#[usage("[image] [--smart] [--circle]")]
+async fn facecrop(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(facecrop_func)
+        .arguments(vec![CropOptions { smart: flags.has("smart"), circle: flags.has("circle") }])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("banner", "header")]
+#[usage("[image] [--preset=<discord|twitter|youtube>]")]
+async fn bannerize(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let preset = flags.get_or("preset", BannerPreset::default())?;
+    let (width, height) = preset.size();
+
+    ImageExecutor::new(ctx, message)
+        .function(bannerize_func)
+        .arguments(vec![preset])
+        .note(format!("**Preset:** {} ({width}x{height})", preset.name()))
         .run(resolved)
         .await
+}
+
+#[command]
+#[aliases("impact", "dutch")]
+#[usage("[image] [--intensity=<0-1>] [--red] [--seed=<seed>]")]
+async fn tilt(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let options = TiltOptions {
+        intensity: flags.get_or("intensity", 0.6)?
+            .clamp(0.1, 1.0),
+        red: flags.has("red"),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(tilt_func)
+        .arguments(vec![options])
+        .seed(flags.get("seed")?)
+        .run_batch(sources)
+        .await
+}
+
+/// renders the resolved images in 3D with [`anaglyph_func`], either in red and cyan or wiggling between the views
+async fn render_anaglyph(ctx: &Context, message: &Message, args: Args, wiggle: bool) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let options = AnaglyphOptions {
+        depth: flags.get_or("depth", 12_u32)?
+            .clamp(2, 40),
+        wiggle,
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(anaglyph_func)
+        .arguments(vec![options])
+        .delay(DelayPolicy::Preserve)
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("emboss3d", "3d")]
+#[usage("[image] [--depth=<pixels>]")]
+async fn anaglyph(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    render_anaglyph(ctx, message, args, false)
+        .await
+}
+
+#[command]
+#[aliases("wiggle3d")]
+#[usage("[image] [--depth=<pixels>]")]
+async fn wiggle(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    render_anaglyph(ctx, message, args, true)
+        .await
+}
+
+#[command]
+#[aliases("document", "deskew")]
+#[usage("[image] [--gray]")]
+async fn scan(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(scan_func)
+        .arguments(vec![!flags.has("gray")])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("redact", "anonymize")]
+#[usage("[image] [--style=<pixelate|bar>] [--region=<x,y,width,height>]")]
+async fn censor(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let options = CensorOptions {
+        style: flags.get_or("style", CensorStyle::Pixelate)?,
+        region: flags.get::<Region>("region")?,
+    };
+    // without the model no face is ever found, which would hide the whole image
+    if options.region.is_none() && !vision::is_available() {
+        return Err("Faces can not be detected right now, pass the region to hide with `--region=<x,y,width,height>` instead".into());
+    }
+
+    ImageExecutor::new(ctx, message)
+        .function(censor_func)
+        .arguments(vec![options])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("pixelart")]
+#[usage("[image] [factor]")]
+async fn upscale(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let mut resolver = ImageResolver::new();
+    let sources = resolver
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    // the factor is taken from the argument following the image, if the image was resolved from one
+    let factor = resolve_extra_arg(resolver.arg_resolved, &mut args);
+    let factor = factor.trim();
+    let factor = if factor.is_empty() {
+        flags.get_or("factor", 4_u32)?
+    } else {
+        factor.trim_end_matches('x')
+            .parse::<u32>()
+            .map_err(|_| Error::InvalidFlag(String::from("factor"), factor.to_string()))?
+    };
+    let filter = flags.get_or("filter", UpscaleFilter::Nearest)?;
+    let factor = factor.clamp(1, 16);
+
+    // the output is bound by its own cap rather than by the usual maximum dimension,
+    // and costs as much more to render as it has pixels
+    ImageExecutor::new(ctx, message)
+        .function(upscale_func)
+        .arguments(vec![(factor, filter)])
+        .weight(u64::from(factor * factor))
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("glow")]
+async fn neon(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let sources = ImageResolver::new()
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(neon_func)
+        .arguments(vec![flags.get_or("glow", 8_u32)?.clamp(2, 24)])
+        .run_batch(sources)
+        .await
+}
+
+#[command]
+#[aliases("difference", "heatmap")]
+#[usage("[image] [image]")]
+async fn diff(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let (first, second) = ImageResolver::new()
+        .resolve_pair(ctx, message, resolve_arg(&mut args), resolve_arg(&mut args))
+        .await?;
+
+    let format = flags.get::<OutputFormat>("format")?
+        .unwrap_or_default();
+
+    let (output, similarity) = inspect(ctx, message, vec![first, second],
+        move |images| {
+            let [first, second] = <[_; 2]>::try_from(images)
+                .map_err(|_| Error::JobFailed)?;
+            let (heatmap, similarity) = diff_func(first, second)?;
+
+            Ok((Output::encode(heatmap, format, None)?, similarity))
+        }
+    )
+        .await?;
+
+    send_output(ctx, message, vec![output], format!("**Similarity:** `{:.2}%`", similarity * 100.0))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("hash")]
+async fn phash(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let hash = inspect(ctx, message, vec![resolved],
+        move |images| hashing::phash(&images[0])
+            .ok_or(Error::JobFailed)
+    )
+        .await?;
+
+    message.reply(ctx, format!("**Perceptual Hash:** `{hash:016x}`"))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+async fn blurhash(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let (hash, output) = inspect(ctx, message, vec![resolved],
+        move |images| {
+            let (hash, placeholder) = hashing::blurhash(&images[0])?;
+
+            Ok((hash, Output::encode(placeholder, OutputFormat::Png, None)?))
+        }
+    )
+        .await?;
+
+    send_output(ctx, message, vec![output], format!("**BlurHash:** `{hash}`"))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("qrcode")]
+#[usage("<text> [--logo[=image]]")]
+async fn qr(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, args) = Flags::extract(&args);
+    let text = args.rest()
+        .trim()
+        .to_string();
+    if text.is_empty() {
+        return Err(Error::MissingArgument("text").into());
+    }
+
+    // the logo is taken from the value of the flag, or from the usual sources such as an attachment if it has none
+    let logo = if flags.has("logo") {
+        Some(
+            ImageResolver::new()
+                .resolve(ctx, message, flags.raw("logo").map(ToString::to_string))
+                .await?
+        )
+    } else {
+        None
+    };
+    let output = inspect(ctx, message, logo.into_iter().collect(),
+        move |images| Ok(Output::encode(render_qr(text.as_str(), images.first())?, OutputFormat::Png, None)?)
+    )
+        .await?;
+
+    send_output(ctx, message, vec![output], String::new())
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("qrdecode")]
+async fn qrscan(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let contents = inspect(ctx, message, vec![resolved],
+        move |images| Ok(scan_codes(&images[0]))
+    )
+        .await?;
+
+    if contents.is_empty() {
+        return Err("No QR code could be found in the provided image".into());
+    }
+
+    let content = contents.iter()
+        .map(|content| format!("```\n{}\n```", content.replace("```", "`\u{200b}``")))
+        .collect::<String>();
+    // discord refuses messages longer than 2000 characters
+    let content = if content.chars().count() > 2000 {
+        content.chars()
+            .take(1997)
+            .chain("...".chars())
+            .collect()
+    } else {
+        content
+    };
+
+    message.channel_id.send_message(ctx, |msg|
+        msg.content(content)
+            .reference_message(message)
+            .allowed_mentions(|am| am.empty_parse())
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("ocr")]
+#[usage("[image] [--lang=<code>]")]
+async fn readtext(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let language = flags.raw("lang")
+        .unwrap_or(DEFAULT_LANGUAGE)
+        .to_string();
+    if !ocr::is_valid_language(language.as_str()) {
+        return Err(Error::InvalidFlag(String::from("lang"), language).into());
+    }
+
+    let text = inspect(ctx, message, vec![resolved],
+        move |images| ocr::read_text(&images[0], language.as_str())
+    )
+        .await?;
+
+    if text.is_empty() {
+        return Err("No text could be found in the provided image".into());
+    }
+
+    // discord refuses messages longer than 2000 characters, leaving room for the code block
+    let text = text.replace("```", "`\u{200b}``")
+        .chars()
+        .take(1990)
+        .collect::<String>();
+
+    message.channel_id.send_message(ctx, |msg|
+        msg.content(format!("```\n{text}\n```"))
+            .reference_message(message)
+            .allowed_mentions(|am| am.empty_parse())
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("colour", "swatch")]
+#[usage("<color> [--size=<width>x<height>]")]
+async fn color(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, args) = Flags::extract(&args);
+    let value = args.rest()
+        .trim();
+    if value.is_empty() {
+        return Err(Error::MissingArgument("color").into());
+    }
+
+    let Color(color) = value.parse::<Color>()
+        .map_err(|_| format!("`{value}` is not a valid color, try a hex code, `rgb(...)` or a CSS color name"))?;
+    let size = flags.get_or("size", Size(256, 256))?;
+
+    let (hue, saturation, lightness, _) = csscolorparser::Color::from_rgba8(color.r, color.g, color.b, color.a)
+        .to_hsla();
+    let note = format!(
+        "**Hex:** `#{:02x}{:02x}{:02x}{:02x}` | **RGB:** `rgb({}, {}, {})` | **HSL:** `hsl({:.0}, {:.0}%, {:.0}%)` | **Alpha:** `{}`",
+        color.r, color.g, color.b, color.a,
+        color.r, color.g, color.b,
+        hue, saturation * 100.0, lightness * 100.0,
+        color.a,
+    );
+
+    let (width, height) = clamp_size(size);
+
+    ImageExecutor::new(ctx, message)
+        .function(swatch_func)
+        .arguments(vec![(color, size)])
+        .note(note)
+        .generate(width, height)
+        .await
+}
+
+#[command]
+#[usage("<color> <color> [colors...] [--radial] [--angle=<degrees>] [--size=<width>x<height>]")]
+async fn gradient(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+
+    let mut colors = Vec::new();
+    while let Some(value) = resolve_arg(&mut args) {
+        let Color(color) = value.parse::<Color>()
+            .map_err(|_| format!("`{value}` is not a valid color, try a hex code or a CSS color name"))?;
+        colors.push(color);
+    }
+    if colors.len() < 2 {
+        return Err(Error::MissingArgument("color").into());
+    }
+
+    let options = GradientOptions {
+        colors,
+        kind: if flags.has("radial") { GradientKind::Radial } else { GradientKind::Linear },
+        angle: flags.get_or("angle", 0.0)?,
+        size: flags.get_or("size", Size(512, 256))?,
+    };
+    let (width, height) = clamp_size(options.size);
+
+    ImageExecutor::new(ctx, message)
+        .function(gradient_func)
+        .arguments(vec![options])
+        .generate(width, height)
+        .await
+}
+
+#[command]
+#[aliases("rank", "level")]
+#[usage("[user] [--rank=<rank>] [--level=<level>] [--xp=<xp>] [--next=<xp>] [--colors=<color,color>]")]
+async fn rankcard(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+
+    let user = if let Some(arg) = resolve_arg(&mut args) {
+        User::convert(ctx, message.guild_id, Some(message.channel_id), arg.as_str())
+            .await?
+    } else {
+        message.author.clone()
+    };
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, Some(user.id.to_string()))
+        .await?;
+
+    let colors = flags.raw("colors")
+        .unwrap_or("#5865f2,#eb459e")
+        .split(',')
+        .map(|value| value.trim()
+            .parse::<Color>()
+            .map(|Color(color)| color)
+            .map_err(|_| format!("`{value}` is not a valid color, try a hex code or a CSS color name"))
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let options = RankOptions {
+        name: user.name,
+        rank: flags.get_or("rank", 1)?,
+        level: flags.get_or("level", 1)?,
+        xp: flags.get_or("xp", 0)?,
+        next: flags.get_or("next", 100)?,
+        colors,
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(rankcard_func)
+        .arguments(vec![options])
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("graph", "plot")]
+#[usage("[bar|line|pie] <label:value, label:value, ...> [--title=<title>]")]
+async fn chart(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, args) = Flags::extract(&args);
+    let content = args.rest()
+        .trim();
+
+    let (kind, data) = match content.split_once(char::is_whitespace) {
+        Some((first, rest)) => match first.parse::<ChartKind>() {
+            Ok(kind) => (kind, rest),
+            Err(_) => (ChartKind::default(), content),
+        },
+        None => (ChartKind::default(), content),
+    };
+    if data.trim().is_empty() {
+        return Err(Error::MissingArgument("data").into());
+    }
+
+    let data = data.parse::<ChartData>()
+        .map_err(|_| "The chart data is invalid, try something like `apples:3, pears:5, plums:2`")?;
+    if data.0.len() > MAX_CHART_ENTRIES {
+        return Err(format!("A chart can have at most {MAX_CHART_ENTRIES} entries").into());
+    }
+
+    let options = ChartOptions {
+        kind,
+        data,
+        title: flags.raw("title").map(ToString::to_string),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(chart_func)
+        .arguments(vec![options])
+        .generate(CHART_SIZE.0, CHART_SIZE.1)
+        .await
+}
+
+#[command]
+#[aliases("write", "render")]
+#[usage("<text> [--font=<name>] [--size=<size>] [--color=<color>] [--stroke=<color>] [--background=<color>] [--align=<left|center|right>]")]
+async fn text(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, args) = Flags::extract(&args);
+    let content = args.rest()
+        .trim()
+        .to_string();
+    if content.is_empty() {
+        return Err(Error::MissingArgument("text").into());
+    }
+
+    if let (Some(font), Some(fonts)) = (flags.raw("font"), ASSETS.fonts()) {
+        if !fonts.names().any(|name| name.eq_ignore_ascii_case(font)) {
+            let names = fonts.names()
+                .map(|name| format!("`{name}`"))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            return Err(format!("There is no font named `{font}`, the available fonts are: {names}").into());
+        }
+    }
+
+    let options = TextOptions {
+        text: content,
+        font: flags.raw("font")
+            .map(ToString::to_string),
+        color: flags.get_or("color", Color(Rgba::black()))?.0,
+        stroke: flags.get::<Color>("stroke")?
+            .map(|color| color.0),
+        background: flags.get_or("background", Color(Rgba::transparent()))?.0,
+        size: flags.get_or("size", 64.0)?
+            .clamp(8.0, 256.0),
+        align: flags.get_or("align", Align::Left)?,
+        max_width: 1024,
+    };
+    let (width, height) = options.estimated_size();
+    // the outline is rendered as a second pass over the whole image
+    let weight = if options.stroke.is_some() { 2 } else { 1 };
+
+    ImageExecutor::new(ctx, message)
+        .function(text_func)
+        .arguments(vec![options])
+        .weight(weight)
+        .generate(width, height)
+        .await
+}
+
+#[command]
+#[aliases("poster", "movie")]
+#[usage("[image] <title> [--director=<name>]")]
+async fn posterfy(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let mut resolver = ImageResolver::new();
+    let sources = resolver
+        .resolve_batch(ctx, message, resolve_arg(&mut args), flags.has("all"))
+        .await?;
+
+    let options = PosterOptions {
+        title: resolve_extra_arg(resolver.arg_resolved, &mut args)
+            .trim()
+            .to_string(),
+        director: flags.raw("director")
+            .map_or_else(|| message.author.name.clone(), ToString::to_string),
+    };
+
+    ImageExecutor::new(ctx, message)
+        .function(posterfy_func)
+        .arguments(vec![options])
+        .run_batch(sources)
+        .await
+}
+
+/// composites the resolved image, by default the author's avatar, into the reaction template named `template`
+async fn react(ctx: &Context, message: &Message, args: Args, template: &str) -> CommandResult {
+    let (_, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+    let template = Template::read(template)?;
+
+    ImageExecutor::new(ctx, message)
+        .function(template_func)
+        .arguments(vec![template])
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("headbang")]
+async fn dance(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    react(ctx, message, args, "dance")
+        .await
+}
+
+#[command]
+#[aliases("confetti")]
+async fn party(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    react(ctx, message, args, "party")
+        .await
+}
+
+#[command]
+#[aliases("vibing")]
+async fn vibe(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    react(ctx, message, args, "vibe")
+        .await
+}
+
+#[command]
+#[aliases("glitchwave")]
+#[usage("[user] [--name=<name>]")]
+async fn intro(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    // the name is that of the mentioned user whose avatar was resolved, falling back to the author's
+    let name = flags.raw("name")
+        .map(ToString::to_string)
+        .or_else(|| message.mentions
+            .first()
+            .map(|user| user.name.clone())
+        )
+        .unwrap_or_else(|| message.author.name.clone());
+
+    ImageExecutor::new(ctx, message)
+        .function(intro_func)
+        .arguments(vec![IntroOptions { name }])
+        .seed(flags.get("seed")?)
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("fakequote", "quotecard")]
+#[usage("<user> <text> or in reply to a message")]
+async fn quote(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (_, mut args) = Flags::extract(&args);
+
+    // a reply quotes the referenced message, otherwise the user and text are given as arguments
+    let (author, text) = if let Some(referenced) = &message.referenced_message {
+        (referenced.author.clone(), referenced.content.clone())
+    } else {
+        let user = resolve_arg(&mut args)
+            .ok_or(Error::MissingArgument("user"))?;
+        let user = User::convert(ctx, message.guild_id, Some(message.channel_id), user.as_str())
+            .await?;
+
+        (user, args.rest().to_string())
+    };
+    if text.trim().is_empty() {
+        return Err(Error::MissingArgument("text").into());
+    }
+
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, Some(author.id.to_string()))
+        .await?;
+
+    ImageExecutor::new(ctx, message)
+        .function(quote_func)
+        .arguments(vec![QuoteOptions { name: author.name, text }])
+        .run(resolved)
+        .await
+}
+
+#[command]
+#[aliases("ico")]
+async fn favicon(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    // only the first frame is used, so animations are neither decoded in full nor rejected for their number of frames
+    let bytes = inspect_first(ctx, message, vec![resolved],
+        move |images| encode_ico(&images[0], &ICO_SIZES)
+    )
+        .await?;
+
+    let sizes = ICO_SIZES.iter()
+        .map(|size| format!("`{size}x{size}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    send_output(ctx, message, vec![Output { bytes, extension: "ico" }], format!("**Sizes:** {sizes}"))
+        .await?;
+
+    Ok(())
+}
+
+/// the maximum size of a guild emoji: 256 KB
+const MAX_EMOJI_SIZE: usize = 256_000;
+
+#[command]
+#[aliases("addemoji")]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_EMOJIS_AND_STICKERS")]
+async fn steal(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let name = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("name"))?;
+    validate_name(name.as_str())?;
+
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let (bytes, is_gif) = run_job(ctx, estimate_cost(&resolved),
+        move || compress_to_fit(
+            decode_frames(&resolved, DEFAULT_MAX_FRAMES)?,
+            128,
+            MAX_EMOJI_SIZE,
+        )
+    )
+        .await??;
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    let emoji = guild_id.create_emoji(
+        ctx,
+        name.as_str(),
+        to_data_uri(&bytes, is_gif).as_str(),
+    )
+        .await?;
+
+    message.reply(ctx, format!("Successfully added {emoji}"))
+        .await?;
+
+    Ok(())
+}
+
+/// the dimensions of a guild sticker: 320x320
+const STICKER_SIZE: u32 = 320;
+/// the maximum size of a guild sticker: 512 KB
+const MAX_STICKER_SIZE: usize = 512_000;
+
+#[command]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_EMOJIS_AND_STICKERS")]
+async fn addsticker(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let name = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("name"))?;
+    validate_name(name.as_str())?;
+
+    let resolved = ImageResolver::new()
+        .resolve(ctx, message, resolve_arg(&mut args))
+        .await?;
+
+    let bytes = run_job(ctx, estimate_cost(&resolved),
+        move || encode_sticker(
+            decode_frames(&resolved, DEFAULT_MAX_FRAMES)?,
+            STICKER_SIZE,
+            MAX_STICKER_SIZE,
+        )
+    )
+        .await??;
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    let tags = flags.raw("tags")
+        .unwrap_or(name.as_str())
+        .to_string();
+    let description = flags.raw("description")
+        .map(ToString::to_string);
+
+    let sticker = guild_id.create_sticker(ctx,
+        |sticker| {
+            sticker.name(name.as_str())
+                .tags(tags)
+                .file(
+                    AttachmentType::Bytes {
+                        data: bytes.into(),
+                        filename: String::from("sticker.png"),
+                    }
+                );
+
+            if let Some(description) = description {
+                sticker.description(description);
+            }
+            sticker
+        }
+    )
+        .await?;
+
+    message.reply(ctx, format!("Successfully added the sticker `{}`", sticker.name))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("av", "pfp")]
+async fn avatar(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+
+    let size = flags.get_or("size", 1024_u16)?;
+    if !(16..=4096).contains(&size) || !size.is_power_of_two() {
+        return Err(Error::InvalidFlag(String::from("size"), size.to_string()).into());
+    }
+
+    let format = flags.raw("format")
+        .map(str::to_lowercase);
+    if let Some(format) = &format {
+        if !["png", "jpg", "jpeg", "webp", "gif"].contains(&format.as_str()) {
+            return Err(Error::InvalidFlag(String::from("format"), format.clone()).into());
+        }
+    }
+
+    let user = if let Some(arg) = resolve_arg(&mut args) {
+        User::convert(ctx, message.guild_id, Some(message.channel_id), arg.as_str())
+            .await?
+    } else {
+        message.author.clone()
+    };
+
+    let member = if flags.has("server") {
+        let guild_id = message.guild_id
+            .ok_or("The `--server` flag can only be used in a server")?;
+
+        Some(
+            Member::convert(ctx, Some(guild_id), Some(message.channel_id), user.id.to_string().as_str())
+                .await?
+        )
+    } else {
+        None
+    };
+
+    let url = match (&member, &user.avatar) {
+        (Some(member), _) if member.avatar.is_some() => avatar_url(
+            user.id,
+            Some(member.guild_id),
+            member.avatar.as_deref().unwrap_or_default(),
+            format.as_deref(),
+            size,
+        ),
+        (Some(_), _) => return Err("This user does not have a server-specific avatar".into()),
+        (None, Some(hash)) => avatar_url(user.id, None, hash, format.as_deref(), size),
+        (None, None) => user.default_avatar_url(),
+    };
+
+    message.channel_id.send_message(ctx,
+        |msg| {
+            msg.reference_message(message)
+                .allowed_mentions(|am| am.empty_parse())
+                .embed(|embed|
+                    embed.title(format!("{}'s avatar", user.name))
+                        .url(&url)
+                        .description(format!("[Download]({url})"))
+                        .image(&url)
+                )
+        }
+    ).await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("avatars", "pfphistory")]
+#[usage("[user] | on | off")]
+async fn avatarhistory(ctx: &Context, message: &Message, args: Args) -> CommandResult {
+    let (flags, mut args) = Flags::extract(&args);
+    let database = Database::get(ctx)
+        .await?;
+
+    let argument = resolve_arg(&mut args);
+    match argument.as_deref().map(str::to_lowercase).as_deref() {
+        Some("on" | "enable" | "true") => {
+            database.set_avatar_tracking(message.author.id, true)
+                .await?;
+            // the current avatar is recorded right away, so that the history starts with it rather than with the next one
+            avatars::record(ctx, &message.author)
+                .await?;
+            message.reply(ctx, "Your avatars will now be tracked, use `avatarhistory off` to stop and forget them")
+                .await?;
+
+            return Ok(());
+        },
+        Some("off" | "disable" | "false") => {
+            database.set_avatar_tracking(message.author.id, false)
+                .await?;
+            message.reply(ctx, "Your avatars are no longer tracked, and those tracked so far have been forgotten")
+                .await?;
+
+            return Ok(());
+        },
+        _ => (),
+    }
+
+    let user = match argument {
+        Some(arg) => User::convert(ctx, message.guild_id, Some(message.channel_id), arg.as_str())
+            .await?,
+        None => message.author.clone(),
+    };
+
+    let history = database.avatar_history(user.id)
+        .await?;
+    if history.is_empty() {
+        let tracked = database.is_avatar_tracked(user.id)
+            .await?;
+
+        return Err(
+            if tracked {
+                format!("No avatars have been recorded for {} yet", user.tag())
+            } else {
+                format!("{} has not opted in to having their avatars tracked with `avatarhistory on`", user.tag())
+            }
+                .into()
+        );
+    }
+
+    let options = GridOptions {
+        labels: history.iter()
+            .map(AvatarEntry::date)
+            .collect(),
+        images: history.into_iter()
+            .map(|entry| entry.thumbnail)
+            .collect(),
+        columns: flags.get_or("columns", 0_u32)?
+            .min(AVATAR_HISTORY),
+    };
+    #[allow(clippy::cast_possible_truncation)]
+    let count = options.images.len() as u32;
+
+    ImageExecutor::new(ctx, message)
+        .function(grid_func)
+        .arguments(vec![options])
+        .note(format!("**Avatar history of** `{}`", user.tag()))
+        .generate(GRID_CELL_SIZE * count, GRID_CELL_SIZE)
+        .await
+}
+
+#[command]
+#[usage("<command> <on|off>")]
+async fn toggle(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let name = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("command"))?;
+    let command = find_command(name.as_str())
+        .ok_or_else(|| format!("`{name}` is not an imaging command"))?;
+
+    let enabled = match resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("on|off"))?
+        .to_lowercase()
+        .as_str()
+    {
+        "on" | "enable" | "true" => true,
+        "off" | "disable" | "false" => false,
+        other => return Err(format!("Expected either `on` or `off`, got `{other}`").into()),
+    };
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    Database::get(ctx)
+        .await?
+        .set_command_enabled(guild_id, command, enabled)
+        .await?;
+
+    message.reply(ctx,
+        format!("`{command}` has been {} in this server", if enabled { "enabled" } else { "disabled" })
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<command> <role|none>")]
+async fn restrict(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let name = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("command"))?;
+    let command = find_command(name.as_str())
+        .ok_or_else(|| format!("`{name}` is not an imaging command"))?;
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    let role = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("role"))?;
+
+    let role = if ["none", "off", "everyone"].contains(&role.to_lowercase().as_str()) {
+        None
+    } else {
+        Some(
+            Role::convert(ctx, Some(guild_id), Some(message.channel_id), role.as_str())
+                .await?
+        )
+    };
+
+    Database::get(ctx)
+        .await?
+        .set_command_restriction(guild_id, command, role.as_ref().map(|role| role.id))
+        .await?;
+
+    let content = role.map_or_else(
+        || format!("`{command}` is no longer restricted"),
+        |role| format!("`{command}` has been restricted to members with the `{}` role", role.name),
+    );
+
+    message.reply(ctx, content)
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<user> <on|off>")]
+async fn premium(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let user = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("user"))?;
+    let user = User::convert(ctx, message.guild_id, Some(message.channel_id), user.as_str())
+        .await?;
+
+    let premium = match resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("on|off"))?
+        .to_lowercase()
+        .as_str()
+    {
+        "on" | "grant" | "true" => true,
+        "off" | "revoke" | "false" => false,
+        other => return Err(format!("Expected either `on` or `off`, got `{other}`").into()),
+    };
+
+    Database::get(ctx)
+        .await?
+        .set_premium(user.id, premium)
+        .await?;
+    Tier::forget(user.id);
+
+    message.reply(ctx,
+        format!("The premium tier has been {} `{}`",
+            if premium { "granted to" } else { "revoked from" },
+            user.tag(),
+        )
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<function> [iterations]")]
+async fn bench(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let name = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("function"))?
+        .to_lowercase();
+    let iterations = resolve_arg(&mut args)
+        .map(|iterations| iterations.parse::<usize>())
+        .transpose()?
+        .unwrap_or(10);
+
+    let report = {
+        let name = name.clone();
+
+        // benchmarks are given the highest cost so that they never hold up the jobs of users
+        run_job(ctx, u64::MAX, move || bench::run(name.as_str(), iterations))
+            .await??
+            .ok_or_else(|| format!("There is no function named `{name}` that can be benchmarked"))?
+    };
+
+    message.reply(ctx,
+        format!("**Function:** `{name}` over `{}` iteration(s)\n**Mean:** `{:.2} ms`\n**p95:** `{:.2} ms`\n**Peak Allocated:** `{}` per iteration",
+            report.iterations,
+            report.mean.as_secs_f64() * 1000.0,
+            report.p95.as_secs_f64() * 1000.0,
+            humanize_bytes(report.peak_allocated),
+        )
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("reloadconfig")]
+async fn reload(ctx: &Context, message: &Message) -> CommandResult {
+    reload_config(&ctx.data)
+        .await
+        .map_err(|err| format!("Failed to reload the configuration: {err}"))?;
+
+    message.reply(ctx,
+        "Reloaded the configuration, though the database, worker threads and `slash_only` only apply after a restart"
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<seconds> [uses]")]
+async fn cooldown(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let seconds = args.single::<f64>()
+        .map_err(|_| Error::MissingArgument("seconds"))?;
+    let limit = args.single::<u32>()
+        .unwrap_or(1);
+
+    if !(0.0..=cooldowns::MAX_DELAY.as_secs_f64()).contains(&seconds) || limit == 0 {
+        return Err(format!(
+            "The delay must be between `0` and `{}` seconds and at least `1` use must be allowed",
+            cooldowns::MAX_DELAY.as_secs(),
+        ).into());
+    }
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    Database::get(ctx)
+        .await?
+        .set_cooldown(guild_id, Cooldown {
+            delay: Duration::from_secs_f64(seconds),
+            limit,
+        })
+        .await?;
+
+    message.reply(ctx,
+        format!("Imaging commands can now be used `{limit}` time(s) every `{seconds}s` in this server")
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<on|off>")]
+async fn reposts(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let enabled = match resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("on|off"))?
+        .to_lowercase()
+        .as_str()
+    {
+        "on" | "enable" | "true" => true,
+        "off" | "disable" | "false" => false,
+        other => return Err(format!("Expected either `on` or `off`, got `{other}`").into()),
+    };
+
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    Database::get(ctx)
+        .await?
+        .set_repost_detection(guild_id, enabled)
+        .await?;
+
+    message.reply(ctx,
+        format!("Repost detection has been {} in this server", if enabled { "enabled" } else { "disabled" })
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("<channel|off> [background]")]
+async fn welcome(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    let channel = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("channel|off"))?;
+    let database = Database::get(ctx)
+        .await?;
+
+    if ["off", "disable", "none"].contains(&channel.to_lowercase().as_str()) {
+        database.set_welcome(guild_id, None, None)
+            .await?;
+        message.reply(ctx, "Welcome cards have been disabled in this server")
+            .await?;
+
+        return Ok(());
+    }
+
+    let channel = GuildChannel::convert(ctx, Some(guild_id), Some(message.channel_id), channel.as_str())
+        .await?;
+    let background = resolve_arg(&mut args);
+    if let Some(name) = &background {
+        if welcome::background(name).is_none() {
+            return Err(format!("There is no welcome background named `{name}`").into());
+        }
+    }
+
+    database.set_welcome(guild_id, Some(channel.id), background.as_deref())
+        .await?;
+
+    message.reply(ctx, format!("Welcome cards will be posted in {}", channel.mention()))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("iotd", "imageoftheday")]
+#[usage("[<channel> [HH:MM] [image url or attachment] | off | now]")]
+async fn daily(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+    let database = Database::get(ctx)
+        .await?;
+
+    let Some(channel) = resolve_arg(&mut args) else {
+        let reply = match database.daily_image(guild_id).await? {
+            Some(config) => format!(
+                "The image of the day is posted in {} at `{}` UTC, using {}",
+                config.channel_id.mention(),
+                daily::format_time(config.minute),
+                config.image_url.as_deref().map_or_else(|| String::from("the server icon"), |url| format!("<{url}>")),
+            ),
+            None => String::from("The image of the day is disabled in this server"),
+        };
+        message.reply(ctx, reply)
+            .await?;
+
+        return Ok(());
+    };
+
+    match channel.to_lowercase().as_str() {
+        "off" | "disable" | "none" => {
+            database.set_daily(guild_id, None, 0, None, None)
+                .await?;
+            message.reply(ctx, "The image of the day has been disabled in this server")
+                .await?;
+
+            return Ok(());
+        },
+        "now" | "preview" => {
+            let config = database.daily_image(guild_id)
+                .await?
+                .ok_or("The image of the day is not set up in this server")?;
+            let (day, _) = daily::now();
+
+            daily::post(ctx, &config, day)
+                .await?;
+
+            return Ok(());
+        },
+        _ => (),
+    }
+
+    let channel = GuildChannel::convert(ctx, Some(guild_id), Some(message.channel_id), channel.as_str())
+        .await?;
+
+    let time = resolve_arg(&mut args);
+    let minute = match time.as_deref() {
+        Some(time) => daily::parse_time(time)
+            .ok_or_else(|| format!("`{time}` is not a valid time, try a 24 hour UTC time such as `18:30`"))?,
+        None => daily::DEFAULT_MINUTE,
+    };
+
+    // an attached image is kept by reference to its message as well, as the url of an attachment expires
+    let (image_url, source) = match resolve_arg(&mut args) {
+        Some(url) => (Some(url), None),
+        None => message.attachments
+            .iter()
+            .find(|attachment| attachment.content_type.as_deref().map_or(false, |kind| kind.starts_with("image/")))
+            .map_or((None, None), |attachment| (Some(attachment.url.clone()), Some(AttachmentRef::new(message, attachment)))),
+    };
+    if let Some(url) = &image_url {
+        if !url.starts_with("https://") && !url.starts_with("http://") {
+            return Err(format!("`{url}` is not a link to an image").into());
+        }
+        // fetched once beforehand, so that a broken link is caught now rather than when it is due
+        ImageResolver::new()
+            .resolve(ctx, message, Some(url.clone()))
+            .await?;
+    } else if ctx.cache.guild_field(guild_id, |guild| guild.icon.is_none()).unwrap_or(true) {
+        return Err("This server has no icon, so the image of the day needs a link to an image to use instead".into());
+    }
+
+    database.set_daily(guild_id, Some(channel.id), minute, image_url.as_deref(), source)
+        .await?;
+
+    message.reply(ctx, format!(
+        "The image of the day will be posted in {} at `{}` UTC",
+        channel.mention(),
+        daily::format_time(minute),
+    ))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("trustrole")]
+#[usage("<role> <factor|off>")]
+async fn cooldownrole(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+
+    let role = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("role"))?;
+    let role = Role::convert(ctx, Some(guild_id), Some(message.channel_id), role.as_str())
+        .await?;
+
+    let value = resolve_arg(&mut args)
+        .ok_or(Error::MissingArgument("factor|off"))?;
+    let factor = if ["off", "none", "reset"].contains(&value.to_lowercase().as_str()) {
+        None
+    } else {
+        let factor = value.trim_end_matches('x')
+            .parse::<f64>()
+            .ok()
+            .filter(|factor| (0.0..=1.0).contains(factor))
+            .ok_or("The factor must be between `0` (no cooldown) and `1` (the full cooldown)")?;
+
+        Some(factor)
+    };
+
+    Database::get(ctx)
+        .await?
+        .set_cooldown_role(guild_id, role.id, factor)
+        .await?;
+
+    let content = match factor {
+        None => format!("Members with the `{}` role are now subject to the usual cooldown", role.name),
+        Some(factor) if factor <= 0.0 => format!("Members with the `{}` role now skip the cooldown", role.name),
+        Some(factor) => format!("Members with the `{}` role now have their cooldown scaled by `{factor}`", role.name),
+    };
+
+    message.reply(ctx, content)
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[usage("[frames|dimension|format] [value|reset]")]
+async fn limits(ctx: &Context, message: &Message, mut args: Args) -> CommandResult {
+    let guild_id = message.guild_id
+        .ok_or("This command can only be used in a server")?;
+    let database = Database::get(ctx)
+        .await?;
+    let mut overrides = database.guild_limits(guild_id)
+        .await?;
+    // overrides can never raise the limits of a tier, so those of the highest tier bound them
+    let bounds = Tier::Premium.limits();
+
+    if let Some(setting) = resolve_arg(&mut args) {
+        let value = resolve_arg(&mut args)
+            .ok_or(Error::MissingArgument("value"))?;
+        let reset = value.eq_ignore_ascii_case("reset");
+
+        match setting.to_lowercase().as_str() {
+            "frames" | "max_frames" => overrides.max_frames = if reset {
+                None
+            } else {
+                Some(
+                    value.parse::<usize>()
+                        .map_err(|_| Error::InvalidFlag(setting.clone(), value.clone()))?
+                        .clamp(1, bounds.max_frames)
+                )
+            },
+            "dimension" | "max_dimension" | "dim" => overrides.max_dim = if reset {
+                None
+            } else {
+                Some(
+                    value.parse::<u32>()
+                        .map_err(|_| Error::InvalidFlag(setting.clone(), value.clone()))?
+                        .clamp(16, bounds.max_dim)
+                )
+            },
+            "format" => overrides.format = if reset {
+                None
+            } else {
+                Some(
+                    // a static default would break every animated output, so only animated formats may be set
+                    value.parse::<OutputFormat>()
+                        .ok()
+                        .filter(|format| !format.is_static())
+                        .ok_or_else(|| Error::InvalidFlag(setting.clone(), value.clone()))?
+                )
+            },
+            other => return Err(format!("Unknown setting `{other}`, expected one of `frames`, `dimension` or `format`").into()),
+        }
+
+        database.set_guild_limits(guild_id, overrides)
+            .await?;
+    }
+
+    let describe = |value: Option<String>| value.map_or_else(|| String::from("default"), |value| format!("`{value}`"));
+
+    message.reply(ctx,
+        format!("**Max Frames:** {}\n**Max Dimension:** {}\n**Output Format:** {}",
+            describe(overrides.max_frames.map(|max_frames| max_frames.to_string())),
+            describe(overrides.max_dim.map(|max_dim| format!("{max_dim}px"))),
+            describe(overrides.format.map(|format| format.as_str().to_string())),
+        )
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("stop")]
+async fn cancel(ctx: &Context, message: &Message) -> CommandResult {
+    let cancelled = JobTracker::get(ctx)
+        .await
+        .map_or(0, |tracker| tracker.cancel_all(message.author.id));
+
+    message.reply(ctx,
+        if cancelled == 0 {
+            String::from("You have no running jobs")
+        } else {
+            format!("Cancelled `{cancelled}` job(s)")
+        }
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("jobs")]
+async fn queue(ctx: &Context, message: &Message) -> CommandResult {
+    let pool = ctx.data.read()
+        .await
+        .get::<PoolData>()
+        .cloned()
+        .ok_or("The worker pool is unavailable")?;
+    let stats = pool.stats();
+    let jobs = JobTracker::get(ctx)
+        .await
+        .map(|tracker| tracker.jobs_of(message.author.id))
+        .unwrap_or_default();
+    let average = METRICS.average_duration();
+
+    let mut content = format!(
+        "**Global:** `{}` running and `{}` waiting on `{}` worker(s)\n",
+        stats.running,
+        stats.waiting.len(),
+        stats.workers,
+    );
+
+    if let Some((average, count)) = average {
+        content.push_str(
+            format!("**Average Job Time:** `{} ms` over the last `{count}` job(s)\n", average.as_millis())
+                .as_str()
+        );
+    }
+
+    if jobs.is_empty() {
+        content.push_str("You have no running jobs");
+    }
+
+    for (i, job) in jobs.iter().enumerate() {
+        if job.running {
+            content.push_str(format!("\n**Job {}:** running", i + 1).as_str());
+            continue;
+        }
+
+        // the queue runs cheaper jobs first, so only those are ahead of this one
+        let position = stats.waiting.iter()
+            .filter(|cost| **cost < job.cost)
+            .count() + 1;
+        let wait = average.map_or_else(
+            || String::from("unknown"),
+            |(average, _)| format!("~{} s",
+                (average * u32::try_from(position.div_ceil(stats.workers)).unwrap_or(u32::MAX))
+                    .as_secs()
+                    .max(1)
+            ),
+        );
+
+        content.push_str(format!("\n**Job {}:** waiting at position `{position}`, estimated wait `{wait}`", i + 1).as_str());
+    }
+
+    message.reply(ctx, content)
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("quota", "mytier")]
+async fn mylimits(ctx: &Context, message: &Message) -> CommandResult {
+    let tier = Tier::lookup(ctx, &message.author)
+        .await;
+    let base = tier.limits();
+    let overrides = GuildLimits::lookup(ctx, message.guild_id)
+        .await;
+    let limits = overrides.apply(base);
+
+    // notes when the server lowered a limit below that of the tier, as that is the usual reason for a rejected image
+    let lowered = |lowered: bool, tier_value: String| if lowered {
+        format!(" (lowered by this server from `{tier_value}`)")
+    } else {
+        String::new()
+    };
+
+    let mut content = format!(
+        "**Tier:** `{}`\n**Max Image Size:** `{}`\n**Max Frames:** `{}`{}\n**Max Dimension:** `{}px`{}\n",
+        if tier == Tier::Premium { "Premium" } else { "Free" },
+        humanize_bytes(limits.max_size),
+        limits.max_frames,
+        lowered(limits.max_frames < base.max_frames, base.max_frames.to_string()),
+        limits.max_dim,
+        lowered(limits.max_dim < base.max_dim, format!("{}px", base.max_dim)),
+    );
+
+    match effective_cooldown(ctx, &message.author, tier, message.guild_id).await {
+        Some(cooldown) => content.push_str(
+            format!("**Cooldown:** `{}` command(s) every `{:.1}s`\n", cooldown.limit, cooldown.delay.as_secs_f64())
+                .as_str()
+        ),
+        None => content.push_str("**Cooldown:** exempt\n"),
+    }
+
+    content.push_str(
+        format!(
+            "**Output:** `{}` by default, up to `{MAX_ATTACHMENTS}` files per message or a `{}` archive",
+            overrides.format.map_or("auto", |format| format.as_str()),
+            humanize_bytes(MAX_ARCHIVE_SIZE as u64),
+        )
+            .as_str()
+    );
+
+    if tier == Tier::Free {
+        content.push_str("\n\nPremium doubles the max image size, frames and dimension, and lifts the cooldown");
+    }
+
+    message.reply(ctx, content)
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("latency")]
+async fn ping(ctx: &Context, message: &Message) -> CommandResult {
+    let shard_manager = ctx.data.read()
+        .await
+        .get::<ShardManagerData>()
+        .cloned();
+
+    let gateway = match shard_manager {
+        Some(manager) => manager.lock()
+            .await
+            .runners
+            .lock()
+            .await
+            .get(&ShardId(ctx.shard_id))
+            .and_then(|runner| runner.latency),
+        None => None,
+    };
+
+    let instant = Instant::now();
+    let mut reply = message.reply(ctx, "Pinging...")
+        .await?;
+    let rest = instant.elapsed();
+
+    reply.edit(ctx, |msg| msg.content(
+        format!("🏓 **Gateway:** `{}` | **REST:** `{} ms`",
+            gateway.map_or_else(|| String::from("unknown"), |latency| format!("{} ms", latency.as_millis())),
+            rest.as_millis(),
+        )
+    ))
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+async fn uptime(ctx: &Context, message: &Message) -> CommandResult {
+    let started = ctx.data.read()
+        .await
+        .get::<StartData>()
+        .copied()
+        .ok_or("The start time of the bot is unknown")?;
+
+    let memory = process_memory("VmRSS")
+        .map_or_else(|| String::from("unknown"), humanize_bytes);
+
+    message.reply(ctx,
+        format!("**Uptime:** `{}`\n**Memory Usage:** `{memory}`", humanize_duration(started.elapsed()))
+    )
+        .await?;
+
+    Ok(())
+}
+
+#[command]
+#[aliases("info", "version")]
+async fn about(ctx: &Context, message: &Message) -> CommandResult {
+    message.channel_id.send_message(ctx, |msg|
+        msg.reference_message(message)
+            .allowed_mentions(|am| am.empty_parse())
+            .embed(|embed|
+                embed.title(env!("CARGO_PKG_NAME"))
+                    .description(env!("CARGO_PKG_DESCRIPTION"))
+                    .url(env!("CARGO_PKG_REPOSITORY"))
+                    .field("Version", format!("`{}`", env!("CARGO_PKG_VERSION")), true)
+                    .field("Build", format!("`{}`", if cfg!(debug_assertions) { "debug" } else { "release" }), true)
+                    .field("Authors", env!("CARGO_PKG_AUTHORS").replace(':', ", "), true)
+                    .field("Effects", format!("`{}`", registry::EFFECTS.len()), true)
+                    .field("Guilds", format!("`{}`", ctx.cache.guild_count()), true)
+                    .colour(Colour::BLURPLE)
+            )
+    )
+        .await?;
+
+    Ok(())
+}
+
+/// the permissions the bot requires for all of its commands to work
+const INVITE_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::SEND_MESSAGES)
+    .union(Permissions::EMBED_LINKS)
+    .union(Permissions::ATTACH_FILES)
+    .union(Permissions::READ_MESSAGE_HISTORY)
+    .union(Permissions::USE_EXTERNAL_EMOJIS)
+    .union(Permissions::MANAGE_EMOJIS_AND_STICKERS);
+
+#[command]
+async fn invite(ctx: &Context, message: &Message) -> CommandResult {
+    let url = ctx.cache.current_user()
+        .invite_url_with_oauth2_scopes(ctx, INVITE_PERMISSIONS, &[OAuth2Scope::Bot, OAuth2Scope::ApplicationsCommands])
+        .await?;
+
+    message.reply(ctx, format!("Invite me with this link: <{url}>"))
+        .await?;
+
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,179 @@
+//! contains the [`AbuseMonitor`], which detects users rapidly cycling commands across channels
+//! or repeatedly feeding in oversized inputs, and temporarily escalates their cooldowns,
+//! independently of the static cooldown of [`super::cooldowns::CooldownManager`]
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use serenity::model::id::{ChannelId, UserId};
+
+
+/// the window within which commands are counted towards cycling across channels
+pub const CYCLE_WINDOW: Duration = Duration::from_secs(30);
+/// the amount of commands within [`CYCLE_WINDOW`] that count as cycling, if spread across enough channels
+pub const CYCLE_COMMANDS: usize = 8;
+/// the amount of distinct channels the commands within [`CYCLE_WINDOW`] must be spread across to count as cycling
+pub const CYCLE_CHANNELS: usize = 3;
+/// the window within which oversized inputs are counted
+pub const OVERSIZED_WINDOW: Duration = Duration::from_secs(120);
+/// the amount of oversized inputs within [`OVERSIZED_WINDOW`] that escalate the cooldown
+pub const OVERSIZED_LIMIT: usize = 3;
+/// the cooldown of the first level of escalation, doubled for each level after
+pub const PENALTY_BASE: Duration = Duration::from_secs(10);
+/// the highest level of escalation
+pub const MAX_LEVEL: u32 = 5;
+/// how long it takes for a single level of escalation to decay
+pub const DECAY: Duration = Duration::from_secs(300);
+/// how often users with nothing left tracked are forgotten, see [`AbuseMonitor::sweep`]
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// what is tracked of a single user
+#[derive(Debug, Default)]
+struct Record {
+    /// when and where the user used a command, oldest first
+    uses: VecDeque<(Instant, ChannelId)>,
+    /// when the user last used a command, kept even once it has fallen out of the window
+    last_use: Option<Instant>,
+    /// when the user fed in an oversized input, oldest first
+    oversized: VecDeque<Instant>,
+    /// the level of escalation as of `escalated`
+    level: u32,
+    /// when the user was last escalated
+    escalated: Option<Instant>,
+}
+
+impl Record {
+    /// the level of escalation at `now`, having decayed by a level for every [`DECAY`] since it was last escalated
+    #[allow(clippy::cast_possible_truncation)]
+    fn level(&self, now: Instant) -> u32 {
+        self.escalated.map_or(0, |escalated| {
+            let decayed = now.duration_since(escalated).as_secs() / DECAY.as_secs();
+
+            self.level.saturating_sub(decayed.min(u64::from(MAX_LEVEL)) as u32)
+        })
+    }
+
+    /// raises the level of escalation by one, starting the decay over
+    fn escalate(&mut self, now: Instant) {
+        self.level = (self.level(now) + 1).min(MAX_LEVEL);
+        self.escalated = Some(now);
+    }
+
+    /// drops the uses and oversized inputs that have fallen out of their windows
+    fn prune(&mut self, now: Instant) {
+        while self.uses.front()
+            .map_or(false, |(used, _)| now.duration_since(*used) >= CYCLE_WINDOW)
+        {
+            self.uses.pop_front();
+        }
+
+        while self.oversized.front()
+            .map_or(false, |fed| now.duration_since(*fed) >= OVERSIZED_WINDOW)
+        {
+            self.oversized.pop_front();
+        }
+    }
+}
+
+/// tracks the behaviour of each user to escalate the cooldowns of those abusing the bot
+#[derive(Debug, Default)]
+pub struct AbuseMonitor {
+    /// maps each user to what is tracked of them
+    records: Mutex<HashMap<UserId, Record>>,
+}
+
+impl AbuseMonitor {
+    /// creates a new, empty monitor
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// records a use of a command by the provided user in the provided channel,
+    /// escalating them if they are cycling commands across channels,
+    /// and returning `Err` with the time remaining if they are still serving the penalty of their escalation
+    ///
+    /// a use refused by the penalty is not recorded
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn hit(&self, user_id: UserId, channel_id: ChannelId) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut records = self.records.lock()
+            .unwrap();
+        let record = records.entry(user_id)
+            .or_default();
+        record.prune(now);
+
+        let level = record.level(now);
+        if level > 0 {
+            let penalty = PENALTY_BASE * 2_u32.pow(level - 1);
+            if let Some(remaining) = record.last_use.map(|last| penalty.saturating_sub(now.duration_since(last)))
+                .filter(|remaining| !remaining.is_zero())
+            {
+                return Err(remaining);
+            }
+        }
+
+        record.uses.push_back((now, channel_id));
+        record.last_use = Some(now);
+
+        let channels = record.uses.iter()
+            .map(|(_, channel_id)| *channel_id)
+            .collect::<HashSet<_>>();
+
+        if record.uses.len() >= CYCLE_COMMANDS && channels.len() >= CYCLE_CHANNELS {
+            record.escalate(now);
+            record.uses.clear();
+        }
+
+        Ok(())
+    }
+
+    /// records that the provided user fed in an oversized input, escalating them if they keep doing so
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn oversized(&self, user_id: UserId) {
+        let now = Instant::now();
+        let mut records = self.records.lock()
+            .unwrap();
+        let record = records.entry(user_id)
+            .or_default();
+        record.prune(now);
+
+        record.oversized.push_back(now);
+        if record.oversized.len() >= OVERSIZED_LIMIT {
+            record.escalate(now);
+            record.oversized.clear();
+        }
+    }
+
+    /// the current level of escalation of the provided user, from `0` to [`MAX_LEVEL`]
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn level(&self, user_id: UserId) -> u32 {
+        self.records.lock()
+            .unwrap()
+            .get(&user_id)
+            .map_or(0, |record| record.level(Instant::now()))
+    }
+
+    /// forgets the users who have nothing left tracked and have fully decayed, to keep the map from growing forever
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn sweep(&self) {
+        let now = Instant::now();
+
+        self.records.lock()
+            .unwrap()
+            .retain(|_, record| {
+                record.prune(now);
+                !record.uses.is_empty() || !record.oversized.is_empty() || record.level(now) > 0
+            });
+    }
+}
\ No newline at end of file
@@ -0,0 +1,194 @@
+//! contains the [`AssetManager`], which loads the files in [`ASSETS_DIR`] such as fonts, templates and overlays,
+//! reloading them whenever they change on disk so that they can be updated without restarting the bot
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use super::text::{FontChain, PRIMARY_FONT, FALLBACK_FONT_DIR};
+
+
+/// the directory all assets are loaded from
+pub const ASSETS_DIR: &str = "./assets";
+/// how often the assets are checked for changes
+pub const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+lazy_static::lazy_static! {
+    /// the assets used by the running bot
+    pub static ref ASSETS: AssetManager = AssetManager::default();
+}
+
+
+/// the modification times of a set of files, used to tell when any of them have changed
+type Fingerprint = Vec<(PathBuf, Option<SystemTime>)>;
+
+/// a loaded asset, along with the fingerprint of the files it was loaded from
+struct Loaded<T> {
+    /// the loaded asset
+    value: Arc<T>,
+    /// the fingerprint of the files it was loaded from when it was loaded
+    fingerprint: Fingerprint,
+}
+
+/// loads assets lazily, on their first use, and caches them until the files they were loaded from change
+#[derive(Default)]
+pub struct AssetManager {
+    /// the font chain used by all text rendering, loaded from [`PRIMARY_FONT`] and [`FALLBACK_FONT_DIR`]
+    fonts: RwLock<Option<Loaded<FontChain>>>,
+    /// the raw bytes of every other asset that has been used, keyed by its path relative to [`ASSETS_DIR`]
+    files: RwLock<HashMap<PathBuf, Loaded<Vec<u8>>>>,
+}
+
+/// returns the modification time of the file at `path`, or `None` if it does not exist
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// returns the fingerprint of the files the font chain is loaded from
+fn fonts_fingerprint() -> Fingerprint {
+    let mut paths = vec![PathBuf::from(PRIMARY_FONT)];
+
+    if let Ok(entries) = fs::read_dir(FALLBACK_FONT_DIR) {
+        let mut fallbacks = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .collect::<Vec<_>>();
+        fallbacks.sort();
+
+        paths.extend(fallbacks);
+    }
+
+    paths.into_iter()
+        .map(|path| {
+            let modified = modified(&path);
+            (path, modified)
+        })
+        .collect()
+}
+
+impl AssetManager {
+    /// returns the font chain, loading it if it has not been loaded yet,
+    /// or `None` if it could not be loaded, such as if the primary font is missing
+    ///
+    /// # Panics
+    /// if the inner lock is poisoned
+    pub fn fonts(&self) -> Option<Arc<FontChain>> {
+        if let Some(loaded) = self.fonts.read().unwrap().as_ref() {
+            return Some(loaded.value.clone());
+        }
+
+        self.reload_fonts(fonts_fingerprint())
+    }
+
+    /// returns the raw bytes of the asset at `path`, relative to [`ASSETS_DIR`],
+    /// reading it if it has not been read yet, or `None` if it could not be read
+    ///
+    /// # Panics
+    /// if the inner lock is poisoned
+    pub fn get<P: AsRef<Path>>(&self, path: P) -> Option<Arc<Vec<u8>>> {
+        let path = path.as_ref();
+
+        if let Some(loaded) = self.files.read().unwrap().get(path) {
+            return Some(loaded.value.clone());
+        }
+
+        self.reload_file(path)
+    }
+
+    /// loads the font chain, replacing the cached one if successful
+    fn reload_fonts(&self, fingerprint: Fingerprint) -> Option<Arc<FontChain>> {
+        match FontChain::load(PRIMARY_FONT, FALLBACK_FONT_DIR) {
+            Ok(fonts) => {
+                let value = Arc::new(fonts);
+
+                *self.fonts.write().unwrap() = Some(Loaded { value: value.clone(), fingerprint });
+                Some(value)
+            },
+            Err(err) => {
+                tracing::warn!("failed to load the fonts: {err}");
+                None
+            },
+        }
+    }
+
+    /// reads the asset at `path`, replacing the cached bytes if successful
+    fn reload_file(&self, path: &Path) -> Option<Arc<Vec<u8>>> {
+        let full_path = Path::new(ASSETS_DIR).join(path);
+        let fingerprint = vec![(full_path.clone(), modified(&full_path))];
+
+        match fs::read(&full_path) {
+            Ok(bytes) => {
+                let value = Arc::new(bytes);
+
+                self.files.write()
+                    .unwrap()
+                    .insert(path.to_path_buf(), Loaded { value: value.clone(), fingerprint });
+                Some(value)
+            },
+            Err(err) => {
+                tracing::warn!("failed to read the asset `{}`: {err}", full_path.display());
+                None
+            },
+        }
+    }
+
+    /// reloads every loaded asset whose files have changed since it was loaded, returning how many were reloaded
+    ///
+    /// assets that fail to reload keep their previously loaded value
+    ///
+    /// # Panics
+    /// if the inner lock is poisoned
+    pub fn refresh(&self) -> usize {
+        let mut reloaded = 0;
+
+        let fingerprint = fonts_fingerprint();
+        let fonts_changed = self.fonts.read()
+            .unwrap()
+            .as_ref()
+            .map_or(false, |loaded| loaded.fingerprint != fingerprint);
+
+        if fonts_changed && self.reload_fonts(fingerprint).is_some() {
+            tracing::info!("reloaded the fonts");
+            reloaded += 1;
+        }
+
+        let changed = self.files.read()
+            .unwrap()
+            .iter()
+            .filter(|(_, loaded)|
+                loaded.fingerprint
+                    .iter()
+                    .any(|(path, modified_at)| modified(path) != *modified_at)
+            )
+            .map(|(path, _)| path.clone())
+            .collect::<Vec<_>>();
+
+        for path in changed {
+            if self.reload_file(&path).is_some() {
+                tracing::info!("reloaded the asset `{}`", path.display());
+                reloaded += 1;
+            }
+        }
+
+        reloaded
+    }
+
+    /// checks the assets for changes every [`WATCH_INTERVAL`], reloading those that have changed, forever
+    pub async fn watch(&'static self) {
+        let mut interval = tokio::time::interval(WATCH_INTERVAL);
+
+        loop {
+            interval.tick()
+                .await;
+
+            tokio::task::spawn_blocking(|| self.refresh())
+                .await
+                .ok();
+        }
+    }
+}
\ No newline at end of file
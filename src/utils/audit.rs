@@ -0,0 +1,123 @@
+//! contains the audit log, which summarizes every image job into a channel or webhook configured by the operator,
+//! giving visibility into how the bot is used without having to search through its output
+
+use std::{sync::Mutex, time::Duration};
+
+use serenity::{
+    builder::CreateEmbed,
+    prelude::Context,
+    model::{
+        channel::Embed,
+        id::{ChannelId, GuildId, UserId},
+        webhook::Webhook,
+    },
+    utils::Colour,
+};
+
+use crate::ConfigData;
+use super::{
+    Error,
+    helpers::humanize_bytes,
+};
+
+
+lazy_static::lazy_static! {
+    /// the audit webhook along with the url it was fetched from, so that it is only fetched again once the url is changed
+    static ref WEBHOOK: Mutex<Option<(String, Webhook)>> = Mutex::new(None);
+}
+
+/// a summary of a single image job, posted to the audit log by [`log`]
+#[derive(Debug, Clone)]
+pub struct JobSummary {
+    /// the id of the user who invoked the command
+    pub user_id: UserId,
+    /// the tag of the user who invoked the command
+    pub user_tag: String,
+    /// the guild the command was invoked in, or `None` in direct messages
+    pub guild_id: Option<GuildId>,
+    /// the name of the command
+    pub command: String,
+    /// whether the command was invoked as a slash command rather than with the prefix
+    pub slash: bool,
+    /// the mime type of the first source image, or `None` for generators
+    pub source: Option<&'static str>,
+    /// how long the job took, from the invocation of the command until it finished
+    pub duration: Duration,
+    /// the total size of the outputs sent, in bytes, or `None` if none were sent
+    pub output_size: Option<u64>,
+    /// the error the job failed with, if any
+    pub error: Option<String>,
+}
+
+/// posts the provided summary to the audit channel or webhook of the configuration, doing nothing if neither is set
+///
+/// # Errors
+/// if the summary could not be posted
+pub async fn log(ctx: &Context, summary: JobSummary) -> Result<(), Error> {
+    let Some(config) = ctx.data.read()
+        .await
+        .get::<ConfigData>()
+        .cloned()
+    else {
+        return Ok(());
+    };
+
+    if let Some(url) = &config.audit_webhook_url {
+        let webhook = webhook(ctx, url)
+            .await?;
+
+        webhook.execute(&ctx.http, false, |execute|
+            execute.embeds(vec![Embed::fake(|embed| describe(embed, &summary))])
+                .allowed_mentions(|am| am.empty_parse())
+        )
+            .await?;
+    } else if let Some(channel_id) = config.audit_channel_id {
+        ChannelId(channel_id).send_message(ctx, |msg|
+            msg.embed(|embed| describe(embed, &summary))
+                .allowed_mentions(|am| am.empty_parse())
+        )
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// returns the webhook at the provided url, only fetching it if it has not been fetched from that url before
+///
+/// # Panics
+/// if the inner mutex is poisoned
+async fn webhook(ctx: &Context, url: &str) -> Result<Webhook, Error> {
+    let cached = WEBHOOK.lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(cached_url, _)| cached_url == url)
+        .map(|(_, webhook)| webhook.clone());
+
+    if let Some(webhook) = cached {
+        return Ok(webhook);
+    }
+
+    let webhook = ctx.http.get_webhook_from_url(url)
+        .await?;
+    *WEBHOOK.lock()
+        .unwrap() = Some((url.to_string(), webhook.clone()));
+
+    Ok(webhook)
+}
+
+/// fills the provided embed with the summary, red if the job failed and green otherwise
+fn describe<'a>(embed: &'a mut CreateEmbed, summary: &JobSummary) -> &'a mut CreateEmbed {
+    embed.title(format!("{}{}", if summary.slash { "/" } else { "r!" }, summary.command))
+        .colour(if summary.error.is_some() { Colour::RED } else { Colour::DARK_GREEN })
+        .field("User", format!("{} (`{}`)", summary.user_tag, summary.user_id), true)
+        .field("Guild", summary.guild_id.map_or_else(|| String::from("Direct Messages"), |id| format!("`{id}`")), true)
+        .field("Source", summary.source.unwrap_or("None"), true)
+        .field("Duration", format!("`{} ms`", summary.duration.as_millis()), true)
+        .field("Output", summary.output_size.map_or_else(|| String::from("None"), humanize_bytes), true);
+
+    if let Some(error) = &summary.error {
+        embed.field("Error", error, false);
+    }
+
+    embed
+}
\ No newline at end of file
@@ -0,0 +1,121 @@
+//! contains the avatar tracker, which records a thumbnail of each new avatar of the users that opted in to it
+//! as their members are updated, to be composited into a collage of their past avatars by [`grid_func`]
+//!
+//! [`grid_func`]: super::functions::grid_func
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serenity::{
+    prelude::Context,
+    model::{guild::Member, user::User},
+};
+
+use crate::ClientData;
+use super::{
+    Error,
+    database::Database,
+    helpers::{avatar_url, url_to_bytes},
+};
+
+
+/// the most avatars kept for each user, the oldest being forgotten first
+pub const AVATAR_HISTORY: u32 = 24;
+/// the width and height of the thumbnails recorded of each avatar
+const THUMBNAIL_SIZE: u16 = 128;
+/// the number of seconds in a day, as dates are counted in days since the unix epoch in utc
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// an avatar recorded for a user
+#[derive(Debug, Clone)]
+pub struct AvatarEntry {
+    /// the hash of the avatar, as found in its CDN url
+    pub hash: String,
+    /// the thumbnail of the avatar, as an encoded `png`
+    pub thumbnail: Vec<u8>,
+    /// the time the avatar was first seen, in seconds since the unix epoch
+    pub changed_at: u64,
+}
+
+impl AvatarEntry {
+    /// formats the date the avatar was first seen on as `YYYY-MM-DD` in utc
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn date(&self) -> String {
+        // converts the days since the epoch into a date of the proleptic gregorian calendar,
+        // counting in eras of 400 years starting on the 1st of march so that leap days fall at the end of each year
+        let days = (self.changed_at / SECONDS_PER_DAY) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+        format!("{year:04}-{month:02}-{day:02}")
+    }
+}
+
+/// records the current avatar of the provided user if it differs from the last one recorded for them,
+/// returning whether or not it was recorded
+///
+/// users using a default avatar have nothing to record
+///
+/// # Errors
+/// if the database could not be accessed, or the avatar could not be fetched
+pub async fn record(ctx: &Context, user: &User) -> Result<bool, Error> {
+    let Some(hash) = &user.avatar else {
+        return Ok(false);
+    };
+
+    let database = Database::get(ctx)
+        .await?;
+
+    // members are updated once for each guild they share with the bot, so the same avatar is seen several times
+    let latest = database.latest_avatar(user.id)
+        .await?;
+    if latest.as_ref() == Some(hash) {
+        return Ok(false);
+    }
+
+    let client = ctx.data.read()
+        .await
+        .get::<ClientData>()
+        .cloned();
+    // animated avatars are recorded by their first frame, as the thumbnails are only ever shown as stills
+    let thumbnail = url_to_bytes(client.as_ref(), avatar_url(user.id, None, hash, Some("png"), THUMBNAIL_SIZE))
+        .await?;
+
+    let changed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    database.record_avatar(user.id, &AvatarEntry { hash: hash.clone(), thumbnail, changed_at })
+        .await?;
+
+    Ok(true)
+}
+
+/// records the avatar of an updated member, if their user opted in to having their avatars tracked
+///
+/// # Errors
+/// if the database could not be accessed, or the avatar could not be fetched
+pub async fn track(ctx: &Context, member: &Member) -> Result<(), Error> {
+    if member.user.bot {
+        return Ok(());
+    }
+
+    if Database::get(ctx)
+        .await?
+        .is_avatar_tracked(member.user.id)
+        .await?
+    {
+        record(ctx, &member.user)
+            .await?;
+    }
+
+    Ok(())
+}
\ No newline at end of file
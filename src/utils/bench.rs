@@ -0,0 +1,169 @@
+//! contains utilities for benchmarking the image functions over a bundled test image,
+//! so that performance regressions can be measured
+
+use std::time::{Duration, Instant};
+
+use super::{
+    Error,
+    assets::ASSETS,
+    functions::{
+        invert_func,
+        huerotate_func,
+        caption_func,
+        overlay_func,
+        noise_func,
+        enlarge_func,
+        frames_func,
+        trim_func,
+        accessory_func,
+        facecrop_func,
+        upscale_func,
+        neon_func,
+        scan_func,
+        tilt_func,
+        anaglyph_func,
+    },
+    memory::Allocation,
+    imaging::{decode_frames, Frames, ImageArguments, DEFAULT_MAX_FRAMES},
+    options::{Accessory, Align, AnaglyphOptions, Anchor, CaptionOptions, CaptionPosition, ChannelMask, CropOptions, OverlayOptions, TiltOptions, UpscaleFilter},
+};
+use ril::Rgba;
+
+/// the path to the test image the functions are benchmarked over, relative to the assets directory
+pub const BENCH_IMAGE: &str = "bench.png";
+/// the maximum amount of iterations a single benchmark may run for
+pub const MAX_ITERATIONS: usize = 200;
+
+
+/// the results of benchmarking a function
+#[derive(Debug, Clone, Copy)]
+pub struct BenchReport {
+    /// the amount of iterations the function was run for
+    pub iterations: usize,
+    /// the mean duration of an iteration
+    pub mean: Duration,
+    /// the 95th percentile duration of an iteration
+    pub p95: Duration,
+    /// the most memory allocated at once by a single iteration, in bytes
+    pub peak_allocated: u64,
+}
+
+/// runs the function named `name` once over the provided frames with its default arguments and a fixed seed,
+/// returning its outputs, or `None` if there is no function by that name
+///
+/// `bytes` are the raw bytes of the image, used as the second image of functions requiring one
+pub fn apply(name: &str, frames: Frames, bytes: &[u8]) -> Option<ril::Result<Vec<Frames>>> {
+    let seed = 0;
+
+    Some(
+        match name {
+            "invert" => invert_func(ImageArguments { frames, arguments: vec![ChannelMask::default()], seed })
+                .map(|frames| vec![frames]),
+            "huerotate" => huerotate_func(ImageArguments { frames, arguments: Vec::new(), seed })
+                .map(|frames| vec![frames]),
+            "caption" => caption_func(ImageArguments {
+                frames,
+                arguments: vec![
+                    CaptionOptions {
+                        text: String::from("the quick brown fox jumps over the lazy dog"),
+                        color: Rgba::black(),
+                        stroke: None,
+                        size: 30.0,
+                        align: Align::Center,
+                        position: CaptionPosition::Top,
+                        transparent: false,
+                    }
+                ],
+                seed,
+            })
+                .map(|frames| vec![frames]),
+            "overlay" => overlay_func(ImageArguments {
+                frames,
+                arguments: vec![
+                    OverlayOptions {
+                        overlay: bytes.to_vec(),
+                        anchor: Anchor::BottomRight,
+                        scale: 0.25,
+                        opacity: 1.0,
+                    }
+                ],
+                seed,
+            })
+                .map(|frames| vec![frames]),
+            "noise" => noise_func(ImageArguments { frames, arguments: vec![0.25], seed })
+                .map(|frames| vec![frames]),
+            "enlarge" => enlarge_func(ImageArguments { frames, arguments: vec![4], seed })
+                .map(|frames| vec![frames]),
+            "frames" => frames_func(ImageArguments { frames, arguments: vec![DEFAULT_MAX_FRAMES], seed }),
+            "trim" => trim_func(ImageArguments { frames, arguments: vec![16], seed })
+                .map(|frames| vec![frames]),
+            "eyes" | "glasses" | "clown" => {
+                let accessory = match name {
+                    "eyes" => Accessory::LaserEyes,
+                    "glasses" => Accessory::Glasses,
+                    _ => Accessory::ClownNose,
+                };
+
+                accessory_func(ImageArguments { frames, arguments: vec![accessory], seed })
+                    .map(|frames| vec![frames])
+            },
+            "facecrop" => facecrop_func(ImageArguments { frames, arguments: vec![CropOptions { smart: true, circle: false }], seed })
+                .map(|frames| vec![frames]),
+            "upscale" => upscale_func(ImageArguments { frames, arguments: vec![(2, UpscaleFilter::Scale2x)], seed })
+                .map(|frames| vec![frames]),
+            "anaglyph" => anaglyph_func(ImageArguments { frames, arguments: vec![AnaglyphOptions { depth: 12, wiggle: false }], seed })
+                .map(|frames| vec![frames]),
+            "tilt" => tilt_func(ImageArguments { frames, arguments: vec![TiltOptions { intensity: 0.6, red: true }], seed })
+                .map(|frames| vec![frames]),
+            "scan" => scan_func(ImageArguments { frames, arguments: vec![true], seed })
+                .map(|frames| vec![frames]),
+            "neon" => neon_func(ImageArguments { frames, arguments: vec![8], seed })
+                .map(|frames| vec![frames]),
+            _ => return None,
+        }
+    )
+}
+
+/// runs the function named `name` `iterations` times over [`BENCH_IMAGE`],
+/// returning `None` if there is no function by that name that can be benchmarked
+///
+/// this is blocking and should be run on the worker pool
+///
+/// # Errors
+/// if the test image could not be read or decoded, or if the function errors
+#[allow(clippy::cast_possible_truncation)]
+pub fn run(name: &str, iterations: usize) -> Result<Option<BenchReport>, Error> {
+    let bytes = ASSETS.get(BENCH_IMAGE)
+        .ok_or(Error::JobFailed)?;
+    let frames = decode_frames(&bytes, DEFAULT_MAX_FRAMES)?;
+
+    let iterations = iterations.clamp(1, MAX_ITERATIONS);
+    let mut durations = Vec::with_capacity(iterations);
+    let mut peak_allocated = 0;
+
+    for _ in 0..iterations {
+        let frames = frames.clone();
+
+        // the frames are cloned before measuring, so that only what the function itself allocates is counted
+        let allocation = Allocation::start();
+        let instant = Instant::now();
+        match apply(name, frames, &bytes) {
+            Some(result) => drop(result?),
+            None => return Ok(None),
+        }
+        durations.push(instant.elapsed());
+        peak_allocated = peak_allocated.max(allocation.peak());
+    }
+
+    durations.sort_unstable();
+    let total = durations.iter()
+        .sum::<Duration>();
+    let p95 = durations[(iterations * 95 / 100).min(iterations - 1)];
+
+    Ok(Some(BenchReport {
+        iterations,
+        mean: total / iterations as u32,
+        p95,
+        peak_allocated,
+    }))
+}
\ No newline at end of file
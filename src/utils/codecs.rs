@@ -0,0 +1,508 @@
+//! contains the codecs every source image is decoded through into [`Frames`], picked by sniffing the bytes of the image,
+//! including decoders for the image formats ril can not decode, such as AVIF, HEIC and JPEG XL,
+//! as well as a fallback decoder for the less common formats ril fails to decode
+//!
+//! adding a format only takes implementing [`InputCodec`] for it and listing it in [`CODECS`]
+//!
+//! AVIF and HEIC decoding depend on native libraries and are only available with the `avif` and `heic` features,
+//! while animated WebP images are decoded by the `image` crate unless the `webp` feature lets ril decode them
+
+use std::{io::Cursor, time::Duration};
+
+use ril::prelude::*;
+use jxl_oxide::JxlImage;
+
+use super::{
+    Error,
+    helpers::sniff_image,
+    imaging::{Frames, DEFAULT_MAX_FRAMES},
+    memory,
+};
+
+
+/// the most bytes a JPEG XL image may take once decoded, as its header allows dimensions of up to a billion pixels,
+/// regardless of whether there is a memory ceiling
+const MAX_JXL_SIZE: u64 = 1 << 30;
+
+
+/// a decoder for a single image format, turning the encoded bytes of an image into [`Frames`]
+pub trait InputCodec: Send + Sync {
+    /// the name of the format, as shown in errors and logs
+    fn name(&self) -> &'static str;
+
+    /// whether or not the provided bytes are of the format of this codec
+    fn matches(&self, bytes: &[u8]) -> bool;
+
+    /// decodes every frame of the provided bytes, erroring if there are more than `max_frames` frames where it is known early
+    ///
+    /// # Errors
+    /// if the bytes could not be decoded
+    fn decode(&self, bytes: &[u8], max_frames: usize) -> Result<Frames, Error>;
+
+    /// decodes only the first frame of the provided bytes, for commands making a still image out of any source
+    ///
+    /// codecs able to stop after the first frame override this, while the others decode up to [`DEFAULT_MAX_FRAMES`] frames
+    /// and keep the first
+    ///
+    /// # Errors
+    /// if the bytes could not be decoded
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        let frames = self.decode(bytes, DEFAULT_MAX_FRAMES)?;
+        let mut sequence = ImageSequence::<Rgba>::new();
+
+        if let Some(frame) = frames.into_iter().next() {
+            sequence.push_frame(frame);
+        }
+
+        Ok(sequence)
+    }
+}
+
+/// every codec, in the order they are matched against, the [`Static`] codec matching anything the others do not
+pub static CODECS: &[&dyn InputCodec] = &[&Gif, &Apng, &WebP, &Avif, &Heic, &Jxl, &Static];
+
+/// returns the codec the provided bytes are decoded with
+#[must_use]
+pub fn codec_for(bytes: &[u8]) -> &'static dyn InputCodec {
+    CODECS.iter()
+        .copied()
+        .find(|codec| codec.matches(bytes))
+        .unwrap_or(&Static)
+}
+
+/// decodes the provided bytes with the codec of their format, see [`codec_for`]
+///
+/// # Errors
+/// if the bytes could not be decoded
+pub fn decode(bytes: &[u8], max_frames: usize) -> Result<Frames, Error> {
+    codec_for(bytes)
+        .decode(bytes, max_frames)
+}
+
+/// decodes only the first frame of the provided bytes with the codec of their format, see [`InputCodec::decode_first`]
+///
+/// # Errors
+/// if the bytes could not be decoded
+pub fn decode_first(bytes: &[u8]) -> Result<Frames, Error> {
+    codec_for(bytes)
+        .decode_first(bytes)
+}
+
+/// decodes the provided bytes with ril in the provided format
+fn decode_ril(bytes: &[u8], format: ImageFormat) -> Result<Frames, Error> {
+    Ok(
+        ImageSequence::<Rgba>::from_bytes(format, bytes)?
+            .into_sequence()?
+    )
+}
+
+/// decodes only the first frame of the provided bytes with ril in the provided format, as ril decodes frames one at a time
+fn decode_ril_first(bytes: &[u8], format: ImageFormat) -> Result<Frames, Error> {
+    let frame = ImageSequence::<Rgba>::from_bytes(format, bytes)?
+        .next()
+        .ok_or(ril::Error::EmptyImageError)??;
+
+    let mut sequence = ImageSequence::<Rgba>::new();
+    sequence.push_frame(frame);
+
+    Ok(sequence)
+}
+
+/// `gif`s, which are decoded with their delays and disposal methods by ril
+pub struct Gif;
+
+impl InputCodec for Gif {
+    fn name(&self) -> &'static str {
+        "GIF"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/gif")
+    }
+
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        decode_ril(bytes, ImageFormat::Gif)
+    }
+
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        decode_ril_first(bytes, ImageFormat::Gif)
+    }
+}
+
+/// animated `png`s, told apart from still ones by the `acTL` chunk read by [`memory::probe`]
+pub struct Apng;
+
+impl InputCodec for Apng {
+    fn name(&self) -> &'static str {
+        "APNG"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/png")
+            && memory::probe(bytes).is_some_and(|(_, _, frames)| frames > 1)
+    }
+
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        decode_ril(bytes, ImageFormat::Png)
+    }
+
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        decode_ril_first(bytes, ImageFormat::Png)
+    }
+}
+
+/// `webp`s, both still and animated
+pub struct WebP;
+
+impl InputCodec for WebP {
+    fn name(&self) -> &'static str {
+        "WebP"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/webp")
+    }
+
+    /// decodes the image with ril, as the `webp` feature is enabled
+    #[cfg(feature = "webp")]
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        decode_ril(bytes, ImageFormat::WebP)
+    }
+
+    #[cfg(feature = "webp")]
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        decode_ril_first(bytes, ImageFormat::WebP)
+    }
+
+    /// decodes the image with the `image` crate, as ril only decodes `webp`s with the `webp` feature
+    #[cfg(not(feature = "webp"))]
+    fn decode(&self, bytes: &[u8], max_frames: usize) -> Result<Frames, Error> {
+        use image::{AnimationDecoder, codecs::webp::WebPDecoder};
+
+        let to_error = |err: image::ImageError| Error::CodecError(err.to_string());
+        let decoder = WebPDecoder::new(Cursor::new(bytes))
+            .map_err(to_error)?;
+
+        if !decoder.has_animation() {
+            return decode_fallback(bytes);
+        }
+
+        let mut sequence = ImageSequence::<Rgba>::new();
+
+        for (index, frame) in decoder.into_frames().enumerate() {
+            if index >= max_frames {
+                return Err(Error::TooManyFrames(index + 1, max_frames));
+            }
+
+            let frame = frame.map_err(to_error)?;
+            let (numerator, denominator) = frame.delay().numer_denom_ms();
+
+            sequence.push_frame(
+                Frame::from_image(from_buffer(&frame.into_buffer()))
+                    .with_delay(Duration::from_millis(u64::from(numerator / denominator.max(1))))
+            );
+        }
+
+        Ok(sequence)
+    }
+
+    #[cfg(not(feature = "webp"))]
+    #[allow(clippy::cast_possible_truncation)]
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        use image::{AnimationDecoder, codecs::webp::WebPDecoder};
+
+        let to_error = |err: image::ImageError| Error::CodecError(err.to_string());
+        let decoder = WebPDecoder::new(Cursor::new(bytes))
+            .map_err(to_error)?;
+
+        if !decoder.has_animation() {
+            return decode_fallback(bytes);
+        }
+
+        let frame = decoder.into_frames()
+            .next()
+            .ok_or(ril::Error::EmptyImageError)?
+            .map_err(to_error)?;
+
+        Ok(still(frame.buffer().width() as usize, &from_buffer(frame.buffer()).data))
+    }
+}
+
+/// AVIF images, see [`decode_avif`]
+pub struct Avif;
+
+impl InputCodec for Avif {
+    fn name(&self) -> &'static str {
+        "AVIF"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/avif")
+    }
+
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        decode_avif(bytes)
+    }
+}
+
+/// HEIC and HEIF images, see [`decode_heic`]
+pub struct Heic;
+
+impl InputCodec for Heic {
+    fn name(&self) -> &'static str {
+        "HEIC"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/heic")
+    }
+
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        decode_heic(bytes)
+    }
+}
+
+/// JPEG XL images, see [`decode_jxl`]
+pub struct Jxl;
+
+impl InputCodec for Jxl {
+    fn name(&self) -> &'static str {
+        "JPEG XL"
+    }
+
+    fn matches(&self, bytes: &[u8]) -> bool {
+        sniff_image(bytes) == Some("image/jxl")
+    }
+
+    fn decode(&self, bytes: &[u8], max_frames: usize) -> Result<Frames, Error> {
+        decode_jxl(bytes, max_frames)
+    }
+}
+
+/// still images of any other format, decoded by ril if it can and by [`decode_fallback`] otherwise
+pub struct Static;
+
+impl InputCodec for Static {
+    fn name(&self) -> &'static str {
+        "still image"
+    }
+
+    fn matches(&self, _bytes: &[u8]) -> bool {
+        true
+    }
+
+    fn decode(&self, bytes: &[u8], _max_frames: usize) -> Result<Frames, Error> {
+        match ImageSequence::<Rgba>::from_bytes_inferred(bytes)
+            .and_then(|image| image.into_sequence())
+        {
+            Ok(image) => Ok(image),
+            // ril's error is the more relevant one for the common formats, so it is kept if the fallback fails as well
+            Err(err) => decode_fallback(bytes)
+                .map_err(|_| err.into()),
+        }
+    }
+
+    fn decode_first(&self, bytes: &[u8]) -> Result<Frames, Error> {
+        let first = ImageSequence::<Rgba>::from_bytes_inferred(bytes)
+            .and_then(|mut image| image.next().ok_or(ril::Error::EmptyImageError)?);
+
+        match first {
+            Ok(frame) => {
+                let mut sequence = ImageSequence::<Rgba>::new();
+                sequence.push_frame(frame);
+
+                Ok(sequence)
+            },
+            Err(err) => decode_fallback(bytes)
+                .map_err(|_| err.into()),
+        }
+    }
+}
+
+/// builds a still image out of rows of pixels
+#[allow(clippy::cast_possible_truncation)]
+fn still(width: usize, pixels: &[Rgba]) -> Frames {
+    let mut sequence = ImageSequence::<Rgba>::new();
+    sequence.push_frame(
+        Frame::from_image(Image::from_pixels(width as u32, pixels))
+    );
+
+    sequence
+}
+
+/// converts an image decoded by the `image` crate into a ril image
+fn from_buffer(image: &image::RgbaImage) -> Image<Rgba> {
+    let pixels = image.pixels()
+        .map(|px| Rgba::new(px[0], px[1], px[2], px[3]))
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(image.width(), pixels)
+}
+
+/// decodes the provided bytes with the `image` crate, used as a fallback when ril fails to decode them,
+/// widening the range of accepted formats to include BMP, TIFF, ICO and TGA
+///
+/// TGA files carry no signature, so they are only tried once the format could not be guessed
+pub fn decode_fallback(bytes: &[u8]) -> Result<Frames, Error> {
+    let image = image::load_from_memory(bytes)
+        .or_else(|_| image::load_from_memory_with_format(bytes, image::ImageFormat::Tga))
+        .map_err(|err| Error::CodecError(err.to_string()))?
+        .into_rgba8();
+
+    Ok(still(image.width() as usize, &from_buffer(&image).data))
+}
+
+/// converts a channel ranging from `0.0` to `1.0` into a byte
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn to_byte(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// decodes an AVIF image, only the first frame of animated images is decoded
+#[cfg(feature = "avif")]
+fn decode_avif(bytes: &[u8]) -> Result<Frames, Error> {
+    use avif_decode::{Decoder, Image as AvifImage};
+
+    let image = Decoder::from_avif(bytes)
+        .and_then(|decoder| decoder.to_image())
+        .map_err(|err| Error::CodecError(err.to_string()))?;
+
+    #[allow(clippy::cast_possible_truncation)]
+    let (width, pixels) = match image {
+        AvifImage::Rgb8(image) => (
+            image.width(),
+            image.pixels().map(|px| Rgba::new(px.r, px.g, px.b, 255)).collect::<Vec<_>>(),
+        ),
+        AvifImage::Rgba8(image) => (
+            image.width(),
+            image.pixels().map(|px| Rgba::new(px.r, px.g, px.b, px.a)).collect(),
+        ),
+        AvifImage::Rgb16(image) => (
+            image.width(),
+            image.pixels()
+                .map(|px| Rgba::new((px.r >> 8) as u8, (px.g >> 8) as u8, (px.b >> 8) as u8, 255))
+                .collect(),
+        ),
+        AvifImage::Rgba16(image) => (
+            image.width(),
+            image.pixels()
+                .map(|px| Rgba::new((px.r >> 8) as u8, (px.g >> 8) as u8, (px.b >> 8) as u8, (px.a >> 8) as u8))
+                .collect(),
+        ),
+        AvifImage::Gray8(image) => (
+            image.width(),
+            image.pixels().map(|px| Rgba::new(px.0, px.0, px.0, 255)).collect(),
+        ),
+        AvifImage::Gray16(image) => (
+            image.width(),
+            image.pixels()
+                .map(|px| {
+                    let value = (px.0 >> 8) as u8;
+                    Rgba::new(value, value, value, 255)
+                })
+                .collect(),
+        ),
+    };
+
+    Ok(still(width, &pixels))
+}
+
+/// refuses AVIF images, as the `avif` feature is not enabled
+#[cfg(not(feature = "avif"))]
+fn decode_avif(_bytes: &[u8]) -> Result<Frames, Error> {
+    Err(Error::CodecError(String::from("AVIF images are not supported by this build of the bot")))
+}
+
+/// decodes a HEIC or HEIF image, only the primary image of the file is decoded
+#[cfg(feature = "heic")]
+fn decode_heic(bytes: &[u8]) -> Result<Frames, Error> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let to_error = |err: libheif_rs::HeifError| Error::CodecError(err.to_string());
+
+    let context = HeifContext::read_from_bytes(bytes)
+        .map_err(to_error)?;
+    let handle = context.primary_image_handle()
+        .map_err(to_error)?;
+    let image = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(to_error)?;
+
+    let planes = image.planes();
+    let plane = planes.interleaved
+        .ok_or_else(|| Error::CodecError(String::from("the image has no interleaved plane")))?;
+    let width = plane.width as usize;
+
+    // rows may be padded, so each is read up to the width of the image rather than up to the stride
+    let pixels = plane.data
+        .chunks_exact(plane.stride)
+        .take(plane.height as usize)
+        .flat_map(|row|
+            row[..width * 4]
+                .chunks_exact(4)
+                .map(|px| Rgba::new(px[0], px[1], px[2], px[3]))
+        )
+        .collect::<Vec<_>>();
+
+    Ok(still(width, &pixels))
+}
+
+/// refuses HEIC images, as the `heic` feature is not enabled
+#[cfg(not(feature = "heic"))]
+fn decode_heic(_bytes: &[u8]) -> Result<Frames, Error> {
+    Err(Error::CodecError(String::from("HEIC images are not supported by this build of the bot")))
+}
+
+/// decodes a JPEG XL image, along with every frame of animated images
+fn decode_jxl(bytes: &[u8], max_frames: usize) -> Result<Frames, Error> {
+    let image = JxlImage::builder()
+        .read(Cursor::new(bytes))
+        .map_err(|err| Error::CodecError(err.to_string()))?;
+
+    // only the headers have been parsed so far, so the size of the image is bound before any of its frames are rendered
+    let count = image.num_loaded_keyframes();
+    let size = memory::decoded_size(image.width(), image.height(), count);
+    if size > MAX_JXL_SIZE {
+        return Err(Error::MemoryLimit(size, MAX_JXL_SIZE));
+    }
+    memory::check_dimensions(image.width(), image.height(), count, max_frames)?;
+
+    // the duration of animation frames is measured in ticks, of which there are `numerator / denominator` per second
+    let ticks_per_second = image.image_header()
+        .metadata
+        .animation
+        .as_ref()
+        .map(|animation| (animation.tps_numerator, animation.tps_denominator));
+
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for index in 0..count {
+        let render = image.render_frame(index)
+            .map_err(|err| Error::CodecError(err.to_string()))?;
+        let buffer = render.image_all_channels();
+
+        let pixels = buffer.buf()
+            .chunks_exact(buffer.channels())
+            .map(|px| match *px {
+                [gray] => Rgba::new(to_byte(gray), to_byte(gray), to_byte(gray), 255),
+                [gray, alpha] => Rgba::new(to_byte(gray), to_byte(gray), to_byte(gray), to_byte(alpha)),
+                [r, g, b] => Rgba::new(to_byte(r), to_byte(g), to_byte(b), 255),
+                [r, g, b, alpha, ..] => Rgba::new(to_byte(r), to_byte(g), to_byte(b), to_byte(alpha)),
+                [] => Rgba::transparent(),
+            })
+            .collect::<Vec<_>>();
+
+        #[allow(clippy::cast_possible_truncation)]
+        let mut frame = Frame::from_image(Image::from_pixels(buffer.width() as u32, pixels));
+
+        if let Some((numerator, denominator)) = ticks_per_second.filter(|(numerator, _)| *numerator > 0) {
+            frame = frame.with_delay(Duration::from_millis(
+                u64::from(render.duration()) * 1000 * u64::from(denominator) / u64::from(numerator)
+            ));
+        }
+
+        sequence.push_frame(frame);
+    }
+
+    Ok(sequence)
+}
\ No newline at end of file
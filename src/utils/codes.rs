@@ -0,0 +1,97 @@
+//! contains the rendering of QR codes, powered by `qrcode`, and the scanning of QR codes found in images, powered by `rqrr`
+//!
+//! only QR codes can be scanned, as `rqrr` does not support linear barcodes
+
+use qrcode::{Color, EcLevel, QrCode};
+use ril::prelude::*;
+
+use super::{Error, imaging::Frames};
+
+
+/// the width and height of each module of a rendered QR code, in pixels
+pub const MODULE_SIZE: u32 = 10;
+/// the width of the blank margin around a rendered QR code, in modules
+pub const QUIET_ZONE: u32 = 4;
+/// the fraction of the width of a QR code covered by its logo,
+/// small enough for the code to be recovered with the highest error correction level
+pub const LOGO_SCALE: f64 = 0.22;
+
+
+/// renders the provided text as a QR code, with the first frame of `logo` placed in its center if provided,
+/// in which case the highest error correction level is used so that the code remains readable
+///
+/// # Errors
+/// if the text is too long to fit in a QR code
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn render_qr(text: &str, logo: Option<&Frames>) -> Result<Frames, Error> {
+    let level = if logo.is_some() { EcLevel::H } else { EcLevel::M };
+    let code = QrCode::with_error_correction_level(text.as_bytes(), level)?;
+
+    let modules = code.width() as u32;
+    let size = (modules + QUIET_ZONE * 2) * MODULE_SIZE;
+    let mut image = Image::<Rgba>::new(size, size, Rgba::white());
+
+    for (i, color) in code.to_colors()
+        .into_iter()
+        .enumerate()
+    {
+        if color == Color::Dark {
+            let (x, y) = (i as u32 % modules + QUIET_ZONE, i as u32 / modules + QUIET_ZONE);
+
+            image.draw(
+                &Rectangle::at(x * MODULE_SIZE, y * MODULE_SIZE)
+                    .with_size(MODULE_SIZE, MODULE_SIZE)
+                    .with_fill(Rgba::black())
+            );
+        }
+    }
+
+    if let Some(frame) = logo.and_then(|logo| logo.first_frame()) {
+        let width = (f64::from(modules * MODULE_SIZE) * LOGO_SCALE) as u32;
+        let height = ((f64::from(width) / f64::from(frame.width())) * f64::from(frame.height())) as u32;
+        let logo = frame.image()
+            .clone()
+            .resized(width.max(1), height.clamp(1, width), ResizeAlgorithm::Lanczos3);
+
+        // a white backdrop keeps the modules around the logo from bleeding into it
+        let (x, y) = ((size - logo.width()) / 2, (size - logo.height()) / 2);
+        image.draw(
+            &Rectangle::at(x - MODULE_SIZE / 2, y - MODULE_SIZE / 2)
+                .with_size(logo.width() + MODULE_SIZE, logo.height() + MODULE_SIZE)
+                .with_fill(Rgba::white())
+        );
+        image.paste(x, y, &logo);
+    }
+
+    let mut sequence = ImageSequence::<Rgba>::new();
+    sequence.push_frame(Frame::from_image(image));
+
+    Ok(sequence)
+}
+
+/// scans the first frame of the provided image for QR codes, returning the contents of each of those that could be decoded
+#[must_use]
+pub fn scan_codes(frames: &Frames) -> Vec<String> {
+    let Some(frame) = frames.first_frame() else {
+        return Vec::new();
+    };
+    let image = frame.image();
+    let width = image.width() as usize;
+
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(width, image.height() as usize, |x, y| {
+        let px = image.data[y * width + x];
+
+        // transparent areas are treated as white, as codes are usually dark on a light background
+        let luma = (u32::from(px.r) * 299 + u32::from(px.g) * 587 + u32::from(px.b) * 114) / 1000;
+        let alpha = u32::from(px.a);
+
+        u8::try_from((luma * alpha + 255 * (255 - alpha)) / 255)
+            .unwrap_or(u8::MAX)
+    });
+
+    prepared.detect_grids()
+        .into_iter()
+        .filter_map(|grid| grid.decode().ok())
+        .map(|(_, content)| content)
+        .collect()
+}
\ No newline at end of file
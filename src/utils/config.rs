@@ -0,0 +1,110 @@
+//! contains the [`Config`] struct, holding the bot's configuration loaded from `config.toml`
+
+use std::{collections::HashMap, fs, path::Path};
+use serde::Deserialize;
+use super::policy::FetchPolicy;
+
+
+/// the default path of the configuration file
+pub const CONFIG_PATH: &str = "./config.toml";
+
+/// the bot's configuration, every field falls back to its default when not specified
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// whether or not commands may be used in direct messages
+    pub allow_dms: bool,
+    /// the url of the sqlite database used to persist settings
+    pub database_url: String,
+    /// the amount of threads in the pool image jobs are run on, defaults to the amount of available cores
+    pub worker_threads: Option<usize>,
+    /// if not empty, only urls of these hosts (and their subdomains) may be fetched
+    pub allowed_hosts: Vec<String>,
+    /// urls of these hosts (and their subdomains) may never be fetched
+    pub denied_hosts: Vec<String>,
+    /// the id of the guild holding the patron role
+    pub patron_guild_id: Option<u64>,
+    /// the id of the role that grants its members the premium tier
+    pub patron_role_id: Option<u64>,
+    /// the client id of the imgur application used to resolve imgur pages
+    pub imgur_client_id: Option<String>,
+    /// whether or not images are resolved from the channel's pinned messages before falling back to the author's avatar
+    pub resolve_pins: bool,
+    /// a link to where users can get support, shown in error messages
+    pub support_url: Option<String>,
+    /// whether or not to run without the privileged `MESSAGE_CONTENT` intent,
+    /// in which case prefix commands are disabled and only slash and context menu commands are available
+    pub slash_only: bool,
+    /// the id of the channel every image job is summarized in, see [`super::audit`]
+    pub audit_channel_id: Option<u64>,
+    /// the url of a webhook every image job is summarized through, taking precedence over `audit_channel_id`
+    pub audit_webhook_url: Option<String>,
+    /// maps the names of bot lists to the tokens the guild count is posted to them with, see [`super::presence::BotList`]
+    pub bot_lists: HashMap<String, String>,
+    /// the ceiling on the memory each image job may allocate in megabytes, past which it is aborted, see [`super::memory`]
+    pub memory_ceiling_mb: Option<u64>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            allow_dms: true,
+            database_url: String::from("sqlite://ril-bot.db?mode=rwc"),
+            worker_threads: None,
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            patron_guild_id: None,
+            patron_role_id: None,
+            imgur_client_id: None,
+            resolve_pins: false,
+            support_url: None,
+            slash_only: false,
+            audit_channel_id: None,
+            audit_webhook_url: None,
+            bot_lists: HashMap::new(),
+            memory_ceiling_mb: None,
+        }
+    }
+}
+
+impl Config {
+    /// the ceiling on the memory each image job may allocate in bytes, if there is one
+    #[must_use]
+    pub fn memory_ceiling(&self) -> Option<u64> {
+        self.memory_ceiling_mb
+            .map(|megabytes| megabytes * 1024 * 1024)
+    }
+
+    /// builds the [`FetchPolicy`] described by this configuration
+    #[must_use]
+    pub fn fetch_policy(&self) -> FetchPolicy {
+        FetchPolicy {
+            allowed_hosts: self.allowed_hosts.clone(),
+            denied_hosts: self.denied_hosts.clone(),
+        }
+    }
+
+    /// loads the configuration from the provided path, falling back to the default configuration if the file does not exist
+    ///
+    /// # Panics
+    /// if the file exists but is not valid
+    #[must_use]
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        Self::try_load(path)
+            .expect("Failed to parse the configuration file")
+    }
+
+    /// same as [`Self::load`], but returns the reason the file is not valid rather than panicking,
+    /// used to reload the configuration of a running bot
+    ///
+    /// # Errors
+    /// if the file exists but is not valid
+    pub fn try_load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        fs::read_to_string(path)
+            .map_or_else(
+                |_| Ok(Self::default()),
+                |content| toml::from_str(content.as_str())
+                    .map_err(|err| err.to_string()),
+            )
+    }
+}
\ No newline at end of file
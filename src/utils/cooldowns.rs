@@ -0,0 +1,170 @@
+//! contains the [`CooldownManager`], which rate limits imaging commands per user
+//! with a delay and limit that can be configured per guild
+
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+use serde::{Deserialize, Serialize};
+use serenity::model::id::UserId;
+
+use super::{Error, database::Database};
+
+
+/// the longest delay a guild may configure, after which every use of a command has expired
+pub const MAX_DELAY: Duration = Duration::from_secs(600);
+/// how often users whose uses have all expired are forgotten, see [`CooldownManager::sweep`]
+pub const SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+/// the key the recent uses are saved under in the database
+pub const COOLDOWNS_STATE_KEY: &str = "cooldowns";
+
+/// the cooldown settings of a guild: at most `limit` commands may be used every `delay`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cooldown {
+    /// the length of the window
+    pub delay: Duration,
+    /// the number of commands allowed within the window
+    pub limit: u32,
+}
+
+impl Default for Cooldown {
+    fn default() -> Self {
+        Self {
+            delay: Duration::from_secs(5),
+            limit: 1,
+        }
+    }
+}
+
+/// a serializable copy of the uses tracked by a [`CooldownManager`], as how long before it was saved each use was
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CooldownSnapshot {
+    /// when the snapshot was saved
+    saved_at: SystemTime,
+    /// how long before the snapshot was saved each user used a command, oldest first
+    uses: HashMap<u64, Vec<Duration>>,
+}
+
+/// tracks when each user last used an imaging command
+#[derive(Debug, Default)]
+pub struct CooldownManager {
+    /// maps each user to the times they used a command, oldest first
+    uses: Mutex<HashMap<UserId, VecDeque<Instant>>>,
+}
+
+impl CooldownManager {
+    /// creates a new, empty manager
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// records a use of a command by the provided user under the provided cooldown,
+    /// returning `Err` with the time remaining if they are still on cooldown
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn hit(&self, user_id: UserId, cooldown: Cooldown) -> Result<(), Duration> {
+        if cooldown.delay.is_zero() || cooldown.limit == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut uses = self.uses.lock()
+            .unwrap();
+        let history = uses.entry(user_id)
+            .or_default();
+
+        while history.front()
+            .map_or(false, |used| now.duration_since(*used) >= cooldown.delay)
+        {
+            history.pop_front();
+        }
+
+        if history.len() >= cooldown.limit as usize {
+            let oldest = history.front()
+                .copied()
+                .unwrap_or(now);
+
+            return Err(cooldown.delay.saturating_sub(now.duration_since(oldest)));
+        }
+
+        history.push_back(now);
+        Ok(())
+    }
+
+    /// forgets the users whose uses have all expired under any cooldown, to keep the map from growing forever
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn sweep(&self) {
+        let now = Instant::now();
+
+        self.uses.lock()
+            .unwrap()
+            .retain(|_, history| history.back()
+                .map_or(false, |used| now.duration_since(*used) < MAX_DELAY)
+            );
+    }
+
+    /// saves the recent uses to the database, so that restarting the bot does not reset every cooldown
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub async fn save(&self, database: &Database) -> Result<(), Error> {
+        let now = Instant::now();
+        let snapshot = CooldownSnapshot {
+            saved_at: SystemTime::now(),
+            uses: self.uses.lock()
+                .unwrap()
+                .iter()
+                .map(|(user_id, history)| (
+                    user_id.0,
+                    history.iter()
+                        .map(|used| now.duration_since(*used))
+                        .collect(),
+                ))
+                .collect(),
+        };
+        let state = serde_json::to_string(&snapshot)
+            .unwrap_or_default();
+
+        database.set_state(COOLDOWNS_STATE_KEY, state.as_str())
+            .await
+    }
+
+    /// restores the recent uses saved to the database, if there are any, counting the time the bot was down towards their expiry
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub async fn load(&self, database: &Database) -> Result<(), Error> {
+        let Some(snapshot) = database.state(COOLDOWNS_STATE_KEY)
+            .await?
+            .and_then(|state| serde_json::from_str::<CooldownSnapshot>(state.as_str()).ok())
+        else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let downtime = snapshot.saved_at
+            .elapsed()
+            .unwrap_or_default();
+        let mut uses = self.uses.lock()
+            .unwrap();
+
+        for (user_id, ages) in snapshot.uses {
+            let history = ages.into_iter()
+                .map(|age| age + downtime)
+                .filter(|age| *age < MAX_DELAY)
+                .filter_map(|age| now.checked_sub(age))
+                .collect::<VecDeque<_>>();
+
+            if !history.is_empty() {
+                uses.insert(UserId(user_id), history);
+            }
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
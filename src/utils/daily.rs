@@ -0,0 +1,222 @@
+//! contains the image of the day, where a random effect is applied to the icon of a guild or an image it configured
+//! and posted to a channel of its choosing once a day
+
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rand::SeedableRng;
+use serenity::{
+    prelude::Context,
+    model::{
+        channel::AttachmentType,
+        id::{ChannelId, GuildId},
+    },
+};
+
+use crate::ClientData;
+use super::{
+    Error,
+    database::Database,
+    entitlements::{GuildLimits, Tier},
+    functions::contain_size,
+    helpers::url_to_bytes,
+    imaging::{decode_frames, Frames, ImageArguments, Output, Rng},
+    options::OutputFormat,
+    pipeline::{PipelineContext, Stage},
+    pool::{estimate_cost, run_job},
+    registry::Effect,
+    resolver::{AttachmentRef, ImageResolver},
+};
+
+
+/// how often the schedules are checked for posts that are due
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// the minute of the day the image of the day is posted at if not configured, which is noon in utc
+pub const DEFAULT_MINUTE: u32 = 12 * 60;
+/// the number of seconds in a day, as days are counted since the unix epoch in utc
+const SECONDS_PER_DAY: u64 = 86_400;
+
+/// whether or not the scheduler has been started, as the cache can become ready more than once
+static SCHEDULER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// the image of the day configured by a guild
+#[derive(Debug, Clone)]
+pub struct DailyImage {
+    /// the guild the image is posted for
+    pub guild_id: GuildId,
+    /// the channel the image is posted to
+    pub channel_id: ChannelId,
+    /// the minute of the day, in utc, at which the image is posted
+    pub minute: u32,
+    /// the url of the image the effect is applied to, or `None` to use the icon of the guild
+    pub image_url: Option<String>,
+    /// the attached file the image url points to, if any, as discord's attachment urls expire
+    pub source: Option<AttachmentRef>,
+    /// the day the image was last posted on, counted since the unix epoch
+    pub last_day: Option<u64>,
+}
+
+impl DailyImage {
+    /// whether or not the image is due to be posted at the provided day and minute
+    #[must_use]
+    pub fn is_due(&self, day: u64, minute: u32) -> bool {
+        self.last_day.map_or(true, |last| last < day) && minute >= self.minute
+    }
+}
+
+/// parses a time of day in utc, as `HH:MM`, into the minute of the day
+#[must_use]
+pub fn parse_time(time: &str) -> Option<u32> {
+    let (hours, minutes) = time.split_once(':')?;
+    let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// formats the minute of the day as `HH:MM`
+#[must_use]
+pub fn format_time(minute: u32) -> String {
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// returns the current day counted since the unix epoch, and the current minute of that day, both in utc
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn now() -> (u64, u32) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    (seconds / SECONDS_PER_DAY, (seconds % SECONDS_PER_DAY / 60) as u32)
+}
+
+/// applies the effect picked by `seed` to the provided frames
+///
+/// the effect and its parameters are picked the same way as the `random` command picks them, see [`Stage::random`],
+/// so that passing the same seed to it reproduces the image
+fn apply(frames: Frames, seed: u64) -> Result<(&'static Effect, Frames), Error> {
+    let stage = Stage::random(&mut Rng::seed_from_u64(seed))
+        .ok_or(Error::JobFailed)?;
+
+    Ok((stage.effect, stage.apply(frames, seed, &mut PipelineContext::default())?))
+}
+
+/// renders and posts the image of the day of a guild
+///
+/// nothing is posted if the guild has neither configured an image nor has an icon
+///
+/// # Errors
+/// if the source image could not be fetched, or the image could not be rendered or sent
+pub async fn post(ctx: &Context, daily: &DailyImage, day: u64) -> Result<(), Error> {
+    let url = match &daily.image_url {
+        Some(url) => url.clone(),
+        None => match ctx.cache.guild_field(daily.guild_id, |guild| guild.icon_url()).flatten() {
+            Some(url) => url.replace(".webp", ".png"),
+            None => return Ok(()),
+        },
+    };
+
+    let client = ctx.data.read()
+        .await
+        .get::<ClientData>()
+        .cloned();
+    // the url of an attached file expires, in which case a fresh one is fetched through its message,
+    // or exchanged for a fresh one if only its url is known
+    let bytes = match url_to_bytes(client.as_ref(), &url).await {
+        Err(err) if matches!(err.root(), Error::FetchUrlError) && daily.image_url.is_some() => {
+            let fresh = match daily.source {
+                Some(source) => source.refresh(ctx)
+                    .await?
+                    .url,
+                None => ImageResolver::refresh_attachment_url(client.as_ref(), ctx, &url)
+                    .await?,
+            };
+
+            url_to_bytes(client.as_ref(), fresh)
+                .await?
+        },
+        result => result?,
+    };
+
+    // nobody invokes the image of the day, so it is held to the limits of the free tier as lowered by the guild
+    let limits = GuildLimits::lookup(ctx, Some(daily.guild_id))
+        .await
+        .apply(Tier::Free.limits());
+    if bytes.len() as u64 > limits.max_size {
+        return Err(Error::ImageTooLarge(bytes.len() as u64, limits.max_size));
+    }
+
+    // seeded by the guild and the day, so that each day of each guild has its own effect
+    let seed = daily.guild_id.0 ^ day.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+
+    let (effect, output) = run_job(ctx, estimate_cost(&bytes), move || -> Result<_, Error> {
+        let frames = contain_size(
+            ImageArguments { frames: decode_frames(&bytes, limits.max_frames)?, arguments: Vec::new(), seed: 0 },
+            None,
+            Some(limits.max_dim),
+        )?;
+        let (effect, frames) = apply(frames, seed)?;
+
+        Ok((effect, Output::encode(frames, OutputFormat::Auto, None)?))
+    })
+        .await??;
+
+    daily.channel_id.send_files(
+        ctx,
+        vec![AttachmentType::Bytes {
+            filename: format!("daily.{}", output.extension),
+            data: Cow::Owned(output.bytes),
+        }],
+        |msg| msg.content(format!("**Image of the day:** `{}` (seed `{seed}`)", effect.name)),
+    )
+        .await?;
+
+    Ok(())
+}
+
+/// posts the images of the day that are due, marking each as posted beforehand
+/// so that one failing to post is not retried every minute for the rest of the day
+async fn post_due(ctx: &Context) -> Result<(), Error> {
+    let (day, minute) = now();
+    let database = Database::get(ctx)
+        .await?;
+
+    for daily in database.daily_images()
+        .await?
+        .into_iter()
+        .filter(|daily| daily.is_due(day, minute))
+    {
+        database.mark_daily_posted(daily.guild_id, day)
+            .await?;
+
+        if let Err(err) = post(ctx, &daily, day).await {
+            tracing::warn!("failed to post the image of the day of {}: {}", daily.guild_id, err.report());
+        }
+    }
+
+    Ok(())
+}
+
+/// starts checking for images of the day that are due in the background, if not started already
+pub fn start_scheduler(ctx: &Context) {
+    if SCHEDULER_STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+
+        loop {
+            interval.tick()
+                .await;
+            if let Err(err) = post_due(&ctx).await {
+                tracing::error!("failed to check for images of the day: {}", err.report());
+            }
+        }
+    });
+}
\ No newline at end of file
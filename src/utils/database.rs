@@ -0,0 +1,725 @@
+//! contains the [`Database`] struct, a thin wrapper around a sqlite connection pool
+//! used to persist per-guild settings, along with any runtime state of the bot that should survive restarts
+
+use serenity::{
+    prelude::Context,
+    model::id::{AttachmentId, ChannelId, GuildId, MessageId, RoleId, UserId},
+};
+use sqlx::{
+    sqlite::{SqlitePool, SqlitePoolOptions, SqliteRow},
+    Row,
+};
+
+use std::time::Duration;
+
+use crate::DatabaseData;
+use super::{
+    Error,
+    avatars::{AvatarEntry, AVATAR_HISTORY},
+    cooldowns::Cooldown,
+    daily::DailyImage,
+    entitlements::GuildLimits,
+    hashing::{self, REPOST_HISTORY},
+    resolver::AttachmentRef,
+    options::OutputFormat,
+};
+
+
+/// the statements run on startup to create any missing tables
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS disabled_commands (
+        guild_id INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        PRIMARY KEY (guild_id, command)
+    )",
+    "CREATE TABLE IF NOT EXISTS command_restrictions (
+        guild_id INTEGER NOT NULL,
+        command TEXT NOT NULL,
+        role_id INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, command)
+    )",
+    "CREATE TABLE IF NOT EXISTS premium_users (
+        user_id INTEGER PRIMARY KEY
+    )",
+    "CREATE TABLE IF NOT EXISTS guild_cooldowns (
+        guild_id INTEGER PRIMARY KEY,
+        delay_ms INTEGER NOT NULL,
+        max_uses INTEGER NOT NULL
+    )",
+    "CREATE TABLE IF NOT EXISTS guild_limits (
+        guild_id INTEGER PRIMARY KEY,
+        max_frames INTEGER,
+        max_dim INTEGER,
+        output_format TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS repost_detection (
+        guild_id INTEGER PRIMARY KEY
+    )",
+    "CREATE TABLE IF NOT EXISTS image_hashes (
+        guild_id INTEGER NOT NULL,
+        hash INTEGER NOT NULL,
+        channel_id INTEGER NOT NULL,
+        message_id INTEGER NOT NULL,
+        PRIMARY KEY (guild_id, message_id, hash)
+    )",
+    "CREATE TABLE IF NOT EXISTS cooldown_roles (
+        guild_id INTEGER NOT NULL,
+        role_id INTEGER NOT NULL,
+        factor REAL NOT NULL,
+        PRIMARY KEY (guild_id, role_id)
+    )",
+    "CREATE TABLE IF NOT EXISTS welcome_cards (
+        guild_id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        background TEXT
+    )",
+    "CREATE TABLE IF NOT EXISTS daily_images (
+        guild_id INTEGER PRIMARY KEY,
+        channel_id INTEGER NOT NULL,
+        minute INTEGER NOT NULL,
+        image_url TEXT,
+        source_channel_id INTEGER,
+        source_message_id INTEGER,
+        source_attachment_id INTEGER,
+        last_day INTEGER
+    )",
+    "CREATE TABLE IF NOT EXISTS avatar_tracking (
+        user_id INTEGER PRIMARY KEY
+    )",
+    "CREATE TABLE IF NOT EXISTS avatar_history (
+        user_id INTEGER NOT NULL,
+        hash TEXT NOT NULL,
+        thumbnail BLOB NOT NULL,
+        changed_at INTEGER NOT NULL,
+        PRIMARY KEY (user_id, hash)
+    )",
+    "CREATE TABLE IF NOT EXISTS custom_effects (
+        user_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        script TEXT NOT NULL,
+        PRIMARY KEY (user_id, name)
+    )",
+    "CREATE TABLE IF NOT EXISTS bot_state (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    )",
+];
+
+/// a handle to the bot's database, cheap to clone
+#[derive(Debug, Clone)]
+pub struct Database {
+    /// the underlying connection pool
+    pool: SqlitePool,
+}
+
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+impl Database {
+    /// connects to the database at the provided url and creates any missing tables
+    pub async fn connect(url: &str) -> Result<Self, Error> {
+        let pool = SqlitePoolOptions::new()
+            .connect(url)
+            .await?;
+
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&pool)
+                .await?;
+        }
+
+        Ok(Self { pool })
+    }
+
+    /// fetches the database handle stored in the client's data
+    pub async fn get(ctx: &Context) -> Result<Self, Error> {
+        ctx.data.read()
+            .await
+            .get::<DatabaseData>()
+            .cloned()
+            .ok_or(Error::DatabaseUnavailable)
+    }
+
+    /// enables or disables a command in a guild
+    pub async fn set_command_enabled(&self, guild_id: GuildId, command: &str, enabled: bool) -> Result<(), Error> {
+        let query = if enabled {
+            "DELETE FROM disabled_commands WHERE guild_id = ? AND command = ?"
+        } else {
+            "INSERT OR IGNORE INTO disabled_commands (guild_id, command) VALUES (?, ?)"
+        };
+
+        sqlx::query(query)
+            .bind(guild_id.0 as i64)
+            .bind(command)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// returns whether or not a command has been disabled in a guild
+    pub async fn is_command_disabled(&self, guild_id: GuildId, command: &str) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("SELECT 1 FROM disabled_commands WHERE guild_id = ? AND command = ?")
+                .bind(guild_id.0 as i64)
+                .bind(command)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some()
+        )
+    }
+
+    /// restricts a command in a guild to members with the provided role, or lifts the restriction if `None`
+    pub async fn set_command_restriction(
+        &self,
+        guild_id: GuildId,
+        command: &str,
+        role_id: Option<RoleId>,
+    ) -> Result<(), Error> {
+        if let Some(role_id) = role_id {
+            sqlx::query("INSERT OR REPLACE INTO command_restrictions (guild_id, command, role_id) VALUES (?, ?, ?)")
+                .bind(guild_id.0 as i64)
+                .bind(command)
+                .bind(role_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM command_restrictions WHERE guild_id = ? AND command = ?")
+                .bind(guild_id.0 as i64)
+                .bind(command)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the role a command has been restricted to in a guild, if any
+    pub async fn command_restriction(&self, guild_id: GuildId, command: &str) -> Result<Option<RoleId>, Error> {
+        Ok(
+            sqlx::query("SELECT role_id FROM command_restrictions WHERE guild_id = ? AND command = ?")
+                .bind(guild_id.0 as i64)
+                .bind(command)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| RoleId(row.get::<i64, _>("role_id") as u64))
+        )
+    }
+
+    /// grants or revokes the premium tier of a user
+    pub async fn set_premium(&self, user_id: UserId, premium: bool) -> Result<(), Error> {
+        let query = if premium {
+            "INSERT OR IGNORE INTO premium_users (user_id) VALUES (?)"
+        } else {
+            "DELETE FROM premium_users WHERE user_id = ?"
+        };
+
+        sqlx::query(query)
+            .bind(user_id.0 as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// returns whether or not a user has been granted the premium tier
+    pub async fn is_premium(&self, user_id: UserId) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("SELECT 1 FROM premium_users WHERE user_id = ?")
+                .bind(user_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some()
+        )
+    }
+
+    /// sets the cooldown of imaging commands in a guild
+    pub async fn set_cooldown(&self, guild_id: GuildId, cooldown: Cooldown) -> Result<(), Error> {
+        sqlx::query("INSERT OR REPLACE INTO guild_cooldowns (guild_id, delay_ms, max_uses) VALUES (?, ?, ?)")
+            .bind(guild_id.0 as i64)
+            .bind(cooldown.delay.as_millis() as i64)
+            .bind(cooldown.limit)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// returns the cooldown of imaging commands configured in a guild, if any
+    pub async fn cooldown(&self, guild_id: GuildId) -> Result<Option<Cooldown>, Error> {
+        Ok(
+            sqlx::query("SELECT delay_ms, max_uses FROM guild_cooldowns WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| Cooldown {
+                    delay: Duration::from_millis(row.get::<i64, _>("delay_ms") as u64),
+                    limit: row.get::<u32, _>("max_uses"),
+                })
+        )
+    }
+
+    /// scales the cooldown of imaging commands by `factor` for members holding the provided role in a guild,
+    /// or removes the role's factor if `None`
+    pub async fn set_cooldown_role(&self, guild_id: GuildId, role_id: RoleId, factor: Option<f64>) -> Result<(), Error> {
+        if let Some(factor) = factor {
+            sqlx::query("INSERT OR REPLACE INTO cooldown_roles (guild_id, role_id, factor) VALUES (?, ?, ?)")
+                .bind(guild_id.0 as i64)
+                .bind(role_id.0 as i64)
+                .bind(factor)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM cooldown_roles WHERE guild_id = ? AND role_id = ?")
+                .bind(guild_id.0 as i64)
+                .bind(role_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the roles of a guild that scale the cooldown of imaging commands, along with their factors
+    pub async fn cooldown_roles(&self, guild_id: GuildId) -> Result<Vec<(RoleId, f64)>, Error> {
+        Ok(
+            sqlx::query("SELECT role_id, factor FROM cooldown_roles WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| (
+                    RoleId(row.get::<i64, _>("role_id") as u64),
+                    row.get::<f64, _>("factor"),
+                ))
+                .collect()
+        )
+    }
+
+    /// sets the limit overrides of a guild, clearing them if they are all empty
+    pub async fn set_guild_limits(&self, guild_id: GuildId, limits: GuildLimits) -> Result<(), Error> {
+        if limits == GuildLimits::default() {
+            sqlx::query("DELETE FROM guild_limits WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("INSERT OR REPLACE INTO guild_limits (guild_id, max_frames, max_dim, output_format) VALUES (?, ?, ?, ?)")
+                .bind(guild_id.0 as i64)
+                .bind(limits.max_frames.map(|max_frames| max_frames as i64))
+                .bind(limits.max_dim)
+                .bind(limits.format.map(OutputFormat::as_str))
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the limit overrides set in a guild
+    pub async fn guild_limits(&self, guild_id: GuildId) -> Result<GuildLimits, Error> {
+        Ok(
+            sqlx::query("SELECT max_frames, max_dim, output_format FROM guild_limits WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| GuildLimits {
+                    max_frames: row.get::<Option<i64>, _>("max_frames")
+                        .map(|max_frames| max_frames as usize),
+                    max_dim: row.get::<Option<u32>, _>("max_dim"),
+                    format: row.get::<Option<String>, _>("output_format")
+                        .and_then(|format| format.parse().ok()),
+                })
+                .unwrap_or_default()
+        )
+    }
+
+    /// enables or disables the detection of reposted images in a guild, forgetting the recorded hashes when disabled
+    pub async fn set_repost_detection(&self, guild_id: GuildId, enabled: bool) -> Result<(), Error> {
+        if enabled {
+            sqlx::query("INSERT OR IGNORE INTO repost_detection (guild_id) VALUES (?)")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM repost_detection WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM image_hashes WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns whether or not the detection of reposted images has been enabled in a guild
+    pub async fn is_repost_detection_enabled(&self, guild_id: GuildId) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("SELECT 1 FROM repost_detection WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some()
+        )
+    }
+
+    /// sets the channel welcome cards are posted to in a guild, along with the name of their background,
+    /// or stops posting them if `channel_id` is `None`
+    pub async fn set_welcome(&self, guild_id: GuildId, channel_id: Option<ChannelId>, background: Option<&str>) -> Result<(), Error> {
+        if let Some(channel_id) = channel_id {
+            sqlx::query("INSERT OR REPLACE INTO welcome_cards (guild_id, channel_id, background) VALUES (?, ?, ?)")
+                .bind(guild_id.0 as i64)
+                .bind(channel_id.0 as i64)
+                .bind(background)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM welcome_cards WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the channel welcome cards are posted to in a guild along with the name of their background, if enabled
+    pub async fn welcome(&self, guild_id: GuildId) -> Result<Option<(ChannelId, Option<String>)>, Error> {
+        Ok(
+            sqlx::query("SELECT channel_id, background FROM welcome_cards WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| (
+                    ChannelId(row.get::<i64, _>("channel_id") as u64),
+                    row.get::<Option<String>, _>("background"),
+                ))
+        )
+    }
+
+    /// sets the channel and minute of the day the image of the day of a guild is posted at, along with the url of its image,
+    /// or stops posting it if `channel_id` is `None`
+    ///
+    /// the day it was last posted on is kept, so that reconfiguring it does not post it twice in a day,
+    /// and `source` is the attached file the image url points to, if any, from which a fresh url is fetched once it expires
+    pub async fn set_daily(
+        &self,
+        guild_id: GuildId,
+        channel_id: Option<ChannelId>,
+        minute: u32,
+        image_url: Option<&str>,
+        source: Option<AttachmentRef>,
+    ) -> Result<(), Error> {
+        if let Some(channel_id) = channel_id {
+            sqlx::query(
+                "INSERT INTO daily_images (guild_id, channel_id, minute, image_url, source_channel_id, source_message_id, source_attachment_id)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (guild_id) DO UPDATE SET
+                    channel_id = excluded.channel_id,
+                    minute = excluded.minute,
+                    image_url = excluded.image_url,
+                    source_channel_id = excluded.source_channel_id,
+                    source_message_id = excluded.source_message_id,
+                    source_attachment_id = excluded.source_attachment_id",
+            )
+                .bind(guild_id.0 as i64)
+                .bind(channel_id.0 as i64)
+                .bind(i64::from(minute))
+                .bind(image_url)
+                .bind(source.map(|source| source.channel_id.0 as i64))
+                .bind(source.map(|source| source.message_id.0 as i64))
+                .bind(source.map(|source| source.attachment_id.0 as i64))
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM daily_images WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns the image of the day configured by a guild, if enabled
+    pub async fn daily_image(&self, guild_id: GuildId) -> Result<Option<DailyImage>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM daily_images WHERE guild_id = ?")
+                .bind(guild_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| Self::daily_from_row(&row))
+        )
+    }
+
+    /// returns the images of the day configured by every guild
+    pub async fn daily_images(&self) -> Result<Vec<DailyImage>, Error> {
+        Ok(
+            sqlx::query("SELECT * FROM daily_images")
+                .fetch_all(&self.pool)
+                .await?
+                .iter()
+                .map(Self::daily_from_row)
+                .collect()
+        )
+    }
+
+    /// records that the image of the day of a guild has been posted on the provided day
+    pub async fn mark_daily_posted(&self, guild_id: GuildId, day: u64) -> Result<(), Error> {
+        sqlx::query("UPDATE daily_images SET last_day = ? WHERE guild_id = ?")
+            .bind(day as i64)
+            .bind(guild_id.0 as i64)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// reads an image of the day from a row of the `daily_images` table
+    fn daily_from_row(row: &SqliteRow) -> DailyImage {
+        DailyImage {
+            guild_id: GuildId(row.get::<i64, _>("guild_id") as u64),
+            channel_id: ChannelId(row.get::<i64, _>("channel_id") as u64),
+            minute: row.get::<i64, _>("minute") as u32,
+            image_url: row.get::<Option<String>, _>("image_url"),
+            source: match (
+                row.get::<Option<i64>, _>("source_channel_id"),
+                row.get::<Option<i64>, _>("source_message_id"),
+                row.get::<Option<i64>, _>("source_attachment_id"),
+            ) {
+                (Some(channel_id), Some(message_id), Some(attachment_id)) => Some(AttachmentRef {
+                    channel_id: ChannelId(channel_id as u64),
+                    message_id: MessageId(message_id as u64),
+                    attachment_id: AttachmentId(attachment_id as u64),
+                }),
+                _ => None,
+            },
+            last_day: row.get::<Option<i64>, _>("last_day")
+                .map(|day| day as u64),
+        }
+    }
+
+    /// returns the most recent message of a guild with an image whose hash is within `threshold` bits of the provided one
+    ///
+    /// sqlite has no way to count bits, so only the hashes sharing one of the [`hashing::bands`] of the provided one are fetched,
+    /// which every hash close enough does, and their distance is then compared here
+    pub async fn find_similar_hash(
+        &self,
+        guild_id: GuildId,
+        hash: u64,
+        threshold: u32,
+    ) -> Result<Option<(ChannelId, MessageId)>, Error> {
+        let bands = hashing::bands(hash, threshold);
+        let sql = format!(
+            "SELECT hash, channel_id, message_id FROM image_hashes WHERE guild_id = ? AND ({}) ORDER BY message_id DESC",
+            vec!["((hash >> ?) & ?) = ?"; bands.len()].join(" OR "),
+        );
+
+        let mut query = sqlx::query(sql.as_str())
+            .bind(guild_id.0 as i64);
+        for (shift, mask, value) in bands {
+            query = query.bind(shift)
+                .bind(mask as i64)
+                .bind(value as i64);
+        }
+
+        Ok(
+            query.fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .find(|row| hashing::distance(row.get::<i64, _>("hash") as u64, hash) <= threshold)
+                .map(|row| (
+                    ChannelId(row.get::<i64, _>("channel_id") as u64),
+                    MessageId(row.get::<i64, _>("message_id") as u64),
+                ))
+        )
+    }
+
+    /// records the hash of an image posted in a guild, keeping only the most recent [`REPOST_HISTORY`] hashes
+    pub async fn record_hash(
+        &self,
+        guild_id: GuildId,
+        hash: u64,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Result<(), Error> {
+        sqlx::query("INSERT OR IGNORE INTO image_hashes (guild_id, hash, channel_id, message_id) VALUES (?, ?, ?, ?)")
+            .bind(guild_id.0 as i64)
+            .bind(hash as i64)
+            .bind(channel_id.0 as i64)
+            .bind(message_id.0 as i64)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            "DELETE FROM image_hashes WHERE guild_id = ? AND message_id NOT IN (
+                SELECT message_id FROM image_hashes WHERE guild_id = ? ORDER BY message_id DESC LIMIT ?
+            )"
+        )
+            .bind(guild_id.0 as i64)
+            .bind(guild_id.0 as i64)
+            .bind(REPOST_HISTORY)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// opts a user in to or out of having their avatars tracked, forgetting those tracked so far when opting out
+    pub async fn set_avatar_tracking(&self, user_id: UserId, enabled: bool) -> Result<(), Error> {
+        if enabled {
+            sqlx::query("INSERT OR IGNORE INTO avatar_tracking (user_id) VALUES (?)")
+                .bind(user_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        } else {
+            sqlx::query("DELETE FROM avatar_tracking WHERE user_id = ?")
+                .bind(user_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+            sqlx::query("DELETE FROM avatar_history WHERE user_id = ?")
+                .bind(user_id.0 as i64)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// returns whether or not a user has opted in to having their avatars tracked
+    pub async fn is_avatar_tracked(&self, user_id: UserId) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("SELECT 1 FROM avatar_tracking WHERE user_id = ?")
+                .bind(user_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some()
+        )
+    }
+
+    /// returns the hash of the most recent avatar recorded for a user, if any
+    pub async fn latest_avatar(&self, user_id: UserId) -> Result<Option<String>, Error> {
+        Ok(
+            sqlx::query("SELECT hash FROM avatar_history WHERE user_id = ? ORDER BY changed_at DESC LIMIT 1")
+                .bind(user_id.0 as i64)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| row.get::<String, _>("hash"))
+        )
+    }
+
+    /// records an avatar of a user, keeping only their most recent [`AVATAR_HISTORY`] avatars
+    ///
+    /// switching back to an earlier avatar moves it to the front rather than recording it twice
+    pub async fn record_avatar(&self, user_id: UserId, entry: &AvatarEntry) -> Result<(), Error> {
+        sqlx::query("INSERT OR REPLACE INTO avatar_history (user_id, hash, thumbnail, changed_at) VALUES (?, ?, ?, ?)")
+            .bind(user_id.0 as i64)
+            .bind(&entry.hash)
+            .bind(&entry.thumbnail)
+            .bind(entry.changed_at as i64)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            "DELETE FROM avatar_history WHERE user_id = ? AND hash NOT IN (
+                SELECT hash FROM avatar_history WHERE user_id = ? ORDER BY changed_at DESC LIMIT ?
+            )"
+        )
+            .bind(user_id.0 as i64)
+            .bind(user_id.0 as i64)
+            .bind(AVATAR_HISTORY)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// returns the avatars recorded for a user, from the most recent to the oldest
+    pub async fn avatar_history(&self, user_id: UserId) -> Result<Vec<AvatarEntry>, Error> {
+        Ok(
+            sqlx::query("SELECT hash, thumbnail, changed_at FROM avatar_history WHERE user_id = ? ORDER BY changed_at DESC")
+                .bind(user_id.0 as i64)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| AvatarEntry {
+                    hash: row.get::<String, _>("hash"),
+                    thumbnail: row.get::<Vec<u8>, _>("thumbnail"),
+                    changed_at: row.get::<i64, _>("changed_at") as u64,
+                })
+                .collect()
+        )
+    }
+
+    /// saves the script of a custom effect of a user under `name`, replacing any previously saved under it
+    pub async fn save_custom_effect(&self, user_id: UserId, name: &str, script: &str) -> Result<(), Error> {
+        sqlx::query("INSERT OR REPLACE INTO custom_effects (user_id, name, script) VALUES (?, ?, ?)")
+            .bind(user_id.0 as i64)
+            .bind(name)
+            .bind(script)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// deletes the custom effect of a user saved under `name`, returning whether or not there was one
+    pub async fn delete_custom_effect(&self, user_id: UserId, name: &str) -> Result<bool, Error> {
+        Ok(
+            sqlx::query("DELETE FROM custom_effects WHERE user_id = ? AND name = ?")
+                .bind(user_id.0 as i64)
+                .bind(name)
+                .execute(&self.pool)
+                .await?
+                .rows_affected() > 0
+        )
+    }
+
+    /// returns the script of the custom effect of a user saved under `name`, if any
+    pub async fn custom_effect(&self, user_id: UserId, name: &str) -> Result<Option<String>, Error> {
+        Ok(
+            sqlx::query("SELECT script FROM custom_effects WHERE user_id = ? AND name = ?")
+                .bind(user_id.0 as i64)
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| row.get::<String, _>("script"))
+        )
+    }
+
+    /// returns the names and scripts of the custom effects saved by a user, ordered by name
+    pub async fn custom_effects(&self, user_id: UserId) -> Result<Vec<(String, String)>, Error> {
+        Ok(
+            sqlx::query("SELECT name, script FROM custom_effects WHERE user_id = ? ORDER BY name")
+                .bind(user_id.0 as i64)
+                .fetch_all(&self.pool)
+                .await?
+                .into_iter()
+                .map(|row| (row.get::<String, _>("name"), row.get::<String, _>("script")))
+                .collect()
+        )
+    }
+
+    /// saves a piece of the bot's runtime state under `key`, replacing any previously saved under it
+    pub async fn set_state(&self, key: &str, value: &str) -> Result<(), Error> {
+        sqlx::query("INSERT OR REPLACE INTO bot_state (key, value) VALUES (?, ?)")
+            .bind(key)
+            .bind(value)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// returns the piece of the bot's runtime state saved under `key`, if any
+    pub async fn state(&self, key: &str) -> Result<Option<String>, Error> {
+        Ok(
+            sqlx::query("SELECT value FROM bot_state WHERE key = ?")
+                .bind(key)
+                .fetch_optional(&self.pool)
+                .await?
+                .map(|row| row.get::<String, _>("value"))
+        )
+    }
+}
\ No newline at end of file
@@ -0,0 +1,296 @@
+//! contains the premium tier system, which determines the limits each user's jobs are subject to,
+//! along with the exemptions from the cooldown of imaging commands
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    prelude::Context,
+    model::{
+        user::User,
+        id::{GuildId, RoleId, UserId},
+    },
+};
+
+use crate::{ConfigData, OwnersData};
+use super::{
+    Error,
+    database::Database,
+    imaging::{DEFAULT_MAX_DIM, DEFAULT_MAX_FRAMES},
+    resolver::DEFAULT_MAX_SIZE,
+    options::OutputFormat,
+};
+
+
+/// how long a looked up tier is reused for before it is looked up again
+pub const TIER_TTL: Duration = Duration::from_secs(300);
+/// the key the cached tiers are saved under in the database
+pub const TIERS_STATE_KEY: &str = "tiers";
+
+lazy_static::lazy_static! {
+    /// the tiers looked up within the last [`TIER_TTL`], along with when they were looked up
+    static ref TIERS: Mutex<HashMap<UserId, (Tier, Instant)>> = Mutex::new(HashMap::new());
+}
+
+/// the tier a user is entitled to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tier {
+    /// the default tier
+    Free,
+    /// granted by a bot owner or by holding the configured patron role
+    Premium,
+}
+
+/// the limits a job is subject to, determined by the invoking user's [`Tier`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// the max size in bytes of a resolved image
+    pub max_size: u64,
+    /// the max number of frames of an input image
+    pub max_frames: usize,
+    /// the max height of an input image, larger images are scaled down
+    pub max_dim: u32,
+    /// the cooldown between imaging commands
+    pub cooldown: Duration,
+}
+
+/// the overrides a guild has set for the jobs run within it, which can only lower the limits of a [`Tier`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GuildLimits {
+    /// the max number of frames of an input image
+    pub max_frames: Option<usize>,
+    /// the max height of an input image
+    pub max_dim: Option<u32>,
+    /// the default format outputs are encoded in
+    pub format: Option<OutputFormat>,
+}
+
+impl Tier {
+    /// returns the limits of this tier
+    #[must_use]
+    pub const fn limits(self) -> Limits {
+        match self {
+            Self::Free => Limits {
+                max_size: DEFAULT_MAX_SIZE,
+                max_frames: DEFAULT_MAX_FRAMES,
+                max_dim: DEFAULT_MAX_DIM,
+                cooldown: Duration::from_secs(5),
+            },
+            Self::Premium => Limits {
+                max_size: DEFAULT_MAX_SIZE * 2,
+                max_frames: DEFAULT_MAX_FRAMES * 2,
+                max_dim: DEFAULT_MAX_DIM * 2,
+                cooldown: Duration::ZERO,
+            },
+        }
+    }
+
+    /// looks up the tier of the provided user, reusing the tier looked up within the last [`TIER_TTL`] if any,
+    /// as it requires a query and possibly a request for the patron role
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub async fn lookup(ctx: &Context, user: &User) -> Self {
+        let now = Instant::now();
+
+        if let Some((tier, _)) = TIERS.lock()
+            .unwrap()
+            .get(&user.id)
+            .filter(|(_, looked_up)| now.duration_since(*looked_up) < TIER_TTL)
+        {
+            return *tier;
+        }
+
+        let tier = Self::fetch(ctx, user)
+            .await;
+
+        let mut tiers = TIERS.lock()
+            .unwrap();
+        tiers.retain(|_, (_, looked_up)| now.duration_since(*looked_up) < TIER_TTL);
+        tiers.insert(user.id, (tier, now));
+
+        tier
+    }
+
+    /// forgets the cached tier of the provided user, for when it has been changed
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn forget(user_id: UserId) {
+        TIERS.lock()
+            .unwrap()
+            .remove(&user_id);
+    }
+
+    /// saves the cached tiers to the database, so that restarting the bot does not look every active user up again
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub async fn save(database: &Database) -> Result<(), Error> {
+        let now = Instant::now();
+        let snapshot = TierSnapshot {
+            saved_at: SystemTime::now(),
+            tiers: TIERS.lock()
+                .unwrap()
+                .iter()
+                .filter(|(_, (_, looked_up))| now.duration_since(*looked_up) < TIER_TTL)
+                .map(|(user_id, (tier, looked_up))| (user_id.0, (*tier, now.duration_since(*looked_up))))
+                .collect(),
+        };
+        let state = serde_json::to_string(&snapshot)
+            .unwrap_or_default();
+
+        database.set_state(TIERS_STATE_KEY, state.as_str())
+            .await
+    }
+
+    /// restores the cached tiers saved to the database, if there are any, counting the time the bot was down towards their expiry
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub async fn load(database: &Database) -> Result<(), Error> {
+        let Some(snapshot) = database.state(TIERS_STATE_KEY)
+            .await?
+            .and_then(|state| serde_json::from_str::<TierSnapshot>(state.as_str()).ok())
+        else {
+            return Ok(());
+        };
+
+        let now = Instant::now();
+        let downtime = snapshot.saved_at
+            .elapsed()
+            .unwrap_or_default();
+        let mut tiers = TIERS.lock()
+            .unwrap();
+
+        for (user_id, (tier, age)) in snapshot.tiers {
+            let age = age + downtime;
+
+            if let Some(looked_up) = (age < TIER_TTL)
+                .then(|| now.checked_sub(age))
+                .flatten()
+            {
+                tiers.insert(UserId(user_id), (tier, looked_up));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// fetches the tier of the provided user,
+    /// who is premium if granted by an owner or if they hold the configured patron role
+    async fn fetch(ctx: &Context, user: &User) -> Self {
+        if let Ok(database) = Database::get(ctx).await {
+            if database.is_premium(user.id)
+                .await
+                .unwrap_or(false)
+            {
+                return Self::Premium;
+            }
+        }
+
+        let patron_role = ctx.data.read()
+            .await
+            .get::<ConfigData>()
+            .and_then(|config| config.patron_guild_id.zip(config.patron_role_id));
+
+        if let Some((guild_id, role_id)) = patron_role {
+            if let Ok(member) = GuildId(guild_id)
+                .member(ctx, user.id)
+                .await
+            {
+                if member.roles.contains(&RoleId(role_id)) {
+                    return Self::Premium;
+                }
+            }
+        }
+
+        Self::Free
+    }
+}
+
+/// a serializable copy of the cached tiers, as how long before it was saved each tier was looked up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TierSnapshot {
+    /// when the snapshot was saved
+    saved_at: SystemTime,
+    /// the tier of each user and how long before the snapshot was saved it was looked up
+    tiers: HashMap<u64, (Tier, Duration)>,
+}
+
+impl Limits {
+    /// a shortcut for looking up the limits of the provided user's [`Tier`]
+    pub async fn lookup(ctx: &Context, user: &User) -> Self {
+        Tier::lookup(ctx, user)
+            .await
+            .limits()
+    }
+}
+
+impl GuildLimits {
+    /// applies these overrides to the provided limits, never raising them above those of the tier
+    #[must_use]
+    pub fn apply(self, limits: Limits) -> Limits {
+        Limits {
+            max_frames: self.max_frames
+                .map_or(limits.max_frames, |max_frames| max_frames.min(limits.max_frames)),
+            max_dim: self.max_dim
+                .map_or(limits.max_dim, |max_dim| max_dim.min(limits.max_dim)),
+            ..limits
+        }
+    }
+
+    /// looks up the overrides of the provided guild, which are empty in direct messages or if the database is unavailable
+    pub async fn lookup(ctx: &Context, guild_id: Option<GuildId>) -> Self {
+        let Some(guild_id) = guild_id else {
+            return Self::default();
+        };
+
+        match Database::get(ctx).await {
+            Ok(database) => database.guild_limits(guild_id)
+                .await
+                .unwrap_or_default(),
+            Err(_) => Self::default(),
+        }
+    }
+}
+
+/// returns the factor the cooldown of imaging commands is scaled by for the provided user, from `0.0` when they are exempt to `1.0`
+///
+/// the bot's owners and premium users are exempt, while within a guild, members holding any of the roles it configured
+/// with the `cooldownrole` command have their cooldown scaled by the lowest factor among those roles
+///
+/// takes the user's already looked up `tier`, so that a single invocation only looks it up once
+pub async fn cooldown_factor(ctx: &Context, user: &User, tier: Tier, guild_id: Option<GuildId>) -> f64 {
+    let is_owner = ctx.data.read()
+        .await
+        .get::<OwnersData>()
+        .map_or(false, |owners| owners.contains(&user.id));
+
+    if is_owner || tier == Tier::Premium {
+        return 0.0;
+    }
+
+    let (Some(guild_id), Ok(database)) = (guild_id, Database::get(ctx).await) else {
+        return 1.0;
+    };
+    let roles = database.cooldown_roles(guild_id)
+        .await
+        .unwrap_or_default();
+
+    // the member is only fetched if the guild configured any roles, as it may require a request
+    if roles.is_empty() {
+        return 1.0;
+    }
+    let Ok(member) = guild_id.member(ctx, user.id).await else {
+        return 1.0;
+    };
+
+    roles.into_iter()
+        .filter(|(role_id, _)| member.roles.contains(role_id))
+        .map(|(_, factor)| factor)
+        .fold(1.0, f64::min)
+}
\ No newline at end of file
@@ -4,7 +4,7 @@
 use std::fmt;
 use serenity::{
     prelude::SerenityError,
-    framework::standard::CommandError,
+    utils::Colour,
 };
 
 use super::helpers::humanize_bytes;
@@ -14,7 +14,10 @@ use super::helpers::humanize_bytes;
 /// used by all the self-defined functions in this module such as the utility functions etc.
 ///
 /// Implements `From<E>` for all the errors from other libraries propogated
-/// and `Into<CommandError>` for easy error handling within the bot commands.
+/// and [`std::error::Error`], so that it can be boxed into a `CommandError` and downcasted back in the error handler,
+/// with the propogated errors as its source.
+///
+/// Its [`fmt::Display`] implementation is meant to be shown to users, while [`Error::report`] is meant for the logs.
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
 pub enum Error {
@@ -32,15 +35,101 @@ pub enum Error {
         /// maximum allowed image size
         u64,
     ),
+    /// Returned by [`super::memory`] when a job would allocate more memory than its ceiling
+    MemoryLimit(
+        /// the memory required, in bytes
+        u64,
+        /// the memory available, in bytes
+        u64,
+    ),
     /// Returned in [`super::resolver::ImageResolver::convert_emoji`] when an emoji could not be parsed from the argument
     EmojiParseError(
         /// provided argument that we failed to parse into an emoji
         String,
     ),
+    /// Returned when a command is invoked without an argument it requires
+    MissingArgument(
+        /// name of the missing argument
+        &'static str,
+    ),
+    /// Returned when the provided name is not a valid name for a guild emoji or sticker
+    InvalidName(
+        /// the provided name
+        String,
+    ),
+    /// Returned when a URL is refused by the [`super::policy::FetchPolicy`]
+    BlockedUrl(
+        /// the refused URL
+        String,
+    ),
     /// Returned when the image URL is invalid or returned a non-ok status code
     FetchUrlError,
     /// Returned when the content-type of the provided source is not of `image/*`
     InvalidContentType,
+    /// Returned when a `data:` URI is malformed or is not base64 encoded
+    InvalidDataUri,
+    /// Returned by [`super::codecs`] when an image in a format ril can not decode could not be decoded
+    CodecError(
+        /// the reason the image could not be decoded
+        String,
+    ),
+    /// Returned when an animated result is to be encoded in a format that can only hold a single frame
+    AnimatedOutput(
+        /// the name of the requested format
+        &'static str,
+    ),
+    /// Returned by [`super::ocr::read_text`] when the text of an image could not be recognized
+    OcrError(
+        /// the reason the text could not be recognized
+        String,
+    ),
+    /// Returned by [`super::resolver::ImageResolver::resolve_flag`] when the flag of a country could not be found
+    InvalidCountryCode(
+        /// the provided argument that is not a known country code
+        String,
+    ),
+    /// Returned by [`super::flags::Flags::get`] when a flag's value could not be parsed
+    InvalidFlag(
+        /// name of the flag
+        String,
+        /// provided value that we failed to parse
+        String,
+    ),
+    /// Returned when the database has not been initialized in the client's data
+    DatabaseUnavailable,
+    /// Returned when an image job stopped before returning a result, such as from a panic
+    JobFailed,
+    /// Returned when an image job was cancelled by its user
+    Cancelled,
+    /// Returned when an image job panicked, which is logged under an incident id
+    RendererCrashed(
+        /// the id of the incident the panic was logged under
+        String,
+    ),
+    /// Returned by [`super::video::encode`] when a video could not be encoded
+    VideoError(
+        /// the reason the video could not be encoded
+        String,
+    ),
+    /// Returned by [`super::templates::Template::read`] when a template or its manifest is missing or invalid
+    MissingTemplate(
+        /// the name of the template
+        String,
+    ),
+    /// Returned when the archive bundling the outputs exceeds the maximum allowed size
+    ArchiveTooLarge(
+        /// size of the archive
+        u64,
+        /// maximum allowed archive size
+        u64,
+    ),
+    /// Wraps another error with the phase of the job it was raised in, see [`Error::context`]
+    WithContext(
+        /// the phase the error was raised in
+        Phase,
+        /// the error raised
+        Box<Error>,
+    ),
     /// Propogated from [`reqwest::Error`]
     RequestError(
         /// Error propogated from
@@ -56,6 +145,284 @@ pub enum Error {
         /// Error propogated from
         ril::Error,
     ),
+    /// Propogated from [`sqlx::Error`]
+    DatabaseError(
+        /// Error propogated from
+        sqlx::Error,
+    ),
+    /// Propogated from [`zip::result::ZipError`]
+    ArchiveError(
+        /// Error propogated from
+        zip::result::ZipError,
+    ),
+    /// Propogated from [`qrcode::types::QrError`]
+    QrError(
+        /// Error propogated from
+        qrcode::types::QrError,
+    ),
+}
+
+/// the broad category an [`Error`] falls under, shown in the error embed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// the provided input could not be used
+    Input,
+    /// the provided input exceeds a limit
+    Limit,
+    /// a request to an external service failed
+    Network,
+    /// something went wrong within the bot itself
+    Internal,
+}
+
+impl ErrorCategory {
+    /// the title of the error embed for this category
+    #[must_use]
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::Input => "Invalid Input",
+            Self::Limit => "Limit Exceeded",
+            Self::Network => "Network Error",
+            Self::Internal => "Internal Error",
+        }
+    }
+
+    /// the color of the error embed for this category
+    #[must_use]
+    pub const fn colour(self) -> Colour {
+        match self {
+            Self::Input => Colour::GOLD,
+            Self::Limit => Colour::ORANGE,
+            Self::Network => Colour::BLUE,
+            Self::Internal => Colour::RED,
+        }
+    }
+}
+
+/// the phase of a job an [`Error`] was raised in, attached to it with [`Error::context`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Phase {
+    /// fetching the source image from the url
+    Fetching(String),
+    /// decoding the source image
+    Decoding,
+    /// executing the function on the image
+    Processing,
+    /// encoding the output
+    Encoding,
+    /// sending the output
+    Sending,
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fetching(url) => write!(f, "while fetching `{url}`"),
+            Self::Decoding => f.write_str("while decoding the image"),
+            Self::Processing => f.write_str("while processing the image"),
+            Self::Encoding => f.write_str("while encoding the output"),
+            Self::Sending => f.write_str("while sending the output"),
+        }
+    }
+}
+
+/// formats an [`Error`] for the logs, with each phase it was raised in and the chain of errors that caused it,
+/// returned by [`Error::report`]
+#[derive(Debug)]
+pub struct Report<'a>(&'a Error);
+
+impl fmt::Display for Report<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut error = self.0;
+        while let Error::WithContext(phase, inner) = error {
+            write!(f, "{phase}: ")?;
+            error = inner;
+        }
+
+        let mut last = error.to_string();
+        f.write_str(&last)?;
+
+        // wrapped errors are shown to users as they are, so each cause is only written if it adds anything
+        let mut cause = std::error::Error::source(error);
+        while let Some(err) = cause {
+            let message = err.to_string();
+            if message != last {
+                write!(f, ": {message}")?;
+                last = message;
+            }
+            cause = err.source();
+        }
+
+        Ok(())
+    }
+}
+
+impl Error {
+    /// wraps this error with the phase of the job it was raised in
+    #[must_use]
+    pub fn context(self, phase: Phase) -> Self {
+        Self::WithContext(phase, Box::new(self))
+    }
+
+    /// returns the error wrapped by any contexts, which determines its category, code and hint
+    #[must_use]
+    pub fn root(&self) -> &Self {
+        match self {
+            Self::WithContext(_, inner) => inner.root(),
+            other => other,
+        }
+    }
+
+    /// returns the outermost phase this error was raised in, if any
+    #[must_use]
+    pub const fn phase(&self) -> Option<&Phase> {
+        match self {
+            Self::WithContext(phase, _) => Some(phase),
+            _ => None,
+        }
+    }
+
+    /// returns this error formatted for the logs, along with its phases and causes,
+    /// whereas its [`fmt::Display`] implementation is meant to be shown to users
+    #[must_use]
+    pub const fn report(&self) -> Report<'_> {
+        Report(self)
+    }
+
+    /// returns the innermost cause of this error if it differs from the error itself,
+    /// such as the reason a request failed, to be shown along with the error
+    #[must_use]
+    pub fn cause(&self) -> Option<String> {
+        let root = self.root();
+        let mut cause = std::error::Error::source(root)?;
+        while let Some(next) = cause.source() {
+            cause = next;
+        }
+
+        let message = cause.to_string();
+        (message != root.to_string()).then_some(message)
+    }
+
+    /// returns the category of this error
+    #[must_use]
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::WithContext(_, inner) => inner.category(),
+            Self::EmojiParseError(_)
+            | Self::MissingArgument(_)
+            | Self::InvalidName(_)
+            | Self::InvalidContentType
+            | Self::InvalidDataUri
+            | Self::CodecError(_)
+            | Self::AnimatedOutput(_)
+            | Self::OcrError(_)
+            | Self::InvalidCountryCode(_)
+            | Self::InvalidFlag(..)
+            | Self::Cancelled
+            | Self::RilError(_)
+            | Self::QrError(_) => ErrorCategory::Input,
+            Self::TooManyFrames(..)
+            | Self::ImageTooLarge(..)
+            | Self::ArchiveTooLarge(..)
+            | Self::MemoryLimit(..)
+            | Self::BlockedUrl(_) => ErrorCategory::Limit,
+            Self::FetchUrlError
+            | Self::RequestError(_)
+            | Self::SerenityError(_) => ErrorCategory::Network,
+            Self::DatabaseUnavailable
+            | Self::JobFailed
+            | Self::RendererCrashed(_)
+            | Self::MissingTemplate(_)
+            | Self::VideoError(_)
+            | Self::DatabaseError(_)
+            | Self::ArchiveError(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// returns the unique code of this error, shown in the error embed so that it can be referred to when asking for support
+    #[must_use]
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::WithContext(_, inner) => inner.code(),
+            Self::MissingArgument(_) => "E100",
+            Self::InvalidFlag(..) => "E101",
+            Self::InvalidName(_) => "E102",
+            Self::EmojiParseError(_) => "E103",
+            Self::InvalidContentType => "E104",
+            Self::InvalidDataUri => "E105",
+            Self::RilError(_) => "E106",
+            Self::Cancelled => "E107",
+            Self::CodecError(_) => "E108",
+            Self::AnimatedOutput(_) => "E109",
+            Self::QrError(_) => "E110",
+            Self::OcrError(_) => "E111",
+            Self::InvalidCountryCode(_) => "E112",
+            Self::TooManyFrames(..) => "E200",
+            Self::ImageTooLarge(..) => "E201",
+            Self::ArchiveTooLarge(..) => "E202",
+            Self::BlockedUrl(_) => "E203",
+            Self::MemoryLimit(..) => "E204",
+            Self::FetchUrlError => "E300",
+            Self::RequestError(_) => "E301",
+            Self::SerenityError(_) => "E302",
+            Self::JobFailed => "E400",
+            Self::DatabaseUnavailable => "E401",
+            Self::DatabaseError(_) => "E402",
+            Self::ArchiveError(_) => "E403",
+            Self::RendererCrashed(_) => "E404",
+            Self::MissingTemplate(_) => "E405",
+            Self::VideoError(_) => "E406",
+        }
+    }
+
+    /// returns a hint on how the user may get around this error, if there is one
+    #[must_use]
+    pub fn hint(&self) -> Option<&'static str> {
+        Some(
+            match self {
+                Self::WithContext(_, inner) =>
+                    return inner.hint(),
+                Self::MissingArgument(_) | Self::InvalidFlag(..) =>
+                    "Check the usage of the command with the `help` command",
+                Self::InvalidName(_) =>
+                    "Try a name such as `my_emoji`",
+                Self::AnimatedOutput(_) =>
+                    "Pick a single frame with `--frame=<n>`, or an animated format such as `--format=gif`",
+                Self::QrError(_) =>
+                    "Try a shorter text, or one without a logo",
+                Self::OcrError(_) =>
+                    "Try a sharper image, or a different language with `--lang=<code>`",
+                Self::InvalidCountryCode(_) =>
+                    "Try a two letter country code such as `ca`, or a flag emoji",
+                Self::EmojiParseError(_) =>
+                    "Try a custom emoji such as `<:name:id>`, its id, or a default emoji",
+                Self::InvalidContentType | Self::InvalidDataUri | Self::CodecError(_) | Self::RilError(_) =>
+                    "Try a `png`, `jpeg`, `gif` or `webp` image, or a direct link to one",
+                Self::TooManyFrames(..) =>
+                    "Try an animation with fewer frames, or trim it first",
+                Self::ImageTooLarge(..) =>
+                    "Try a smaller image, or compress it first",
+                Self::ArchiveTooLarge(..) =>
+                    "Try fewer or smaller images",
+                Self::MemoryLimit(..) =>
+                    "Try a smaller image or an animation with fewer frames",
+                Self::BlockedUrl(_) =>
+                    "Try uploading the image as an attachment instead",
+                Self::FetchUrlError | Self::RequestError(_) =>
+                    "Make sure the link is a public, direct link and try again",
+                Self::SerenityError(_) | Self::JobFailed | Self::DatabaseUnavailable =>
+                    "Try again in a moment",
+                Self::RendererCrashed(_) =>
+                    "Try a different image, and mention the incident id when reporting this",
+                Self::MissingTemplate(_) =>
+                    "Let the bot's owner know, so that the template can be installed",
+                Self::VideoError(_) =>
+                    "Try an image format instead, such as `--format=gif`",
+                Self::Cancelled | Self::DatabaseError(_) | Self::ArchiveError(_) =>
+                    return None,
+            }
+        )
+    }
 }
 
 impl fmt::Display for Error {
@@ -69,18 +436,67 @@ impl fmt::Display for Error {
                         humanize_bytes(*size),
                         humanize_bytes(*max_size),
                     ),
+                Self::MemoryLimit(required, available) =>
+                    format!("Processing this image needs an estimated `{}` of memory, but only `{}` is available",
+                        humanize_bytes(*required),
+                        humanize_bytes(*available),
+                    ),
                 Self::EmojiParseError(argument) =>
                     format!("An emoji could not be parsed from the provided argument: `{argument}`"),
+                Self::MissingArgument(name) =>
+                    format!("Missing required argument: `{name}`"),
+                Self::InvalidName(name) =>
+                    format!("`{name}` is not a valid name, names must be 2-32 characters of only letters, numbers and underscores"),
+                Self::BlockedUrl(url) =>
+                    format!("Fetching the URL `{url}` is not allowed"),
                 Self::FetchUrlError =>
                     String::from("Something went wrong during the HTTP request to the provided URL"),
                 Self::InvalidContentType =>
                     String::from("Only content types of `image/*` are supported"),
-                Self::RequestError(err) =>
-                    err.to_string(),
-                Self::SerenityError(err) =>
-                    err.to_string(),
+                Self::InvalidDataUri =>
+                    String::from("The provided `data:` URI is malformed, only base64 encoded images are supported"),
+                Self::CodecError(reason) =>
+                    format!("The provided image could not be decoded: {reason}"),
+                Self::OcrError(reason) =>
+                    format!("The text of the provided image could not be read: {reason}"),
+                Self::InvalidCountryCode(argument) =>
+                    format!("There is no country flag for `{argument}`"),
+                Self::AnimatedOutput(format) =>
+                    format!("The result is animated, which can not be encoded as a `{format}`"),
+                Self::InvalidFlag(name, value) =>
+                    format!("Invalid value `{value}` provided for the flag `--{name}`"),
+                Self::DatabaseUnavailable =>
+                    String::from("The database is currently unavailable"),
+                Self::JobFailed =>
+                    String::from("Something went wrong while processing the image"),
+                Self::Cancelled =>
+                    String::from("The job was cancelled"),
+                Self::RendererCrashed(incident) =>
+                    format!("The renderer crashed on this image (incident `{incident}`)"),
+                Self::MissingTemplate(name) =>
+                    format!("The template `{name}` is not installed"),
+                Self::VideoError(reason) =>
+                    format!("The video could not be encoded: {reason}"),
+                Self::ArchiveTooLarge(size, max_size) =>
+                    format!("The output archive has a size of `{}` which exceeds the limit of `{}`",
+                        humanize_bytes(*size),
+                        humanize_bytes(*max_size),
+                    ),
+                Self::WithContext(phase, inner) =>
+                    format!("{inner} {phase}"),
+                // the errors of other libraries are summarized for users, with the errors themselves kept as the source
+                Self::RequestError(_) =>
+                    String::from("The request to the provided URL failed"),
+                Self::SerenityError(_) =>
+                    String::from("Discord could not complete the request"),
                 Self::RilError(err) =>
                     err.to_string(),
+                Self::DatabaseError(_) =>
+                    String::from("The database could not complete the request"),
+                Self::ArchiveError(_) =>
+                    String::from("The output archive could not be written"),
+                Self::QrError(err) =>
+                    format!("The QR code could not be rendered: {err}"),
             }
             .as_str()
         )
@@ -105,8 +521,35 @@ impl From<ril::Error> for Error {
     }
 }
 
-impl From<Error> for CommandError {
-    fn from(err: Error) -> Self {
-        Self::from(err.to_string())
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        Self::DatabaseError(err)
+    }
+}
+
+impl From<zip::result::ZipError> for Error {
+    fn from(err: zip::result::ZipError) -> Self {
+        Self::ArchiveError(err)
+    }
+}
+
+impl From<qrcode::types::QrError> for Error {
+    fn from(err: qrcode::types::QrError) -> Self {
+        Self::QrError(err)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::WithContext(_, inner) => Some(inner.as_ref()),
+            Self::RequestError(err) => Some(err),
+            Self::SerenityError(err) => Some(err),
+            Self::RilError(err) => Some(err),
+            Self::DatabaseError(err) => Some(err),
+            Self::ArchiveError(err) => Some(err),
+            Self::QrError(err) => Some(err),
+            _ => None,
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,276 @@
+//! contains the filters shared between the effects in [`super::functions`], each operating on a single image,
+//! so that multi-stage effects can be composed out of them rather than each re-implementing their own
+
+use rand::Rng;
+use ril::prelude::*;
+
+
+/// the perceived brightness of a pixel, ranging from `0.0` to `1.0`, ignoring its alpha
+#[must_use]
+pub fn luma(px: Rgba) -> f64 {
+    (f64::from(px.r) * 0.299 + f64::from(px.g) * 0.587 + f64::from(px.b) * 0.114) / 255.0
+}
+
+/// converts the provided image to grayscale, keeping its alpha
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn grayscale(image: Image<Rgba>) -> Image<Rgba> {
+    image.map_pixels(|px| {
+        let value = (luma(px) * 255.0).round() as u8;
+
+        Rgba::new(value, value, value, px.a)
+    })
+}
+
+/// the average of each channel over the rectangle of the provided image whose top-left corner is at `(x, y)`,
+/// which must lie within the image
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn average_color(image: &Image<Rgba>, (x, y): (u32, u32), (width, height): (u32, u32)) -> Rgba {
+    let mut sums = [0_u64; 4];
+    for py in y..y + height {
+        for px in x..x + width {
+            let px = image.pixel(px, py);
+            sums[0] += u64::from(px.r);
+            sums[1] += u64::from(px.g);
+            sums[2] += u64::from(px.b);
+            sums[3] += u64::from(px.a);
+        }
+    }
+
+    let count = (u64::from(width) * u64::from(height)).max(1);
+    let [r, g, b, a] = sums.map(|sum| (sum / count) as u8);
+
+    Rgba::new(r, g, b, a)
+}
+
+/// scales the color channels of every pixel of the provided image by `factor`, darkening it if below `1.0`
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn brightness(image: Image<Rgba>, factor: f64) -> Image<Rgba> {
+    let scale = |c: u8| (f64::from(c) * factor).round().clamp(0.0, 255.0) as u8;
+
+    image.map_pixels(|px| Rgba::new(scale(px.r), scale(px.g), scale(px.b), px.a))
+}
+
+/// turns each pixel of the provided image black or white, depending on whether it is darker than the average brightness
+/// of the pixels within `radius` of it by more than `offset`, which unlike a global threshold copes with uneven lighting
+///
+/// transparent pixels are treated as white
+#[must_use]
+pub fn adaptive_threshold(image: &Image<Rgba>, radius: u32, offset: f64) -> Image<Rgba> {
+    let gray = grayscale(
+        image.clone()
+            .map_pixels(blend_over_white)
+    );
+    let local = box_blur(gray.clone(), radius);
+
+    let pixels = gray.data
+        .iter()
+        .zip(local.data.iter())
+        .map(|(&px, &mean)| if luma(px) < luma(mean) - offset { Rgba::black() } else { Rgba::white() })
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(image.width(), pixels)
+}
+
+/// composites the provided pixel over an opaque white background
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn blend_over_white(px: Rgba) -> Rgba {
+    let alpha = u32::from(px.a);
+    let channel = |c: u8| ((u32::from(c) * alpha + 255 * (255 - alpha)) / 255) as u8;
+
+    Rgba::new(channel(px.r), channel(px.g), channel(px.b), 255)
+}
+
+/// rotates the provided image clockwise by `degrees` around its center, keeping its dimensions
+/// and filling the corners uncovered by the rotation with `fill`
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn rotate(image: &Image<Rgba>, degrees: f64, fill: Rgba) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+
+    // each output pixel samples the source pixel that rotates onto it, so that there are no gaps
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let (dx, dy) = (f64::from(x) + 0.5 - cx, f64::from(y) + 0.5 - cy);
+            let (sx, sy) = (dx * cos + dy * sin + cx, -dx * sin + dy * cos + cy);
+
+            if sx < 0.0 || sy < 0.0 || sx >= f64::from(width) || sy >= f64::from(height) {
+                fill
+            } else {
+                *image.pixel(sx as u32, sy as u32)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(width, pixels)
+}
+
+/// the magnitude of the gradient of the brightness at every pixel of the provided image, in row-major order,
+/// found with the sobel operator and ranging from `0.0` to `1.0`
+///
+/// transparent pixels are treated as black, and pixels past the edges of the image repeat those on its edges
+#[must_use]
+pub fn sobel(image: &Image<Rgba>) -> Vec<f64> {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let brightness = image.data
+        .iter()
+        .map(|px| luma(*px) * f64::from(px.a) / 255.0)
+        .collect::<Vec<_>>();
+    let at = |x: usize, y: usize, dx: isize, dy: isize| {
+        let x = x.saturating_add_signed(dx).min(width - 1);
+        let y = y.saturating_add_signed(dy).min(height - 1);
+
+        brightness[y * width + x]
+    };
+
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let gx = at(x, y, 1, -1) + 2.0 * at(x, y, 1, 0) + at(x, y, 1, 1)
+                - at(x, y, -1, -1) - 2.0 * at(x, y, -1, 0) - at(x, y, -1, 1);
+            let gy = at(x, y, -1, 1) + 2.0 * at(x, y, 0, 1) + at(x, y, 1, 1)
+                - at(x, y, -1, -1) - 2.0 * at(x, y, 0, -1) - at(x, y, 1, -1);
+
+            // the largest possible magnitude is that of a hard edge between black and white
+            (gx.hypot(gy) / 4.0_f64.hypot(4.0)).min(1.0)
+        })
+        .collect()
+}
+
+/// averages each channel over a window of `radius` pixels on either side, along a single row or column of `len` pixels
+/// starting at `start` and `stride` apart, with the pixels past the ends repeating those on the ends
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+fn blur_line(src: &[Rgba], dst: &mut [Rgba], start: usize, stride: usize, len: usize, radius: usize) {
+    let at = |i: isize| src[start + i.clamp(0, len as isize - 1) as usize * stride];
+    let window = (radius * 2 + 1) as f64;
+    let radius = radius as isize;
+
+    let mut sums = [0.0; 4];
+    for i in -radius..=radius {
+        let px = at(i);
+        sums[0] += f64::from(px.r);
+        sums[1] += f64::from(px.g);
+        sums[2] += f64::from(px.b);
+        sums[3] += f64::from(px.a);
+    }
+
+    for i in 0..len as isize {
+        let channel = |sum: f64| (sum / window).round() as u8;
+        dst[start + i as usize * stride] = Rgba::new(channel(sums[0]), channel(sums[1]), channel(sums[2]), channel(sums[3]));
+
+        let (incoming, outgoing) = (at(i + radius + 1), at(i - radius));
+        sums[0] += f64::from(incoming.r) - f64::from(outgoing.r);
+        sums[1] += f64::from(incoming.g) - f64::from(outgoing.g);
+        sums[2] += f64::from(incoming.b) - f64::from(outgoing.b);
+        sums[3] += f64::from(incoming.a) - f64::from(outgoing.a);
+    }
+}
+
+/// blurs the provided image with a box of `radius` pixels on either side, in linear time regardless of the radius
+#[must_use]
+pub fn box_blur(mut image: Image<Rgba>, radius: u32) -> Image<Rgba> {
+    if radius == 0 {
+        return image;
+    }
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let radius = radius as usize;
+    let mut buffer = image.data.clone();
+
+    for y in 0..height {
+        blur_line(&image.data, &mut buffer, y * width, 1, width, radius);
+    }
+    for x in 0..width {
+        blur_line(&buffer, &mut image.data, x, width, height, radius);
+    }
+
+    image
+}
+
+/// approximates a gaussian blur of the provided image with three successive box blurs,
+/// with `radius` being roughly the distance at which the blur fades out
+#[must_use]
+pub fn soft_blur(image: Image<Rgba>, radius: u32) -> Image<Rgba> {
+    let step = radius.div_ceil(3);
+
+    (0..3).fold(image, |image, _| box_blur(image, step))
+}
+
+/// composites `src` over `dst` with the screen blend mode, which only ever brightens, as light does,
+/// with `src`'s alpha scaled by `opacity`
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn screen(dst: Rgba, src: Rgba, opacity: f64) -> Rgba {
+    let alpha = f64::from(src.a) / 255.0 * opacity.clamp(0.0, 1.0);
+    let channel = |d: u8, s: u8| {
+        let (d, s) = (f64::from(d) / 255.0, f64::from(s) / 255.0 * alpha);
+
+        ((1.0 - (1.0 - d) * (1.0 - s)) * 255.0).round() as u8
+    };
+
+    Rgba::new(channel(dst.r, src.r), channel(dst.g, src.g), channel(dst.b, src.b), dst.a)
+}
+
+/// masks the provided image to the ellipse inscribed within it, as avatars are displayed
+#[must_use]
+pub fn circle_mask(mut image: Image<Rgba>) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+    let (rx, ry) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+
+    for y in 0..height {
+        for x in 0..width {
+            let (dx, dy) = ((f64::from(x) + 0.5 - rx) / rx, (f64::from(y) + 0.5 - ry) / ry);
+
+            if dx.hypot(dy) > 1.0 {
+                image.set_pixel(x, y, Rgba::transparent());
+            }
+        }
+    }
+
+    image
+}
+
+/// distorts the provided image like a corrupted video signal, splitting its red and blue channels apart
+/// and tearing random horizontal slices sideways, with `intensity` ranging from `0.0` (untouched) to `1.0`
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+pub fn glitch<R: Rng>(image: &Image<Rgba>, rng: &mut R, intensity: f64) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+    let intensity = intensity.clamp(0.0, 1.0);
+    let shift = (f64::from(width) * 0.04 * intensity).round() as i64;
+
+    if shift == 0 || height == 0 {
+        return image.clone();
+    }
+
+    // the horizontal offset of each row, torn in slices of a few rows at a time
+    let mut offsets = vec![0_i64; height as usize];
+    for _ in 0..(intensity * 8.0).ceil() as u32 {
+        let start = rng.gen_range(0..height) as usize;
+        let end = (start + rng.gen_range(1..=(height / 10).max(1)) as usize).min(height as usize);
+        let offset = rng.gen_range(-shift * 3..=shift * 3);
+
+        offsets[start..end]
+            .iter_mut()
+            .for_each(|row| *row = offset);
+    }
+
+    let at = |x: i64, y: u32| image.pixel(x.rem_euclid(i64::from(width)) as u32, y);
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let x = i64::from(x) + offsets[y as usize];
+            let (red, base, blue) = (at(x + shift, y), at(x, y), at(x - shift, y));
+
+            Rgba::new(red.r, base.g, blue.b, base.a.max(red.a).max(blue.a))
+        })
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(width, pixels)
+}
\ No newline at end of file
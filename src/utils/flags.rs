@@ -0,0 +1,88 @@
+//! contains the [`Flags`] struct used to parse `--key=value` style options out of command arguments
+
+use std::{
+    collections::HashMap,
+    str::FromStr,
+};
+use serenity::framework::standard::{Args, Delimiter};
+use super::Error;
+
+
+/// a simple container for the `--key=value` and `--switch` flags passed to a command
+#[derive(Debug, Clone, Default)]
+pub struct Flags {
+    /// maps the lowercased name of each flag to its value, if one was provided
+    inner: HashMap<String, Option<String>>,
+}
+
+impl Flags {
+    /// splits the flags out of the provided arguments,
+    /// returning them along with a new [`Args`] containing only the remaining positional arguments
+    #[must_use]
+    pub fn extract(args: &Args) -> (Self, Args) {
+        let mut inner = HashMap::new();
+        let mut rest = Vec::new();
+
+        for arg in args.raw() {
+            if let Some(flag) = arg.strip_prefix("--") {
+                let (name, value) = flag.split_once('=')
+                    .map_or(
+                        (flag, None),
+                        |(name, value)| (name, Some(value.trim_matches('"').to_string())),
+                    );
+
+                inner.insert(name.to_lowercase(), value);
+            } else {
+                rest.push(arg);
+            }
+        }
+
+        let args = Args::new(
+            rest.join(" ").as_str(),
+            &[Delimiter::Single(' ')],
+        );
+
+        (Self { inner }, args)
+    }
+
+    /// parses the flags out of the raw content of a message, discarding the remaining arguments,
+    /// used to read flags that apply to every command
+    #[must_use]
+    pub fn from_content(content: &str) -> Self {
+        Self::extract(&Args::new(content, &[Delimiter::Single(' ')]))
+            .0
+    }
+
+    /// returns whether or not the provided flag was passed at all
+    #[must_use]
+    pub fn has(&self, name: &str) -> bool {
+        self.inner.contains_key(name)
+    }
+
+    /// returns the raw value of the provided flag, if it was passed with one
+    #[must_use]
+    pub fn raw(&self, name: &str) -> Option<&str> {
+        self.inner.get(name)
+            .and_then(Option::as_deref)
+    }
+
+    /// parses the value of the provided flag into `T`,
+    /// returning `None` if the flag was not passed and [`Error::InvalidFlag`] if the value failed to parse
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, Error> {
+        self.inner.get(name)
+            .map(|value| {
+                let value = value.as_deref()
+                    .unwrap_or_default();
+
+                value.parse::<T>()
+                    .map_err(|_| Error::InvalidFlag(name.to_string(), value.to_string()))
+            })
+            .transpose()
+    }
+
+    /// same as [`Self::get`] but falls back to `default` when the flag was not passed
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, Error> {
+        self.get(name)
+            .map(|value| value.unwrap_or(default))
+    }
+}
\ No newline at end of file
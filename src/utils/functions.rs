@@ -3,30 +3,82 @@
 #![allow(clippy::unnecessary_wraps)]
 
 use ril::{prelude::*, Result};
-use super::imaging::{
-    process_gif,
-    Frames,
-    ImageArguments,
+use rand::Rng as _;
+use super::{
+    imaging::{
+        cut_frames,
+        cut_time,
+        decode_secondary,
+        frame_delay,
+        process_gif,
+        Frames,
+        ImageArguments,
+        DEFAULT_MAX_FRAMES,
+    },
+    text::{blend, escape_markdown, FontChain, MarkdownBlock, TextBlock},
+    filters::{self, sobel, soft_blur},
+    jobs::checked,
+    assets::ASSETS,
+    templates::Template,
+    vision::{detect_faces, faces_or_center, Face},
+    options::{
+        Accessory,
+        Align,
+        AnaglyphOptions,
+        BannerPreset,
+        CensorOptions,
+        CensorStyle,
+        ChartKind,
+        ChartOptions,
+        ChannelMask,
+        CompareOptions,
+        CropOptions,
+        Cut,
+        FlagStyle,
+        FlagifyOptions,
+        GridOptions,
+        UpscaleFilter,
+        GradientKind,
+        GradientOptions,
+        IntroOptions,
+        MosaicOptions,
+        PosterOptions,
+        QuoteOptions,
+        RankOptions,
+        Size,
+        StackOptions,
+        TextOptions,
+        TiltOptions,
+        WelcomeOptions,
+        CaptionOptions,
+        CaptionPosition,
+        OverlayOptions,
+    },
 };
 
-lazy_static::lazy_static! {
-    static ref IMPACT_FONT: Font = Font::open("./assets/impact.ttf", 30.0)
-        .unwrap();
-}
-
 
-/// negates the provided image
-pub fn invert_func(data: ImageArguments) -> Result<Frames> {
+/// negates the provided channels of the image, the color channels by default
+pub fn invert_func(data: ImageArguments<ChannelMask>) -> Result<Frames> {
     let mut sequence =
         ImageSequence::<Rgba>::new();
+    let mask = data.arguments
+        .first()
+        .copied()
+        .unwrap_or_default();
+    let invert = |include: bool, value: u8| if include { 255 - value } else { value };
 
-    for frame in data.frames {
+    for frame in checked(data.frames) {
         let delay = frame.delay();
         let disposal = frame.disposal();
 
         let frame = Frame::from_image(
             frame.into_image()
-                .map_rgb_pixels(|px| px.inverted())
+                .map_pixels(|px| Rgba {
+                    r: invert(mask.r, px.r),
+                    g: invert(mask.g, px.g),
+                    b: invert(mask.b, px.b),
+                    a: invert(mask.a, px.a),
+                })
         )
         .with_delay(delay)
         .with_disposal(disposal);
@@ -53,40 +105,92 @@ pub fn huerotate_func(data: ImageArguments) -> Result<Frames> {
     Ok(sequence)
 }
 
+/// fills a rectangle at `(x, y)` of `(width, height)` with `radius` rounded corners on the provided image
+fn fill_rounded_rect(image: &mut Image<Rgba>, (x, y): (u32, u32), (width, height): (u32, u32), radius: u32, color: Rgba) {
+    let radius = radius.min(width / 2).min(height / 2);
+
+    for dy in 0..height {
+        for dx in 0..width {
+            // distance into the corner region on each axis, zero outside of the corners
+            let cx = radius.saturating_sub(dx.min(width - 1 - dx));
+            let cy = radius.saturating_sub(dy.min(height - 1 - dy));
+
+            if cx * cx + cy * cy <= radius * radius {
+                image.set_pixel(x + dx, y + dy, color);
+            }
+        }
+    }
+}
+
 /// adds a meme caption onto a provided image
-pub fn caption_func(data: ImageArguments<String>) -> Result<Frames> {
+pub fn caption_func(data: ImageArguments<CaptionOptions>) -> Result<Frames> {
     let mut sequence =
         ImageSequence::<Rgba>::new();
-    let segment = TextSegment::new(
-        &IMPACT_FONT, data.arguments[0].as_str(), Rgba::black()
-    );
+    let options = &data.arguments[0];
 
-    for frame in data.frames {
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        let mut layout = TextLayout::new()
-            .with_width((f64::from(frame.width()) * 0.9) as u32)
-            .with_wrap(WrapStyle::Word)
-            .centered()
-            .with_segment(&segment);
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let stroke_width = (options.size / 15.0)
+        .ceil()
+        .max(1.0) as u32;
+
+    let Some(width) = data.frames
+        .first_frame()
+        .map(|frame| frame.width())
+    else {
+        return Ok(sequence);
+    };
 
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+
+    // frames of an animated image share their dimensions, so the text only needs to be shaped once
+    #[allow(clippy::cast_precision_loss)]
+    let text_width = width as f32 * 0.9;
+    let text = TextBlock::new(&fonts, options.text.as_str(), options.size, text_width);
+
+    for frame in checked(data.frames) {
         #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        let extra_height =
-            (
-                f64::from(layout.height()) +
-                f64::from(IMPACT_FONT.optimal_size()) / 1.9
-            ) as u32;
+        let extra_height = (text.height() + options.size / 1.9) as u32;
 
-        layout = layout
-            .with_position(frame.width() / 2, extra_height / 2)
-            .with_segment(&segment);
+        let (bar_y, frame_y) = match options.position {
+            CaptionPosition::Top => (0, extra_height),
+            CaptionPosition::Bottom => (frame.height(), 0),
+        };
 
-        let mut image = Image::<Rgba>::new(
-            frame.width(),
-            frame.height() + extra_height,
-            Rgba::white(),
+        #[allow(clippy::cast_possible_truncation)]
+        let origin = (
+            i64::from(frame.width() / 20),
+            i64::from(bar_y) + ((f64::from(extra_height) - f64::from(text.height())) / 2.0) as i64,
         );
-        image.draw(&layout);
-        image.paste(0, extra_height, frame.image());
+
+        let mut image = if options.transparent {
+            let mut image = Image::<Rgba>::new(
+                frame.width(),
+                frame.height() + extra_height,
+                Rgba::transparent(),
+            );
+            fill_rounded_rect(
+                &mut image,
+                (0, bar_y),
+                (frame.width(), extra_height),
+                extra_height / 4,
+                Rgba::white(),
+            );
+
+            image
+        } else {
+            Image::<Rgba>::new(
+                frame.width(),
+                frame.height() + extra_height,
+                Rgba::white(),
+            )
+        };
+
+        if let Some(stroke) = options.stroke {
+            text.draw_stroke(&mut image, &fonts, origin, text_width, options.align, stroke, stroke_width);
+        }
+        text.draw(&mut image, &fonts, origin, text_width, options.align, options.color);
+        image.paste(0, frame_y, frame.image());
 
         let frame = Frame::from_image(image)
             .with_delay(frame.delay())
@@ -97,6 +201,2226 @@ pub fn caption_func(data: ImageArguments<String>) -> Result<Frames> {
     Ok(sequence)
 }
 
+/// composites a second image onto the provided image at the configured anchor, scale and opacity
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn overlay_func(data: ImageArguments<OverlayOptions>) -> Result<Frames> {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    let options = &data.arguments[0];
+
+    let overlay = decode_secondary(&options.overlay[..])?;
+
+    let count = data.frames.len()
+        .max(overlay.len())
+        .min(DEFAULT_MAX_FRAMES);
+    let base_is_longer = data.frames.len() >= overlay.len();
+
+    for (frame, layer) in checked(data.frames
+        .into_iter()
+        .cycle()
+        .zip(overlay.into_iter().cycle())
+        .take(count))
+    {
+        let (delay, disposal) = if base_is_longer {
+            (frame.delay(), frame.disposal())
+        } else {
+            (layer.delay(), layer.disposal())
+        };
+
+        let width = ((f64::from(frame.width()) * options.scale) as u32)
+            .max(1);
+        let height = ((f64::from(width) / f64::from(layer.width())) * f64::from(layer.height()))
+            .ceil() as u32;
+
+        let layer = layer.into_image()
+            .resized(width, height.max(1), ResizeAlgorithm::Lanczos3)
+            .map_pixels(|px| Rgba {
+                a: (f64::from(px.a) * options.opacity) as u8,
+                ..px
+            });
+
+        let (x, y) = options.anchor.resolve(
+            frame.dimensions(),
+            layer.dimensions(),
+            frame.width() / 50,
+        );
+
+        let mut image = frame.into_image()
+            .with_overlay_mode(OverlayMode::Merge);
+        image.paste(x, y, &layer);
+
+        let frame = Frame::from_image(image)
+            .with_delay(delay)
+            .with_disposal(disposal);
+        sequence.push_frame(frame);
+    }
+
+    Ok(sequence)
+}
+
+/// the smallest width and height of a tile of [`mosaic_func`], below which the tiles can no longer be made out
+pub const MIN_TILE_SIZE: u32 = 6;
+
+/// rebuilds the provided image out of a grid of copies of the tile image, each tinted to the average color of the area it covers
+///
+/// the brightness of each tile is scaled around its mean so that the tiles keep their detail while averaging to the covered color
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn mosaic_func(data: ImageArguments<MosaicOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+    let Some(width) = data.frames
+        .first_frame()
+        .map(|frame| frame.width())
+    else {
+        return Ok(data.frames);
+    };
+
+    let size = (width / options.tiles.max(1)).max(MIN_TILE_SIZE);
+    let tile = decode_secondary(&options.tile[..])?
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image()
+        .resized(size, size, ResizeAlgorithm::Lanczos3);
+
+    // the relative brightness of each pixel of the tile, so that a tile tinted with a color averages back to it
+    let lumas = tile.data
+        .iter()
+        .map(|px| filters::luma(*px))
+        .collect::<Vec<_>>();
+    let mean = (lumas.iter().sum::<f64>() / lumas.len().max(1) as f64).max(0.05);
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let source = frame.into_image();
+        let (width, height) = source.dimensions();
+        let mut image = Image::<Rgba>::new(width, height, Rgba::transparent());
+
+        for tile_y in (0..height).step_by(size as usize) {
+            for tile_x in (0..width).step_by(size as usize) {
+                let (tile_width, tile_height) = (size.min(width - tile_x), size.min(height - tile_y));
+                let color = filters::average_color(&source, (tile_x, tile_y), (tile_width, tile_height));
+
+                for y in 0..tile_height {
+                    for x in 0..tile_width {
+                        let i = (y * size + x) as usize;
+                        let factor = lumas[i] / mean;
+                        let tint = |c: u8| (f64::from(c) * factor).round().clamp(0.0, 255.0) as u8;
+                        let alpha = (u32::from(color.a) * u32::from(tile.data[i].a) / 255) as u8;
+
+                        image.set_pixel(tile_x + x, tile_y + y, Rgba::new(tint(color.r), tint(color.g), tint(color.b), alpha));
+                    }
+                }
+            }
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// scales the provided image to fit within the box of `width` and `height`, centering it on a transparent canvas of that size
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+#[must_use]
+pub fn letterbox(image: Image<Rgba>, width: u32, height: u32) -> Image<Rgba> {
+    if image.dimensions() == (width, height) {
+        return image;
+    }
+
+    let scale = (f64::from(width) / f64::from(image.width()))
+        .min(f64::from(height) / f64::from(image.height()));
+    let (scaled_width, scaled_height) = (
+        ((f64::from(image.width()) * scale).round() as u32).clamp(1, width),
+        ((f64::from(image.height()) * scale).round() as u32).clamp(1, height),
+    );
+
+    let mut canvas = Image::<Rgba>::new(width, height, Rgba::transparent());
+    canvas.paste(
+        (width - scaled_width) / 2,
+        (height - scaled_height) / 2,
+        &image.resized(scaled_width, scaled_height, ResizeAlgorithm::Lanczos3),
+    );
+
+    canvas
+}
+
+/// appends a second animation after the provided one, letterboxing its frames to the dimensions of the first
+///
+/// frames without a delay are given the one they are displayed with, and if the result would have more than
+/// [`DEFAULT_MAX_FRAMES`] frames, only every few frames are kept with their delays lengthened to keep the same duration
+pub fn stack_func(data: ImageArguments<StackOptions>) -> Result<Frames> {
+    let next = decode_secondary(&data.arguments[0].next[..])?;
+    let Some((width, height)) = data.frames
+        .first_frame()
+        .map(|frame| frame.dimensions())
+    else {
+        return Ok(next);
+    };
+
+    let frames = data.frames
+        .into_iter()
+        .chain(next)
+        .map(|frame| {
+            let delay = frame_delay(&frame);
+            let disposal = frame.disposal();
+
+            Frame::from_image(letterbox(frame.into_image(), width, height))
+                .with_delay(delay)
+                .with_disposal(disposal)
+        })
+        .collect::<Vec<_>>();
+
+    let step = frames.len().div_ceil(DEFAULT_MAX_FRAMES).max(1);
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for chunk in frames.chunks(step) {
+        // each kept frame is displayed for as long as the frames it replaces were
+        let delay = chunk.iter()
+            .map(Frame::delay)
+            .sum();
+
+        sequence.push_frame(chunk[0].clone().with_delay(delay));
+    }
+
+    Ok(sequence)
+}
+
+/// the gap between the images placed side by side by [`compare_func`], in pixels
+const COMPARE_GAP: u32 = 8;
+/// the number of frames a sweep of the divider of [`compare_func`] takes, back and forth
+const SLIDER_FRAMES: usize = 40;
+/// the delay between the frames of the divider of [`compare_func`] sweeping over still images
+const SLIDER_DELAY: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// places the provided image and a second one side by side for a before and after comparison, scaling the second to the same height,
+/// or reveals the second over the first with a divider sweeping back and forth
+///
+/// animations are looped until the longest of them ends, and still images are animated to show the divider sweeping
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn compare_func(data: ImageArguments<CompareOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+    let after = decode_secondary(&options.after[..])?;
+    let Some((width, height)) = data.frames
+        .first_frame()
+        .map(|frame| frame.dimensions())
+    else {
+        return Ok(after);
+    };
+
+    let count = data.frames.len()
+        .max(after.len())
+        .max(if options.slider { SLIDER_FRAMES } else { 1 })
+        .min(DEFAULT_MAX_FRAMES);
+    let before_is_longer = data.frames.len() >= after.len();
+    let animated = data.frames.len() > 1 || after.len() > 1;
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for (i, (before, after)) in checked(data.frames
+        .into_iter()
+        .cycle()
+        .zip(after.into_iter().cycle())
+        .take(count)
+        .enumerate())
+    {
+        let source = if before_is_longer { &before } else { &after };
+        let delay = if animated { frame_delay(source) } else { SLIDER_DELAY };
+        let disposal = source.disposal();
+
+        let image = if options.slider {
+            let after = letterbox(after.into_image(), width, height);
+            let mut image = before.into_image();
+
+            // the divider moves from the left edge to the right and back over each sweep
+            let phase = (i % SLIDER_FRAMES) as f64 / SLIDER_FRAMES as f64;
+            let position = (1.0 - (phase * 2.0 - 1.0).abs()) * f64::from(width);
+            let divider = (position as u32).min(width);
+
+            for y in 0..height {
+                for x in 0..divider {
+                    image.set_pixel(x, y, *after.pixel(x, y));
+                }
+            }
+            image.draw(
+                &Rectangle::at(divider.saturating_sub(1), 0)
+                    .with_size(2, height)
+                    .with_fill(Rgba::white())
+            );
+
+            image
+        } else {
+            let after = after.into_image();
+            // the second image is no wider than the longest side of the first, which has already been contained to the tier's limits,
+            // so that an extremely wide one can not blow up the canvas, being centered vertically at a lower height instead
+            let max_width = width.max(height);
+            let scaled_width = (f64::from(after.width()) * f64::from(height) / f64::from(after.height())).round();
+            let (after_width, after_height) = if scaled_width > f64::from(max_width) {
+                (max_width, ((f64::from(after.height()) * f64::from(max_width) / f64::from(after.width())).round() as u32).clamp(1, height))
+            } else {
+                ((scaled_width as u32).max(1), height)
+            };
+
+            let mut image = Image::<Rgba>::new(width + COMPARE_GAP + after_width, height, Rgba::transparent());
+            image.paste(0, 0, &before.into_image());
+            image.paste(
+                width + COMPARE_GAP,
+                (height - after_height) / 2,
+                &after.resized(after_width, after_height, ResizeAlgorithm::Lanczos3),
+            );
+
+            image
+        };
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// composites the provided image into every frame of an animated template
+pub fn template_func(data: ImageArguments<Template>) -> Result<Frames> {
+    data.arguments[0].render(&data.frames)
+}
+
+/// the thickness of the ring drawn by [`flagify_func`], relative to the radius of the image
+const FLAG_RING_THICKNESS: f64 = 0.14;
+
+/// applies a country flag to the provided image, either as a ring framing it masked to a circle, or washed translucently over it
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn flagify_func(data: ImageArguments<FlagifyOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+    let Some((width, height)) = data.frames
+        .first_frame()
+        .map(|frame| frame.dimensions())
+    else {
+        return Ok(data.frames);
+    };
+
+    let flag = decode_secondary(&options.flag[..])?
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image();
+    let flag = cover(flag, width, height);
+
+    let (cx, cy) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+    let radius = cx.min(cy);
+    let inner = radius * (1.0 - FLAG_RING_THICKNESS);
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let image = frame.into_image();
+
+        let pixels = image.data
+            .iter()
+            .zip(flag.data.iter())
+            .enumerate()
+            .map(|(i, (&px, &flag_px))| match options.style {
+                FlagStyle::Ring => {
+                    let (x, y) = (i as u32 % width, i as u32 / width);
+                    let distance = (f64::from(x) + 0.5 - cx).hypot(f64::from(y) + 0.5 - cy);
+
+                    if distance > radius {
+                        Rgba::transparent()
+                    } else if distance > inner {
+                        flag_px
+                    } else {
+                        px
+                    }
+                },
+                FlagStyle::Wash => Rgba {
+                    a: px.a,
+                    ..blend(px, flag_px, (options.opacity * 255.0) as u8)
+                },
+            })
+            .collect::<Vec<_>>();
+
+        sequence.push_frame(
+            Frame::from_image(Image::from_pixels(width, pixels))
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the number of frames of the wiggle rendered by [`anaglyph_func`] for still images
+const WIGGLE_FRAMES: u32 = 8;
+/// the delay between the frames of the wiggle rendered by [`anaglyph_func`] for still images
+const WIGGLE_DELAY: std::time::Duration = std::time::Duration::from_millis(70);
+
+/// shifts each pixel of the provided image horizontally by `shift` pixels, scaled by its depth,
+/// which is taken from its brightness so that brighter areas appear closer
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn parallax(image: &Image<Rgba>, depths: &[f64], shift: f64) -> Image<Rgba> {
+    let (width, height) = image.dimensions();
+
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            let depth = depths[(y * width + x) as usize];
+            let source = (i64::from(x) - (shift * depth).round() as i64).clamp(0, i64::from(width) - 1);
+
+            *image.pixel(source as u32, y)
+        })
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(width, pixels)
+}
+
+/// fakes a stereoscopic 3D image out of the provided image, using its brightness as a depth map:
+/// either overlaying the left and right views in red and cyan for anaglyph glasses,
+/// or wiggling between them, which still images are animated into
+#[allow(clippy::cast_precision_loss)]
+pub fn anaglyph_func(data: ImageArguments<AnaglyphOptions>) -> Result<Frames> {
+    let options = data.arguments[0];
+    let depth = f64::from(options.depth) / 2.0;
+
+    let mut frames = data.frames
+        .into_iter()
+        .collect::<Vec<_>>();
+    let still = frames.len() == 1;
+    if options.wiggle && still {
+        let frame = frames.remove(0);
+        frames = (0..WIGGLE_FRAMES)
+            .map(|_| Frame::from_image(frame.image().clone()).with_delay(WIGGLE_DELAY))
+            .collect();
+    }
+    let count = frames.len().max(1) as f64;
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for (i, frame) in checked(frames.into_iter().enumerate()) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let image = frame.into_image();
+
+        // the depths range from -1.0 to 1.0, around the plane of the screen
+        let depths = image.data
+            .iter()
+            .map(|px| filters::luma(*px) * 2.0 - 1.0)
+            .collect::<Vec<_>>();
+
+        let output = if options.wiggle {
+            // still images sweep between the views once per loop, while animations keep their own timing
+            let phase = if still { i as f64 / count * std::f64::consts::TAU } else { i as f64 * 0.8 };
+            parallax(&image, &depths, depth * phase.sin())
+        } else {
+            let (left, right) = (parallax(&image, &depths, -depth), parallax(&image, &depths, depth));
+            let pixels = left.data
+                .iter()
+                .zip(right.data.iter())
+                .map(|(l, r)| Rgba::new(l.r, r.g, r.b, l.a.max(r.a)))
+                .collect::<Vec<_>>();
+
+            Image::from_pixels(image.width(), pixels)
+        };
+
+        sequence.push_frame(
+            Frame::from_image(output)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the largest angle [`tilt_func`] tilts an image by, in degrees, at full intensity
+const MAX_TILT: f64 = 12.0;
+/// the number of angular bins the speed lines of [`tilt_func`] are rasterized into
+const SPEED_LINE_BINS: usize = 4096;
+
+/// rasterizes radial speed lines into angular bins, each holding the distance from the center at which its line starts,
+/// as a fraction of the distance to the corners, or `None` if no line passes through it
+///
+/// each line is a thin wedge, so that it tapers towards the center as speed lines do
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn speed_lines<R: rand::Rng>(rng: &mut R, count: u32) -> Vec<Option<f64>> {
+    let mut bins = vec![None; SPEED_LINE_BINS];
+
+    for _ in 0..count {
+        let center = rng.gen_range(0..SPEED_LINE_BINS);
+        let half_width = rng.gen_range(1..=6);
+        let start = rng.gen_range(0.35..0.7);
+
+        for offset in 0..=half_width * 2 {
+            let bin = (center + SPEED_LINE_BINS + offset - half_width) % SPEED_LINE_BINS;
+            bins[bin] = Some(bins[bin].map_or(start, |existing: f64| existing.min(start)));
+        }
+    }
+
+    bins
+}
+
+/// gives the provided image a dramatic "impact" look: tilting it at a dutch angle, zooming in to hide the corners,
+/// drawing radial speed lines from its center and optionally tinting it red
+///
+/// the speed lines are drawn anew on every frame of animations, so that they flicker as they do in anime
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn tilt_func(data: ImageArguments<TiltOptions>) -> Result<Frames> {
+    let options = data.arguments[0];
+    let intensity = options.intensity.clamp(0.0, 1.0);
+    let mut rng = data.rng();
+
+    let angle = MAX_TILT * intensity * if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    let zoom = 1.0 + intensity * 0.35;
+    let count = (40.0 + 120.0 * intensity) as u32;
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let image = frame.into_image();
+        let (width, height) = image.dimensions();
+
+        let tilted = filters::rotate(&image, angle, Rgba::black());
+        let (crop_width, crop_height) = (
+            ((f64::from(width) / zoom) as u32).max(1),
+            ((f64::from(height) / zoom) as u32).max(1),
+        );
+        let (x, y) = ((width - crop_width) / 2, (height - crop_height) / 2);
+        let zoomed = tilted.cropped(x, y, x + crop_width, y + crop_height)
+            .resized(width, height, ResizeAlgorithm::Bilinear);
+
+        let bins = speed_lines(&mut rng, count);
+        let (cx, cy) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+        let corner = cx.hypot(cy).max(1.0);
+
+        let pixels = zoomed.data
+            .iter()
+            .enumerate()
+            .map(|(i, &px)| {
+                let (dx, dy) = (f64::from(i as u32 % width) + 0.5 - cx, f64::from(i as u32 / width) + 0.5 - cy);
+                let distance = dx.hypot(dy) / corner;
+                let bin = ((dy.atan2(dx) / std::f64::consts::TAU + 0.5) * SPEED_LINE_BINS as f64) as usize % SPEED_LINE_BINS;
+
+                let px = if options.red {
+                    Rgba::new(px.r.saturating_add(60), px.g / 2, px.b / 2, px.a)
+                } else {
+                    px
+                };
+
+                match bins[bin] {
+                    Some(start) if distance > start => {
+                        let alpha = ((distance - start) / (1.0 - start)).clamp(0.0, 1.0) * 0.9;
+                        blend(px, Rgba::white(), (alpha * 255.0) as u8)
+                    },
+                    _ => px,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        sequence.push_frame(
+            Frame::from_image(Image::from_pixels(width, pixels))
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// adds random grain to the provided image, with `intensity` ranging from `0.0` to `1.0`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn noise_func(data: ImageArguments<f64>) -> Result<Frames> {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    let mut rng = data.rng();
+    let amount = (data.arguments[0] * 255.0) as i16;
+
+    for frame in checked(data.frames) {
+        let delay = frame.delay();
+        let disposal = frame.disposal();
+
+        let mut image = frame.into_image();
+        for px in &mut image.data {
+            let offset = rng.gen_range(-amount..=amount);
+            let channel = |c: u8| (i16::from(c) + offset).clamp(0, 255) as u8;
+
+            *px = Rgba::new(channel(px.r), channel(px.g), channel(px.b), px.a);
+        }
+
+        let frame = Frame::from_image(image)
+        .with_delay(delay)
+        .with_disposal(disposal);
+
+        sequence.push_frame(frame);
+    }
+
+    Ok(sequence)
+}
+
+/// scales up the provided image by an integer factor
+pub fn enlarge_func(data: ImageArguments<u32>) -> Result<Frames> {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    let factor = data.arguments[0];
+
+    for mut frame in checked(data.frames) {
+        frame.resize(
+            frame.width() * factor,
+            frame.height() * factor,
+            ResizeAlgorithm::Lanczos3,
+        );
+        sequence.push_frame(frame);
+    }
+
+    Ok(sequence)
+}
+
+/// splits the provided image into up to `count` evenly spaced frames, each returned as a separate still image
+pub fn frames_func(data: ImageArguments<usize>) -> Result<Vec<Frames>> {
+    let count = data.arguments[0].max(1);
+    let step = data.frames.len().div_ceil(count);
+
+    Ok(
+        data.frames
+            .into_iter()
+            .step_by(step.max(1))
+            .map(|frame| {
+                let mut sequence =
+                    ImageSequence::<Rgba>::new();
+                sequence.push_frame(Frame::from_image(frame.into_image()));
+
+                sequence
+            })
+            .collect()
+    )
+}
+
+/// decomposes the provided image into its red, green, blue and alpha channels, each returned as a separate grayscale image
+pub fn split_func(data: ImageArguments) -> Result<Vec<Frames>> {
+    let channels: [fn(Rgba) -> u8; 4] = [|px| px.r, |px| px.g, |px| px.b, |px| px.a];
+
+    Ok(
+        channels.into_iter()
+            .map(|channel| {
+                let mut sequence =
+                    ImageSequence::<Rgba>::new();
+
+                checked(data.frames.iter())
+                    .map(|frame| Frame::from_image(
+                        frame.image()
+                            .clone()
+                            .map_pixels(|px| {
+                                let value = channel(px);
+                                Rgba::new(value, value, value, 255)
+                            })
+                    )
+                        .with_delay(frame.delay())
+                        .with_disposal(frame.disposal())
+                    )
+                    .for_each(|frame| sequence.push_frame(frame));
+
+                sequence
+            })
+            .collect()
+    )
+}
+
+/// the width and height of each tile cut by [`emojipack_func`], the size discord displays large emojis at
+pub const EMOJI_TILE_SIZE: u32 = 128;
+
+/// copies the square of `size` at `x` and `y` out of the provided image, without copying the rest of it
+#[allow(clippy::cast_possible_truncation)]
+fn tile_at(image: &Image<Rgba>, x: u32, y: u32, size: u32) -> Image<Rgba> {
+    let width = image.width() as usize;
+    let pixels = (y..y + size)
+        .flat_map(|row| {
+            let start = row as usize * width + x as usize;
+            image.data[start..start + size as usize].iter().copied()
+        })
+        .collect::<Vec<_>>();
+
+    Image::from_pixels(size, pixels)
+}
+
+/// scales the provided image to cover a grid of `columns` by `rows` emoji-sized tiles,
+/// cutting it into each tile from left to right, top to bottom
+#[allow(clippy::cast_possible_truncation)]
+pub fn emojipack_func(data: ImageArguments<Size>) -> Result<Vec<Frames>> {
+    let Size(columns, rows) = data.arguments[0];
+    let mut tiles = (0..columns * rows)
+        .map(|_| ImageSequence::<Rgba>::new())
+        .collect::<Vec<_>>();
+
+    for frame in checked(data.frames.iter()) {
+        let image = cover(frame.image().clone(), columns * EMOJI_TILE_SIZE, rows * EMOJI_TILE_SIZE);
+
+        for (i, tile) in tiles.iter_mut().enumerate() {
+            let i = i as u32;
+            let (x, y) = ((i % columns) * EMOJI_TILE_SIZE, (i / columns) * EMOJI_TILE_SIZE);
+
+            tile.push_frame(
+                Frame::from_image(tile_at(&image, x, y, EMOJI_TILE_SIZE))
+                    .with_delay(frame.delay())
+                    .with_disposal(frame.disposal())
+            );
+        }
+    }
+
+    Ok(tiles)
+}
+
+/// cuts the provided animation down to a range of time or frames, keeping at least one frame
+pub fn trimgif_func(data: ImageArguments<Cut>) -> Result<Frames> {
+    Ok(
+        match data.arguments[0] {
+            Cut::Time(start, end) => cut_time(data.frames, start, end),
+            Cut::Frames(range) => {
+                let last = data.frames.len().saturating_sub(1);
+                let indices = range.indices();
+
+                // a range starting past the last frame keeps the last frame, as a cut by time does
+                cut_frames(data.frames, indices.start.min(last)..indices.end.max(indices.start.min(last) + 1))
+            },
+        }
+    )
+}
+
+/// crops away the uniform borders of the provided image, either transparent or of the color of its top left pixel,
+/// with `tolerance` being how far each channel of a pixel may be from the border color while still counting as border
+///
+/// the bounding box is the union of those of every frame, so that animations keep a consistent size
+pub fn trim_func(data: ImageArguments<u8>) -> Result<Frames> {
+    let tolerance = data.arguments[0];
+    let Some(background) = data.frames
+        .first_frame()
+        .map(|frame| *frame.image().pixel(0, 0))
+    else {
+        return Ok(data.frames);
+    };
+
+    let is_border = |px: &Rgba| if background.a == 0 {
+        px.a <= tolerance
+    } else {
+        [(px.r, background.r), (px.g, background.g), (px.b, background.b), (px.a, background.a)]
+            .iter()
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+    };
+
+    // (x1, y1, x2, y2) with the end being exclusive
+    let mut bounds: Option<(u32, u32, u32, u32)> = None;
+
+    for frame in checked(data.frames.iter()) {
+        let image = frame.image();
+
+        for y in 0..image.height() {
+            for x in 0..image.width() {
+                if is_border(image.pixel(x, y)) {
+                    continue;
+                }
+
+                bounds = Some(bounds.map_or(
+                    (x, y, x + 1, y + 1),
+                    |(x1, y1, x2, y2)| (x1.min(x), y1.min(y), x2.max(x + 1), y2.max(y + 1)),
+                ));
+            }
+        }
+    }
+
+    // an image made up only of its border is left as is
+    let Some((x1, y1, x2, y2)) = bounds else {
+        return Ok(data.frames);
+    };
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+
+        sequence.push_frame(
+            Frame::from_image(frame.into_image().cropped(x1, y1, x2, y2))
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// composites `color` over the pixel at `(x, y)` according to its alpha, ignoring coordinates outside of the image
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn blend_pixel(image: &mut Image<Rgba>, x: i64, y: i64, color: Rgba) {
+    let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+        return;
+    };
+    if x >= image.width() || y >= image.height() {
+        return;
+    }
+
+    let dst = *image.pixel(x, y);
+    let alpha = f32::from(color.a) / 255.0;
+    let mix = |src: u8, dst: u8| (f32::from(src) * alpha + f32::from(dst) * (1.0 - alpha)).round() as u8;
+
+    image.set_pixel(x, y, Rgba::new(
+        mix(color.r, dst.r),
+        mix(color.g, dst.g),
+        mix(color.b, dst.b),
+        dst.a.max(color.a),
+    ));
+}
+
+/// fills a circle of `radius` centered on `(cx, cy)`, fading out towards its edge if `soft`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn fill_circle(image: &mut Image<Rgba>, (cx, cy): (i64, i64), radius: i64, color: Rgba, soft: bool) {
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius.max(1) as f32;
+            if distance > 1.0 {
+                continue;
+            }
+
+            let alpha = if soft { (1.0 - distance) * f32::from(color.a) } else { f32::from(color.a) };
+            blend_pixel(image, cx + dx, cy + dy, Rgba { a: alpha as u8, ..color });
+        }
+    }
+}
+
+/// draws the provided accessory onto the provided face
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn draw_accessory(image: &mut Image<Rgba>, face: Face, accessory: Accessory) {
+    let ((lx, ly), (rx, ry)) = face.eyes();
+    let (lx, ly, rx, ry) = (i64::from(lx), i64::from(ly), i64::from(rx), i64::from(ry));
+    let size = i64::from(face.width.max(1));
+
+    match accessory {
+        Accessory::LaserEyes => {
+            let reach = i64::from(image.width().max(image.height()));
+
+            for (ex, ey) in [(lx, ly), (rx, ry)] {
+                // beams shoot diagonally down and away from the center of the face
+                let direction = if ex < i64::from(face.center().0) { -1 } else { 1 };
+
+                for step in 0..reach {
+                    let width = (size / 40).max(1) + step / 40;
+                    fill_circle(image, (ex + direction * step, ey + step / 2), width, Rgba::new(255, 30, 30, 160), true);
+                }
+                fill_circle(image, (ex, ey), size / 8, Rgba::new(255, 60, 60, 255), true);
+                fill_circle(image, (ex, ey), size / 24, Rgba::white(), false);
+            }
+        },
+        Accessory::Glasses => {
+            // the glasses are drawn on a grid of square "pixels" to match the meme's 8-bit look
+            let cell = (size / 16).max(1);
+            let (top, lens) = (ly - cell * 2, size * 3 / 10);
+
+            for x in (lx - lens / 2 - cell..rx + lens / 2 + cell).step_by(cell as usize) {
+                for y in (top..top + cell).step_by(cell as usize) {
+                    fill_square(image, x, y, cell, Rgba::black());
+                }
+            }
+            for cx in [lx, rx] {
+                for x in (cx - lens / 2..cx + lens / 2).step_by(cell as usize) {
+                    for y in (top + cell..top + cell * 4).step_by(cell as usize) {
+                        // a diagonal white glint across the upper part of each lens
+                        let glint = y < top + cell * 3 && (x - (cx - lens / 2)) / cell % 4 == (y - top) / cell;
+                        fill_square(image, x, y, cell, if glint { Rgba::white() } else { Rgba::black() });
+                    }
+                }
+            }
+        },
+        Accessory::ClownNose => {
+            let (nx, ny) = face.nose();
+            let (nx, ny) = (i64::from(nx), i64::from(ny));
+            let radius = size / 8;
+
+            fill_circle(image, (nx, ny), radius, Rgba::new(220, 20, 30, 255), false);
+            fill_circle(image, (nx - radius / 3, ny - radius / 3), radius / 4, Rgba::new(255, 255, 255, 200), true);
+        },
+    }
+}
+
+/// fills a square of `size` with its top left corner at `(x, y)`
+fn fill_square(image: &mut Image<Rgba>, x: i64, y: i64, size: i64, color: Rgba) {
+    for dy in 0..size {
+        for dx in 0..size {
+            blend_pixel(image, x + dx, y + dy, color);
+        }
+    }
+}
+
+/// draws an accessory onto each face detected in the provided image, or onto its center if none are found
+///
+/// faces are only detected in the first frame, and reused for the rest of the frames of animations
+pub fn accessory_func(data: ImageArguments<Accessory>) -> Result<Frames> {
+    let accessory = data.arguments[0];
+    let Some(faces) = data.frames
+        .first_frame()
+        .map(|frame| faces_or_center(frame.image()))
+    else {
+        return Ok(data.frames);
+    };
+
+    Ok(apply_accessory(data.frames, &faces, accessory))
+}
+
+/// draws the accessory on each of the provided faces in every frame,
+/// split out of [`accessory_func`] so that faces detected once can be reused
+#[must_use]
+pub fn apply_accessory(frames: Frames, faces: &[Face], accessory: Accessory) -> Frames {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let mut image = frame.into_image();
+
+        for face in faces {
+            draw_accessory(&mut image, *face, accessory);
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    sequence
+}
+
+/// crops the largest square out of the provided image that is centered on its geometric center,
+/// or on its most prominent face if `smart` is set and one is found, masking it to a circle if `circle` is set
+///
+/// the face is only detected in the first frame, so that animations are cropped consistently
+pub fn facecrop_func(data: ImageArguments<CropOptions>) -> Result<Frames> {
+    let CropOptions { smart, circle } = data.arguments[0];
+    let Some((width, height, (cx, cy))) = data.frames
+        .first_frame()
+        .map(|frame| {
+            let center = if smart {
+                faces_or_center(frame.image())[0].center()
+            } else {
+                (frame.width() / 2, frame.height() / 2)
+            };
+
+            (frame.width(), frame.height(), center)
+        })
+    else {
+        return Ok(data.frames);
+    };
+
+    let size = width.min(height);
+    let x = cx.saturating_sub(size / 2).min(width - size);
+    let y = cy.saturating_sub(size / 2).min(height - size);
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let mut image = frame.into_image()
+            .cropped(x, y, x + size, y + size);
+
+        if circle {
+            let radius = f64::from(size) / 2.0;
+
+            for py in 0..size {
+                for px in 0..size {
+                    let (dx, dy) = (f64::from(px) + 0.5 - radius, f64::from(py) + 0.5 - radius);
+
+                    if dx.hypot(dy) > radius {
+                        image.set_pixel(px, py, Rgba::transparent());
+                    }
+                }
+            }
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the most pixels across every frame output by [`bannerize_func`], as presets upscale small images to as much as 2560x1440
+const MAX_BANNER_PIXELS: u64 = 64 * 1024 * 1024;
+
+/// crops the largest region of the aspect ratio of the provided preset out of the image,
+/// centered on its most prominent face, or on its geometric center if none are found,
+/// and resizes it to the exact dimensions of the preset
+///
+/// the face is only detected in the first frame, so that animations are cropped consistently,
+/// and animations are thinned out by keeping every `n`th frame to stay within [`MAX_BANNER_PIXELS`]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn bannerize_func(data: ImageArguments<BannerPreset>) -> Result<Frames> {
+    let (target_width, target_height) = data.arguments[0].size();
+    let Some((width, height, face)) = data.frames
+        .first_frame()
+        .map(|frame| (frame.width(), frame.height(), faces_or_center(frame.image())[0]))
+    else {
+        return Ok(data.frames);
+    };
+
+    let ratio = f64::from(target_width) / f64::from(target_height);
+    let (crop_width, crop_height) = if f64::from(width) / f64::from(height) > ratio {
+        (((f64::from(height) * ratio) as u32).clamp(1, width), height)
+    } else {
+        (width, ((f64::from(width) / ratio) as u32).clamp(1, height))
+    };
+
+    let (cx, cy) = face.center();
+    let x = cx.saturating_sub(crop_width / 2).min(width - crop_width);
+    let y = cy.saturating_sub(crop_height / 2).min(height - crop_height);
+
+    let max_frames = (MAX_BANNER_PIXELS / (u64::from(target_width) * u64::from(target_height))).max(1) as usize;
+    let frames = data.frames
+        .into_iter()
+        .collect::<Vec<_>>();
+    let step = frames.len()
+        .div_ceil(max_frames)
+        .max(1);
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for chunk in checked(frames.chunks(step)) {
+        // each kept frame is displayed for as long as the frames it replaces were
+        let delay = chunk.iter()
+            .map(Frame::delay)
+            .sum();
+        let disposal = chunk[0].disposal();
+        let image = chunk[0].image()
+            .clone()
+            .cropped(x, y, x + crop_width, y + crop_height)
+            .resized(target_width, target_height, ResizeAlgorithm::Lanczos3);
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// how much each detected face is grown on every side before being censored, so that the hair and chin are covered too
+const CENSOR_MARGIN: f64 = 0.2;
+
+/// replaces each block of `size` pixels within the provided region with its average color
+fn pixelate_region(image: &mut Image<Rgba>, region: Face, size: u32) {
+    for block_y in (region.y..region.y + region.height).step_by(size as usize) {
+        for block_x in (region.x..region.x + region.width).step_by(size as usize) {
+            let (block_width, block_height) = (
+                size.min(region.x + region.width - block_x),
+                size.min(region.y + region.height - block_y),
+            );
+            let average = filters::average_color(image, (block_x, block_y), (block_width, block_height));
+
+            // the pixels are replaced rather than drawn over, which would blend in the original through translucent averages
+            for y in block_y..block_y + block_height {
+                for x in block_x..block_x + block_width {
+                    image.set_pixel(x, y, average);
+                }
+            }
+        }
+    }
+}
+
+/// hides the faces detected in each frame of the provided image, or the region passed instead, by pixelating or painting over them
+///
+/// faces are detected in every frame so that moving faces stay hidden, reusing those of the previous frame when none are found;
+/// if no face is found in any frame, the whole image is hidden rather than risk leaving a face visible
+pub fn censor_func(data: ImageArguments<CensorOptions>) -> Result<Frames> {
+    let options = data.arguments[0];
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    let mut previous = Vec::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let mut image = frame.into_image();
+        let (width, height) = image.dimensions();
+
+        let regions = if let Some(region) = options.region {
+            vec![region.0.grown(0.0, width, height)]
+        } else {
+            let faces = detect_faces(&image)
+                .into_iter()
+                .map(|face| face.grown(CENSOR_MARGIN, width, height))
+                .collect::<Vec<_>>();
+
+            if !faces.is_empty() {
+                previous.clone_from(&faces);
+            }
+            if previous.is_empty() {
+                vec![Face { x: 0, y: 0, width, height }]
+            } else {
+                previous.clone()
+            }
+        };
+
+        for region in regions.into_iter().filter(|region| region.width > 0 && region.height > 0) {
+            match options.style {
+                CensorStyle::Pixelate => {
+                    // the blocks are scaled with the region so that large faces are just as unrecognizable as small ones
+                    let size = (region.width.max(region.height) / 8).max(4);
+                    pixelate_region(&mut image, region, size);
+                },
+                CensorStyle::Bar => image.draw(
+                    &Rectangle::at(region.x, region.y)
+                        .with_size(region.width, region.height)
+                        .with_fill(Rgba::black())
+                ),
+            }
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the largest skew of a document, in degrees, that [`scan_func`] detects and corrects
+pub const MAX_SKEW: f64 = 15.0;
+/// the step between the angles tried when detecting the skew of a document, in degrees
+const SKEW_STEP: f64 = 0.25;
+/// the largest width or height the dark pixels of a document are sampled over when detecting its skew
+const SKEW_SAMPLE: u32 = 400;
+
+/// detects the skew of the lines of text in the provided black and white image, in degrees clockwise,
+/// as the angle at which the rows of its dark pixels are the most uneven, which is when the lines of text lie flat
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn detect_skew(binary: &Image<Rgba>) -> f64 {
+    let (width, height) = binary.dimensions();
+    let stride = (width.max(height) / SKEW_SAMPLE).max(1);
+
+    let points = (0..height)
+        .step_by(stride as usize)
+        .flat_map(|y| (0..width).step_by(stride as usize).map(move |x| (x, y)))
+        .filter(|&(x, y)| binary.pixel(x, y).r == 0)
+        .map(|(x, y)| (f64::from(x), f64::from(y)))
+        .collect::<Vec<_>>();
+    if points.len() < 16 {
+        return 0.0;
+    }
+
+    // the projected rows can range over the diagonal of the image, in either direction
+    let diagonal = f64::from(width).hypot(f64::from(height));
+    let bins = (diagonal * 2.0 / f64::from(stride)) as usize + 1;
+    let steps = (MAX_SKEW / SKEW_STEP) as i32;
+
+    (-steps..=steps)
+        .map(|step| f64::from(step) * SKEW_STEP)
+        .map(|angle| {
+            let (sin, cos) = angle.to_radians().sin_cos();
+            let mut rows = vec![0_u64; bins];
+
+            for &(x, y) in &points {
+                let row = ((-x * sin + y * cos + diagonal) / f64::from(stride)) as usize;
+                rows[row.min(bins - 1)] += 1;
+            }
+
+            (angle, rows.into_iter().map(|count| count * count).sum::<u64>())
+        })
+        .max_by_key(|&(angle, score)| (score, std::cmp::Reverse((angle.abs() * 100.0) as u64)))
+        .map_or(0.0, |(angle, _)| angle)
+}
+
+/// cleans up a photographed or scanned document: turning it black and white with an adaptive threshold,
+/// straightening its lines of text and cropping it to them with a small margin, or keeping it grayscale if `binarize` is unset
+///
+/// the skew and margins are detected on the first frame, and applied to every frame
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn scan_func(data: ImageArguments<bool>) -> Result<Frames> {
+    let binarize = data.arguments[0];
+    let Some(first) = data.frames.first_frame() else {
+        return Ok(data.frames);
+    };
+    let (width, height) = first.dimensions();
+
+    // the threshold adapts to the brightness within roughly the height of a line of text
+    let radius = (width.max(height) / 40).max(4);
+    let binary = filters::adaptive_threshold(first.image(), radius, 0.06);
+    let skew = detect_skew(&binary);
+    let straightened = filters::rotate(&binary, -skew, Rgba::white());
+
+    let dark = straightened.data
+        .iter()
+        .enumerate()
+        .filter(|(_, px)| px.r == 0)
+        .map(|(i, _)| (i as u32 % width, i as u32 / width));
+    let (left, top, right, bottom) = dark.fold(
+        (width, height, 0, 0),
+        |(left, top, right, bottom), (x, y)| (left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1)),
+    );
+    let margin = width.max(height) / 50;
+    let crop = (left < right && top < bottom).then(|| (
+        left.saturating_sub(margin),
+        top.saturating_sub(margin),
+        (right + margin).min(width),
+        (bottom + margin).min(height),
+    ));
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let image = frame.into_image();
+
+        let cleaned = if binarize {
+            filters::adaptive_threshold(&image, radius, 0.06)
+        } else {
+            filters::grayscale(image.map_pixels(filters::blend_over_white))
+        };
+        let mut cleaned = filters::rotate(&cleaned, -skew, Rgba::white());
+        if let Some((x1, y1, x2, y2)) = crop {
+            cleaned = cleaned.cropped(x1, y1, x2, y2);
+        }
+
+        sequence.push_frame(
+            Frame::from_image(cleaned)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the maximum width or height of an image output by [`upscale_func`]
+pub const MAX_UPSCALE_DIM: u32 = 2048;
+/// the maximum number of pixels output by [`upscale_func`] across every frame
+pub const MAX_UPSCALE_PIXELS: u64 = 32 * 1024 * 1024;
+
+/// scales up the provided image by a factor of two with the `scale2x` algorithm
+fn scale2x(image: &Image<Rgba>) -> Image<Rgba> {
+    let (width, height) = (image.width(), image.height());
+    let mut output = Image::<Rgba>::new(width * 2, height * 2, Rgba::transparent());
+
+    for y in 0..height {
+        for x in 0..width {
+            let p = *image.pixel(x, y);
+            // the neighbours above, to the right, to the left and below, repeating the edge pixels
+            let a = *image.pixel(x, y.saturating_sub(1));
+            let b = *image.pixel((x + 1).min(width - 1), y);
+            let c = *image.pixel(x.saturating_sub(1), y);
+            let d = *image.pixel(x, (y + 1).min(height - 1));
+
+            let pick = |first: Rgba, second: Rgba, other_first: Rgba, other_second: Rgba|
+                if first == second && first != other_first && second != other_second { first } else { p };
+
+            output.set_pixel(x * 2, y * 2, pick(c, a, d, b));
+            output.set_pixel(x * 2 + 1, y * 2, pick(a, b, c, d));
+            output.set_pixel(x * 2, y * 2 + 1, pick(d, c, b, a));
+            output.set_pixel(x * 2 + 1, y * 2 + 1, pick(b, d, a, c));
+        }
+    }
+
+    output
+}
+
+/// scales up the provided pixel art by an integer `factor`, capped so that each frame fits within [`MAX_UPSCALE_DIM`]
+/// and every frame together within [`MAX_UPSCALE_PIXELS`],
+/// with [`UpscaleFilter::Scale2x`] being applied for each power of two in the factor, and nearest neighbor for the rest
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn upscale_func(data: ImageArguments<(u32, UpscaleFilter)>) -> Result<Frames> {
+    let (factor, filter) = data.arguments[0];
+    let Some(largest) = data.frames
+        .first_frame()
+        .map(|frame| frame.width().max(frame.height()).max(1))
+    else {
+        return Ok(data.frames);
+    };
+    let pixels = data.frames
+        .iter()
+        .map(|frame| u64::from(frame.width()) * u64::from(frame.height()))
+        .sum::<u64>()
+        .max(1);
+
+    // the number of pixels grows with the square of the factor
+    let max_factor = (MAX_UPSCALE_DIM / largest)
+        .min((MAX_UPSCALE_PIXELS as f64 / pixels as f64).sqrt() as u32);
+    let factor = factor.clamp(1, max_factor.max(1));
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let (width, height) = (frame.width() * factor, frame.height() * factor);
+        let mut image = frame.into_image();
+
+        if filter == UpscaleFilter::Scale2x {
+            let mut scale = 2;
+            while scale <= factor {
+                image = scale2x(&image);
+                scale *= 2;
+            }
+        }
+        if image.width() != width || image.height() != height {
+            image.resize(width, height, ResizeAlgorithm::Nearest);
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// maps a difference ranging from `0.0` to `1.0` onto a heatmap going from black, through red and yellow, to white
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn heat(delta: f64) -> Rgba {
+    let channel = |offset: f64| ((delta * 3.0 - offset).clamp(0.0, 1.0) * 255.0) as u8;
+
+    Rgba::new(channel(0.0), channel(1.0), channel(2.0), 255)
+}
+
+/// compares the first frames of the provided images, the second being resized to the dimensions of the first,
+/// returning a heatmap of the per-pixel difference between them along with their similarity, ranging from `0.0` to `1.0`
+pub fn diff_func(base: Frames, other: Frames) -> Result<(Frames, f64)> {
+    let (Some(base), Some(other)) = (base.into_iter().next(), other.into_iter().next()) else {
+        return Err(ril::Error::EmptyImageError);
+    };
+    let base = base.into_image();
+    let other = other.into_image()
+        .resized(base.width(), base.height(), ResizeAlgorithm::Lanczos3);
+
+    let mut total = 0.0;
+    let pixels = base.data
+        .iter()
+        .zip(other.data.iter())
+        .map(|(a, b)| {
+            let delta = [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)]
+                .into_iter()
+                .map(|(x, y)| f64::from(x.abs_diff(y)))
+                .sum::<f64>() / (255.0 * 4.0);
+
+            total += delta;
+            heat(delta)
+        })
+        .collect::<Vec<_>>();
+
+    #[allow(clippy::cast_precision_loss)]
+    let similarity = 1.0 - total / pixels.len().max(1) as f64;
+
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    sequence.push_frame(
+        Frame::from_image(Image::from_pixels(base.width(), pixels))
+    );
+
+    Ok((sequence, similarity))
+}
+
+/// the maximum width or height of a generated image
+pub const MAX_GENERATED_DIM: u32 = 2048;
+
+/// wraps a single generated image into a sequence
+fn still(image: Image<Rgba>) -> Frames {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+    sequence.push_frame(Frame::from_image(image));
+
+    sequence
+}
+
+/// clamps the provided size within [`MAX_GENERATED_DIM`]
+#[must_use]
+pub fn clamp_size(Size(width, height): Size) -> (u32, u32) {
+    (width.clamp(1, MAX_GENERATED_DIM), height.clamp(1, MAX_GENERATED_DIM))
+}
+
+/// renders a swatch of a single color, ignoring the source frames
+pub fn swatch_func(data: ImageArguments<(Rgba, Size)>) -> Result<Frames> {
+    let (color, size) = data.arguments[0];
+    let (width, height) = clamp_size(size);
+
+    Ok(still(Image::new(width, height, color)))
+}
+
+/// renders text onto a canvas fitted to it, ignoring the source frames
+pub fn text_func(data: ImageArguments<TextOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let fonts = match options.font.as_deref() {
+        Some(name) => std::sync::Arc::new(
+            fonts.preferring(name)
+                .ok_or(ril::Error::FontError("There is no font by that name"))?
+        ),
+        None => fonts,
+    };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let stroke_width = if options.stroke.is_some() {
+        (options.size / 15.0)
+            .ceil()
+            .max(1.0) as u32
+    } else {
+        0
+    };
+
+    #[allow(clippy::cast_precision_loss)]
+    let text = TextBlock::new(&fonts, options.text.as_str(), options.size, options.max_width as f32);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let padding = (options.size / 4.0).ceil() as u32 + stroke_width;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (width, height) = (
+        (text.width().ceil() as u32 + padding * 2).min(MAX_GENERATED_DIM),
+        (text.height().ceil() as u32 + padding * 2).min(MAX_GENERATED_DIM),
+    );
+
+    let mut image = Image::<Rgba>::new(width, height, options.background);
+    let origin = (i64::from(padding), i64::from(padding));
+
+    if let Some(stroke) = options.stroke {
+        text.draw_stroke(&mut image, &fonts, origin, text.width(), options.align, stroke, stroke_width);
+    }
+    text.draw(&mut image, &fonts, origin, text.width(), options.align, options.color);
+
+    Ok(still(image))
+}
+
+/// linearly interpolates between the evenly spaced colors at `t`, ranging from `0.0` to `1.0`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn sample_gradient(colors: &[Rgba], t: f64) -> Rgba {
+    if colors.len() < 2 {
+        return colors.first()
+            .copied()
+            .unwrap_or_else(Rgba::transparent);
+    }
+
+    let position = t.clamp(0.0, 1.0) * (colors.len() - 1) as f64;
+    let index = (position as usize).min(colors.len() - 2);
+    let (from, to, t) = (colors[index], colors[index + 1], position - index as f64);
+
+    let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * t).round() as u8;
+    Rgba::new(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b), mix(from.a, to.a))
+}
+
+/// renders a linear or radial gradient between the provided colors, ignoring the source frames
+pub fn gradient_func(data: ImageArguments<GradientOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+    let (width, height) = clamp_size(options.size);
+    let (cx, cy) = (f64::from(width) / 2.0, f64::from(height) / 2.0);
+
+    let t: Box<dyn Fn(f64, f64) -> f64> = match options.kind {
+        GradientKind::Linear => {
+            let (sin, cos) = options.angle.to_radians().sin_cos();
+            // the extent of the image along the direction of the gradient, so that it spans from corner to corner
+            let extent = (cx * cos).abs() + (cy * sin).abs();
+
+            Box::new(move |x, y| ((x - cx) * cos + (y - cy) * sin) / (extent * 2.0).max(1.0) + 0.5)
+        },
+        GradientKind::Radial => {
+            let radius = cx.hypot(cy).max(1.0);
+
+            Box::new(move |x, y| (x - cx).hypot(y - cy) / radius)
+        },
+    };
+
+    let pixels = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .map(|(x, y)| sample_gradient(&options.colors, t(f64::from(x) + 0.5, f64::from(y) + 0.5)))
+        .collect::<Vec<_>>();
+
+    Ok(still(Image::from_pixels(width, pixels)))
+}
+
+/// the colors the edges found by [`neon_func`] are tinted with, spread from the top left corner of the image to its bottom right
+const NEON_COLORS: [(u8, u8, u8); 3] = [(255, 40, 200), (140, 60, 255), (0, 230, 255)];
+/// the magnitude of the gradient below which a pixel is not considered part of an edge by [`neon_func`]
+const NEON_THRESHOLD: f64 = 0.12;
+
+/// traces the edges of the provided image in neon tubes that glow over a darkened copy of it,
+/// with `glow` being how far the glow spreads from the edges, in pixels
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn neon_func(data: ImageArguments<u32>) -> Result<Frames> {
+    let glow = data.arguments[0];
+    let colors = NEON_COLORS.map(|(r, g, b)| Rgba::new(r, g, b, 255));
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let image = frame.into_image();
+        let (width, height) = image.dimensions();
+        let span = f64::from(width + height).max(1.0);
+
+        let edges = sobel(&image);
+        let tubes = Image::from_pixels(
+            width,
+            edges.iter()
+                .enumerate()
+                .map(|(i, magnitude)| {
+                    let (x, y) = (i as u32 % width, i as u32 / width);
+                    let strength = ((magnitude - NEON_THRESHOLD) / (0.4 - NEON_THRESHOLD)).clamp(0.0, 1.0);
+
+                    Rgba {
+                        a: (strength * 255.0).round() as u8,
+                        ..sample_gradient(&colors, f64::from(x + y) / span)
+                    }
+                })
+                .collect::<Vec<_>>(),
+        );
+        let halo = soft_blur(tubes.clone(), glow);
+        let base = filters::brightness(image, 0.3);
+
+        // the halo is screened twice, as a single pass of a wide blur is too faint to read as a glow
+        let pixels = base.data
+            .iter()
+            .zip(halo.data.iter().zip(tubes.data.iter()))
+            .map(|(&px, (&halo, &tube))| filters::screen(
+                filters::screen(filters::screen(px, halo, 1.0), halo, 0.8),
+                tube,
+                1.0,
+            ))
+            .collect::<Vec<_>>();
+
+        sequence.push_frame(
+            Frame::from_image(Image::from_pixels(width, pixels))
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the width and height of the intros rendered by [`intro_func`]
+pub const INTRO_SIZE: (u32, u32) = (480, 270);
+/// the width and height of the avatar within an intro
+const INTRO_AVATAR_SIZE: u32 = 128;
+/// the number of frames the avatar of an intro takes to slide into place
+const INTRO_SLIDE_FRAMES: u32 = 12;
+/// the number of frames an intro holds on its final frame, after the name has been typed out
+const INTRO_HOLD_FRAMES: u32 = 18;
+/// the delay between the frames of an intro
+const INTRO_FRAME_DELAY: std::time::Duration = std::time::Duration::from_millis(60);
+
+/// renders a short intro animation, in which the avatar slides into the center through glitched frames
+/// and the name is then typed out underneath it, holding on the result with the occasional burst of glitches
+///
+/// only the first frame of the avatar is used, and the glitches are drawn from the seeded rng so that an intro can be reproduced
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+pub fn intro_func(data: ImageArguments<IntroOptions>) -> Result<Frames> {
+    let (width, height) = INTRO_SIZE;
+    let options = &data.arguments[0];
+    let mut rng = data.rng();
+
+    let avatar = data.frames
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image()
+        .resized(INTRO_AVATAR_SIZE, INTRO_AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
+    let avatar = filters::circle_mask(avatar);
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let name = options.name
+        .chars()
+        .take(32)
+        .collect::<Vec<_>>();
+    let text_width = width as f32 * 0.9;
+
+    let avatar_x = i64::from((width - INTRO_AVATAR_SIZE) / 2);
+    let avatar_y = height / 12;
+    let text_y = i64::from(avatar_y + INTRO_AVATAR_SIZE + height / 20);
+    let background = Rgba::new(12, 10, 24, 255);
+
+    let total = INTRO_SLIDE_FRAMES + name.len() as u32 + INTRO_HOLD_FRAMES;
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for i in 0..total {
+        let mut image = Image::<Rgba>::new(width, height, background);
+
+        // the avatar eases out of the left edge, glitching less and less as it settles
+        let progress = (f64::from(i) / f64::from(INTRO_SLIDE_FRAMES)).min(1.0);
+        let eased = 1.0 - (1.0 - progress).powi(3);
+        let x = -i64::from(INTRO_AVATAR_SIZE) + ((avatar_x + i64::from(INTRO_AVATAR_SIZE)) as f64 * eased).round() as i64;
+
+        let burst = i >= INTRO_SLIDE_FRAMES && rng.gen_bool(0.12);
+        let intensity = if burst { 0.6 } else { 1.0 - progress };
+        let layer = filters::glitch(&avatar, &mut rng, intensity);
+
+        if x >= 0 {
+            image.paste(x as u32, avatar_y, &layer);
+        } else if x + i64::from(INTRO_AVATAR_SIZE) > 0 {
+            let visible = layer.cropped((-x) as u32, 0, INTRO_AVATAR_SIZE, INTRO_AVATAR_SIZE);
+            image.paste(0, avatar_y, &visible);
+        }
+
+        // one more character of the name is typed out on each frame after the slide, behind a blinking cursor
+        let typed = i.saturating_sub(INTRO_SLIDE_FRAMES) as usize;
+        let mut text = name.iter()
+            .take(typed)
+            .collect::<String>();
+        if i >= INTRO_SLIDE_FRAMES && (typed < name.len() || (i / 4) % 2 == 0) {
+            text.push('_');
+        }
+        if !text.is_empty() {
+            let block = TextBlock::new(&fonts, &text, 36.0, text_width);
+            block.draw(&mut image, &fonts, (i64::from(width / 20), text_y), text_width, Align::Center, Rgba::new(0, 230, 255, 255));
+        }
+
+        if burst {
+            image = filters::glitch(&image, &mut rng, 0.4);
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(INTRO_FRAME_DELAY)
+        );
+    }
+
+    Ok(sequence)
+}
+
+/// the width and height of the charts rendered by [`chart_func`]
+pub const CHART_SIZE: (u32, u32) = (800, 500);
+/// the most entries a chart may have, beyond which their labels no longer fit
+pub const MAX_CHART_ENTRIES: usize = 24;
+/// the most characters of the title of a chart, which wraps onto at most two lines at this length
+const MAX_CHART_TITLE_LENGTH: usize = 80;
+/// the colors of the entries of a chart, repeating if there are more entries
+const CHART_PALETTE: [Rgba; 8] = [
+    Rgba { r: 88, g: 101, b: 242, a: 255 },
+    Rgba { r: 235, g: 69, b: 158, a: 255 },
+    Rgba { r: 87, g: 242, b: 135, a: 255 },
+    Rgba { r: 254, g: 231, b: 92, a: 255 },
+    Rgba { r: 237, g: 66, b: 69, a: 255 },
+    Rgba { r: 69, g: 196, b: 237, a: 255 },
+    Rgba { r: 250, g: 166, b: 26, a: 255 },
+    Rgba { r: 155, g: 89, b: 182, a: 255 },
+];
+
+/// formats the value of a chart, without decimals if it is whole
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{value:.0}")
+    } else {
+        format!("{value:.1}")
+    }
+}
+
+/// draws a line of `thickness` between the provided points, as a trail of circles
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn stroke_line(image: &mut Image<Rgba>, (x1, y1): (f64, f64), (x2, y2): (f64, f64), thickness: i64, color: Rgba) {
+    let steps = (x2 - x1).hypot(y2 - y1).ceil().max(1.0) as i64;
+
+    for i in 0..=steps {
+        let t = i as f64 / steps as f64;
+        let point = ((x1 + (x2 - x1) * t).round() as i64, (y1 + (y2 - y1) * t).round() as i64);
+
+        fill_circle(image, point, thickness / 2, color, false);
+    }
+}
+
+/// draws a bar or line chart of the provided entries below `top`, with gridlines labelled along the left
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+fn draw_plot(image: &mut Image<Rgba>, fonts: &FontChain, entries: &[(String, f64)], kind: ChartKind, top: u32, colors: (Rgba, Rgba)) {
+    let (foreground, muted) = colors;
+    let (left, right, bottom) = (70, image.width() - 30, image.height() - 50);
+
+    // the axis always includes zero, so that bars grow from a baseline
+    let min = entries.iter().map(|(_, value)| *value).fold(0.0, f64::min);
+    let max = entries.iter().map(|(_, value)| *value).fold(0.0, f64::max);
+    let span = if max - min > 0.0 { max - min } else { 1.0 };
+    let y_of = |value: f64| f64::from(bottom) - (value - min) / span * f64::from(bottom.saturating_sub(top));
+
+    for i in 0..=4 {
+        let value = min + span * f64::from(i) / 4.0;
+        let y = y_of(value).round() as u32;
+        fill_rounded_rect(image, (left, y), (right - left, 1), 0, Rgba::new(60, 63, 68, 255));
+
+        let label = TextBlock::new(fonts, &format_value(value), 16.0, left as f32 - 10.0);
+        label.draw(image, fonts, (0, i64::from(y) - (label.height() / 2.0) as i64), left as f32 - 10.0, Align::Right, muted);
+    }
+
+    let zero = y_of(0.0);
+    fill_rounded_rect(image, (left, zero.round() as u32), (right - left, 2), 0, muted);
+
+    let slot = f64::from(right - left) / entries.len() as f64;
+    let points = entries.iter()
+        .enumerate()
+        .map(|(i, (_, value))| (f64::from(left) + slot * (i as f64 + 0.5), y_of(*value)))
+        .collect::<Vec<_>>();
+
+    match kind {
+        ChartKind::Line => {
+            for pair in points.windows(2) {
+                stroke_line(image, pair[0], pair[1], 4, CHART_PALETTE[0]);
+            }
+            for &(x, y) in &points {
+                fill_circle(image, (x.round() as i64, y.round() as i64), 6, CHART_PALETTE[0], false);
+            }
+        },
+        _ => {
+            let bar_width = (slot * 0.7).max(1.0);
+
+            for (i, &(x, y)) in points.iter().enumerate() {
+                let (y1, y2) = (y.min(zero), y.max(zero));
+
+                fill_rounded_rect(
+                    image,
+                    ((x - bar_width / 2.0).round() as u32, y1.round() as u32),
+                    (bar_width.round() as u32, ((y2 - y1).round() as u32).max(1)),
+                    4,
+                    CHART_PALETTE[i % CHART_PALETTE.len()],
+                );
+            }
+        },
+    }
+
+    for ((label, _), (x, _)) in entries.iter().zip(&points) {
+        let block = TextBlock::new(fonts, label, 16.0, slot as f32);
+        block.draw(image, fonts, ((x - slot / 2.0) as i64, i64::from(bottom) + 10), slot as f32, Align::Center, foreground);
+    }
+}
+
+/// draws a pie chart of the positive entries below `top`, starting from the top and going clockwise,
+/// with a legend of each entry and its share of the total on the right
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+fn draw_pie(image: &mut Image<Rgba>, fonts: &FontChain, entries: &[(String, f64)], top: u32, foreground: Rgba) {
+    let (width, height) = image.dimensions();
+    let total = entries.iter()
+        .map(|(_, value)| value.max(0.0))
+        .sum::<f64>();
+    if total <= 0.0 {
+        let block = TextBlock::new(fonts, "There are no positive values to chart", 24.0, width as f32 * 0.9);
+        block.draw(image, fonts, (i64::from(width / 20), i64::from(height / 2)), width as f32 * 0.9, Align::Center, foreground);
+        return;
+    }
+
+    let shares = entries.iter()
+        .map(|(_, value)| value.max(0.0) / total)
+        .collect::<Vec<_>>();
+    let bounds = shares.iter()
+        .scan(0.0, |sum, share| {
+            *sum += share;
+            Some(*sum)
+        })
+        .collect::<Vec<_>>();
+
+    let radius = f64::from(height.saturating_sub(top + 24).min(width / 2 - 40)) / 2.0;
+    let (cx, cy) = (f64::from(width) * 0.3, f64::from(top) + f64::from(height.saturating_sub(top)) / 2.0);
+
+    for y in (cy - radius).floor() as u32..(cy + radius).ceil() as u32 {
+        for x in (cx - radius).floor() as u32..(cx + radius).ceil() as u32 {
+            let (dx, dy) = (f64::from(x) + 0.5 - cx, f64::from(y) + 0.5 - cy);
+            if dx.hypot(dy) > radius {
+                continue;
+            }
+
+            let turn = (dx.atan2(-dy) / std::f64::consts::TAU).rem_euclid(1.0);
+            let i = bounds.iter()
+                .position(|bound| turn < *bound)
+                .unwrap_or(bounds.len() - 1);
+
+            image.set_pixel(x, y, CHART_PALETTE[i % CHART_PALETTE.len()]);
+        }
+    }
+
+    let legend_x = width * 3 / 5;
+    let row = (height.saturating_sub(top) / entries.len() as u32).min(32);
+    let legend_width = (width - legend_x - 20) as f32;
+
+    for (i, ((label, _), share)) in entries.iter().zip(&shares).enumerate() {
+        let y = top + row * i as u32;
+        fill_rounded_rect(image, (legend_x, y + row / 4), (row / 2, row / 2), 3, CHART_PALETTE[i % CHART_PALETTE.len()]);
+
+        let block = TextBlock::new(fonts, &format!("{label} ({:.1}%)", share * 100.0), (row as f32 * 0.6).max(10.0), legend_width);
+        block.draw(image, fonts, (i64::from(legend_x + row), i64::from(y)), legend_width, Align::Left, foreground);
+    }
+}
+
+/// renders a bar, line or pie chart of the provided entries, ignoring the source frames
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn chart_func(data: ImageArguments<ChartOptions>) -> Result<Frames> {
+    let (width, height) = CHART_SIZE;
+    let options = &data.arguments[0];
+    let entries = &options.data.0[..options.data.0.len().min(MAX_CHART_ENTRIES)];
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let (foreground, muted) = (Rgba::new(220, 221, 222, 255), Rgba::new(130, 134, 140, 255));
+    let mut image = Image::<Rgba>::new(width, height, Rgba::new(30, 31, 34, 255));
+
+    let top = match &options.title {
+        Some(title) => {
+            // the title is kept to a single paragraph of limited length, so that it can not push the chart off the canvas
+            let title = title.split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+                .chars()
+                .take(MAX_CHART_TITLE_LENGTH)
+                .collect::<String>();
+            let text_width = width as f32 * 0.9;
+            let block = TextBlock::new(&fonts, title.as_str(), 30.0, text_width);
+            block.draw(&mut image, &fonts, (i64::from(width / 20), 16), text_width, Align::Center, foreground);
+
+            (24 + block.height().ceil() as u32 + 8)
+                .min(height / 3)
+        },
+        None => 24,
+    };
+
+    match options.kind {
+        ChartKind::Pie => draw_pie(&mut image, &fonts, entries, top, foreground),
+        kind => draw_plot(&mut image, &fonts, entries, kind, top, (foreground, muted)),
+    }
+
+    Ok(still(image))
+}
+
+/// the width and height of each cell of the grids composited by [`grid_func`]
+pub const GRID_CELL_SIZE: u32 = 128;
+/// the space around and between the cells of a grid
+const GRID_GAP: u32 = 12;
+/// the height of the space beneath each cell of a grid that its caption is drawn in
+const GRID_LABEL_HEIGHT: u32 = 24;
+
+/// composites the provided images into a grid of square cells, each cropped to cover its cell, with its caption beneath it
+///
+/// only the first frame of each image is used, and images that could not be decoded are left as empty cells
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn grid_func(data: ImageArguments<GridOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+    let count = options.images.len().max(1) as u32;
+    let columns = match options.columns {
+        0 => f64::from(count).sqrt().ceil() as u32,
+        columns => columns.min(count),
+    };
+    let rows = (count + columns - 1) / columns;
+
+    let label_height = if options.labels.is_empty() { 0 } else { GRID_LABEL_HEIGHT };
+    let (cell_width, cell_height) = (GRID_CELL_SIZE + GRID_GAP, GRID_CELL_SIZE + label_height + GRID_GAP);
+    let mut image = Image::<Rgba>::new(
+        columns * cell_width + GRID_GAP,
+        rows * cell_height + GRID_GAP,
+        Rgba::new(30, 31, 34, 255),
+    );
+
+    let fonts = if options.labels.is_empty() {
+        None
+    } else {
+        Some(
+            ASSETS.fonts()
+                .ok_or(ril::Error::FontError("The fonts could not be loaded"))?
+        )
+    };
+
+    for (i, bytes) in options.images.iter().enumerate() {
+        let (x, y) = (
+            GRID_GAP + (i as u32 % columns) * cell_width,
+            GRID_GAP + (i as u32 / columns) * cell_height,
+        );
+
+        if let Some(frame) = decode_secondary(bytes)
+            .ok()
+            .and_then(|frames| frames.into_iter().next())
+        {
+            image.paste(x, y, &cover(frame.into_image(), GRID_CELL_SIZE, GRID_CELL_SIZE));
+        }
+
+        if let (Some(fonts), Some(label)) = (&fonts, options.labels.get(i)) {
+            let block = TextBlock::new(fonts, label, 16.0, GRID_CELL_SIZE as f32);
+            block.draw(
+                &mut image,
+                fonts,
+                (i64::from(x), i64::from(y + GRID_CELL_SIZE + 4)),
+                GRID_CELL_SIZE as f32,
+                Align::Center,
+                Rgba::new(220, 221, 222, 255),
+            );
+        }
+    }
+
+    Ok(still(image))
+}
+
+/// the width and height of the rank cards rendered by [`rankcard_func`]
+pub const RANK_CARD_SIZE: (u32, u32) = (934, 282);
+/// the width and height of the avatar within a rank card
+const RANK_AVATAR_SIZE: u32 = 180;
+/// the height of the progress bar of a rank card, which is rounded into a pill
+const RANK_BAR_HEIGHT: u32 = 36;
+
+/// formats large amounts of experience compactly, such as `12.3K`
+#[allow(clippy::cast_precision_loss)]
+fn compact_number(value: u64) -> String {
+    match value {
+        0..=999 => value.to_string(),
+        1_000..=999_999 => format!("{:.1}K", value as f64 / 1e3),
+        _ => format!("{:.1}M", value as f64 / 1e6),
+    }
+}
+
+/// renders a rank card of a leveling system: the circular avatar beside the name, rank and level of the member,
+/// over a rounded progress bar filled with a gradient up to their progress towards the next level
+///
+/// this is the entry point for leveling systems to render their cards, only the first frame of the avatar is used
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+pub fn rankcard_func(data: ImageArguments<RankOptions>) -> Result<Frames> {
+    let (width, height) = RANK_CARD_SIZE;
+    let options = &data.arguments[0];
+
+    let avatar = data.frames
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image()
+        .resized(RANK_AVATAR_SIZE, RANK_AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
+    let avatar = filters::circle_mask(avatar);
+
+    let mut image = Image::<Rgba>::new(width, height, Rgba::transparent());
+    fill_rounded_rect(&mut image, (0, 0), (width, height), 24, Rgba::new(35, 39, 42, 255));
+
+    let avatar_x = (height - RANK_AVATAR_SIZE) / 2;
+    image.paste(avatar_x, avatar_x, &avatar);
+
+    let left = avatar_x * 2 + RANK_AVATAR_SIZE;
+    let bar_width = width - left - avatar_x;
+    let bar_y = height - avatar_x - RANK_BAR_HEIGHT - 10;
+
+    // the track of the bar, then its filled part, masked by the same pill so that the gradient keeps rounded ends
+    fill_rounded_rect(&mut image, (left, bar_y), (bar_width, RANK_BAR_HEIGHT), RANK_BAR_HEIGHT / 2, Rgba::new(72, 75, 78, 255));
+
+    let progress = if options.next == 0 { 1.0 } else { (options.xp as f64 / options.next as f64).clamp(0.0, 1.0) };
+    let filled = (f64::from(bar_width) * progress).round() as u32;
+    if filled > 0 {
+        let mut mask = Image::<Rgba>::new(bar_width, RANK_BAR_HEIGHT, Rgba::transparent());
+        fill_rounded_rect(&mut mask, (0, 0), (filled.max(RANK_BAR_HEIGHT).min(bar_width), RANK_BAR_HEIGHT), RANK_BAR_HEIGHT / 2, Rgba::white());
+
+        for y in 0..RANK_BAR_HEIGHT {
+            for x in 0..bar_width {
+                if mask.pixel(x, y).a > 0 {
+                    let color = sample_gradient(&options.colors, f64::from(x) / f64::from(bar_width.max(2) - 1));
+                    image.set_pixel(left + x, bar_y + y, color);
+                }
+            }
+        }
+    }
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let text_width = bar_width as f32;
+    let muted = Rgba::new(170, 174, 180, 255);
+
+    let stats = TextBlock::new(&fonts, &format!("RANK #{}   LEVEL {}", options.rank, options.level), 34.0, text_width);
+    stats.draw(&mut image, &fonts, (i64::from(left), i64::from(avatar_x)), text_width, Align::Right, options.colors.first().copied().unwrap_or_else(Rgba::white));
+
+    let name = options.name
+        .chars()
+        .take(24)
+        .collect::<String>();
+    let name = TextBlock::new(&fonts, &name, 38.0, text_width);
+    let label_y = i64::from(bar_y) - name.height().ceil() as i64 - 6;
+    name.draw(&mut image, &fonts, (i64::from(left) + 8, label_y), text_width, Align::Left, Rgba::white());
+
+    let xp = TextBlock::new(&fonts, &format!("{} / {} XP", compact_number(options.xp), compact_number(options.next)), 26.0, text_width);
+    let xp_y = i64::from(bar_y) - xp.height().ceil() as i64 - 8;
+    xp.draw(&mut image, &fonts, (i64::from(left) - 8, xp_y), text_width, Align::Right, muted);
+
+    Ok(still(image))
+}
+
+/// the width and height of the welcome cards rendered by [`welcome_func`]
+pub const WELCOME_SIZE: (u32, u32) = (1000, 360);
+/// the width and height of the avatar within a welcome card
+const WELCOME_AVATAR_SIZE: u32 = 180;
+/// the thickness of the white ring around the avatar of a welcome card
+const WELCOME_RING: u32 = 6;
+
+/// renders a welcome card, the circular avatar of the new member over the background,
+/// which is darkened so that the greeting and member count underneath the avatar stay legible
+///
+/// only the first frame of the avatar and background are used, and a purple gradient stands in for a missing background
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn welcome_func(data: ImageArguments<WelcomeOptions>) -> Result<Frames> {
+    let (width, height) = WELCOME_SIZE;
+    let options = &data.arguments[0];
+
+    let avatar = data.frames
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image()
+        .resized(WELCOME_AVATAR_SIZE, WELCOME_AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
+    let avatar = filters::circle_mask(avatar);
+
+    let mut image = match &options.background {
+        Some(bytes) => cover(
+            decode_secondary(bytes)?
+                .into_iter()
+                .next()
+                .ok_or(ril::Error::EmptyImageError)?
+                .into_image(),
+            width,
+            height,
+        ),
+        None => Image::from_pixels(
+            width,
+            (0..width * height)
+                .map(|i| {
+                    let (x, y) = (i % width, i / width);
+                    let t = (f64::from(x) / f64::from(width) + f64::from(y) / f64::from(height)) / 2.0;
+                    let channel = |from: f64, to: f64| (from + (to - from) * t) as u8;
+
+                    Rgba::new(channel(88.0, 35.0), channel(101.0, 39.0), channel(242.0, 120.0), 255)
+                })
+                .collect(),
+        ),
+    }
+        .map_pixels(|px| blend(px, Rgba::black(), 110));
+
+    let ring_size = WELCOME_AVATAR_SIZE + WELCOME_RING * 2;
+    let ring = filters::circle_mask(Image::<Rgba>::new(ring_size, ring_size, Rgba::white()));
+    let ring_x = (width - ring_size) / 2;
+    let ring_y = height / 16;
+    image.paste(ring_x, ring_y, &ring);
+    image.paste(ring_x + WELCOME_RING, ring_y + WELCOME_RING, &avatar);
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let text_width = width as f32 * 0.9;
+    let text_x = i64::from(width / 20);
+
+    let name = options.name
+        .chars()
+        .take(32)
+        .collect::<String>();
+    let greeting = TextBlock::new(&fonts, &format!("Welcome, {name}!"), 52.0, text_width);
+    let greeting_y = i64::from(ring_y + ring_size + height / 24);
+    greeting.draw(&mut image, &fonts, (text_x, greeting_y), text_width, Align::Center, Rgba::white());
+
+    let count = TextBlock::new(&fonts, &format!("Member #{}", options.member_count), 28.0, text_width);
+    count.draw(
+        &mut image,
+        &fonts,
+        (text_x, greeting_y + greeting.height().ceil() as i64),
+        text_width,
+        Align::Center,
+        Rgba::new(210, 210, 220, 255),
+    );
+
+    Ok(still(image))
+}
+
+/// the width of the quote cards rendered by [`quote_func`]
+pub const QUOTE_WIDTH: u32 = 720;
+/// the width and height of the avatar within a quote card
+const QUOTE_AVATAR_SIZE: u32 = 80;
+/// the padding around the contents of a quote card
+const QUOTE_PADDING: u32 = 24;
+
+/// renders a message as a quote card in the style of discord's dark theme, the avatar beside the author's name,
+/// with the content underneath it honoring basic markdown through [`MarkdownBlock`]
+///
+/// only the first frame of the avatar is used
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+pub fn quote_func(data: ImageArguments<QuoteOptions>) -> Result<Frames> {
+    let options = &data.arguments[0];
+
+    let avatar = data.frames
+        .into_iter()
+        .next()
+        .ok_or(ril::Error::EmptyImageError)?
+        .into_image()
+        .resized(QUOTE_AVATAR_SIZE, QUOTE_AVATAR_SIZE, ResizeAlgorithm::Lanczos3);
+    let avatar = filters::circle_mask(avatar);
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let left = QUOTE_PADDING * 2 + QUOTE_AVATAR_SIZE;
+    let text_width = (QUOTE_WIDTH - left - QUOTE_PADDING) as f32;
+
+    let name = MarkdownBlock::new(&fonts, &format!("**{}**", escape_markdown(&options.name)), 26.0, text_width);
+    let content = MarkdownBlock::new(&fonts, &options.text, 22.0, text_width);
+
+    let text_height = (name.height() + content.height()).ceil() as u32;
+    let height = QUOTE_PADDING * 2 + text_height.max(QUOTE_AVATAR_SIZE);
+
+    let mut image = Image::<Rgba>::new(QUOTE_WIDTH, height, Rgba::new(49, 51, 56, 255));
+    image.paste(QUOTE_PADDING, QUOTE_PADDING, &avatar);
+
+    name.draw(&mut image, &fonts, (i64::from(left), i64::from(QUOTE_PADDING)), text_width, Align::Left, Rgba::white());
+    content.draw(
+        &mut image,
+        &fonts,
+        (i64::from(left), i64::from(QUOTE_PADDING) + name.height().ceil() as i64),
+        text_width,
+        Align::Left,
+        Rgba::new(219, 222, 225, 255),
+    );
+
+    Ok(still(image))
+}
+
+/// the width and height of the posters rendered by [`posterfy_func`], in the 2:3 ratio of a one-sheet
+pub const POSTER_SIZE: (u32, u32) = (600, 900);
+/// the height of the artwork at the top of a poster, which fades into the black backdrop of the title and credits
+const POSTER_ART_HEIGHT: u32 = 700;
+/// how much taller the credits block is stretched, imitating the tall condensed lettering of a billing block
+const CREDITS_STRETCH: f64 = 2.2;
+/// the roles billed in the credits block of a poster, after the director
+const CREDIT_ROLES: &[&str] = &[
+    "written by",
+    "produced by",
+    "music by",
+    "director of photography",
+    "edited by",
+    "production designer",
+    "costume designer",
+    "casting by",
+    "executive producers",
+];
+/// the syllables the made-up names in the credits block are built from
+const NAME_SYLLABLES: &[&str] = &[
+    "an", "bel", "co", "dar", "el", "fen", "ga", "hol", "is", "jo", "ka", "len",
+    "mar", "no", "or", "pel", "quin", "ro", "sol", "ta", "ul", "ven", "wil", "zo",
+];
+
+/// makes up a name of a first and last name, each of two or three syllables
+fn made_up_name<R: rand::Rng>(rng: &mut R) -> String {
+    let mut word = || (0..rng.gen_range(2..=3))
+        .map(|_| NAME_SYLLABLES[rng.gen_range(0..NAME_SYLLABLES.len())])
+        .collect::<String>();
+
+    let first = word();
+    format!("{first} {}", word())
+}
+
+/// scales the provided image to cover the box of `width` and `height`, cropping away whatever overflows it on either side
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn cover(image: Image<Rgba>, width: u32, height: u32) -> Image<Rgba> {
+    let scale = (f64::from(width) / f64::from(image.width()))
+        .max(f64::from(height) / f64::from(image.height()));
+    let (scaled_width, scaled_height) = (
+        ((f64::from(image.width()) * scale).ceil() as u32).max(width),
+        ((f64::from(image.height()) * scale).ceil() as u32).max(height),
+    );
+    let (x, y) = ((scaled_width - width) / 2, (scaled_height - height) / 2);
+
+    image.resized(scaled_width, scaled_height, ResizeAlgorithm::Lanczos3)
+        .cropped(x, y, x + width, y + height)
+}
+
+/// places the provided image into a movie poster, fading into the title, rendered in the condensed primary font,
+/// and a billing block of credits led by the director and filled out with made-up names
+///
+/// the title and credits are the same for every frame, so they are rendered once onto a transparent layer
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_possible_wrap, clippy::cast_precision_loss)]
+pub fn posterfy_func(data: ImageArguments<PosterOptions>) -> Result<Frames> {
+    let (width, height) = POSTER_SIZE;
+    let options = &data.arguments[0];
+    let mut rng = data.rng();
+
+    let fonts = ASSETS.fonts()
+        .ok_or(ril::Error::FontError("The fonts could not be loaded"))?;
+    let mut layer = Image::<Rgba>::new(width, height, Rgba::transparent());
+    let text_width = width as f32 * 0.9;
+    let margin = i64::from(width / 20);
+
+    let credits = std::iter::once(format!("a film by {}", options.director))
+        .chain(CREDIT_ROLES.iter().map(|role| {
+            let count = if *role == "executive producers" { 2 } else { 1 };
+            let names = (0..count)
+                .map(|_| made_up_name(&mut rng))
+                .collect::<Vec<_>>()
+                .join(" ");
+
+            format!("{role} {names}")
+        }))
+        .collect::<Vec<_>>()
+        .join("   ");
+    let credits = TextBlock::new(&fonts, &credits.to_uppercase(), 13.0, text_width);
+
+    // the credits are rendered at their natural height, then stretched into the tall, condensed texture of a billing block
+    let mut texture = Image::<Rgba>::new(text_width.ceil() as u32, credits.height().ceil().max(1.0) as u32, Rgba::transparent());
+    credits.draw(&mut texture, &fonts, (0, 0), text_width, Align::Center, Rgba::new(200, 200, 200, 255));
+    let texture_height = (f64::from(texture.height()) * CREDITS_STRETCH) as u32;
+    let texture = texture.resized(texture.width(), texture_height.max(1), ResizeAlgorithm::Bilinear);
+    let credits_y = height.saturating_sub(texture.height() + height / 30);
+    layer.paste(margin as u32, credits_y, &texture);
+
+    // the title is shrunk until it fits within three lines
+    let title = if options.title.trim().is_empty() {
+        String::from("UNTITLED")
+    } else {
+        options.title.trim().to_uppercase()
+    };
+    let mut size = 84.0;
+    let mut block = TextBlock::new(&fonts, &title, size, text_width);
+    while block.height() > size * 3.5 && size > 24.0 {
+        size -= 6.0;
+        block = TextBlock::new(&fonts, &title, size, text_width);
+    }
+    let title_y = i64::from(credits_y) - block.height().ceil() as i64 - i64::from(height / 40);
+    block.draw(&mut layer, &fonts, (margin, title_y), text_width, Align::Center, Rgba::white());
+
+    let fade_start = POSTER_ART_HEIGHT / 2;
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(data.frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let art = cover(frame.into_image(), width, POSTER_ART_HEIGHT);
+
+        let mut image = Image::<Rgba>::new(width, height, Rgba::black());
+        image.paste(0, 0, &art);
+
+        for y in fade_start..POSTER_ART_HEIGHT {
+            let factor = 1.0 - f64::from(y - fade_start) / f64::from(POSTER_ART_HEIGHT - fade_start);
+
+            for x in 0..width {
+                let px = image.pixel_mut(x, y);
+                let fade = |c: u8| (f64::from(c) * factor) as u8;
+
+                *px = Rgba::new(fade(px.r), fade(px.g), fade(px.b), 255);
+            }
+        }
+        image.paste(0, 0, &layer);
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    Ok(sequence)
+}
+
 /// resizes an image to a provided size, only if it is larger
 #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
 pub fn contain_size(
@@ -142,7 +2466,7 @@ pub fn contain_size(
         let mut sequence =
             ImageSequence::<Rgba>::new();
 
-        for mut frame in frames {
+        for mut frame in checked(frames) {
             frame.resize(
                 resolved_width,
                 resolved_height,
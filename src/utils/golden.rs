@@ -0,0 +1,124 @@
+//! contains the golden image tests, which run every effect in the [`super::registry`] over the fixture images
+//! and compare their outputs to the golden images stored in [`GOLDEN_DIR`],
+//! so that changes to [`super::functions`] can not silently change their visual output
+//!
+//! a missing golden image fails the test, and every golden image is (re-)recorded from the current outputs
+//! when the `UPDATE_GOLDEN` environment variable is set, after which the changes should be reviewed before being committed
+//!
+//! the comparison is ignored until the golden images have been recorded and committed to [`GOLDEN_DIR`]
+
+use std::{fs, path::{Path, PathBuf}};
+
+use super::{
+    assets::ASSETS_DIR,
+    bench::{self, BENCH_IMAGE},
+    imaging::{decode_frames, encode_frames, Frames, DEFAULT_MAX_FRAMES},
+    options::OutputFormat,
+    registry::EFFECTS,
+};
+
+
+/// the directory the golden images are stored in
+const GOLDEN_DIR: &str = "./tests/golden";
+/// the fixture images every effect is run over, relative to the assets directory
+const FIXTURES: &[&str] = &[BENCH_IMAGE];
+/// the maximum mean difference of a channel between an output and its golden image, out of `255`,
+/// allowing for small differences in rounding between platforms
+const TOLERANCE: f64 = 1.5;
+
+
+/// the mean absolute difference of each channel between two frames of the same dimensions
+fn mean_difference(a: &Frames, b: &Frames) -> Result<f64, String> {
+    if a.len() != b.len() {
+        return Err(format!("has {} frames, while the golden image has {}", a.len(), b.len()));
+    }
+
+    let mut total = 0.0;
+    let mut count = 0_u32;
+
+    for (frame, golden) in a.iter().zip(b.iter()) {
+        if frame.dimensions() != golden.dimensions() {
+            return Err(format!("has dimensions of {:?}, while the golden image has {:?}", frame.dimensions(), golden.dimensions()));
+        }
+
+        for (px, expected) in frame.image().data.iter().zip(golden.image().data.iter()) {
+            total += [(px.r, expected.r), (px.g, expected.g), (px.b, expected.b), (px.a, expected.a)]
+                .into_iter()
+                .map(|(x, y)| f64::from(x.abs_diff(y)))
+                .sum::<f64>();
+            count += 4;
+        }
+    }
+
+    Ok(total / f64::from(count.max(1)))
+}
+
+/// compares a single output to the golden image at `path`, recording it instead if the golden images are being updated
+fn check(output: Frames, path: &Path) -> Result<(), String> {
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let (bytes, _) = encode_frames(output, OutputFormat::Png, None, None)
+            .map_err(|err| err.to_string())?;
+
+        return fs::create_dir_all(GOLDEN_DIR)
+            .and_then(|()| fs::write(path, bytes))
+            .map_err(|err| err.to_string());
+    }
+
+    if !path.exists() {
+        return Err(format!("has no golden image at `{}`, record it by running the tests with `UPDATE_GOLDEN=1`", path.display()));
+    }
+
+    let golden = fs::read(path)
+        .map_err(|err| err.to_string())
+        .and_then(|bytes| decode_frames(&bytes, usize::MAX).map_err(|err| err.to_string()))?;
+
+    let difference = mean_difference(&output, &golden)?;
+    if difference > TOLERANCE {
+        return Err(format!("differs from the golden image by {difference:.2}, exceeding the tolerance of {TOLERANCE}"));
+    }
+
+    Ok(())
+}
+
+#[test]
+#[ignore = "the golden images have not been recorded yet, see the module documentation"]
+fn effects_match_golden_images() {
+    let mut failures = Vec::new();
+
+    for fixture in FIXTURES {
+        let bytes = fs::read(Path::new(ASSETS_DIR).join(fixture))
+            .expect("failed to read the fixture image");
+        let frames = decode_frames(&bytes, DEFAULT_MAX_FRAMES)
+            .expect("failed to decode the fixture image");
+        let stem = Path::new(fixture)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        for effect in EFFECTS {
+            let outputs = bench::apply(effect.name, frames.clone(), &bytes)
+                .unwrap_or_else(|| panic!("`{}` is registered but can not be applied by `bench::apply`", effect.name))
+                .unwrap_or_else(|err| panic!("`{}` failed on `{fixture}`: {err}", effect.name));
+
+            for (i, output) in outputs.into_iter().enumerate() {
+                let path = PathBuf::from(GOLDEN_DIR).join(format!("{stem}_{}_{i}.png", effect.name));
+
+                if let Err(reason) = check(output, &path) {
+                    failures.push(format!("`{}` on `{fixture}` (output {i}) {reason}", effect.name));
+                }
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "the outputs of some effects changed:\n{}", failures.join("\n"));
+}
+
+#[test]
+fn identical_frames_have_no_difference() {
+    let bytes = fs::read(Path::new(ASSETS_DIR).join(BENCH_IMAGE))
+        .expect("failed to read the fixture image");
+    let frames = decode_frames(&bytes, DEFAULT_MAX_FRAMES)
+        .expect("failed to decode the fixture image");
+
+    assert_eq!(mean_difference(&frames, &frames), Ok(0.0));
+}
\ No newline at end of file
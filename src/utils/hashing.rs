@@ -0,0 +1,144 @@
+//! contains the perceptual hashing of images, used by the `phash` command
+//! and to detect images reposted in a guild that has enabled repost detection,
+//! as well as the encoding of images into BlurHash placeholders
+//!
+//! the hash is the sign of the lowest frequencies of the discrete cosine transform of a downscaled grayscale image,
+//! so that images which look alike have hashes differing in only a few bits, regardless of their size or compression
+
+use std::f64::consts::PI;
+
+use ril::prelude::*;
+
+use super::{Error, imaging::Frames};
+
+
+/// the width and height the image is downscaled to before being transformed
+pub const SAMPLE_SIZE: u32 = 32;
+/// the width and height of the block of low frequencies the hash is built from, giving a 64 bit hash
+pub const HASH_SIZE: u32 = 8;
+/// the maximum amount of differing bits for two images to be considered near-duplicates
+pub const REPOST_THRESHOLD: u32 = 8;
+/// the amount of recent hashes kept per guild to detect reposts against
+pub const REPOST_HISTORY: u32 = 1000;
+/// the largest width or height an image is downscaled to before being encoded into a BlurHash, which only keeps its colors
+pub const BLURHASH_SAMPLE: u32 = 64;
+/// the largest width or height of the placeholder decoded from a BlurHash
+pub const BLURHASH_PREVIEW: u32 = 256;
+
+
+/// computes the perceptual hash of the first frame of the provided image, or `None` if it has no frames
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn phash(frames: &Frames) -> Option<u64> {
+    let image = frames.first_frame()?
+        .image()
+        .clone()
+        .resized(SAMPLE_SIZE, SAMPLE_SIZE, ResizeAlgorithm::Lanczos3);
+
+    let luma = image.data
+        .iter()
+        .map(|px| f64::from(px.r) * 0.299 + f64::from(px.g) * 0.587 + f64::from(px.b) * 0.114)
+        .collect::<Vec<_>>();
+
+    let size = f64::from(SAMPLE_SIZE);
+    let coefficients = (0..HASH_SIZE)
+        .flat_map(|v| (0..HASH_SIZE).map(move |u| (u, v)))
+        .map(|(u, v)| {
+            luma.iter()
+                .enumerate()
+                .map(|(i, value)| {
+                    let (x, y) = ((i % SAMPLE_SIZE as usize) as f64, (i / SAMPLE_SIZE as usize) as f64);
+
+                    value
+                        * ((2.0 * x + 1.0) * f64::from(u) * PI / (2.0 * size)).cos()
+                        * ((2.0 * y + 1.0) * f64::from(v) * PI / (2.0 * size)).cos()
+                })
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+
+    // the first coefficient is the average brightness, which would skew the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    Some(
+        coefficients.iter()
+            .enumerate()
+            .filter(|(_, coefficient)| **coefficient > median)
+            .fold(0, |hash, (i, _)| hash | (1 << i))
+    )
+}
+
+/// the amount of bits differing between two hashes, lower meaning the images are more alike
+#[must_use]
+pub const fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// splits the provided hash into `threshold + 1` bands of bits, returned as their shift, mask and value,
+/// so that any hash within `threshold` bits of it has at least one band equal to its own, as not every band can hold a differing bit
+#[must_use]
+pub fn bands(hash: u64, threshold: u32) -> Vec<(u32, u64, u64)> {
+    let count = (threshold + 1).min(64);
+    let width = 64 / count;
+
+    (0..count)
+        .map(|i| {
+            let shift = i * width;
+            // the last band takes the bits left over by the division
+            let bits = if i == count - 1 { 64 - shift } else { width };
+            let mask = if bits == 64 { u64::MAX } else { (1 << bits) - 1 };
+
+            (shift, mask, (hash >> shift) & mask)
+        })
+        .collect()
+}
+
+/// scales the provided dimensions so that the largest of them is `size`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fit(width: u32, height: u32, size: u32) -> (u32, u32) {
+    let scale = f64::from(size) / f64::from(width.max(height).max(1));
+
+    (
+        ((f64::from(width) * scale) as u32).max(1),
+        ((f64::from(height) * scale) as u32).max(1),
+    )
+}
+
+/// encodes the first frame of the provided image into a BlurHash, using more components along its longer side,
+/// returning the hash along with the placeholder it decodes into, at the aspect ratio of the image
+///
+/// # Errors
+/// if the image has no frames, or if the hash could not be encoded or decoded
+pub fn blurhash(frames: &Frames) -> Result<(String, Frames), Error> {
+    let image = frames.first_frame()
+        .ok_or_else(|| Error::CodecError(String::from("the image has no frames")))?
+        .image();
+
+    let (width, height) = fit(image.width(), image.height(), BLURHASH_SAMPLE);
+    let sample = image.clone()
+        .resized(width, height, ResizeAlgorithm::Bilinear);
+    let components = if width >= height { (4, 3) } else { (3, 4) };
+
+    let bytes = sample.data
+        .iter()
+        .flat_map(|px| [px.r, px.g, px.b, px.a])
+        .collect::<Vec<_>>();
+    let hash = blurhash::encode(components.0, components.1, width, height, &bytes)
+        .map_err(|err| Error::CodecError(err.to_string()))?;
+
+    let (width, height) = fit(image.width(), image.height(), BLURHASH_PREVIEW);
+    let pixels = blurhash::decode(hash.as_str(), width, height, 1.0)
+        .map_err(|err| Error::CodecError(err.to_string()))?
+        .chunks_exact(4)
+        .map(|px| Rgba::new(px[0], px[1], px[2], px[3]))
+        .collect::<Vec<_>>();
+
+    let mut placeholder = ImageSequence::<Rgba>::new();
+    placeholder.push_frame(
+        Frame::from_image(Image::from_pixels(width, pixels))
+    );
+
+    Ok((hash, placeholder))
+}
\ No newline at end of file
@@ -1,10 +1,35 @@
 //! contains various frequently used small, general helper functions
 
-use serenity::framework::standard::Args;
-use reqwest::{Client, IntoUrl};
-use super::error::Error;
+use serenity::{
+    framework::standard::Args,
+    model::{
+        channel::Message,
+        id::{GuildId, UserId},
+    },
+};
+use regex::Regex;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, CONTENT_RANGE, CONTENT_TYPE, RANGE},
+    Client,
+    Method,
+    Response,
+    StatusCode,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use super::{
+    error::{Error, Phase},
+    resolver::DEFAULT_MAX_SIZE,
+    policy,
+};
 
 
+lazy_static::lazy_static! {
+    /// regex that matches the mentions, custom emojis and links stripped by [`strip_mentions`]
+    static ref MENTION_REGEX: Regex = Regex::new(r"<(@[!&]?|#)[0-9]{15,20}>|<a?:[a-zA-Z0-9_]{1,32}:[0-9]{15,20}>|https?://\S+").unwrap();
+    /// regex for collapsing runs of whitespace
+    static ref WS_REGEX: Regex = Regex::new(r"\s+").unwrap();
+}
+
 /// simple helper function to resolve the first argument in a command
 pub fn resolve_arg(args: &mut Args) -> Option<String> {
     args.single_quoted::<String>().ok()
@@ -27,31 +52,244 @@ pub fn resolve_extra_arg(img_resolved: bool, args: &mut Args) -> String {
     }
 }
 
+/// resolves the remaining content as a second argument with [`resolve_extra_arg`],
+/// falling back to the content of the message being replied to, stripped of mentions and links, if there is none
+pub fn resolve_extra_arg_or_reply(img_resolved: bool, args: &mut Args, message: &Message) -> String {
+    let arg = resolve_extra_arg(img_resolved, args);
+
+    if arg.trim().is_empty() {
+        message.referenced_message
+            .as_ref()
+            .map(|referenced| strip_mentions(&referenced.content))
+            .filter(|content| !content.is_empty())
+            .unwrap_or(arg)
+    } else {
+        arg
+    }
+}
+
+/// strips the mentions, custom emojis and links out of the provided message content, collapsing the whitespace left behind
+pub fn strip_mentions(content: &str) -> String {
+    let stripped = MENTION_REGEX.replace_all(content, "");
+
+    WS_REGEX.replace_all(stripped.trim(), " ")
+        .into_owned()
+}
+
+/// validates a name for a guild emoji or sticker, which must be 2-32 alphanumeric or underscore characters
+pub fn validate_name(name: &str) -> Result<(), Error> {
+    if (2..=32).contains(&name.chars().count())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        Ok(())
+    } else {
+        Err(Error::InvalidName(name.to_string()))
+    }
+}
+
+/// encodes the provided image bytes into a base64 `data:` URI, as required by the discord API for uploading emojis
+pub fn to_data_uri(bytes: &[u8], is_gif: bool) -> String {
+    format!("data:image/{};base64,{}",
+        if is_gif { "gif" } else { "png" },
+        STANDARD.encode(bytes),
+    )
+}
+
+/// builds the CDN url of an avatar from its hash, for either a user's global avatar
+/// or, if `guild_id` is provided, their guild-specific avatar
+///
+/// `format` defaults to `gif` for animated avatars and `png` otherwise
+pub fn avatar_url(
+    user_id: UserId,
+    guild_id: Option<GuildId>,
+    hash: &str,
+    format: Option<&str>,
+    size: u16,
+) -> String {
+    let format = format.unwrap_or(
+        if hash.starts_with("a_") { "gif" } else { "png" }
+    );
+
+    guild_id.map_or_else(
+        || format!("https://cdn.discordapp.com/avatars/{user_id}/{hash}.{format}?size={size}"),
+        |guild_id| format!("https://cdn.discordapp.com/guilds/{guild_id}/users/{user_id}/avatars/{hash}.{format}?size={size}"),
+    )
+}
+
 /// a helper function to fetch the bytes of a provided url
-/// does not implement checks such as for content type or length, as we will assume it is done beforehand
+/// does not implement checks such as for content type, as we will assume it is done beforehand,
+/// though the download is still aborted if it exceeds [`DEFAULT_MAX_SIZE`]
 pub async fn url_to_bytes<T>(client: Option<&Client>, url: T) -> Result<Vec<u8>, Error>
 where
-    T: IntoUrl + Send
+    T: AsRef<str> + Send
 {
-    let result = if let Some(client) = client {
-        client.get(url)
-            .send()
+    url_to_bytes_limited(client, url, DEFAULT_MAX_SIZE)
+        .await
+}
+
+/// same as [`url_to_bytes`], but with a custom limit on the size of the downloaded content
+pub async fn url_to_bytes_limited<T>(client: Option<&Client>, url: T, max_size: u64) -> Result<Vec<u8>, Error>
+where
+    T: AsRef<str> + Send
+{
+    let phase = || Phase::Fetching(url.as_ref().to_string());
+    let result = policy::get(client, url.as_ref())
+        .await
+        .map_err(|err| err.context(phase()))?;
+
+    if result.status().is_success() {
+        read_limited(result, max_size)
             .await
+            .map_err(|err| err.context(phase()))
     } else {
-        reqwest::get(url)
+        Err(Error::FetchUrlError.context(phase()))
+    }
+}
+
+/// reads the body of a response chunk by chunk,
+/// aborting with [`Error::ImageTooLarge`] as soon as either the reported `Content-Length`
+/// or the amount of bytes received so far reaches `max_size`, rather than buffering the entire body first
+pub async fn read_limited(response: Response, max_size: u64) -> Result<Vec<u8>, Error> {
+    read_remaining(response, Vec::new(), max_size)
+        .await
+}
+
+/// same as [`read_limited`], but continues on from `bytes` that have already been read from the response
+pub async fn read_remaining(mut response: Response, mut bytes: Vec<u8>, max_size: u64) -> Result<Vec<u8>, Error> {
+    let content_length = response.content_length()
+        .unwrap_or(0);
+
+    if content_length >= max_size {
+        return Err(Error::ImageTooLarge(content_length, max_size));
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    bytes.reserve(content_length as usize);
+
+    while let Some(chunk) = response.chunk()
+        .await?
+    {
+        bytes.extend_from_slice(&chunk);
+
+        if bytes.len() as u64 >= max_size {
+            return Err(Error::ImageTooLarge(bytes.len() as u64, max_size));
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// the information gathered by [`probe_url`] before downloading a url
+#[derive(Debug, Clone, Default)]
+pub struct Probe {
+    /// the total size of the content, if reported
+    pub content_length: Option<u64>,
+    /// the reported `Content-Type` of the content
+    pub content_type: Option<String>,
+}
+
+/// cheaply probes a url for its size and content type without downloading it,
+/// using a `HEAD` request and falling back to a ranged `GET` of a single byte for servers that do not support it
+///
+/// returns `None` if neither succeeded
+pub async fn probe_url(client: Option<&Client>, url: &str) -> Option<Probe> {
+    let content_type = |response: &Response|
+        response.headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(ToString::to_string);
+
+    if let Ok(response) =
+        policy::send(client, Method::HEAD, url, HeaderMap::new())
             .await
-    }?;
+    {
+        if response.status().is_success() {
+            return Some(Probe {
+                content_length: response.content_length(),
+                content_type: content_type(&response),
+            });
+        }
+    }
 
-    if result.status().is_success() {
-        Ok(result
-            .bytes()
-            .await?
-            .into())
-    } else {
-        Err(Error::FetchUrlError)
+    let mut headers = HeaderMap::new();
+    headers.insert(RANGE, HeaderValue::from_static("bytes=0-0"));
+
+    let response = policy::send(client, Method::GET, url, headers)
+        .await
+        .ok()?;
+
+    match response.status() {
+        // the total size comes after the slash of a `Content-Range` header such as `bytes 0-0/12345`
+        StatusCode::PARTIAL_CONTENT => Some(Probe {
+            content_length: response.headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.rsplit_once('/'))
+                .and_then(|(_, total)| total.parse().ok()),
+            content_type: content_type(&response),
+        }),
+        status if status.is_success() => Some(Probe {
+            content_length: response.content_length(),
+            content_type: content_type(&response),
+        }),
+        _ => None,
     }
 }
 
+/// sniffs the type of an image from the magic bytes at the start of its content,
+/// returning its mime type, or `None` if it is not a recognized image format
+#[must_use]
+pub fn sniff_image(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xFF\xD8\xFF", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"BM", "image/bmp"),
+        (b"II*\0", "image/tiff"),
+        (b"MM\0*", "image/tiff"),
+        (b"\0\0\x01\0", "image/x-icon"),
+        (b"\xFF\x0A", "image/jxl"),
+        (b"\0\0\0\x0CJXL \r\n\x87\n", "image/jxl"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return Some(*mime);
+    }
+
+    if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    // ISO base media files, such as AVIF and HEIC, carry their major brand and the brands they are compatible with in an `ftyp` box,
+    // the generic `mif1` and `msf1` brands of HEIF being shared by both, so the more specific brands are looked for first
+    if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" {
+        let size = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        let brands = bytes[8..size.clamp(12, bytes.len())]
+            .chunks_exact(4)
+            // the minor version follows the major brand, and is not a brand itself
+            .enumerate()
+            .filter(|(i, _)| *i != 1)
+            .map(|(_, brand)| brand)
+            .collect::<Vec<_>>();
+        let has = |names: &[&[u8; 4]]| brands.iter()
+            .any(|brand| names.iter().any(|name| name.as_slice() == *brand));
+
+        if has(&[b"avif", b"avis"]) {
+            return Some("image/avif");
+        }
+        if has(&[b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1"]) {
+            return Some("image/heic");
+        }
+
+        return None;
+    }
+
+    None
+}
+
 /// helper function that humanizes an integer representing a number of bytes to a human readable formats with SI units
 #[allow(clippy::cast_precision_loss)]
 pub fn humanize_bytes(size: u64) -> String {
@@ -67,4 +305,35 @@ pub fn humanize_bytes(size: u64) -> String {
     }
 
     "NaN".to_string()
+}
+
+/// helper function that humanizes a duration into days, hours, minutes and seconds, omitting the leading units that are zero
+pub fn humanize_duration(duration: std::time::Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (secs / 86400, secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+    if days > 0 {
+        format!("{days}d {hours}h {minutes}m {seconds}s")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m {seconds}s")
+    } else if minutes > 0 {
+        format!("{minutes}m {seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// helper function that reads a memory statistic of the running process in bytes from `/proc/self/status`,
+/// such as `VmRSS` for the current resident memory or `VmHWM` for the peak, only available on linux
+pub fn process_memory(field: &str) -> Option<u64> {
+    std::fs::read_to_string("/proc/self/status")
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix(field)?.strip_prefix(':'))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .map(|kilobytes| kilobytes * 1024)
 }
\ No newline at end of file
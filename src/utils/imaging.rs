@@ -1,63 +1,261 @@
 //! Contains general utility functions for image processing
 
 use std::{
-    vec::IntoIter,
-    iter::{Zip, Cycle},
     time::Instant,
     borrow::Cow,
+    marker::PhantomData,
+    io::{Cursor, Write},
+    sync::Arc,
+    time::Duration,
+    future::Future,
+    fmt,
 };
 
 use serenity::{
     prelude::*,
+    http::HttpError,
     framework::standard::CommandResult,
-    model::prelude::{Message, AttachmentType},
+    model::{
+        prelude::{Message, AttachmentType},
+        application::{
+            component::ButtonStyle,
+            interaction::InteractionResponseType,
+        },
+    },
 };
 
-use ril::prelude::*;
+use ril::{
+    prelude::*,
+    encode::{Encoder, EncoderMetadata},
+    encodings::{
+        gif::GifEncoder,
+        jpeg::{JpegEncoder, JpegEncoderOptions},
+    },
+};
+#[cfg(feature = "webp")]
+use ril::encodings::webp::{WebPEncoder, WebPEncoderOptions};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use zip::{
+    write::FileOptions,
+    result::ZipError,
+    CompressionMethod,
+    ZipWriter,
+};
 use super::{
     Error,
+    error::Phase,
+    codecs,
+    flags::Flags,
     functions::contain_size,
+    entitlements::{Limits, GuildLimits},
+    helpers::{humanize_bytes, sniff_image},
+    memory::{self, Allocation},
+    options::{DelayPolicy, OutputFormat},
+    pool::estimate_cost,
+    jobs::{checked, run_tracked, CancelToken, JobReport, JobTracker},
+    video::{self, Container},
 };
 
 /// `TypeAlias` for an [`ImageSequence`] the bot decodes into and passes around
 pub type Frames = ImageSequence<Rgba>;
+/// `TypeAlias` for the random number generator used by stochastic effects,
+/// which produces the same sequence for a seed across platforms and versions
+pub type Rng = ChaCha8Rng;
 
 /// constant representing the default max dimensions for an input image
 pub const DEFAULT_MAX_DIM: u32 = 500;
 /// constant representing the default max frame count for an input image
 pub const DEFAULT_MAX_FRAMES: usize = 200;
+/// the delay frames without one are displayed for, as browsers and discord do
+pub const ZERO_DELAY_FALLBACK: Duration = Duration::from_millis(100);
+/// the shortest delay a frame can be given through [`DelayPolicy::Override`], as shorter delays are slowed down by most viewers
+pub const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+
+
+/// the amount of attempts made to send a message before giving up
+pub const SEND_ATTEMPTS: u32 = 3;
+/// the delay before retrying to send a message, doubled for each retry after
+pub const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// the maximum amount of files discord allows to be attached to a single message
+pub const MAX_ATTACHMENTS: usize = 10;
 
+/// the factor previews are shrunk by, on both sides as well as in frame count
+pub const PREVIEW_FACTOR: u32 = 4;
+/// how long the button to render a preview at full quality stays active
+pub const PREVIEW_TIMEOUT: Duration = Duration::from_secs(60);
+/// the custom id of the button to render a preview at full quality
+const RENDER_BUTTON_ID: &str = "render_full";
 
-/// a helper function to send the output image to the discord channel,
+/// the maximum size of an archive bundling several outputs: 25 MB
+pub const MAX_ARCHIVE_SIZE: usize = 25_000_000;
+
+/// a single encoded output file
+#[derive(Debug, Clone)]
+pub struct Output {
+    /// the encoded bytes of the file
+    pub bytes: Vec<u8>,
+    /// the extension of the file, such as `png`, `gif` or `zip`
+    pub extension: &'static str,
+}
+
+impl Output {
+    /// encodes the provided frames in the provided format and quality with [`encode_frames`]
+    pub fn encode(sequence: Frames, format: OutputFormat, quality: Option<u8>) -> Result<Self, Error> {
+        let (bytes, extension) = encode_frames(sequence, format, quality, None)?;
+
+        Ok(Self { bytes, extension })
+    }
+
+    /// same as [`Self::encode`], but reports the progress of animated encodes to `token` and stops once it is cancelled
+    pub fn encode_tracked(sequence: Frames, format: OutputFormat, quality: Option<u8>, token: &CancelToken) -> Result<Self, Error> {
+        let (bytes, extension) = encode_frames(sequence, format, quality, Some(token))?;
+
+        Ok(Self { bytes, extension })
+    }
+
+    /// bundles the provided outputs into a single `zip` archive, erroring if it exceeds `max_bytes`
+    ///
+    /// the files are stored without compression, as the images are already compressed
+    pub fn archive(outputs: Vec<Self>, max_bytes: usize) -> Result<Self, Error> {
+        let numbered = outputs.len() > 1;
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = FileOptions::default()
+            .compression_method(CompressionMethod::Stored);
+
+        for (i, output) in outputs.into_iter().enumerate() {
+            writer.start_file(output.filename(i, numbered), options)?;
+            writer.write_all(&output.bytes)
+                .map_err(ZipError::Io)?;
+
+            let size = writer.get_ref().get_ref().len();
+            if size > max_bytes {
+                return Err(Error::ArchiveTooLarge(size as u64, max_bytes as u64));
+            }
+        }
+
+        Ok(Self {
+            bytes: writer.finish()?
+                .into_inner(),
+            extension: "zip",
+        })
+    }
+
+    /// the filename of this output, either `output.{extension}` or numbered as `output_{i + 1}.{extension}`
+    fn filename(&self, i: usize, numbered: bool) -> String {
+        if numbered {
+            format!("output_{}.{}", i + 1, self.extension)
+        } else {
+            format!("output.{}", self.extension)
+        }
+    }
+}
+
+/// a trait for the values an image function may return, either a single image or several
+pub trait IntoOutputs {
+    /// converts this value into the list of images to encode and send
+    fn into_outputs(self) -> Vec<Frames>;
+}
+
+impl IntoOutputs for Frames {
+    fn into_outputs(self) -> Vec<Frames> {
+        vec![self]
+    }
+}
+
+impl IntoOutputs for Vec<Frames> {
+    fn into_outputs(self) -> Vec<Frames> {
+        self
+    }
+}
+
+/// a helper function to send the output images to the discord channel,
 /// used by [`ImageExecutor::run`]
-pub async fn send_output<'a, T>(
+///
+/// a single output is named `output.{extension}`, while several are numbered as `output_1.{extension}`, `output_2.{extension}` ...
+/// and split across as many messages as needed to stay within [`MAX_ATTACHMENTS`], returning the first message sent
+pub async fn send_output(
     ctx: &Context,
     message: &Message,
-    output: T,
-    elapsed: u128,
-    is_gif: bool,
-) -> serenity::Result<()>
+    outputs: Vec<Output>,
+    content: String,
+) -> serenity::Result<Message> {
+    let numbered = outputs.len() > 1;
+
+    let files = outputs.into_iter()
+        .enumerate()
+        .map(|(i, output)| AttachmentType::Bytes {
+            filename: output.filename(i, numbered),
+            data: Cow::Owned(output.bytes),
+        })
+        .collect::<Vec<_>>();
+
+    let mut chunks = files.chunks(MAX_ATTACHMENTS);
+    let first = chunks.next()
+        .unwrap_or_default();
+    let content = content.as_str();
+
+    let sent = retry_transient(move ||
+        message.channel_id.send_message(ctx,
+            move |msg| {
+                msg.content(content)
+                    .reference_message(message)
+                    .allowed_mentions(|am| am.empty_parse())
+                    .add_files(first.to_vec())
+            }
+        )
+    ).await?;
+
+    for chunk in chunks {
+        retry_transient(move ||
+            message.channel_id.send_message(ctx,
+                move |msg| {
+                    msg.allowed_mentions(|am| am.empty_parse())
+                        .add_files(chunk.to_vec())
+                }
+            )
+        ).await?;
+    }
+
+    Ok(sent)
+}
+
+/// returns whether or not the error is likely to be transient, being either a server error or a connection failure
+fn is_transient(err: &SerenityError) -> bool {
+    match err {
+        SerenityError::Http(err) => match err.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => response.status_code.is_server_error(),
+            HttpError::Request(err) => err.is_connect() || err.is_timeout(),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// runs the provided request up to [`SEND_ATTEMPTS`] times, retrying with an exponential backoff
+/// starting from [`RETRY_DELAY`] as long as it fails with a transient error,
+/// so that a finished render is not thrown away because a single upload failed
+pub async fn retry_transient<F, Fut, T>(mut request: F) -> serenity::Result<T>
 where
-    T: Into<Cow<'a, [u8]>> + Send
+    F: FnMut() -> Fut,
+    Fut: Future<Output = serenity::Result<T>>,
 {
-    let content = format!("**Process Time:** `{elapsed} ms`");
-    let format = if is_gif { "gif" } else { "png" };
-
-    message.channel_id.send_message(ctx,
-        |msg| {
-            msg.content(content)
-                .reference_message(message)
-                .allowed_mentions(|am| am.empty_parse())
-                .add_file(
-                    AttachmentType::Bytes {
-                        data: output.into(),
-                        filename: format!("output.{format}"),
-                    }
-                )
-        }
-    ).await?;
+    let mut delay = RETRY_DELAY;
+    let mut attempt = 1;
 
-    Ok(())
+    loop {
+        match request().await {
+            Err(err) if attempt < SEND_ATTEMPTS && is_transient(&err) => {
+                tokio::time::sleep(delay)
+                    .await;
+
+                delay *= 2;
+                attempt += 1;
+            },
+            result => return result,
+        }
+    }
 }
 
 /// a wrapper struct to allow for a dynamic amount of arguments
@@ -68,16 +266,30 @@ pub struct ImageArguments<A = ()> {
     pub frames: Frames,
     /// a vector of arguments
     pub arguments: Vec<A>,
+    /// the seed of the random number generator, see [`Self::rng`]
+    pub seed: u64,
+}
+
+impl<A> ImageArguments<A> {
+    /// returns a new random number generator seeded with [`Self::seed`],
+    /// so that stochastic effects render identically when given the same seed
+    #[must_use]
+    pub fn rng(&self) -> Rng {
+        Rng::seed_from_u64(self.seed)
+    }
 }
 
 /// a general struct to execute a function to process an image
 /// and hold configuration information for the execution
 ///
 /// does repetitive things such as resolving, opening, encoding and sending the image.
+///
+/// the function may either return a single image, or several which are all sent together, see [`IntoOutputs`]
 #[derive(Clone)]
-pub struct ImageExecutor<'a, F, A>
+pub struct ImageExecutor<'a, F, A, O = Frames>
 where
-    F: Fn(ImageArguments<A>) -> ril::Result<Frames> + Send + Sync + 'static,
+    F: Fn(ImageArguments<A>) -> ril::Result<O> + Send + Sync + 'static,
+    O: IntoOutputs,
 {
     /// the current command context
     ctx: &'a Context,
@@ -87,18 +299,37 @@ where
     function: Option<F>,
     /// the maximum width allowed for an image
     max_width: Option<u32>,
-    /// the maximum height allowed for an image
+    /// the maximum height allowed for an image, defaults to the limit of the invoking user's tier
     max_height: Option<u32>,
-    /// the maximum number of frames allowed for an image
+    /// the maximum number of frames allowed for an image, defaults to the limit of the invoking user's tier
     max_frames: Option<usize>,
     /// any extra arguments passed to the function
     arguments: Vec<A>,
+    /// whether or not to bundle the outputs into a single `zip` archive
+    archive: bool,
+    /// whether or not the function is stochastic, in which case the seed used is shown in the output message
+    stochastic: bool,
+    /// the seed of the random number generator passed to the function, randomly generated if not specified
+    seed: Option<u64>,
+    /// how the delays of animated outputs are chosen, keeping those returned by the function if not specified
+    delay: Option<DelayPolicy>,
+    /// an extra line of text shown above the process time in the output message
+    note: Option<String>,
+    /// whether or not to first send a low resolution preview, only rendering at full quality once requested
+    preview: bool,
+    /// the factor the estimated cost of the job is scaled by, for functions doing the work of several effects
+    weight: u64,
+    /// the estimated size in bytes of the image generated by functions without a source, see [`Self::generate`]
+    generated: u64,
+    /// the type of the value returned by the function
+    output: PhantomData<fn() -> O>,
 }
 
-impl<'a, F, A> ImageExecutor<'a, F, A>
+impl<'a, F, A, O> ImageExecutor<'a, F, A, O>
 where
-    A: Send + Sync + 'static,
-    F: Fn(ImageArguments<A>) -> ril::Result<Frames> + Send + Sync + 'static,
+    A: Clone + Send + Sync + 'static,
+    F: Fn(ImageArguments<A>) -> ril::Result<O> + Send + Sync + 'static,
+    O: IntoOutputs,
 {
     /// creates a new instance of [`ImageExecutor`] with the basic, required information passed
     #[must_use]
@@ -107,9 +338,18 @@ where
             ctx, message,
             function: None,
             max_width: None,
-            max_height: Some(DEFAULT_MAX_DIM),
-            max_frames: Some(DEFAULT_MAX_FRAMES),
+            max_height: None,
+            max_frames: None,
             arguments: Vec::new(),
+            archive: false,
+            stochastic: false,
+            seed: None,
+            delay: None,
+            note: None,
+            preview: false,
+            weight: 1,
+            generated: 0,
+            output: PhantomData,
         }
     }
 
@@ -131,7 +371,6 @@ where
 
     /// a builder method to set [`self.max_height`]
     #[must_use]
-    #[allow(dead_code)]
     pub const fn max_height(mut self, max_height: u32) -> Self {
         self.max_height = Some(max_height);
         self
@@ -153,74 +392,948 @@ where
         self
     }
 
+    /// a builder method to set [`self.archive`],
+    /// bundling the outputs into a single `zip` attachment of up to [`MAX_ARCHIVE_SIZE`]
+    #[must_use]
+    pub const fn archive(mut self, archive: bool) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// a builder method marking the function as stochastic and setting [`self.seed`],
+    /// generally from the `--seed` flag so that users can reproduce a render
+    #[must_use]
+    pub const fn seed(mut self, seed: Option<u64>) -> Self {
+        self.stochastic = true;
+        self.seed = seed;
+        self
+    }
+
+    /// a builder method to set [`self.delay`], which can still be overridden by the `--delay` flag,
+    /// generally [`DelayPolicy::Preserve`] for functions synthesizing frames so that they follow the timing of animated sources
+    #[must_use]
+    pub const fn delay(mut self, delay: DelayPolicy) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// a builder method to set [`self.note`]
+    #[must_use]
+    pub fn note<T: Into<String>>(mut self, note: T) -> Self {
+        self.note = Some(note.into());
+        self
+    }
+
+    /// a builder method to set [`self.preview`], generally from the `--preview` flag of expensive effects
+    #[must_use]
+    pub const fn preview(mut self, preview: bool) -> Self {
+        self.preview = preview;
+        self
+    }
+
+    /// a builder method to set [`self.weight`], such as the number of effects chained by the function
+    #[must_use]
+    pub const fn weight(mut self, weight: u64) -> Self {
+        self.weight = weight;
+        self
+    }
+
     /// the primary method to call, this basically uses all of the passed information
     /// it accepts the bytes of the source image which you should resolve beforehand using `ImageResolver`
     /// and proceeds to execute the provided function, with all the wrapping tasks also done here
     pub async fn run(self, bytes: Vec<u8>) -> CommandResult {
-        let instant = Instant::now();
-        let (result, is_gif) = tokio::task::spawn_blocking(
-            move || -> Result<(Vec<u8>, bool), Error> {
-                let mut image = ImageSequence::<Rgba>::from_bytes_inferred(&bytes[..])?
-                    .into_sequence()?;
+        self.run_batch(vec![bytes])
+            .await
+    }
 
-                let max_frames = self.max_frames
-                    .unwrap_or(DEFAULT_MAX_FRAMES);
+    /// similar to [`Self::run`], but executes the function once without a source image,
+    /// for functions generating their images from their arguments alone, which receive empty frames
+    ///
+    /// the cost of the job is estimated from the provided dimensions of the generated image, as there is no source to estimate it from
+    pub async fn generate(mut self, width: u32, height: u32) -> CommandResult {
+        self.generated = memory::decoded_size(width, height, 1);
+        self.run_batch(Vec::new())
+            .await
+    }
 
-                if image.len() > max_frames {
-                    return Err(Error::TooManyFrames(image.len(), max_frames))
-                }
+    /// similar to [`Self::run`], but executes the function on each of the provided source images,
+    /// sending all of the outputs together
+    ///
+    /// the size and frame limits of the invoking user's tier apply to the sources as a whole, rather than to each of them
+    pub async fn run_batch(self, sources: Vec<Vec<u8>>) -> CommandResult {
+        let started = Instant::now();
+        let tracker = JobTracker::get(self.ctx)
+            .await;
+        let invoked = tracker.as_ref()
+            .and_then(|tracker| tracker.invocation(self.message.id));
 
-                image = contain_size(
-                    ImageArguments { frames: image, arguments: Vec::new() },
-                    self.max_width,
-                    self.max_height,
-                )?;
-
-                let sequence = self.function
-                    .expect("No function was specified or passed, have you called the builder method `function(f)`?")
-                    (ImageArguments::<A> { frames: image, arguments: self.arguments })?
-                    .looped_infinitely();
-
-                let is_gif = sequence.len() > 1;
-                let format =
-                    if is_gif {
-                        ImageFormat::Gif
-                    } else {
-                        ImageFormat::Png
-                    };
-
-                let mut bytes: Vec<u8> = Vec::new();
-                sequence.encode(format, &mut bytes)?;
-
-                Ok((bytes, is_gif))
-            }
+        // the output format, frame, quality and verbosity apply to every command, so they are read from the message rather than passed in
+        let flags = Flags::from_content(self.message.content.as_str());
+        let overrides = GuildLimits::lookup(self.ctx, self.message.guild_id)
+            .await;
+        let limits = overrides.apply(
+            Limits::lookup(self.ctx, &self.message.author)
+                .await
+        );
+
+        let total_size = sources.iter()
+            .map(|bytes| bytes.len() as u64)
+            .sum::<u64>();
+
+        if sources.len() > 1 && total_size > limits.max_size {
+            return Err(Error::ImageTooLarge(total_size, limits.max_size).into());
+        }
+
+        let settings = RenderSettings {
+            max_width: self.max_width,
+            max_height: self.max_height
+                .or(Some(limits.max_dim)),
+            max_frames: self.max_frames
+                .unwrap_or(limits.max_frames),
+            seed: self.seed
+                .unwrap_or_else(rand::random),
+            archive: self.archive,
+            preview: false,
+            format: flags.get::<OutputFormat>("format")?
+                .or(overrides.format)
+                .unwrap_or_default(),
+            frame: flags.get("frame")?,
+            delay: flags.get::<DelayPolicy>("delay")?
+                .or(self.delay),
+            quality: flags.get::<u8>("quality")?
+                .map(|quality| quality.clamp(1, 100)),
+        };
+        let cost = sources.iter()
+            .map(|bytes| estimate_cost(bytes))
+            .sum::<u64>()
+            .saturating_add(self.generated)
+            .saturating_mul(self.weight.max(1));
+        let source_type = sources.first()
+            .and_then(|bytes| sniff_image(bytes));
+
+        let function = Arc::new(
+            self.function
+                .expect("No function was specified or passed, have you called the builder method `function(f)`?")
+        );
+        let arguments = Arc::new(self.arguments);
+        let sources = Arc::new(sources);
+
+        let mut content = self.note
+            .map(|note| note + "\n")
+            .unwrap_or_default();
+        if self.stochastic {
+            content.push_str(format!("**Seed:** `{}`\n", settings.seed).as_str());
+        }
+
+        if self.preview {
+            let instant = Instant::now();
+            let outputs = {
+                let (function, arguments, sources) = (function.clone(), arguments.clone(), sources.clone());
+
+                run_tracked(self.ctx, self.message, cost / u64::from(PREVIEW_FACTOR * PREVIEW_FACTOR),
+                    move |token| render(
+                        function.as_ref(),
+                        &arguments,
+                        &sources,
+                        RenderSettings { preview: true, ..settings },
+                        token,
+                    )
+                )
+                .await?
+                .0
+            };
+
+            let elapsed = instant.elapsed()
+                .as_millis();
+
+            let mut preview = send_output(
+                self.ctx,
+                self.message,
+                outputs,
+                format!("{content}**Preview** rendered in `{elapsed} ms`, at a quarter of the resolution and frame rate"),
+            )
+                .await?;
+
+            preview.edit(self.ctx, |msg|
+                msg.components(|components|
+                    components.create_action_row(|row|
+                        row.create_button(|button|
+                            button.custom_id(RENDER_BUTTON_ID)
+                                .label("Render full quality")
+                                .style(ButtonStyle::Primary)
+                        )
+                    )
+                )
+            )
+                .await?;
+
+            let interaction = preview.await_component_interaction(self.ctx)
+                .author_id(self.message.author.id)
+                .timeout(PREVIEW_TIMEOUT)
+                .await;
+
+            preview.edit(self.ctx, |msg| msg.components(|components| components))
+                .await?;
+
+            let Some(interaction) = interaction else {
+                return Ok(());
+            };
+
+            interaction.create_interaction_response(self.ctx, |response|
+                response.kind(InteractionResponseType::DeferredUpdateMessage)
+            )
+                .await?;
+        }
+
+        let instant = Instant::now();
+        let (outputs, mut timings) = run_tracked(self.ctx, self.message, cost,
+            move |token| render(function.as_ref(), &arguments, &sources, settings, token)
         )
-        .await?
-        .map_err(Error::from)?;
+        .await?;
 
         let elapsed = instant.elapsed()
             .as_millis();
 
-        send_output(
-            self.ctx,
-            self.message,
-            result, elapsed, is_gif,
-        )
-            .await?;
+        content.push_str(format!("**Process Time:** `{elapsed} ms`").as_str());
+        if flags.has("verbose") {
+            timings.resolve = invoked.map_or(Duration::ZERO, |invoked| started - invoked);
+            content.push_str(format!("\n{timings}").as_str());
+        }
+
+        let report = JobReport {
+            source: source_type,
+            output_size: outputs.iter()
+                .map(|output| output.bytes.len() as u64)
+                .sum(),
+        };
+
+        send_output(self.ctx, self.message, outputs, content)
+            .await
+            .map_err(|err| Error::from(err).context(Phase::Sending))?;
+
+        if let Some(tracker) = tracker {
+            tracker.report(self.message.id, report);
+        }
 
         Ok(())
     }
 }
 
+/// decodes the provided sources on the worker pool under the limits of the invoking user's tier and of the guild,
+/// as [`ImageExecutor::run_batch`] does, passing their frames contained within the maximum dimension to `function`,
+/// for commands reading their sources, such as to hash them or scan them for codes, rather than rendering them into outputs
+///
+/// # Errors
+/// if the sources are too large or could not be decoded, or if `function` fails
+pub async fn inspect<F, T>(ctx: &Context, message: &Message, sources: Vec<Vec<u8>>, function: F) -> Result<T, Error>
+where
+    F: FnOnce(Vec<Frames>) -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    inspect_with(ctx, message, sources, decode_frames, function)
+        .await
+}
+
+/// similar to [`inspect`], but only decodes the first frame of each source through [`decode_first_frame`],
+/// for commands making a still image out of any source
+///
+/// # Errors
+/// if the sources are too large or could not be decoded, or if `function` fails
+pub async fn inspect_first<F, T>(ctx: &Context, message: &Message, sources: Vec<Vec<u8>>, function: F) -> Result<T, Error>
+where
+    F: FnOnce(Vec<Frames>) -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    inspect_with(ctx, message, sources, |bytes, _| decode_first_frame(bytes), function)
+        .await
+}
+
+/// the shared implementation of [`inspect`] and [`inspect_first`], decoding each source with `decode`
+/// and passing it the maximum number of frames
+async fn inspect_with<F, T>(
+    ctx: &Context,
+    message: &Message,
+    sources: Vec<Vec<u8>>,
+    decode: fn(&[u8], usize) -> Result<Frames, Error>,
+    function: F,
+) -> Result<T, Error>
+where
+    F: FnOnce(Vec<Frames>) -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let limits = GuildLimits::lookup(ctx, message.guild_id)
+        .await
+        .apply(
+            Limits::lookup(ctx, &message.author)
+                .await
+        );
+
+    let total_size = sources.iter()
+        .map(|bytes| bytes.len() as u64)
+        .sum::<u64>();
+    if total_size > limits.max_size {
+        return Err(Error::ImageTooLarge(total_size, limits.max_size));
+    }
+
+    let cost = sources.iter()
+        .map(|bytes| estimate_cost(bytes))
+        .sum::<u64>();
+
+    run_tracked(ctx, message, cost, move |token| {
+        let mut images = Vec::with_capacity(sources.len());
+
+        for bytes in &sources {
+            token.check()?;
+            let frames = decode(bytes, limits.max_frames)
+                .map_err(|err| err.context(Phase::Decoding))?;
+
+            images.push(
+                contain_size(ImageArguments { frames, arguments: Vec::new(), seed: 0 }, None, Some(limits.max_dim))?
+            );
+        }
+
+        function(images)
+    })
+        .await
+}
+
+/// the settings a render is performed with, resolved by [`ImageExecutor::run_batch`]
+#[derive(Debug, Clone, Copy)]
+struct RenderSettings {
+    /// the maximum width allowed for an image
+    max_width: Option<u32>,
+    /// the maximum height allowed for an image
+    max_height: Option<u32>,
+    /// the maximum number of frames allowed across all of the images
+    max_frames: usize,
+    /// the seed of the random number generator passed to the function
+    seed: u64,
+    /// whether or not to bundle the outputs into a single `zip` archive
+    archive: bool,
+    /// whether or not to render a low resolution preview, see [`downsample`]
+    preview: bool,
+    /// the format the outputs are encoded in, overriding the default of the guild with the `--format` flag
+    format: OutputFormat,
+    /// the frame of animated outputs to keep, counting from `1`, from the `--frame` flag
+    frame: Option<usize>,
+    /// how the delays of animated outputs are chosen, from the `--delay` flag or the command's default
+    delay: Option<DelayPolicy>,
+    /// the quality lossy outputs are encoded at, from `1` to `100`, from the `--quality` flag
+    quality: Option<u8>,
+}
+
+/// how long each phase of a job took, shown in the output message with the `--verbose` flag
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimings {
+    /// from the invocation of the command until its images were resolved and fetched
+    pub resolve: Duration,
+    /// decoding the source images
+    pub decode: Duration,
+    /// executing the function on each of the images
+    pub process: Duration,
+    /// encoding the outputs
+    pub encode: Duration,
+    /// the most memory allocated at once by the job itself by the end of decoding, processing and encoding,
+    /// see [`memory::Allocation`]
+    pub memory: [u64; 3],
+}
+
+impl PhaseTimings {
+    /// records the peak of `allocation` after the phase at `index` of [`Self::memory`], keeping the highest seen
+    fn record_memory(&mut self, index: usize, allocation: &Allocation) {
+        self.memory[index] = self.memory[index].max(allocation.peak());
+    }
+}
+
+impl fmt::Display for PhaseTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f,
+            "**Resolve:** `{} ms` | **Decode:** `{} ms` | **Process:** `{} ms` | **Encode:** `{} ms`",
+            self.resolve.as_millis(),
+            self.decode.as_millis(),
+            self.process.as_millis(),
+            self.encode.as_millis(),
+        )?;
+
+        let [decode, process, encode] = self.memory;
+        write!(f,
+            "\n**Peak Allocated:** `{}` by decoding | `{}` by processing | `{}` by encoding",
+            humanize_bytes(decode),
+            humanize_bytes(process),
+            humanize_bytes(encode),
+        )
+    }
+}
+
+/// decodes the provided sources and executes the function on each of them, encoding the outputs,
+/// this is blocking and is run on the worker pool by [`ImageExecutor::run_batch`]
+///
+/// `token` is checked between each stage and each image, stopping the render once it has been cancelled,
+/// the time spent in each phase is returned along with the outputs, apart from [`PhaseTimings::resolve`]
+fn render<F, A, O>(
+    function: &F,
+    arguments: &[A],
+    sources: &[Vec<u8>],
+    settings: RenderSettings,
+    token: &CancelToken,
+) -> Result<(Vec<Output>, PhaseTimings), Error>
+where
+    A: Clone,
+    F: Fn(ImageArguments<A>) -> ril::Result<O>,
+    O: IntoOutputs,
+{
+    let allocation = Allocation::start();
+    let mut timings = PhaseTimings::default();
+    let mut images = Vec::with_capacity(sources.len());
+    let mut total_frames = 0;
+
+    for bytes in sources {
+        token.check()?;
+        let instant = Instant::now();
+        let image = decode_frames(bytes, settings.max_frames)
+            .map_err(|err| err.context(Phase::Decoding))?;
+        timings.decode += instant.elapsed();
+        timings.record_memory(0, &allocation);
+
+        total_frames += image.len();
+        if total_frames > settings.max_frames {
+            return Err(Error::TooManyFrames(total_frames, settings.max_frames));
+        }
+
+        images.push(image);
+    }
+
+    // generators have no sources, but are still executed once
+    if sources.is_empty() {
+        images.push(ImageSequence::new());
+    }
+
+    let mut outputs = Vec::new();
+
+    for image in images {
+        token.check()?;
+        let instant = Instant::now();
+        let mut image = contain_size(
+            ImageArguments { frames: image, arguments: Vec::new(), seed: 0 },
+            settings.max_width,
+            settings.max_height,
+        )?;
+
+        if settings.preview {
+            image = downsample(image, PREVIEW_FACTOR);
+        }
+        let source_delays = image.iter()
+            .map(Frame::delay)
+            .collect::<Vec<_>>();
+
+        let sequences = function(
+            ImageArguments { frames: image, arguments: arguments.to_vec(), seed: settings.seed }
+        )
+            .map_err(|err| Error::from(err).context(Phase::Processing))?
+            .into_outputs();
+        timings.process += instant.elapsed();
+        timings.record_memory(1, &allocation);
+
+        for mut sequence in sequences {
+            token.check()?;
+
+            if let Some(policy) = settings.delay {
+                sequence = retime(sequence, policy, &source_delays);
+            }
+
+            if let Some(frame) = settings.frame {
+                sequence = select_frame(sequence, frame);
+            } else if settings.format.is_static() && sequence.len() > 1 {
+                return Err(Error::AnimatedOutput(settings.format.as_str()));
+            }
+
+            let instant = Instant::now();
+            outputs.push(
+                Output::encode_tracked(sequence, settings.format, settings.quality, token)
+                    .map_err(|err| err.context(Phase::Encoding))?
+            );
+            timings.encode += instant.elapsed();
+            timings.record_memory(2, &allocation);
+        }
+    }
+
+    if settings.archive {
+        let instant = Instant::now();
+        let archive = Output::archive(outputs, MAX_ARCHIVE_SIZE)?;
+        timings.encode += instant.elapsed();
+
+        Ok((vec![archive], timings))
+    } else {
+        Ok((outputs, timings))
+    }
+}
+
+/// sets the delays of the provided frames according to `policy`,
+/// with `source_delays` being the delays of the frames of the source they were made from
+///
+/// still outputs are left as they are, as are outputs of still sources under [`DelayPolicy::Preserve`],
+/// which have no timing to match
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn retime(frames: Frames, policy: DelayPolicy, source_delays: &[Duration]) -> Frames {
+    if frames.len() < 2 || (policy == DelayPolicy::Preserve && source_delays.len() < 2) {
+        return frames;
+    }
+
+    let mean = total_duration(&frames) / frames.len() as u32;
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for (i, frame) in frames.into_iter().enumerate() {
+        let delay = match policy {
+            DelayPolicy::Preserve => source_delays[i % source_delays.len()],
+            DelayPolicy::Normalize => mean,
+            DelayPolicy::Override(delay) => delay.max(MIN_FRAME_DELAY),
+        };
+
+        sequence.push_frame(frame.with_delay(delay));
+    }
+
+    sequence
+}
+
+/// keeps only the `frame`-th frame of the provided frames, counting from `1`, or the last one if there are fewer
+#[must_use]
+pub fn select_frame(frames: Frames, frame: usize) -> Frames {
+    let index = frame.saturating_sub(1)
+        .min(frames.len().saturating_sub(1));
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    if let Some(frame) = frames.into_iter().nth(index) {
+        sequence.push_frame(frame);
+    }
+
+    sequence
+}
+
+/// the delay the provided frame is displayed for, treating a delay of zero as [`ZERO_DELAY_FALLBACK`]
+#[must_use]
+pub fn frame_delay(frame: &Frame<Rgba>) -> Duration {
+    match frame.delay() {
+        Duration::ZERO => ZERO_DELAY_FALLBACK,
+        delay => delay,
+    }
+}
+
+/// the time at which each of the provided frames starts being displayed, from the start of the animation
+#[must_use]
+pub fn frame_timestamps(frames: &Frames) -> Vec<Duration> {
+    frames.iter()
+        .scan(Duration::ZERO, |elapsed, frame| {
+            let start = *elapsed;
+            *elapsed += frame_delay(frame);
+
+            Some(start)
+        })
+        .collect()
+}
+
+/// the total duration of the provided frames
+#[must_use]
+pub fn total_duration(frames: &Frames) -> Duration {
+    frames.iter()
+        .map(frame_delay)
+        .sum()
+}
+
+/// the index of the frame that is displayed at `time` from the start of the animation,
+/// or the last frame if `time` is past its end
+#[must_use]
+pub fn frame_at(frames: &Frames, time: Duration) -> usize {
+    frame_timestamps(frames)
+        .into_iter()
+        .rposition(|start| start <= time)
+        .unwrap_or_default()
+}
+
+/// keeps only the frames within `range` of indices, which is clamped to the frames there are
+#[must_use]
+pub fn cut_frames(frames: Frames, range: std::ops::Range<usize>) -> Frames {
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for frame in frames.into_iter()
+        .skip(range.start)
+        .take(range.end.saturating_sub(range.start))
+    {
+        sequence.push_frame(frame);
+    }
+
+    sequence
+}
+
+/// keeps only the part of the animation displayed between `start` and `end`, or its end if `None`,
+/// shortening the delays of the frames cut through so that the result lasts exactly as long as the range
+///
+/// at least one frame is always kept, so that a range past the end of the animation keeps its last frame
+#[must_use]
+pub fn cut_time(frames: Frames, start: Duration, end: Option<Duration>) -> Frames {
+    let end = end.unwrap_or(Duration::MAX);
+    let timestamps = frame_timestamps(&frames);
+    let last = frames.len().saturating_sub(1);
+    let mut sequence = ImageSequence::<Rgba>::new();
+    let mut kept = false;
+
+    for (i, (frame, frame_start)) in frames.into_iter()
+        .zip(timestamps)
+        .enumerate()
+    {
+        let frame_end = frame_start + frame_delay(&frame);
+        let (visible_start, visible_end) = (frame_start.max(start), frame_end.min(end));
+
+        if visible_start < visible_end || (i == last && !kept) {
+            let delay = visible_end.saturating_sub(visible_start);
+            let delay = if delay.is_zero() { frame_delay(&frame) } else { delay };
+
+            sequence.push_frame(frame.with_delay(delay));
+            kept = true;
+        }
+    }
+
+    sequence
+}
+
+/// shrinks the provided frames by `factor` on both sides and keeps only every `factor`-th frame,
+/// lengthening their delays so that animations keep the same duration, used to render previews
+#[must_use]
+pub fn downsample(frames: Frames, factor: u32) -> Frames {
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for mut frame in frames.into_iter().step_by(factor as usize) {
+        let delay = frame.delay() * factor;
+
+        frame.resize(
+            (frame.width() / factor).max(1),
+            (frame.height() / factor).max(1),
+            ResizeAlgorithm::Nearest,
+        );
+        sequence.push_frame(frame.with_delay(delay));
+    }
+
+    sequence
+}
+
+/// decodes the provided bytes into [`Frames`], erroring if there are more than `max_frames` frames
+/// or if decoding them would exceed the memory ceiling, see [`memory::check_decode`]
+pub fn decode_frames(bytes: &[u8], max_frames: usize) -> Result<Frames, Error> {
+    // decoders allocate every frame at once, so the size is checked against the memory ceiling beforehand
+    memory::check_decode(bytes, max_frames)?;
+
+    let image = codecs::decode(bytes, max_frames)?;
+
+    if image.len() > max_frames {
+        return Err(Error::TooManyFrames(image.len(), max_frames))
+    }
+
+    Ok(image)
+}
+
+/// decodes only the first frame of the provided bytes into [`Frames`], so that animations are neither rejected
+/// for their number of frames nor decoded in full where the codec can stop early, see [`codecs::decode_first`]
+pub fn decode_first_frame(bytes: &[u8]) -> Result<Frames, Error> {
+    if let Some((width, height, _)) = memory::probe(bytes) {
+        memory::check_dimensions(width, height, 1, 1)?;
+    }
+
+    codecs::decode_first(bytes)
+}
+
+/// decodes an image passed to a function alongside its source, such as an overlay or a template, through [`decode_frames`]
+///
+/// the error is converted into ril's, as that is what the functions return
+pub fn decode_secondary(bytes: &[u8]) -> ril::Result<Frames> {
+    decode_frames(bytes, DEFAULT_MAX_FRAMES)
+        .map_err(|err| match err {
+            Error::RilError(err) => err,
+            err => ril::Error::DecodingError(err.to_string()),
+        })
+}
+
+/// encodes the provided frames in the provided format, [`OutputFormat::Auto`] being a looping `gif` if animated or a `png` otherwise,
+/// returning the encoded bytes along with the extension of the format they were encoded in
+///
+/// `quality` ranges from `1` to `100` and trades fidelity for size in lossy formats:
+/// it is passed on to the `jpeg` and `webp` encoders, and shrinks the palette of `gif`s with [`reduce_palette`],
+/// while lossless formats ignore it
+///
+/// only the first frame is encoded for formats that can not be animated
+///
+/// `mp4`s and `webm`s are encoded by `ffmpeg` through [`video::encode`]
+///
+/// `gif`s and videos are encoded frame by frame, so that if a `token` is provided,
+/// the amount of frames encoded is reported to it and the encode stops once it is cancelled
+pub fn encode_frames(
+    sequence: Frames,
+    format: OutputFormat,
+    quality: Option<u8>,
+    token: Option<&CancelToken>,
+) -> Result<(Vec<u8>, &'static str), Error> {
+    let container = match format {
+        OutputFormat::Mp4 => Some(Container::Mp4),
+        OutputFormat::Webm => Some(Container::Webm),
+        _ => None,
+    };
+    if let Some(container) = container {
+        return video::encode(&sequence, container, token)
+            .map(|bytes| (bytes, container.extension()));
+    }
+
+    let mut sequence = sequence.looped_infinitely();
+
+    let (image_format, extension) = match format {
+        OutputFormat::Auto if sequence.len() > 1 => (ImageFormat::Gif, "gif"),
+        OutputFormat::Auto | OutputFormat::Png => (ImageFormat::Png, "png"),
+        OutputFormat::Gif => (ImageFormat::Gif, "gif"),
+        OutputFormat::Webp => (ImageFormat::WebP, "webp"),
+        OutputFormat::Jpeg => (ImageFormat::Jpeg, "jpeg"),
+        OutputFormat::Mp4 | OutputFormat::Webm => unreachable!("videos are encoded by `video::encode`"),
+    };
+
+    let mut bytes: Vec<u8> = Vec::new();
+
+    match (image_format, quality) {
+        (ImageFormat::Jpeg, Some(quality)) => {
+            if let Some(frame) = sequence.first_frame() {
+                let metadata = EncoderMetadata::from(frame.image())
+                    .with_config(JpegEncoderOptions::new().with_quality(quality));
+
+                let mut encoder = JpegEncoder::new(&mut bytes, metadata)?;
+                encoder.add_frame(frame.image())?;
+                encoder.finish()?;
+            }
+        },
+        #[cfg(feature = "webp")]
+        (ImageFormat::WebP, Some(quality)) => {
+            let metadata = EncoderMetadata::from(&sequence)
+                .with_config(WebPEncoderOptions::new().with_quality(f32::from(quality)));
+
+            let mut encoder = WebPEncoder::new(&mut bytes, metadata)?;
+            for frame in sequence.iter() {
+                encoder.add_frame(frame)?;
+            }
+            encoder.finish()?;
+        },
+        _ => {
+            if let (ImageFormat::Gif, Some(quality)) = (image_format, quality) {
+                sequence = reduce_palette(sequence, quality);
+            }
+
+            if format.is_static() {
+                if let Some(frame) = sequence.first_frame() {
+                    frame.image()
+                        .encode(image_format, &mut bytes)?;
+                }
+            } else if image_format == ImageFormat::Gif {
+                let total = sequence.len();
+                let mut encoder = GifEncoder::new(&mut bytes, EncoderMetadata::from(&sequence))?;
+
+                for (i, frame) in sequence.iter().enumerate() {
+                    if let Some(token) = token {
+                        token.check()?;
+                    }
+                    encoder.add_frame(frame)?;
+
+                    if let Some(token) = token {
+                        token.report_progress(i + 1, total);
+                    }
+                }
+                encoder.finish()?;
+            } else {
+                sequence.encode(image_format, &mut bytes)?;
+            }
+        },
+    }
+
+    Ok((bytes, extension))
+}
+
+/// reduces the amount of distinct colors in the provided frames according to `quality`, from `1` to `100`,
+/// by keeping fewer bits of each channel, from `8` at the highest quality down to `1` at the lowest,
+/// so that `gif`s compress better at the cost of banding
+#[must_use]
+pub fn reduce_palette(frames: Frames, quality: u8) -> Frames {
+    let bits = 1 + u32::from(quality.clamp(1, 100)) * 7 / 100;
+    let mask = 0xFF_u8 << (8 - bits);
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for frame in frames {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+
+        let mut image = frame.into_image();
+        for px in &mut image.data {
+            *px = Rgba::new(px.r & mask, px.g & mask, px.b & mask, px.a);
+        }
+
+        sequence.push_frame(
+            Frame::from_image(image)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+
+    sequence.looped_infinitely()
+}
+
+/// repeatedly shrinks and encodes the provided frames until the output fits within `max_bytes`,
+/// first containing them within `max_dim` on both sides, then dropping every other frame of animations
+/// and scaling down the dimensions, used for uploads with strict limits such as emojis and stickers
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn compress_to_fit(mut frames: Frames, max_dim: u32, max_bytes: usize) -> Result<(Vec<u8>, bool), Error> {
+    let mut dim = max_dim;
+
+    loop {
+        frames = contain_size(
+            ImageArguments { frames, arguments: Vec::new(), seed: 0 },
+            Some(dim),
+            Some(dim),
+        )?;
+
+        let (bytes, extension) = encode_frames(frames.clone(), OutputFormat::Auto, None, None)?;
+
+        if bytes.len() <= max_bytes {
+            return Ok((bytes, extension == "gif"));
+        }
+
+        if frames.len() > 10 {
+            let mut sequence = ImageSequence::<Rgba>::new();
+
+            for frame in frames.into_iter().step_by(2) {
+                let delay = frame.delay() * 2;
+                sequence.push_frame(frame.with_delay(delay));
+            }
+            frames = sequence;
+        } else if dim > 32 {
+            dim = (f64::from(dim) * 0.75) as u32;
+        } else {
+            return Err(Error::ImageTooLarge(bytes.len() as u64, max_bytes as u64));
+        }
+    }
+}
+
+/// fits the provided frames within a transparent, centered square canvas of `size` and encodes them as a `png`,
+/// (an `apng` if animated) dropping every other frame of animations until the output fits within `max_bytes`,
+/// as required by discord for guild stickers
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn encode_sticker(mut frames: Frames, size: u32, max_bytes: usize) -> Result<Vec<u8>, Error> {
+    let mut sequence = ImageSequence::<Rgba>::new();
+
+    for frame in frames {
+        let scale = f64::from(size) / f64::from(frame.width().max(frame.height()));
+        let width = ((f64::from(frame.width()) * scale) as u32).clamp(1, size);
+        let height = ((f64::from(frame.height()) * scale) as u32).clamp(1, size);
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+
+        let image = frame.into_image()
+            .resized(width, height, ResizeAlgorithm::Lanczos3);
+
+        let mut canvas = Image::<Rgba>::new(size, size, Rgba::transparent());
+        canvas.paste(
+            (size - image.width()) / 2,
+            (size - image.height()) / 2,
+            &image,
+        );
+
+        sequence.push_frame(
+            Frame::from_image(canvas)
+                .with_delay(delay)
+                .with_disposal(disposal)
+        );
+    }
+    frames = sequence;
+
+    loop {
+        let mut bytes = Vec::new();
+        frames.clone()
+            .looped_infinitely()
+            .encode(ImageFormat::Png, &mut bytes)?;
+
+        if bytes.len() <= max_bytes {
+            return Ok(bytes);
+        }
+
+        if frames.len() <= 1 {
+            return Err(Error::ImageTooLarge(bytes.len() as u64, max_bytes as u64));
+        }
+
+        let mut sequence = ImageSequence::<Rgba>::new();
+
+        for frame in frames.into_iter().step_by(2) {
+            let delay = frame.delay() * 2;
+            sequence.push_frame(frame.with_delay(delay));
+        }
+        frames = sequence;
+    }
+}
+
+/// the width and height of each layer of the favicons encoded by [`encode_ico`]
+pub const ICO_SIZES: [u32; 4] = [16, 32, 48, 64];
+
+/// encodes the first frame of the provided frames into a multi-size `ico` favicon, with a layer for each of `sizes`,
+/// center-cropping it to a square first
+///
+/// each layer is stored as a `png`, which every browser and windows since vista supports within `ico`s
+#[allow(clippy::cast_possible_truncation)]
+pub fn encode_ico(frames: &Frames, sizes: &[u32]) -> Result<Vec<u8>, Error> {
+    let image = frames.first_frame()
+        .ok_or(ril::Error::EmptyImageError)?
+        .image();
+    let side = image.width().min(image.height());
+    let (x, y) = ((image.width() - side) / 2, (image.height() - side) / 2);
+    let square = image.clone()
+        .cropped(x, y, x + side, y + side);
+
+    let layers = sizes.iter()
+        .map(|&size| {
+            let mut bytes = Vec::new();
+            square.clone()
+                .resized(size, size, ResizeAlgorithm::Lanczos3)
+                .encode(ImageFormat::Png, &mut bytes)?;
+
+            Ok((size, bytes))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    // the header, followed by a 16 byte directory entry for each layer, followed by the layers themselves
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&[0, 0, 1, 0]);
+    bytes.extend_from_slice(&(layers.len() as u16).to_le_bytes());
+
+    let mut offset = 6 + 16 * layers.len() as u32;
+    for (size, layer) in &layers {
+        // a dimension of `0` stands for `256`, the largest an `ico` can hold
+        let dimension = if *size >= 256 { 0 } else { *size as u8 };
+
+        bytes.extend_from_slice(&[dimension, dimension, 0, 0]);
+        bytes.extend_from_slice(&1_u16.to_le_bytes());
+        bytes.extend_from_slice(&32_u16.to_le_bytes());
+        bytes.extend_from_slice(&(layer.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&offset.to_le_bytes());
+
+        offset += layer.len() as u32;
+    }
+    for (_, layer) in layers {
+        bytes.extend_from_slice(&layer);
+    }
+
+    Ok(bytes)
+}
+
 /// helper function that zips together an iterator that generates a gif
 /// with the original input gif frames to allow for partial gif support on gif functions
+///
+/// the iteration ends early once the job running it should stop, see [`checked`]
 pub fn process_gif<I>(frames: Frames, iterable: I)
-    -> Zip<Cycle<IntoIter<Frame<Rgba>>>, I>
+    -> impl Iterator<Item = (Frame<Rgba>, i32)>
 where
     I: Iterator<Item = i32>
 {
-    frames
-        .into_iter()
-        .cycle()
-        .zip(iterable)
+    checked(
+        frames
+            .into_iter()
+            .cycle()
+            .zip(iterable)
+    )
 }
\ No newline at end of file
@@ -0,0 +1,461 @@
+//! contains the [`JobTracker`], which keeps track of the image jobs running for each user so that they can be cancelled
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serenity::{
+    prelude::Context,
+    model::{
+        id::{MessageId, UserId},
+        channel::Message,
+        application::{
+            component::ButtonStyle,
+            interaction::InteractionResponseType,
+        },
+    },
+};
+
+use crate::JobData;
+use super::{
+    Error,
+    memory,
+    pool::run_job,
+};
+
+
+/// how long a job may run before a progress message with a cancel button is sent
+pub const PROGRESS_DELAY: Duration = Duration::from_secs(2);
+/// how often the progress message is updated with the progress reported by the job
+pub const PROGRESS_INTERVAL: Duration = Duration::from_secs(3);
+/// the custom id of the button to cancel a job
+const CANCEL_BUTTON_ID: &str = "cancel_job";
+
+thread_local! {
+    /// the cancel token of the job running on this thread, see [`CancelToken::enter`]
+    static CURRENT: RefCell<Option<CancelToken>> = RefCell::new(None);
+}
+
+/// a flag shared with a running job, which stops at its next checkpoint once the flag is set,
+/// through which the job may also report its progress
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    /// set once the job has been requested to stop
+    cancelled: Arc<AtomicBool>,
+    /// the amount of frames encoded so far and the total amount of frames to encode, reported by long encodes
+    progress: Arc<(AtomicUsize, AtomicUsize)>,
+    /// the memory the job had allocated and the ceiling once it went past it, or zeroes if it has not,
+    /// so that the job keeps failing with [`Error::MemoryLimit`] even if it frees memory after an effect stopped early
+    exceeded: Arc<(AtomicU64, AtomicU64)>,
+}
+
+impl CancelToken {
+    /// requests the job to stop
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// returns whether or not the job has been requested to stop
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// reports that `done` out of `total` frames have been encoded
+    pub fn report_progress(&self, done: usize, total: usize) {
+        self.progress.0.store(done, Ordering::Relaxed);
+        self.progress.1.store(total, Ordering::Relaxed);
+    }
+
+    /// returns the amount of frames encoded so far out of the total, if the job has reported any progress
+    #[must_use]
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        let total = self.progress.1.load(Ordering::Relaxed);
+
+        (total > 0).then(|| (self.progress.0.load(Ordering::Relaxed), total))
+    }
+
+    /// returns [`Error::Cancelled`] if the job has been requested to stop,
+    /// or [`Error::MemoryLimit`] if the job has allocated more than the memory ceiling, see [`memory::check`],
+    /// to be called by jobs between frames and between each of their stages
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            return Err(Error::Cancelled);
+        }
+
+        let allocated = self.exceeded.0.load(Ordering::Relaxed);
+        if allocated > 0 {
+            return Err(Error::MemoryLimit(allocated, self.exceeded.1.load(Ordering::Relaxed)));
+        }
+
+        memory::check()
+            .map_err(|err| {
+                if let Error::MemoryLimit(allocated, ceiling) = &err {
+                    self.exceeded.0.store(*allocated, Ordering::Relaxed);
+                    self.exceeded.1.store(*ceiling, Ordering::Relaxed);
+                }
+
+                err
+            })
+    }
+
+    /// makes this the token of the job running on the current thread until the returned guard is dropped,
+    /// so that the effects it runs can stop between frames through [`checked`] without being passed the token,
+    /// counting the memory the job allocates from now on against the ceiling, see [`memory::start_job`]
+    #[must_use]
+    pub fn enter(&self) -> EnteredToken {
+        CURRENT.with(|current| *current.borrow_mut() = Some(self.clone()));
+        memory::start_job();
+
+        EnteredToken(())
+    }
+}
+
+/// clears the token of the job running on the current thread once dropped, see [`CancelToken::enter`]
+#[derive(Debug)]
+pub struct EnteredToken(());
+
+impl Drop for EnteredToken {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.borrow_mut().take());
+        memory::end_job();
+    }
+}
+
+/// returns whether or not the job running on this thread should stop, see [`CancelToken::check`]
+#[must_use]
+pub fn should_stop() -> bool {
+    CURRENT.with(|current| current.borrow()
+        .as_ref()
+        .map_or(false, |token| token.check().is_err())
+    )
+}
+
+/// wraps the frames an effect iterates over, ending the iteration early once the job running on this thread should stop
+///
+/// the truncated output of the effect is then discarded by [`run_tracked`], which reports why the job stopped instead
+pub fn checked<I: IntoIterator>(items: I) -> impl Iterator<Item = I::Item> {
+    items.into_iter()
+        .take_while(|_| !should_stop())
+}
+
+/// a job tracked by the [`JobTracker`]
+#[derive(Debug, Clone)]
+struct TrackedJob {
+    /// the id of the job
+    id: u64,
+    /// the estimated cost of the job
+    cost: u64,
+    /// the cancel token shared with the job
+    token: CancelToken,
+    /// set once the job has left the queue and started running
+    running: Arc<AtomicBool>,
+}
+
+/// a snapshot of the state of a tracked job
+#[derive(Debug, Clone, Copy)]
+pub struct JobStatus {
+    /// the estimated cost of the job
+    pub cost: u64,
+    /// whether or not the job has started running, otherwise it is still waiting in the queue
+    pub running: bool,
+}
+
+/// tracks the jobs running, or waiting in the queue, for each user
+#[derive(Debug, Default)]
+pub struct JobTracker {
+    /// maps each user to their jobs
+    jobs: Mutex<HashMap<UserId, Vec<TrackedJob>>>,
+    /// the id given to the next job
+    next_id: AtomicU64,
+    /// maps each invocation message of an imaging command to when it was invoked,
+    /// used to measure how long resolving its images took
+    invocations: Mutex<HashMap<MessageId, Instant>>,
+    /// maps each invocation message of an imaging command to the report of its job, once it has finished
+    reports: Mutex<HashMap<MessageId, JobReport>>,
+}
+
+/// what the executor reports about a finished job, summarized in the audit log along with how the command went
+#[derive(Debug, Clone, Copy)]
+pub struct JobReport {
+    /// the mime type of the first source image, or `None` for generators
+    pub source: Option<&'static str>,
+    /// the total size of the outputs sent, in bytes
+    pub output_size: u64,
+}
+
+impl JobTracker {
+    /// creates a new, empty tracker
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers a new job of the provided user with the provided estimated cost,
+    /// returning its id, its cancel token and the flag to set once it starts running
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn start(&self, user_id: UserId, cost: u64) -> (u64, CancelToken, Arc<AtomicBool>) {
+        let job = TrackedJob {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            cost,
+            token: CancelToken::default(),
+            running: Arc::default(),
+        };
+        let handles = (job.id, job.token.clone(), job.running.clone());
+
+        self.jobs.lock()
+            .unwrap()
+            .entry(user_id)
+            .or_default()
+            .push(job);
+
+        handles
+    }
+
+    /// unregisters a job once it has finished
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn finish(&self, user_id: UserId, id: u64) {
+        let mut jobs = self.jobs.lock()
+            .unwrap();
+
+        if let Some(tracked) = jobs.get_mut(&user_id) {
+            tracked.retain(|job| job.id != id);
+
+            if tracked.is_empty() {
+                jobs.remove(&user_id);
+            }
+        }
+    }
+
+    /// cancels every job of the provided user, returning how many were cancelled
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn cancel_all(&self, user_id: UserId) -> usize {
+        self.jobs.lock()
+            .unwrap()
+            .get(&user_id)
+            .map_or(0, |jobs| {
+                for job in jobs {
+                    job.token.cancel();
+                }
+
+                jobs.len()
+            })
+    }
+
+    /// returns the status of each job of the provided user, oldest first
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn jobs_of(&self, user_id: UserId) -> Vec<JobStatus> {
+        self.jobs.lock()
+            .unwrap()
+            .get(&user_id)
+            .map_or_else(Vec::new, |jobs|
+                jobs.iter()
+                    .map(|job| JobStatus {
+                        cost: job.cost,
+                        running: job.running.load(Ordering::Relaxed),
+                    })
+                    .collect()
+            )
+    }
+
+    /// records that the command of the provided message was invoked just now
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn invoked(&self, message_id: MessageId) {
+        self.invocations.lock()
+            .unwrap()
+            .insert(message_id, Instant::now());
+    }
+
+    /// returns when the command of the provided message was invoked, if it was recorded
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn invocation(&self, message_id: MessageId) -> Option<Instant> {
+        self.invocations.lock()
+            .unwrap()
+            .get(&message_id)
+            .copied()
+    }
+
+    /// removes and returns when the command of the provided message was invoked, if it was recorded
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn take_invocation(&self, message_id: MessageId) -> Option<Instant> {
+        self.invocations.lock()
+            .unwrap()
+            .remove(&message_id)
+    }
+
+    /// records the report of the job of the provided message
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn report(&self, message_id: MessageId, report: JobReport) {
+        self.reports.lock()
+            .unwrap()
+            .insert(message_id, report);
+    }
+
+    /// removes and returns the report of the job of the provided message, if it finished
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn take_report(&self, message_id: MessageId) -> Option<JobReport> {
+        self.reports.lock()
+            .unwrap()
+            .remove(&message_id)
+    }
+
+    /// fetches the tracker stored in the client's data
+    pub async fn get(ctx: &Context) -> Option<Arc<Self>> {
+        ctx.data.read()
+            .await
+            .get::<JobData>()
+            .cloned()
+    }
+}
+
+/// unregisters a job from the [`JobTracker`] once dropped,
+/// so that it is unregistered even if the future running it is dropped before it finishes
+struct Registration {
+    tracker: Arc<JobTracker>,
+    user_id: UserId,
+    id: u64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.tracker.finish(self.user_id, self.id);
+    }
+}
+
+/// runs a job with [`run_job`], tracking it under the author of `message` so that it can be cancelled
+///
+/// the job is passed a [`CancelToken`] which it should check between its stages, and which is entered while it runs
+/// so that the effects it runs stop between frames, see [`checked`], and is cancelled before it even starts if it is still waiting in the queue;
+/// if it runs for longer than [`PROGRESS_DELAY`], a progress message with a cancel button is sent and removed once it finishes
+pub async fn run_tracked<F, T>(ctx: &Context, message: &Message, cost: u64, function: F) -> Result<T, Error>
+where
+    F: FnOnce(&CancelToken) -> Result<T, Error> + Send + 'static,
+    T: Send + 'static,
+{
+    let tracker = JobTracker::get(ctx)
+        .await
+        .unwrap_or_default();
+    let (id, token, running) = tracker.start(message.author.id, cost);
+    let _registration = Registration { tracker, user_id: message.author.id, id };
+
+    let job = {
+        let token = token.clone();
+
+        run_job(ctx, cost, move || {
+            running.store(true, Ordering::Relaxed);
+
+            token.check()?;
+            let _entered = token.enter();
+            let result = function(&token);
+
+            // effects cut their iteration short once the job should stop, so their output is discarded if it has
+            token.check()?;
+            result
+        })
+    };
+    tokio::pin!(job);
+
+    let result = tokio::select! {
+        result = &mut job => result,
+        () = tokio::time::sleep(PROGRESS_DELAY) => {
+            let mut progress = message.channel_id.send_message(ctx, |msg|
+                msg.content("Processing your image...")
+                    .reference_message(message)
+                    .allowed_mentions(|am| am.empty_parse())
+                    .components(|components|
+                        components.create_action_row(|row|
+                            row.create_button(|button|
+                                button.custom_id(CANCEL_BUTTON_ID)
+                                    .label("Cancel")
+                                    .style(ButtonStyle::Danger)
+                            )
+                        )
+                    )
+            )
+                .await
+                .ok();
+
+            let result = if let Some(progress) = &mut progress {
+                let interaction = progress.await_component_interaction(ctx)
+                    .author_id(message.author.id);
+                tokio::pin!(interaction);
+
+                let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+                let mut reported = None;
+                let mut collecting = true;
+
+                loop {
+                    tokio::select! {
+                        result = &mut job => break result,
+                        interaction = &mut interaction, if collecting => {
+                            let Some(interaction) = interaction else {
+                                collecting = false;
+                                continue;
+                            };
+                            token.cancel();
+
+                            interaction.create_interaction_response(ctx, |response|
+                                response.kind(InteractionResponseType::DeferredUpdateMessage)
+                            )
+                                .await
+                                .ok();
+
+                            break (&mut job).await;
+                        },
+                        _ = interval.tick() => {
+                            // the message is only edited when the progress changed, to stay clear of rate limits
+                            let current = token.progress();
+
+                            if let Some((done, total)) = current.filter(|_| current != reported) {
+                                reported = current;
+
+                                progress.edit(ctx, |msg|
+                                    msg.content(format!("Encoding your image... `{done}/{total}` frames"))
+                                )
+                                    .await
+                                    .ok();
+                            }
+                        },
+                    }
+                }
+            } else {
+                job.await
+            };
+
+            if let Some(progress) = progress {
+                progress.delete(ctx)
+                    .await
+                    .ok();
+            }
+
+            result
+        },
+    };
+
+    result?
+}
\ No newline at end of file
@@ -0,0 +1,503 @@
+//! contains the memory guard, which keeps each image job from allocating more than a configured ceiling
+//!
+//! the decoded size of an image is estimated from its header before it is decoded, as decoders allocate every frame at once,
+//! and the memory allocated by each job, as counted by the [`CountingAllocator`], is checked between its frames and stages,
+//! so that concurrent jobs do not count against each other and memory kept by the allocator after a job does not fail later ones
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use super::Error;
+
+
+thread_local! {
+    /// the bytes allocated by this thread less those it freed, which may be negative if it frees memory allocated by others
+    static ALLOCATED: Cell<i64> = const { Cell::new(0) };
+    /// the highest [`ALLOCATED`] has been since the last [`Allocation::start`] on this thread
+    static PEAK: Cell<i64> = const { Cell::new(0) };
+    /// the memory allocated by the job running on this thread, see [`start_job`]
+    static JOB: Cell<Option<Allocation>> = const { Cell::new(None) };
+}
+
+/// the global allocator, which wraps the system allocator to count the bytes allocated by each thread,
+/// so that the memory used by a job can be told apart from that of the jobs running alongside it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    /// adds `delta` to the bytes allocated by this thread, doing nothing while its thread locals are being destroyed
+    #[allow(clippy::cast_possible_wrap)]
+    fn record(delta: i64) {
+        ALLOCATED.try_with(|allocated| {
+            let current = allocated.get() + delta;
+            allocated.set(current);
+
+            PEAK.try_with(|peak| peak.set(peak.get().max(current)))
+                .ok();
+        })
+            .ok();
+    }
+}
+
+#[allow(clippy::cast_possible_wrap)]
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::record(layout.size() as i64);
+        }
+
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::record(layout.size() as i64);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::record(-(layout.size() as i64));
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::record(new_size as i64 - layout.size() as i64);
+        }
+
+        new_ptr
+    }
+}
+
+/// measures the memory allocated by the current thread from when it was started, see [`CountingAllocator`]
+#[derive(Debug, Clone, Copy)]
+pub struct Allocation {
+    /// the bytes allocated by the thread when the measurement was started
+    start: i64,
+}
+
+impl Allocation {
+    /// starts measuring the memory allocated by the current thread from now on, resetting its peak
+    #[must_use]
+    pub fn start() -> Self {
+        let start = ALLOCATED.with(Cell::get);
+        PEAK.with(|peak| peak.set(start));
+
+        Self { start }
+    }
+
+    /// the bytes allocated by the current thread since the measurement was started and not yet freed
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn current(&self) -> u64 {
+        (ALLOCATED.with(Cell::get) - self.start).max(0) as u64
+    }
+
+    /// the most bytes allocated by the current thread at once since the measurement was started
+    #[must_use]
+    #[allow(clippy::cast_sign_loss)]
+    pub fn peak(&self) -> u64 {
+        (PEAK.with(Cell::get) - self.start).max(0) as u64
+    }
+}
+
+/// the ceiling on the memory each job may allocate in bytes, or `0` if there is none
+static CEILING: AtomicU64 = AtomicU64::new(0);
+
+/// sets the ceiling on the memory each job may allocate in bytes, or removes it if `None`
+pub fn set_ceiling(ceiling: Option<u64>) {
+    CEILING.store(ceiling.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// returns the ceiling on the memory each job may allocate in bytes, if there is one
+#[must_use]
+pub fn ceiling() -> Option<u64> {
+    Some(CEILING.load(Ordering::Relaxed))
+        .filter(|ceiling| *ceiling > 0)
+}
+
+/// starts counting the memory allocated by the job about to run on the current thread against the ceiling, see [`check`]
+pub fn start_job() {
+    JOB.with(|job| job.set(Some(Allocation::start())));
+}
+
+/// stops counting the memory allocated by the job that ran on the current thread
+pub fn end_job() {
+    JOB.with(|job| job.set(None));
+}
+
+/// the bytes allocated by the job running on the current thread and not yet freed, or `0` outside of jobs
+#[must_use]
+pub fn job_allocated() -> u64 {
+    JOB.with(|job| job.get()
+        .map_or(0, |allocation| allocation.current())
+    )
+}
+
+/// returns [`Error::MemoryLimit`] if the job running on the current thread has allocated more than the ceiling,
+/// to be called between the frames and stages of jobs
+///
+/// nothing is checked if there is no ceiling, or outside of jobs
+pub fn check() -> Result<(), Error> {
+    match ceiling() {
+        Some(ceiling) if job_allocated() > ceiling =>
+            Err(Error::MemoryLimit(job_allocated(), ceiling)),
+        _ => Ok(()),
+    }
+}
+
+/// returns [`Error::MemoryLimit`] if decoding the provided image would take the job past the ceiling,
+/// or [`Error::TooManyFrames`] if its header declares more than `max_frames` frames, before any of it is decoded
+///
+/// images whose size could not be estimated are let through, and left to [`check`]
+pub fn check_decode(bytes: &[u8], max_frames: usize) -> Result<(), Error> {
+    let Some((width, height, frames)) = probe(bytes) else {
+        return Ok(());
+    };
+
+    check_dimensions(width, height, frames, max_frames)
+}
+
+/// returns [`Error::MemoryLimit`] if decoding `frames` frames of `width` by `height` pixels would take the job running
+/// on the current thread past the ceiling, or [`Error::TooManyFrames`] if there are more than `max_frames` frames,
+/// for decoders that read the dimensions of an image themselves before decoding its pixels
+pub fn check_dimensions(width: u32, height: u32, frames: usize, max_frames: usize) -> Result<(), Error> {
+    if frames > max_frames {
+        return Err(Error::TooManyFrames(frames, max_frames));
+    }
+
+    if let Some(ceiling) = ceiling() {
+        let estimate = decoded_size(width, height, frames);
+        let available = ceiling.saturating_sub(job_allocated());
+
+        if estimate > available {
+            return Err(Error::MemoryLimit(estimate, available));
+        }
+    }
+
+    Ok(())
+}
+
+/// the size in bytes of `frames` frames of `width` by `height` pixels once decoded to 8-bit RGBA
+#[must_use]
+pub fn decoded_size(width: u32, height: u32, frames: usize) -> u64 {
+    u64::from(width) * u64::from(height) * 4 * frames as u64
+}
+
+/// reads the dimensions and the number of frames of an image from its header,
+/// for `png`s (including `apng`s), `gif`s, `webp`s and `jpeg`s
+#[must_use]
+pub fn probe(bytes: &[u8]) -> Option<(u32, u32, usize)> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        probe_png(bytes)
+    } else if bytes.starts_with(b"GIF8") {
+        probe_gif(bytes)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        probe_webp(bytes)
+    } else if bytes.starts_with(&[0xFF, 0xD8]) {
+        probe_jpeg(bytes)
+    } else {
+        None
+    }
+}
+
+/// reads a big endian `u32` at `offset`
+fn u32_be(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// reads a little endian `u32` at `offset`
+fn u32_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// reads a little endian `u16` at `offset`
+fn u16_le(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+/// reads a little endian 24-bit integer at `offset`
+fn u24_le(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 3)?;
+
+    Some(u32::from(bytes[0]) | (u32::from(bytes[1]) << 8) | (u32::from(bytes[2]) << 16))
+}
+
+/// reads the dimensions from the `IHDR` chunk, and the number of frames from the `acTL` chunk of animated `png`s
+fn probe_png(bytes: &[u8]) -> Option<(u32, u32, usize)> {
+    let (width, height) = (u32_be(bytes, 16)?, u32_be(bytes, 20)?);
+    let mut frames = 1;
+
+    // the `acTL` chunk must come before the image data, so the chunks after it are never read
+    let mut offset = 8;
+    while let (Some(length), Some(kind)) = (u32_be(bytes, offset), bytes.get(offset + 4..offset + 8)) {
+        match kind {
+            b"acTL" => {
+                frames = u32_be(bytes, offset + 8)? as usize;
+                break;
+            },
+            b"IDAT" => break,
+            _ => offset += 12 + length as usize,
+        }
+    }
+
+    Some((width, height, frames))
+}
+
+/// reads the dimensions from the logical screen descriptor, counting the frames by walking the blocks of the `gif`
+///
+/// every frame is decoded to the size of the logical screen, regardless of the size of its own image
+fn probe_gif(bytes: &[u8]) -> Option<(u32, u32, usize)> {
+    let (width, height) = (u32::from(u16_le(bytes, 6)?), u32::from(u16_le(bytes, 8)?));
+    let color_table_size = |packed: u8| if packed & 0x80 == 0 { 0 } else { 3 << ((packed & 0x07) + 1) };
+
+    // skips a sequence of sub-blocks, returning the offset after its terminator
+    let skip_sub_blocks = |mut offset: usize| -> Option<usize> {
+        loop {
+            let size = *bytes.get(offset)? as usize;
+            offset += 1 + size;
+            if size == 0 {
+                return Some(offset);
+            }
+        }
+    };
+
+    let mut offset = 13 + color_table_size(*bytes.get(10)?);
+    let mut frames = 0;
+
+    // a truncated `gif` still decodes the frames before the point it was cut at
+    while let Some(&block) = bytes.get(offset) {
+        offset = match block {
+            // an extension, with its label followed by its sub-blocks
+            0x21 => match skip_sub_blocks(offset + 2) {
+                Some(offset) => offset,
+                None => break,
+            },
+            // an image descriptor, followed by its local color table, the minimum code size and its sub-blocks
+            0x2C => {
+                frames += 1;
+                let Some(&packed) = bytes.get(offset + 9) else {
+                    break;
+                };
+                match skip_sub_blocks(offset + 10 + color_table_size(packed) + 1) {
+                    Some(offset) => offset,
+                    None => break,
+                }
+            },
+            _ => break,
+        };
+    }
+
+    Some((width, height, frames.max(1)))
+}
+
+/// reads the dimensions from the first chunk of the `webp`, counting the `ANMF` chunks of animated ones
+fn probe_webp(bytes: &[u8]) -> Option<(u32, u32, usize)> {
+    let (width, height) = match bytes.get(12..16)? {
+        b"VP8X" => (u24_le(bytes, 24)? + 1, u24_le(bytes, 27)? + 1),
+        b"VP8 " => (
+            u32::from(u16_le(bytes, 26)? & 0x3FFF),
+            u32::from(u16_le(bytes, 28)? & 0x3FFF),
+        ),
+        b"VP8L" => {
+            let bits = u32_le(bytes, 21)?;
+            ((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1)
+        },
+        _ => return None,
+    };
+
+    let mut frames = 0;
+    let mut offset = 12;
+    while let (Some(kind), Some(size)) = (bytes.get(offset..offset + 4), u32_le(bytes, offset + 4)) {
+        if kind == b"ANMF" {
+            frames += 1;
+        }
+        // chunks are padded to an even size
+        offset += 8 + (size as usize + 1) / 2 * 2;
+    }
+
+    Some((width, height, frames.max(1)))
+}
+
+/// reads the dimensions from the start of frame segment of the `jpeg`
+fn probe_jpeg(bytes: &[u8]) -> Option<(u32, u32, usize)> {
+    let mut offset = 2;
+
+    loop {
+        if *bytes.get(offset)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(offset + 1)?;
+        let length = u32::from(u16::from_be_bytes(bytes.get(offset + 2..offset + 4)?.try_into().ok()?));
+
+        // the start of frame markers, apart from those reused for the huffman and arithmetic coding tables
+        if matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC) {
+            let height = u16::from_be_bytes(bytes.get(offset + 5..offset + 7)?.try_into().ok()?);
+            let width = u16::from_be_bytes(bytes.get(offset + 7..offset + 9)?.try_into().ok()?);
+
+            return Some((u32::from(width), u32::from(height), 1));
+        }
+
+        offset += 2 + length as usize;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// builds a `png` chunk, with a zeroed checksum as it is never verified by the probe
+    fn png_chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = u32::try_from(data.len())
+            .unwrap()
+            .to_be_bytes()
+            .to_vec();
+        chunk.extend_from_slice(kind);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0; 4]);
+
+        chunk
+    }
+
+    /// builds a `png` of the provided dimensions, animated with `frames` frames if provided
+    fn png(width: u32, height: u32, frames: Option<u32>) -> Vec<u8> {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut bytes = b"\x89PNG\r\n\x1a\n".to_vec();
+        bytes.extend(png_chunk(b"IHDR", &header));
+        if let Some(frames) = frames {
+            let mut control = frames.to_be_bytes().to_vec();
+            control.extend_from_slice(&0_u32.to_be_bytes());
+            bytes.extend(png_chunk(b"acTL", &control));
+        }
+        bytes.extend(png_chunk(b"IDAT", &[0; 8]));
+
+        bytes
+    }
+
+    /// builds a `gif` of the provided dimensions with `frames` frames, each preceded by a graphic control extension
+    fn gif(width: u16, height: u16, frames: usize) -> Vec<u8> {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        // a global color table of 2 colors
+        bytes.extend_from_slice(&[0x80, 0, 0]);
+        bytes.extend_from_slice(&[0; 6]);
+
+        for _ in 0..frames {
+            bytes.extend_from_slice(&[0x21, 0xF9, 4, 0, 10, 0, 0, 0]);
+            bytes.push(0x2C);
+            bytes.extend_from_slice(&[0; 4]);
+            bytes.extend_from_slice(&width.to_le_bytes());
+            bytes.extend_from_slice(&height.to_le_bytes());
+            bytes.extend_from_slice(&[0, 2, 2, 0x4C, 0x01, 0]);
+        }
+        bytes.push(0x3B);
+
+        bytes
+    }
+
+    /// builds a `webp` container around the provided chunks
+    fn webp(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut body = b"WEBP".to_vec();
+        for (kind, data) in chunks {
+            body.extend_from_slice(*kind);
+            body.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+            body.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                body.push(0);
+            }
+        }
+
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&u32::try_from(body.len()).unwrap().to_le_bytes());
+        bytes.extend(body);
+
+        bytes
+    }
+
+    #[test]
+    fn probes_still_png() {
+        assert_eq!(probe(&png(640, 480, None)), Some((640, 480, 1)));
+    }
+
+    #[test]
+    fn probes_animated_png() {
+        assert_eq!(probe(&png(32, 16, Some(12))), Some((32, 16, 12)));
+    }
+
+    #[test]
+    fn probes_gif_frames() {
+        assert_eq!(probe(&gif(300, 200, 1)), Some((300, 200, 1)));
+        assert_eq!(probe(&gif(300, 200, 5)), Some((300, 200, 5)));
+    }
+
+    #[test]
+    fn probes_truncated_gif() {
+        let mut bytes = gif(10, 10, 3);
+        bytes.truncate(bytes.len() - 10);
+
+        assert_eq!(probe(&bytes), Some((10, 10, 3)));
+    }
+
+    #[test]
+    fn probes_extended_webp() {
+        let mut header = vec![0x02, 0, 0, 0];
+        header.extend_from_slice(&[0x3F, 0x01, 0x00]);
+        header.extend_from_slice(&[0xC7, 0x00, 0x00]);
+        let frame = vec![0; 16];
+
+        let bytes = webp(&[
+            (b"VP8X", header),
+            (b"ANIM", vec![0; 6]),
+            (b"ANMF", frame.clone()),
+            (b"ANMF", frame.clone()),
+            (b"ANMF", frame),
+        ]);
+
+        assert_eq!(probe(&bytes), Some((320, 200, 3)));
+    }
+
+    #[test]
+    fn probes_lossless_webp() {
+        let bits = 99_u32 | (49 << 14);
+        let mut data = vec![0x2F];
+        data.extend_from_slice(&bits.to_le_bytes());
+
+        assert_eq!(probe(&webp(&[(b"VP8L", data)])), Some((100, 50, 1)));
+    }
+
+    #[test]
+    fn probes_jpeg_past_other_segments() {
+        let mut bytes = vec![0xFF, 0xD8];
+        // an application segment and a huffman table, whose marker lies within the start of frame range
+        bytes.extend_from_slice(&[0xFF, 0xE0, 0x00, 0x04, 0, 0]);
+        bytes.extend_from_slice(&[0xFF, 0xC4, 0x00, 0x04, 0, 0]);
+        bytes.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 8, 0x01, 0xE0, 0x02, 0x80]);
+
+        assert_eq!(probe(&bytes), Some((640, 480, 1)));
+    }
+
+    #[test]
+    fn rejects_unknown_and_truncated_headers() {
+        assert_eq!(probe(b"not an image"), None);
+        assert_eq!(probe(&png(1, 1, None)[..12]), None);
+        assert_eq!(probe(&[0xFF, 0xD8, 0xFF, 0xC0]), None);
+    }
+
+    #[test]
+    fn refuses_too_many_frames() {
+        assert!(matches!(check_dimensions(10, 10, 5, 4), Err(Error::TooManyFrames(5, 4))));
+        assert!(check_dimensions(10, 10, 4, 4).is_ok());
+    }
+}
\ No newline at end of file
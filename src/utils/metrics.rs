@@ -0,0 +1,149 @@
+//! contains the [`Metrics`] struct, collecting statistics about the image jobs that have been run,
+//! which are saved to the database on shutdown and restored on startup so that they persist across restarts
+
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Error, database::Database};
+
+
+/// the amount of recent job durations kept to compute the average from
+pub const RECENT_JOBS: usize = 50;
+/// the key the metrics are saved under in the database
+pub const METRICS_STATE_KEY: &str = "metrics";
+
+lazy_static::lazy_static! {
+    /// the metrics of the running bot
+    pub static ref METRICS: Metrics = Metrics::default();
+}
+
+
+/// a serializable copy of the [`Metrics`] at a point in time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    /// the total amount of jobs completed
+    pub completed: u64,
+    /// the durations of the most recent jobs, oldest first
+    pub recent: Vec<Duration>,
+}
+
+/// statistics about the image jobs that have been run
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// the total amount of jobs completed
+    completed: AtomicU64,
+    /// the durations of the most recent jobs, oldest first
+    recent: Mutex<VecDeque<Duration>>,
+}
+
+impl Metrics {
+    /// records a completed job which ran for the provided duration
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn record_job(&self, duration: Duration) {
+        self.completed.fetch_add(1, Ordering::Relaxed);
+
+        let mut recent = self.recent.lock()
+            .unwrap();
+
+        if recent.len() >= RECENT_JOBS {
+            recent.pop_front();
+        }
+        recent.push_back(duration);
+    }
+
+    /// the total amount of jobs completed
+    #[must_use]
+    pub fn completed(&self) -> u64 {
+        self.completed.load(Ordering::Relaxed)
+    }
+
+    /// the average duration of the most recent jobs, along with how many it was computed from
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn average_duration(&self) -> Option<(Duration, usize)> {
+        let recent = self.recent.lock()
+            .unwrap();
+
+        if recent.is_empty() {
+            return None;
+        }
+
+        Some((
+            recent.iter().sum::<Duration>() / recent.len() as u32,
+            recent.len(),
+        ))
+    }
+
+    /// takes a snapshot of the current metrics
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    #[must_use]
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            completed: self.completed(),
+            recent: self.recent.lock()
+                .unwrap()
+                .iter()
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// adds the metrics of a snapshot onto the current ones, such as those saved before the last restart
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    pub fn restore(&self, snapshot: MetricsSnapshot) {
+        self.completed.fetch_add(snapshot.completed, Ordering::Relaxed);
+
+        let mut recent = self.recent.lock()
+            .unwrap();
+        let current = recent.drain(..)
+            .collect::<Vec<_>>();
+
+        // the restored durations are older than any recorded since startup
+        recent.extend(
+            snapshot.recent
+                .into_iter()
+                .chain(current)
+        );
+        while recent.len() > RECENT_JOBS {
+            recent.pop_front();
+        }
+    }
+
+    /// saves a snapshot of the current metrics to the database
+    pub async fn save(&self, database: &Database) -> Result<(), Error> {
+        let state = serde_json::to_string(&self.snapshot())
+            .unwrap_or_default();
+
+        database.set_state(METRICS_STATE_KEY, state.as_str())
+            .await
+    }
+
+    /// restores the metrics saved to the database, if there are any
+    pub async fn load(&self, database: &Database) -> Result<(), Error> {
+        if let Some(snapshot) = database.state(METRICS_STATE_KEY)
+            .await?
+            .and_then(|state| serde_json::from_str::<MetricsSnapshot>(state.as_str()).ok())
+        {
+            self.restore(snapshot);
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
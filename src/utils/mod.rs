@@ -4,7 +4,41 @@ pub use resolver::ImageResolver;
 pub use error::Error;
 
 pub mod functions;
+pub mod filters;
 pub mod resolver;
 pub mod imaging;
 pub mod helpers;
+pub mod flags;
+pub mod options;
+pub mod text;
+pub mod registry;
+pub mod config;
+pub mod database;
+pub mod entitlements;
+pub mod cooldowns;
+pub mod abuse;
+pub mod pool;
+pub mod memory;
+pub mod jobs;
+pub mod metrics;
+pub mod audit;
+pub mod presence;
+pub mod welcome;
+pub mod daily;
+pub mod avatars;
+pub mod pipeline;
+pub mod script;
+pub mod bench;
+pub mod assets;
+pub mod slash;
+pub mod codecs;
+pub mod video;
+pub mod vision;
+pub mod hashing;
+pub mod codes;
+pub mod ocr;
+pub mod templates;
+#[cfg(test)]
+mod golden;
+pub mod policy;
 pub mod error;
\ No newline at end of file
@@ -0,0 +1,56 @@
+//! contains the text recognition used by the `readtext` command, powered by tesseract through `leptess`
+//!
+//! tesseract is a native library, so recognition is only available with the `ocr` feature,
+//! and each language used requires its trained data to be installed alongside it
+
+use super::{Error, imaging::Frames};
+
+
+/// the language text is recognized in when none is provided
+pub const DEFAULT_LANGUAGE: &str = "eng";
+
+
+/// returns whether or not `language` is a valid tesseract language code,
+/// such as `eng`, `chi_sim` or several joined with a `+` such as `eng+deu`
+#[must_use]
+pub fn is_valid_language(language: &str) -> bool {
+    !language.is_empty()
+        && language.split('+')
+            .all(|code| !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+}
+
+/// recognizes the text in the first frame of the provided image, in `language`
+///
+/// # Errors
+/// if the language is invalid or is not installed, or if recognition fails
+#[cfg(feature = "ocr")]
+pub fn read_text(frames: &Frames, language: &str) -> Result<String, Error> {
+    use ril::ImageFormat;
+
+    if !is_valid_language(language) {
+        return Err(Error::OcrError(format!("`{language}` is not a valid language code")));
+    }
+
+    let frame = frames.first_frame()
+        .ok_or_else(|| Error::OcrError(String::from("the image has no frames")))?;
+
+    // tesseract reads images through leptonica, which does not support every format ril does, so the frame is passed as a `png`
+    let mut bytes = Vec::new();
+    frame.image()
+        .encode(ImageFormat::Png, &mut bytes)?;
+
+    let mut tesseract = leptess::LepTess::new(None, language)
+        .map_err(|_| Error::OcrError(format!("the language `{language}` is not installed")))?;
+    tesseract.set_image_from_mem(&bytes)
+        .map_err(|err| Error::OcrError(err.to_string()))?;
+
+    tesseract.get_utf8_text()
+        .map(|text| text.trim().to_string())
+        .map_err(|err| Error::OcrError(err.to_string()))
+}
+
+/// refuses to recognize text, as the `ocr` feature is not enabled
+#[cfg(not(feature = "ocr"))]
+pub fn read_text(_frames: &Frames, _language: &str) -> Result<String, Error> {
+    Err(Error::OcrError(String::from("text recognition is not supported by this build of the bot")))
+}
\ No newline at end of file
@@ -0,0 +1,815 @@
+//! contains the structured argument types passed to the image functions through [`super::imaging::ImageArguments`]
+
+use std::{str::FromStr, time::Duration};
+use ril::prelude::*;
+
+use super::vision::Face;
+
+
+/// represents where on a canvas an item should be placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl FromStr for Anchor {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(
+            match s.to_lowercase()
+                .replace(['-', '_'], "")
+                .as_str()
+            {
+                "topleft" | "tl" => Self::TopLeft,
+                "top" | "t" => Self::Top,
+                "topright" | "tr" => Self::TopRight,
+                "left" | "l" => Self::Left,
+                "center" | "centre" | "middle" | "c" => Self::Center,
+                "right" | "r" => Self::Right,
+                "bottomleft" | "bl" => Self::BottomLeft,
+                "bottom" | "b" => Self::Bottom,
+                "bottomright" | "br" => Self::BottomRight,
+                _ => return Err(()),
+            }
+        )
+    }
+}
+
+impl Anchor {
+    /// computes the top-left coordinate to place an item of size `item`
+    /// inside of a canvas of size `canvas`, keeping `margin` pixels away from the edges
+    #[must_use]
+    pub const fn resolve(self, canvas: (u32, u32), item: (u32, u32), margin: u32) -> (u32, u32) {
+        let (width, height) = canvas;
+        let (w, h) = item;
+
+        let left = margin;
+        let center_x = width.saturating_sub(w) / 2;
+        let right = width.saturating_sub(w).saturating_sub(margin);
+
+        let top = margin;
+        let center_y = height.saturating_sub(h) / 2;
+        let bottom = height.saturating_sub(h).saturating_sub(margin);
+
+        match self {
+            Self::TopLeft => (left, top),
+            Self::Top => (center_x, top),
+            Self::TopRight => (right, top),
+            Self::Left => (left, center_y),
+            Self::Center => (center_x, center_y),
+            Self::Right => (right, center_y),
+            Self::BottomLeft => (left, bottom),
+            Self::Bottom => (center_x, bottom),
+            Self::BottomRight => (right, bottom),
+        }
+    }
+}
+
+/// a wrapper around [`Rgba`] that can be parsed from hex codes, `rgb()` notation and CSS color names
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Color(pub Rgba);
+
+impl FromStr for Color {
+    type Err = csscolorparser::ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let [r, g, b, a] = csscolorparser::parse(s)?
+            .to_rgba8();
+
+        Ok(Self(Rgba::new(r, g, b, a)))
+    }
+}
+
+/// a set of color channels, parsed from the letters of the channels such as `rg` or `rgba`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct ChannelMask {
+    /// whether or not the red channel is included
+    pub r: bool,
+    /// whether or not the green channel is included
+    pub g: bool,
+    /// whether or not the blue channel is included
+    pub b: bool,
+    /// whether or not the alpha channel is included
+    pub a: bool,
+}
+
+impl ChannelMask {
+    /// this mask with the alpha channel included as well
+    #[must_use]
+    pub const fn with_alpha(self) -> Self {
+        Self { a: true, ..self }
+    }
+}
+
+impl Default for ChannelMask {
+    /// the color channels, without the alpha channel
+    fn default() -> Self {
+        Self { r: true, g: true, b: true, a: false }
+    }
+}
+
+impl FromStr for ChannelMask {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut mask = Self { r: false, g: false, b: false, a: false };
+
+        for c in s.to_lowercase().chars() {
+            match c {
+                'r' => mask.r = true,
+                'g' => mask.g = true,
+                'b' => mask.b = true,
+                'a' => mask.a = true,
+                _ => return Err(()),
+            }
+        }
+
+        if mask == (Self { r: false, g: false, b: false, a: false }) {
+            Err(())
+        } else {
+            Ok(mask)
+        }
+    }
+}
+
+/// represents the horizontal alignment of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl FromStr for Align {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(
+            match s.to_lowercase()
+                .as_str()
+            {
+                "left" | "l" => Self::Left,
+                "center" | "centre" | "middle" | "c" => Self::Center,
+                "right" | "r" => Self::Right,
+                _ => return Err(()),
+            }
+        )
+    }
+}
+
+/// represents which edge of the image a caption bar is placed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptionPosition {
+    Top,
+    Bottom,
+}
+
+impl FromStr for CaptionPosition {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase()
+            .as_str()
+        {
+            "top" | "t" => Ok(Self::Top),
+            "bottom" | "b" => Ok(Self::Bottom),
+            _ => Err(()),
+        }
+    }
+}
+
+/// represents the format outputs are encoded in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// a `gif` if animated, or a `png` otherwise
+    #[default]
+    Auto,
+    /// always a `png`, which is an `apng` if animated
+    Png,
+    /// always a `gif`
+    Gif,
+    /// always a `webp`, requires the `webp` feature
+    Webp,
+    /// always a `jpeg`, which can not be animated
+    Jpeg,
+    /// an `h.264` video, requires `ffmpeg`
+    Mp4,
+    /// a `vp9` video keeping transparency, requires `ffmpeg`
+    Webm,
+}
+
+impl OutputFormat {
+    /// the name of this format, as parsed by [`FromStr`]
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Png => "png",
+            Self::Gif => "gif",
+            Self::Webp => "webp",
+            Self::Jpeg => "jpeg",
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// whether or not this format can only hold a single frame
+    #[must_use]
+    pub const fn is_static(self) -> bool {
+        matches!(self, Self::Jpeg)
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase()
+            .trim_start_matches('.')
+        {
+            "auto" | "default" => Ok(Self::Auto),
+            "png" | "apng" => Ok(Self::Png),
+            "gif" => Ok(Self::Gif),
+            // refused up front without the `webp` feature, rather than once the result is about to be encoded
+            "webp" if cfg!(feature = "webp") => Ok(Self::Webp),
+            "jpeg" | "jpg" => Ok(Self::Jpeg),
+            "mp4" | "h264" => Ok(Self::Mp4),
+            "webm" | "vp9" => Ok(Self::Webm),
+            _ => Err(()),
+        }
+    }
+}
+
+/// an accessory drawn onto each detected face by [`super::functions::accessory_func`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accessory {
+    /// red laser beams shooting out of the eyes
+    LaserEyes,
+    /// pixelated "deal with it" sunglasses over the eyes
+    Glasses,
+    /// a red clown nose
+    ClownNose,
+}
+
+/// how [`super::functions::censor_func`] hides each region
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CensorStyle {
+    /// replaces the region with large blocks of its average colors
+    #[default]
+    Pixelate,
+    /// paints over the region in solid black
+    Bar,
+}
+
+impl FromStr for CensorStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pixelate" | "pixel" | "mosaic" => Ok(Self::Pixelate),
+            "bar" | "black" | "blackbar" => Ok(Self::Bar),
+            _ => Err(()),
+        }
+    }
+}
+
+/// a rectangle within an image, parsed from its left edge, top edge, width and height such as `10,20,100,80`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region(pub Face);
+
+impl FromStr for Region {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s.split(',')
+            .map(|value| value.trim().parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ())?;
+
+        match values[..] {
+            [x, y, width, height] if width > 0 && height > 0 => Ok(Self(Face { x, y, width, height })),
+            _ => Err(()),
+        }
+    }
+}
+
+/// the options passed to [`super::functions::censor_func`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CensorOptions {
+    /// how each region is hidden
+    pub style: CensorStyle,
+    /// the region to hide instead of the detected faces, if any
+    pub region: Option<Region>,
+}
+
+/// the filter used by [`super::functions::upscale_func`] to scale up pixel art
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UpscaleFilter {
+    /// repeats each pixel, keeping hard edges
+    #[default]
+    Nearest,
+    /// the `scale2x` (EPX) algorithm, which smooths out diagonal edges while keeping the palette
+    Scale2x,
+}
+
+impl FromStr for UpscaleFilter {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nearest" | "none" => Ok(Self::Nearest),
+            "scale2x" | "epx" => Ok(Self::Scale2x),
+            _ => Err(()),
+        }
+    }
+}
+
+/// the options passed to [`super::functions::caption_func`]
+#[derive(Debug, Clone)]
+pub struct CaptionOptions {
+    /// the caption text
+    pub text: String,
+    /// the color of the text
+    pub color: Rgba,
+    /// the color of the outline drawn around the text, if any
+    pub stroke: Option<Rgba>,
+    /// the font size of the text
+    pub size: f32,
+    /// the horizontal alignment of the text
+    pub align: Align,
+    /// which edge of the image the caption bar is placed on
+    pub position: CaptionPosition,
+    /// whether or not to keep the transparency of the image,
+    /// drawing the caption bar with rounded corners over a transparent background instead of onto an opaque canvas
+    pub transparent: bool,
+}
+
+/// the options passed to [`super::functions::posterfy_func`]
+#[derive(Debug, Clone)]
+pub struct PosterOptions {
+    /// the title of the movie, rendered above the credits block
+    pub title: String,
+    /// the name credited as the director, which is billed first in the credits block
+    pub director: String,
+}
+
+/// the options passed to [`super::functions::intro_func`]
+#[derive(Debug, Clone)]
+pub struct IntroOptions {
+    /// the name typed out underneath the avatar
+    pub name: String,
+}
+
+/// the kind of chart rendered by [`super::functions::chart_func`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChartKind {
+    /// a bar for each value
+    #[default]
+    Bar,
+    /// a line through each value
+    Line,
+    /// a slice of a circle for each value, sized by its share of the total
+    Pie,
+}
+
+impl FromStr for ChartKind {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bar" | "bars" | "column" => Ok(Self::Bar),
+            "line" | "lines" | "plot" => Ok(Self::Line),
+            "pie" | "donut" => Ok(Self::Pie),
+            _ => Err(()),
+        }
+    }
+}
+
+/// the labelled values of a chart, parsed from entries such as `apples:3, pears:5`,
+/// separated by commas, semicolons or new lines, each being `<label>:<value>`, `<label>=<value>` or just a value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartData(pub Vec<(String, f64)>);
+
+impl FromStr for ChartData {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let entries = s.split([',', ';', '\n'])
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .enumerate()
+            .map(|(i, entry)| {
+                let (label, value) = entry.rsplit_once([':', '='])
+                    .map_or_else(
+                        || ((i + 1).to_string(), entry),
+                        |(label, value)| (label.trim().to_string(), value),
+                    );
+
+                value.trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .map(|value| (label, value))
+                    .ok_or(())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if entries.is_empty() {
+            Err(())
+        } else {
+            Ok(Self(entries))
+        }
+    }
+}
+
+/// the options passed to [`super::functions::chart_func`]
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    /// the kind of chart
+    pub kind: ChartKind,
+    /// the labelled values of the chart, in order
+    pub data: ChartData,
+    /// the title shown above the chart
+    pub title: Option<String>,
+}
+
+/// the options passed to [`super::functions::grid_func`]
+#[derive(Debug, Clone)]
+pub struct GridOptions {
+    /// the encoded images placed in the cells of the grid, in order
+    pub images: Vec<Vec<u8>>,
+    /// the captions drawn beneath each image, or none to leave them uncaptioned
+    pub labels: Vec<String>,
+    /// the number of columns of the grid, or `0` to keep the grid close to a square
+    pub columns: u32,
+}
+
+/// the options passed to [`super::functions::rankcard_func`], as provided by a leveling system
+#[derive(Debug, Clone)]
+pub struct RankOptions {
+    /// the name of the member
+    pub name: String,
+    /// the position of the member on the leaderboard, counting from `1`
+    pub rank: u32,
+    /// the current level of the member
+    pub level: u32,
+    /// the experience the member has gained towards the next level
+    pub xp: u64,
+    /// the experience required to reach the next level
+    pub next: u64,
+    /// the colors of the gradient filling the progress bar, from left to right
+    pub colors: Vec<Rgba>,
+}
+
+/// the options passed to [`super::functions::welcome_func`]
+#[derive(Debug, Clone)]
+pub struct WelcomeOptions {
+    /// the name of the member being welcomed
+    pub name: String,
+    /// the amount of members in the guild, including the new member
+    pub member_count: u64,
+    /// the raw bytes of the background of the card, or `None` for a plain gradient
+    pub background: Option<Vec<u8>>,
+}
+
+/// the options passed to [`super::functions::quote_func`]
+#[derive(Debug, Clone)]
+pub struct QuoteOptions {
+    /// the name of the author of the quoted message
+    pub name: String,
+    /// the markdown content of the quoted message
+    pub text: String,
+}
+
+/// the options passed to [`super::functions::text_func`]
+#[derive(Debug, Clone)]
+pub struct TextOptions {
+    /// the text to render
+    pub text: String,
+    /// the name of the font to render the text in, falling back to the other fonts for characters it does not support
+    pub font: Option<String>,
+    /// the color of the text
+    pub color: Rgba,
+    /// the color of the outline drawn around the text, if any
+    pub stroke: Option<Rgba>,
+    /// the color of the canvas, which is transparent by default
+    pub background: Rgba,
+    /// the font size of the text
+    pub size: f32,
+    /// the horizontal alignment of the lines of text
+    pub align: Align,
+    /// the width past which the text is wrapped onto a new line
+    pub max_width: u32,
+}
+
+impl TextOptions {
+    /// estimates the dimensions of the rendered text before it is shaped, each character taking about 60% of the size in width,
+    /// and each line, whether wrapped or broken, 120% of the size in height
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn estimated_size(&self) -> (u32, u32) {
+        let length = self.text.chars().count() as f32 * self.size * 0.6;
+        let max_width = self.max_width.max(1) as f32;
+        let breaks = self.text.lines().count().saturating_sub(1) as f32;
+        let lines = (length / max_width).ceil().max(1.0) + breaks;
+
+        (length.min(max_width) as u32, (lines * self.size * 1.2) as u32)
+    }
+}
+
+/// the dimensions of a generated image, parsed from either `<width>x<height>` or a single size for square images
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size(pub u32, pub u32);
+
+impl FromStr for Size {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |value: &str| value.trim()
+            .parse::<u32>()
+            .map_err(|_| ());
+
+        match s.to_lowercase().split_once('x') {
+            Some((width, height)) => Ok(Self(parse(width)?, parse(height)?)),
+            None => parse(s).map(|size| Self(size, size)),
+        }
+    }
+}
+
+/// the shape of a gradient rendered by [`super::functions::gradient_func`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GradientKind {
+    /// the colors change along a straight line
+    #[default]
+    Linear,
+    /// the colors change outwards from the center
+    Radial,
+}
+
+/// the options passed to [`super::functions::gradient_func`]
+#[derive(Debug, Clone)]
+pub struct GradientOptions {
+    /// the colors of the gradient, evenly spaced from its start to its end
+    pub colors: Vec<Rgba>,
+    /// the shape of the gradient
+    pub kind: GradientKind,
+    /// the angle of a linear gradient in degrees, with `0` going from left to right
+    pub angle: f64,
+    /// the dimensions of the image
+    pub size: Size,
+}
+
+/// the options passed to [`super::functions::mosaic_func`]
+#[derive(Debug, Clone)]
+pub struct MosaicOptions {
+    /// the raw bytes of the image the tiles are copies of
+    pub tile: Vec<u8>,
+    /// the number of tiles along the width of the source image
+    pub tiles: u32,
+}
+
+/// how [`super::functions::flagify_func`] applies a flag to an image
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FlagStyle {
+    /// frames the image, masked to a circle, with a ring of the flag
+    #[default]
+    Ring,
+    /// washes the flag translucently over the whole image
+    Wash,
+}
+
+impl FromStr for FlagStyle {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "ring" | "border" | "circle" => Ok(Self::Ring),
+            "wash" | "overlay" | "tint" => Ok(Self::Wash),
+            _ => Err(()),
+        }
+    }
+}
+
+/// the options passed to [`super::functions::flagify_func`]
+#[derive(Debug, Clone)]
+pub struct FlagifyOptions {
+    /// the raw bytes of the flag
+    pub flag: Vec<u8>,
+    /// how the flag is applied
+    pub style: FlagStyle,
+    /// the opacity of the flag when washed over the image, from `0.0` to `1.0`
+    pub opacity: f64,
+}
+
+/// a preset size of banner that [`super::functions::bannerize_func`] crops images to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BannerPreset {
+    /// a discord server banner, 960x540
+    #[default]
+    Discord,
+    /// a twitter profile header, 1500x500
+    Twitter,
+    /// a youtube channel banner, 2560x1440
+    Youtube,
+}
+
+impl BannerPreset {
+    /// the exact width and height required by the preset
+    #[must_use]
+    pub const fn size(self) -> (u32, u32) {
+        match self {
+            Self::Discord => (960, 540),
+            Self::Twitter => (1500, 500),
+            Self::Youtube => (2560, 1440),
+        }
+    }
+
+    /// the human readable name of the preset
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Discord => "Discord server banner",
+            Self::Twitter => "Twitter header",
+            Self::Youtube => "YouTube banner",
+        }
+    }
+}
+
+impl FromStr for BannerPreset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "discord" | "server" | "guild" => Ok(Self::Discord),
+            "twitter" | "x" | "header" => Ok(Self::Twitter),
+            "youtube" | "yt" | "channel" => Ok(Self::Youtube),
+            _ => Err(()),
+        }
+    }
+}
+
+/// a point in time within an animation, parsed from seconds such as `1.5` or `1.5s`, or milliseconds such as `500ms`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timestamp(pub Duration);
+
+impl FromStr for Timestamp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim()
+            .to_lowercase();
+        let (value, scale) = if let Some(ms) = s.strip_suffix("ms") {
+            (ms, 1000.0)
+        } else {
+            (s.strip_suffix('s').unwrap_or(&s), 1.0)
+        };
+
+        value.trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .and_then(|value| Duration::try_from_secs_f64(value / scale).ok())
+            .map(Self)
+            .ok_or(())
+    }
+}
+
+/// how the delays of animated outputs are chosen, set per command and overridden with the `--delay` flag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DelayPolicy {
+    /// each frame takes the delay of the source frame it was made from, cycling through the source,
+    /// so that the output matches the timing of an animated source
+    Preserve,
+    /// every frame is displayed for the mean delay of the output, evening out its rate
+    Normalize,
+    /// every frame is displayed for the provided delay
+    Override(Duration),
+}
+
+impl FromStr for DelayPolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "preserve" | "source" | "original" => Ok(Self::Preserve),
+            "normalize" | "normalise" | "even" => Ok(Self::Normalize),
+            delay => delay.parse::<Timestamp>()
+                .map(|Timestamp(delay)| Self::Override(delay)),
+        }
+    }
+}
+
+/// an inclusive range of frames counting from `1`, parsed from `5..40`, `5-40`, `5..` or `..40`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameRange {
+    /// the first frame in the range
+    pub start: usize,
+    /// the last frame in the range, or `None` to continue until the last frame
+    pub end: Option<usize>,
+}
+
+impl FrameRange {
+    /// the indices of the frames in this range, counting from `0`
+    #[must_use]
+    pub fn indices(self) -> std::ops::Range<usize> {
+        self.start.saturating_sub(1)..self.end.unwrap_or(usize::MAX)
+    }
+}
+
+impl FromStr for FrameRange {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s.split_once("..")
+            .or_else(|| s.split_once('-'))
+            .ok_or(())?;
+        let bound = |value: &str| match value.trim() {
+            "" => Ok(None),
+            value => value.parse::<usize>()
+                .map(Some)
+                .map_err(|_| ()),
+        };
+
+        let (start, end) = (bound(start)?.unwrap_or(1).max(1), bound(end)?);
+        if end.is_some_and(|end| end < start) {
+            return Err(());
+        }
+
+        Ok(Self { start, end })
+    }
+}
+
+/// the part of an animation kept by [`super::functions::trimgif_func`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cut {
+    /// the part displayed between two points in time, or until the end if the second is `None`
+    Time(Duration, Option<Duration>),
+    /// a range of frames
+    Frames(FrameRange),
+}
+
+/// the options passed to [`super::functions::anaglyph_func`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnaglyphOptions {
+    /// the largest horizontal offset between the two views, in pixels
+    pub depth: u32,
+    /// whether to render an animated wiggle between the views instead of overlaying them in red and cyan
+    pub wiggle: bool,
+}
+
+/// the options passed to [`super::functions::facecrop_func`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropOptions {
+    /// whether to center the crop on the most prominent face rather than on the geometric center of the image
+    pub smart: bool,
+    /// whether to mask the crop to a circle
+    pub circle: bool,
+}
+
+/// the options passed to [`super::functions::tilt_func`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TiltOptions {
+    /// how dramatic the effect is, from `0.0` to `1.0`, scaling the angle, zoom and number of speed lines
+    pub intensity: f64,
+    /// whether or not to tint the image red
+    pub red: bool,
+}
+
+/// the options passed to [`super::functions::stack_func`]
+#[derive(Debug, Clone)]
+pub struct StackOptions {
+    /// the raw bytes of the animation appended after the source animation
+    pub next: Vec<u8>,
+}
+
+/// the options passed to [`super::functions::compare_func`]
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// the raw bytes of the image shown after the source image
+    pub after: Vec<u8>,
+    /// whether to reveal the second image with a sliding divider over the first, rather than placing them side by side
+    pub slider: bool,
+}
+
+/// the options passed to [`super::functions::overlay_func`]
+#[derive(Debug, Clone)]
+pub struct OverlayOptions {
+    /// the raw bytes of the image to composite on top of the source image
+    pub overlay: Vec<u8>,
+    /// where to place the overlay
+    pub anchor: Anchor,
+    /// the width of the overlay relative to the width of the source image
+    pub scale: f64,
+    /// the opacity of the overlay, from `0.0` to `1.0`
+    pub opacity: f64,
+}
\ No newline at end of file
@@ -0,0 +1,270 @@
+//! contains the pipeline used by the `pipe` command, which chains effects one after another
+//! and is run as a [`super::script::Script`] of those effects
+//!
+//! the frames are handed from one stage to the next still decoded, rather than being encoded and decoded again between stages,
+//! and data computed by one stage, such as the faces found in the first frame, is kept in a [`PipelineContext`]
+//! for the stages after it for as long as they leave it valid
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    fmt,
+    str::FromStr,
+    sync::Arc,
+};
+
+use rand::Rng;
+
+use super::{
+    functions::{
+        anaglyph_func,
+        apply_accessory,
+        huerotate_func,
+        invert_func,
+        neon_func,
+        noise_func,
+        tilt_func,
+    },
+    imaging::{Frames, ImageArguments},
+    options::{Accessory, AnaglyphOptions, ChannelMask, TiltOptions},
+    registry::{self, Effect},
+    vision::faces_or_center,
+};
+
+
+/// the most stages a single pipeline may chain
+pub const MAX_STAGES: usize = 8;
+
+/// the key of the faces found in the first frame, see [`faces_or_center`]
+pub const FACES: &str = "faces";
+
+/// a single effect within a pipeline, along with the value of its parameter if it has one
+#[derive(Debug, Clone, Copy)]
+pub struct Stage {
+    /// the effect applied by this stage
+    pub effect: &'static Effect,
+    /// the value of the parameter of the effect, clamped to its range, or `None` to use its default
+    pub value: Option<f64>,
+}
+
+impl Stage {
+    /// the names of the effects that may be chained
+    pub const EFFECTS: &'static [&'static str] = &[
+        "invert", "huerotate", "noise", "eyes", "glasses", "clown", "anaglyph", "tilt", "neon",
+    ];
+
+    /// creates the stage applying the effect named `name`, clamping the value of its parameter to its range
+    ///
+    /// # Errors
+    /// if the effect can not be chained, or a value is passed to an effect without a parameter
+    pub fn new(name: &str, value: Option<f64>) -> Result<Self, String> {
+        let effect = registry::find(name)
+            .filter(|effect| Self::EFFECTS.contains(&effect.name))
+            .ok_or_else(|| format!(
+                "`{name}` can not be chained, the effects that can are: {}",
+                Self::EFFECTS.join(", "),
+            ))?;
+
+        let value = match (value, effect.params.first()) {
+            (None, _) => None,
+            (Some(value), Some(param)) => Some(param.clamp(value)),
+            (Some(_), None) => return Err(format!("`{}` does not take a value", effect.name)),
+        };
+
+        Ok(Self { effect, value })
+    }
+
+    /// picks one of the effects that may be picked at random, along with a random value of its parameter,
+    /// picking the same stage for the same state of `rng`
+    ///
+    /// this is how the `random` command and the image of the day pick their effect, so that either reproduces the other
+    pub fn random<R: Rng>(rng: &mut R) -> Option<Self> {
+        let effect = registry::choose_random(rng)?;
+        let value = effect.params
+            .first()
+            .map(|param| param.sample(rng));
+
+        Self::new(effect.name, value)
+            .ok()
+    }
+
+    /// applies a single stage to the frames, for running an effect of the registry on its own through the executor
+    pub fn run(data: ImageArguments<Self>) -> ril::Result<Frames> {
+        data.arguments[0].apply(data.frames, data.seed, &mut PipelineContext::default())
+    }
+
+    /// the value of the parameter of the effect, falling back to the default of its command
+    #[must_use]
+    pub fn value(&self) -> f64 {
+        self.value.unwrap_or(match self.effect.name {
+            "noise" => 0.25,
+            "anaglyph" => 12.0,
+            "tilt" => 0.6,
+            "neon" => 8.0,
+            _ => 0.0,
+        })
+    }
+
+    /// the keys of the data in the [`PipelineContext`] that is still valid after this stage has been applied
+    ///
+    /// recoloring the frames or drawing on them keeps the faces in place, while moving their contents does not
+    ///
+    /// nothing else is kept, as the only other data worth sharing, the edges traced by `neon`,
+    /// change with any change to the pixels of the frames, which every stage makes
+    #[must_use]
+    pub fn keeps(&self) -> &'static [&'static str] {
+        match self.effect.name {
+            "invert" | "huerotate" | "noise" | "eyes" | "glasses" | "clown" | "neon" => &[FACES],
+            _ => &[],
+        }
+    }
+
+    /// applies this stage to the provided frames, reusing the data in `context` that it needs
+    ///
+    /// # Errors
+    /// if the effect fails to apply
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn apply(&self, frames: Frames, seed: u64, context: &mut PipelineContext) -> ril::Result<Frames> {
+        let accessory = |frames: Frames, context: &mut PipelineContext, accessory| {
+            let faces = context.memo(FACES, || frames
+                .first_frame()
+                .map(|frame| faces_or_center(frame.image()))
+                .unwrap_or_default()
+            );
+
+            apply_accessory(frames, &faces, accessory)
+        };
+
+        Ok(match self.effect.name {
+            "invert" => invert_func(ImageArguments { frames, arguments: vec![ChannelMask::default()], seed })?,
+            "huerotate" => huerotate_func(ImageArguments { frames, arguments: vec![], seed })?,
+            "noise" => noise_func(ImageArguments { frames, arguments: vec![self.value()], seed })?,
+            "eyes" => accessory(frames, context, Accessory::LaserEyes),
+            "glasses" => accessory(frames, context, Accessory::Glasses),
+            "clown" => accessory(frames, context, Accessory::ClownNose),
+            "anaglyph" => anaglyph_func(ImageArguments {
+                frames,
+                arguments: vec![AnaglyphOptions { depth: self.value() as u32, wiggle: false }],
+                seed,
+            })?,
+            "tilt" => tilt_func(ImageArguments {
+                frames,
+                arguments: vec![TiltOptions { intensity: self.value(), red: false }],
+                seed,
+            })?,
+            "neon" => neon_func(ImageArguments { frames, arguments: vec![self.value() as u32], seed })?,
+            _ => frames,
+        })
+    }
+}
+
+impl FromStr for Stage {
+    type Err = String;
+
+    /// parses a stage from the name of its effect, optionally followed by the value of its parameter as `name:value`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = match s.trim().split_once([':', '=']) {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (s.trim(), None),
+        };
+
+        let value = value
+            .map(|value| value.parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .ok_or_else(|| format!("`{value}` is not a valid value for `{name}`"))
+            )
+            .transpose()?;
+
+        Self::new(name, value)
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Some(value) => write!(f, "{}:{value}", self.effect.name),
+            None => f.write_str(self.effect.name),
+        }
+    }
+}
+
+/// the stages of a pipeline, applied in order
+#[derive(Debug, Clone)]
+pub struct Pipeline(pub Vec<Stage>);
+
+impl FromStr for Pipeline {
+    type Err = String;
+
+    /// parses the stages of a pipeline, separated by `|`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stages = s.split('|')
+            .map(str::trim)
+            .filter(|stage| !stage.is_empty())
+            .map(str::parse)
+            .collect::<Result<Vec<Stage>, _>>()?;
+
+        match stages.len() {
+            0 => Err("No effects were provided to chain".to_string()),
+            len if len > MAX_STAGES => Err(format!("At most {MAX_STAGES} effects can be chained, not {len}")),
+            _ => Ok(Self(stages)),
+        }
+    }
+}
+
+impl fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, stage) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            write!(f, "{stage}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// data computed by the stages of a pipeline, shared with the stages after them
+///
+/// each entry is tagged with the generation of the frames it was computed from, and is only reused within that generation
+#[derive(Default)]
+pub struct PipelineContext {
+    /// incremented every time a stage changes the frames
+    generation: u64,
+    /// the data computed so far by its key, along with the generation it was computed in
+    memos: HashMap<&'static str, (u64, Arc<dyn Any + Send + Sync>)>,
+}
+
+impl PipelineContext {
+    /// returns the data stored under `key` if it is still valid, or computes and stores it otherwise
+    pub fn memo<T, F>(&mut self, key: &'static str, compute: F) -> Arc<T>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce() -> T,
+    {
+        if let Some(data) = self.memos
+            .get(key)
+            .filter(|(generation, _)| *generation == self.generation)
+            .and_then(|(_, data)| Arc::clone(data).downcast::<T>().ok())
+        {
+            return data;
+        }
+
+        let data = Arc::new(compute());
+        self.memos.insert(key, (self.generation, Arc::clone(&data) as Arc<dyn Any + Send + Sync>));
+
+        data
+    }
+
+    /// moves on to the next generation of frames after a stage has been applied,
+    /// carrying over the data under the keys in `keeps` and discarding the rest
+    pub fn advance(&mut self, keeps: &[&'static str]) {
+        self.generation += 1;
+        self.memos.retain(|key, _| keeps.contains(key));
+
+        for (computed, _) in self.memos.values_mut() {
+            *computed = self.generation;
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,221 @@
+//! contains the [`FetchPolicy`], which every request to a user-supplied url goes through
+//!
+//! redirects are followed manually so that each hop is checked against the policy,
+//! only `http` and `https` urls are allowed, hosts can be allowed or denied through the config,
+//! and hosts resolving to private, loopback or link-local addresses are refused
+//! both when checking the url and when connecting, so that DNS rebinding cannot bypass the check
+
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, RwLock},
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::{HeaderMap, LOCATION},
+    redirect,
+    Client,
+    Method,
+    Response,
+    Url,
+};
+
+use super::Error;
+
+
+/// the maximum amount of redirects followed for a single request
+pub const MAX_REDIRECTS: usize = 5;
+
+lazy_static::lazy_static! {
+    /// the active fetch policy
+    static ref POLICY: RwLock<Arc<FetchPolicy>> = RwLock::new(Arc::new(FetchPolicy::default()));
+}
+
+
+/// the rules urls must follow in order to be fetched
+#[derive(Debug, Clone, Default)]
+pub struct FetchPolicy {
+    /// if not empty, only these hosts (and their subdomains) may be fetched
+    pub allowed_hosts: Vec<String>,
+    /// these hosts (and their subdomains) may never be fetched
+    pub denied_hosts: Vec<String>,
+}
+
+/// returns whether or not `host` is `pattern` or a subdomain of it
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim_start_matches("*.");
+
+    host.eq_ignore_ascii_case(pattern)
+        || host.to_lowercase().ends_with(&format!(".{}", pattern.to_lowercase()))
+}
+
+/// returns whether or not the address is public, excluding loopback, private, link-local and other reserved ranges
+#[must_use]
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // shared address space (100.64.0.0/10) and "this network" (0.0.0.0/8)
+                || (a == 100 && (64..128).contains(&b))
+                || a == 0)
+        },
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_ip(IpAddr::V4(mapped));
+            }
+            let segments = ip.segments();
+            let first = segments[0];
+            let embedded = |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+
+            // addresses translated to ipv4 by NAT64 (64:ff9b::/96) or tunneled by 6to4 (2002::/16)
+            // reach the ipv4 address embedded in them, so it must be public too
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                return is_public_ip(IpAddr::V4(embedded(segments[6], segments[7])));
+            }
+            if first == 0x2002 {
+                return is_public_ip(IpAddr::V4(embedded(segments[1], segments[2])));
+            }
+
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // unique local (fc00::/7) and link-local (fe80::/10) addresses
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        },
+    }
+}
+
+impl FetchPolicy {
+    /// returns the active policy
+    ///
+    /// # Panics
+    /// if the inner lock is poisoned
+    #[must_use]
+    pub fn current() -> Arc<Self> {
+        Arc::clone(&POLICY.read().unwrap())
+    }
+
+    /// replaces the active policy
+    ///
+    /// # Panics
+    /// if the inner lock is poisoned
+    pub fn set(policy: Self) {
+        *POLICY.write().unwrap() = Arc::new(policy);
+    }
+
+    /// checks the scheme and host of a url against this policy
+    pub fn check(&self, url: &Url) -> Result<(), Error> {
+        let blocked = || Error::BlockedUrl(url.to_string());
+
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(blocked());
+        }
+
+        let host = url.host_str()
+            .ok_or_else(blocked)?;
+
+        if self.denied_hosts.iter().any(|pattern| host_matches(host, pattern))
+            || (!self.allowed_hosts.is_empty()
+                && !self.allowed_hosts.iter().any(|pattern| host_matches(host, pattern)))
+        {
+            return Err(blocked());
+        }
+
+        // literal addresses never go through the resolver, so they are checked here instead
+        if let Ok(ip) = host.trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            if !is_public_ip(ip) {
+                return Err(blocked());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// a DNS resolver that drops any non-public addresses, refusing hosts that only resolve to such addresses
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public_ip(addr.ip()))
+                .collect::<Vec<SocketAddr>>();
+
+            if addrs.is_empty() {
+                return Err(format!("{} does not resolve to a public address", name.as_str()).into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// builds an HTTP client that does not follow redirects on its own and only connects to public addresses,
+/// meant to be used with [`send`]
+///
+/// # Panics
+/// if the client fails to build
+#[must_use]
+pub fn build_client() -> Client {
+    Client::builder()
+        .user_agent(concat!("ril-bot/", env!("CARGO_PKG_VERSION")))
+        .redirect(redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        // a proxy from the environment would resolve and connect to hosts itself, bypassing the resolver
+        .no_proxy()
+        .build()
+        .expect("Failed to build the HTTP client")
+}
+
+/// sends a request to the provided url, following up to [`MAX_REDIRECTS`] redirects
+/// and checking every url along the way against the active [`FetchPolicy`]
+pub async fn send(client: Option<&Client>, method: Method, url: &str, headers: HeaderMap) -> Result<Response, Error> {
+    let client = client.cloned()
+        .unwrap_or_else(build_client);
+    let policy = FetchPolicy::current();
+
+    let mut url = Url::parse(url)
+        .map_err(|_| Error::FetchUrlError)?;
+
+    for _ in 0..=MAX_REDIRECTS {
+        policy.check(&url)?;
+
+        let response = client.request(method.clone(), url.clone())
+            .headers(headers.clone())
+            .send()
+            .await?;
+
+        if !response.status().is_redirection() {
+            return Ok(response);
+        }
+
+        url = response.headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| url.join(location).ok())
+            .ok_or(Error::FetchUrlError)?;
+    }
+
+    Err(Error::FetchUrlError)
+}
+
+/// a shortcut for sending a plain `GET` request through [`send`]
+pub async fn get(client: Option<&Client>, url: &str) -> Result<Response, Error> {
+    send(client, Method::GET, url, HeaderMap::new())
+        .await
+}
\ No newline at end of file
@@ -0,0 +1,280 @@
+//! contains the [`WorkerPool`], a dedicated fixed-size pool of threads that image jobs are run on
+//!
+//! jobs are scheduled by their estimated cost rather than in order of submission,
+//! so that small, static images are not stuck waiting behind large animated ones,
+//! while jobs gain priority the longer they wait so that large ones are not starved by a steady stream of small ones
+
+use std::{
+    backtrace::Backtrace,
+    cell::RefCell,
+    collections::BinaryHeap,
+    cmp::Ordering,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+        Condvar,
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serenity::prelude::Context;
+use tokio::sync::oneshot;
+
+use crate::PoolData;
+use super::{
+    Error,
+    metrics::METRICS,
+};
+
+
+/// the cost a waiting job is discounted by for every second it has waited
+pub const AGING_RATE: u64 = 1 << 20;
+
+thread_local! {
+    /// the message and backtrace of the last panic on this thread, captured by the hook installed by [`install_panic_hook`]
+    static LAST_PANIC: RefCell<Option<(String, Backtrace)>> = RefCell::new(None);
+}
+
+/// installs a panic hook that captures the message and backtrace of panics,
+/// so that [`run_job`] can log them when a job panics, before calling the previously installed hook
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        LAST_PANIC.with(|last| {
+            *last.borrow_mut() = Some((info.to_string(), Backtrace::force_capture()));
+        });
+
+        previous(info);
+    }));
+}
+
+/// a job waiting in the queue
+struct Job {
+    /// the estimated cost of the job
+    cost: u64,
+    /// the cost of the job offset by when it was submitted, lower priorities are run first
+    ///
+    /// discounting the cost of every waiting job by [`AGING_RATE`] per second ranks them the same way as
+    /// adding the time of submission to each cost at the same rate, which unlike the former never changes while waiting
+    priority: u64,
+    /// the order in which the job was submitted, used to run jobs of equal cost in order
+    sequence: u64,
+    /// the work to run
+    task: Box<dyn FnOnce() + Send>,
+}
+
+impl PartialEq for Job {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Job {}
+
+impl PartialOrd for Job {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Job {
+    // `BinaryHeap` is a max-heap, so the comparison is reversed to pop the cheapest, oldest job first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// the state shared between the pool and its workers
+#[derive(Default)]
+struct Shared {
+    /// the queue of waiting jobs and the next sequence number
+    queue: Mutex<(BinaryHeap<Job>, u64)>,
+    /// notified whenever a job is pushed onto the queue
+    available: Condvar,
+    /// the amount of jobs currently being run
+    running: AtomicUsize,
+}
+
+/// a snapshot of the state of a [`WorkerPool`]
+#[derive(Debug, Clone)]
+pub struct PoolStats {
+    /// the amount of worker threads
+    pub workers: usize,
+    /// the amount of jobs currently being run
+    pub running: usize,
+    /// the estimated costs of the jobs waiting in the queue
+    pub waiting: Vec<u64>,
+}
+
+/// a fixed-size pool of worker threads that runs jobs from a priority queue
+pub struct WorkerPool {
+    /// the state shared with the workers
+    shared: Arc<Shared>,
+    /// the amount of worker threads
+    workers: usize,
+    /// when the pool was spawned, which the time of submission of each job is measured from
+    started: Instant,
+}
+
+impl WorkerPool {
+    /// spawns a new pool with the provided amount of worker threads
+    ///
+    /// # Panics
+    /// if a worker thread fails to spawn
+    #[must_use]
+    pub fn new(workers: usize) -> Self {
+        let shared = Arc::new(Shared::default());
+        let workers = workers.max(1);
+
+        for i in 0..workers {
+            let shared = Arc::clone(&shared);
+
+            thread::Builder::new()
+                .name(format!("image-worker-{i}"))
+                .spawn(move || Self::work(&shared))
+                .expect("Failed to spawn an image worker thread");
+        }
+
+        Self { shared, workers, started: Instant::now() }
+    }
+
+    /// the loop each worker thread runs, popping and running jobs forever
+    #[allow(clippy::significant_drop_tightening)]
+    fn work(shared: &Shared) {
+        loop {
+            let job = {
+                let mut queue = shared.queue.lock()
+                    .unwrap();
+
+                loop {
+                    if let Some(job) = queue.0.pop() {
+                        break job;
+                    }
+                    queue = shared.available.wait(queue)
+                        .unwrap();
+                }
+            };
+
+            shared.running.fetch_add(1, AtomicOrdering::Relaxed);
+
+            // the job's result channel is dropped if it panics, which is reported to the submitter,
+            // so the worker itself can carry on
+            panic::catch_unwind(AssertUnwindSafe(job.task))
+                .ok();
+
+            shared.running.fetch_sub(1, AtomicOrdering::Relaxed);
+        }
+    }
+
+    /// returns a snapshot of the state of this pool
+    ///
+    /// # Panics
+    /// if the inner mutex is poisoned
+    #[must_use]
+    pub fn stats(&self) -> PoolStats {
+        PoolStats {
+            workers: self.workers,
+            running: self.shared.running.load(AtomicOrdering::Relaxed),
+            waiting: self.shared.queue.lock()
+                .unwrap()
+                .0
+                .iter()
+                .map(|job| job.cost)
+                .collect(),
+        }
+    }
+
+    /// submits a job with the provided estimated cost, returning its result once it has been run
+    pub async fn run<F, T>(&self, cost: u64, function: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+
+        {
+            let mut queue = self.shared.queue.lock()
+                .unwrap();
+            let sequence = queue.1;
+            queue.1 += 1;
+
+            queue.0.push(Job {
+                cost,
+                priority: cost.saturating_add(aging(self.started.elapsed())),
+                sequence,
+                task: Box::new(move || {
+                    sender.send(function()).ok();
+                }),
+            });
+        }
+        self.shared.available.notify_one();
+
+        receiver.await
+            .map_err(|_| Error::JobFailed)
+    }
+}
+
+/// the offset of the priority of a job submitted `elapsed` after the pool was spawned, see [`Job::priority`]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn aging(elapsed: Duration) -> u64 {
+    (elapsed.as_secs_f64() * AGING_RATE as f64) as u64
+}
+
+/// runs a job on the [`WorkerPool`] stored in the client's data,
+/// falling back to tokio's blocking pool if there is none, and records its duration in [`METRICS`]
+///
+/// if the job panics, the panic is logged and [`Error::RendererCrashed`] is returned with the id of the incident
+pub async fn run_job<F, T>(ctx: &Context, cost: u64, function: F) -> Result<T, Error>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let pool = ctx.data.read()
+        .await
+        .get::<PoolData>()
+        .cloned();
+
+    let function = move || {
+        let instant = Instant::now();
+        let result = panic::catch_unwind(AssertUnwindSafe(function));
+
+        METRICS.record_job(instant.elapsed());
+        result.map_err(|_| report_panic())
+    };
+
+    if let Some(pool) = pool {
+        pool.run(cost, function)
+            .await?
+    } else {
+        tokio::task::spawn_blocking(function)
+            .await
+            .map_err(|_| Error::JobFailed)?
+    }
+}
+
+/// logs the panic captured on this thread under a new incident id, returning [`Error::RendererCrashed`] with that id
+fn report_panic() -> Error {
+    let incident = format!("{:08x}", rand::random::<u32>());
+
+    match LAST_PANIC.with(|last| last.borrow_mut().take()) {
+        Some((message, backtrace)) =>
+            tracing::error!(incident = %incident, "an image job panicked: {message}\n{backtrace}"),
+        None =>
+            tracing::error!(incident = %incident, "an image job panicked"),
+    }
+
+    Error::RendererCrashed(incident)
+}
+
+/// estimates the cost of processing the provided encoded image, animated images being weighted much heavier
+#[must_use]
+pub fn estimate_cost(bytes: &[u8]) -> u64 {
+    let weight = if bytes.starts_with(b"GIF8") { 8 } else { 1 };
+
+    bytes.len() as u64 * weight
+}
\ No newline at end of file
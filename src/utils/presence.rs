@@ -0,0 +1,138 @@
+//! contains the presence updater, which shows the guild count in the bot's activity,
+//! and the poster sending the guild count to the bot lists configured in [`super::config::Config::bot_lists`]
+
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use serenity::{
+    prelude::Context,
+    model::{gateway::Activity, id::UserId},
+};
+use serde_json::json;
+
+use crate::{ClientData, ConfigData};
+
+
+/// how often the guild count is posted to the bot lists, which rate limit far more strictly than the gateway
+pub const POST_INTERVAL: Duration = Duration::from_secs(1800);
+
+/// set once the poster has been started, as `ready` is dispatched again whenever a shard reconnects
+static POSTER_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// a bot list the guild count can be posted to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotList {
+    /// <https://top.gg>
+    TopGg,
+    /// <https://discord.bots.gg>
+    DiscordBotsGg,
+    /// <https://discordbotlist.com>
+    DiscordBotList,
+}
+
+impl BotList {
+    /// the url the stats of the bot with the provided id are posted to
+    #[must_use]
+    pub fn stats_url(self, bot_id: UserId) -> String {
+        match self {
+            Self::TopGg => format!("https://top.gg/api/bots/{bot_id}/stats"),
+            Self::DiscordBotsGg => format!("https://discord.bots.gg/api/v1/bots/{bot_id}/stats"),
+            Self::DiscordBotList => format!("https://discordbotlist.com/api/v1/bots/{bot_id}/stats"),
+        }
+    }
+
+    /// the body of the request posting the provided guild count, each list naming the field differently
+    #[must_use]
+    pub fn body(self, guild_count: usize) -> serde_json::Value {
+        match self {
+            Self::TopGg => json!({ "server_count": guild_count }),
+            Self::DiscordBotsGg => json!({ "guildCount": guild_count }),
+            Self::DiscordBotList => json!({ "guilds": guild_count }),
+        }
+    }
+}
+
+impl FromStr for BotList {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase()
+            .replace(['.', '-', '_'], "")
+            .as_str()
+        {
+            "topgg" => Ok(Self::TopGg),
+            "discordbotsgg" => Ok(Self::DiscordBotsGg),
+            "discordbotlist" | "discordbotlistcom" => Ok(Self::DiscordBotList),
+            _ => Err(()),
+        }
+    }
+}
+
+/// sets the bot's activity to show the amount of guilds it is in
+pub async fn update(ctx: &Context) {
+    let count = ctx.cache.guild_count();
+
+    ctx.set_activity(Activity::playing(format!("r!help | {count} servers")))
+        .await;
+}
+
+/// posts the guild count to each bot list configured with a token, logging those that fail
+pub async fn post_stats(ctx: &Context) {
+    let (Some(config), Some(client)) = ({
+        let data = ctx.data.read()
+            .await;
+
+        (data.get::<ConfigData>().cloned(), data.get::<ClientData>().cloned())
+    }) else {
+        return;
+    };
+
+    let bot_id = ctx.cache.current_user_id();
+    let count = ctx.cache.guild_count();
+
+    for (name, token) in &config.bot_lists {
+        let Ok(list) = name.parse::<BotList>() else {
+            tracing::warn!("unknown bot list `{name}` in the configuration");
+            continue;
+        };
+
+        let result = client.post(list.stats_url(bot_id))
+            .header("Authorization", token)
+            .json(&list.body(count))
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status);
+
+        if let Err(err) = result {
+            tracing::warn!("failed to post the guild count to `{name}`: {err}");
+        }
+    }
+}
+
+/// starts posting the guild count to the bot lists every [`POST_INTERVAL`], if any are configured,
+/// doing nothing if the poster was already started
+pub async fn start_poster(ctx: &Context) {
+    let configured = ctx.data.read()
+        .await
+        .get::<ConfigData>()
+        .map_or(false, |config| !config.bot_lists.is_empty());
+
+    if !configured || POSTER_STARTED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    let ctx = ctx.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(POST_INTERVAL);
+
+        loop {
+            interval.tick()
+                .await;
+            post_stats(&ctx)
+                .await;
+        }
+    });
+}
\ No newline at end of file
@@ -0,0 +1,201 @@
+//! contains the registry of every effect the bot provides, along with metadata about each of them
+
+use rand::{seq::SliceRandom, Rng};
+
+/// metadata about a single effect
+#[derive(Debug, Clone, Copy)]
+pub struct Effect {
+    /// the name of the command that applies this effect
+    pub name: &'static str,
+    /// a short description of the effect
+    pub description: &'static str,
+    /// whether or not this effect may only be used in age-restricted channels
+    pub nsfw: bool,
+    /// whether or not this effect may be picked by the `random` command, which requires it to be chainable,
+    /// see [`super::pipeline::Stage::EFFECTS`]
+    pub random: bool,
+    /// the numeric parameters this effect accepts, passed as flags
+    pub params: &'static [Param],
+}
+
+/// a numeric parameter of an effect, along with the range of values it accepts
+#[derive(Debug, Clone, Copy)]
+pub struct Param {
+    /// the name of the flag setting this parameter
+    pub name: &'static str,
+    /// the minimum value accepted
+    pub min: f64,
+    /// the maximum value accepted
+    pub max: f64,
+}
+
+impl Param {
+    /// clamps the provided value to the range of this parameter
+    #[must_use]
+    pub fn clamp(&self, value: f64) -> f64 {
+        value.clamp(self.min, self.max)
+    }
+
+    /// picks a random value within the range of this parameter
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> f64 {
+        rng.gen_range(self.min..=self.max)
+    }
+}
+
+/// every effect the bot provides
+pub static EFFECTS: &[Effect] = &[
+    Effect {
+        name: "invert",
+        description: "negates the colors of the image",
+        nsfw: false,
+        random: true,
+        params: &[],
+    },
+    Effect {
+        name: "huerotate",
+        description: "rotates the hue of the image through 360 degrees",
+        nsfw: false,
+        random: true,
+        params: &[],
+    },
+    Effect {
+        name: "caption",
+        description: "adds a meme caption onto the image",
+        nsfw: false,
+        random: false,
+        params: &[
+            Param { name: "size", min: 8.0, max: 120.0 },
+        ],
+    },
+    Effect {
+        name: "overlay",
+        description: "composites a second image onto the image",
+        nsfw: false,
+        random: false,
+        params: &[
+            Param { name: "scale", min: 0.01, max: 1.0 },
+            Param { name: "opacity", min: 0.0, max: 1.0 },
+        ],
+    },
+    Effect {
+        name: "noise",
+        description: "adds random grain to the image",
+        nsfw: false,
+        random: true,
+        params: &[
+            Param { name: "intensity", min: 0.05, max: 1.0 },
+        ],
+    },
+    Effect {
+        name: "enlarge",
+        description: "returns an emoji at a larger size",
+        nsfw: false,
+        random: false,
+        params: &[],
+    },
+    Effect {
+        name: "frames",
+        description: "extracts evenly spaced frames of an animated image",
+        nsfw: false,
+        random: false,
+        params: &[
+            Param { name: "count", min: 1.0, max: 10.0 },
+        ],
+    },
+    Effect {
+        name: "trim",
+        description: "crops away uniform transparent or solid borders of the image",
+        nsfw: false,
+        random: false,
+        params: &[
+            Param { name: "tolerance", min: 0.0, max: 64.0 },
+        ],
+    },
+    Effect {
+        name: "eyes",
+        description: "gives the faces in the image laser eyes",
+        nsfw: false,
+        random: true,
+        params: &[],
+    },
+    Effect {
+        name: "glasses",
+        description: "puts deal with it sunglasses on the faces in the image",
+        nsfw: false,
+        random: true,
+        params: &[],
+    },
+    Effect {
+        name: "clown",
+        description: "puts a clown nose on the faces in the image",
+        nsfw: false,
+        random: true,
+        params: &[],
+    },
+    Effect {
+        name: "facecrop",
+        description: "crops the image into a square, centered on its most prominent face with `smart`",
+        nsfw: false,
+        random: false,
+        params: &[],
+    },
+    Effect {
+        name: "upscale",
+        description: "scales up pixel art and small emotes without blurring them",
+        nsfw: false,
+        random: false,
+        params: &[
+            Param { name: "factor", min: 1.0, max: 16.0 },
+        ],
+    },
+    Effect {
+        name: "anaglyph",
+        description: "turns the image into red and cyan 3D, using its brightness as depth",
+        nsfw: false,
+        random: true,
+        params: &[
+            Param { name: "depth", min: 2.0, max: 40.0 },
+        ],
+    },
+    Effect {
+        name: "tilt",
+        description: "tilts the image at a dramatic angle with anime speed lines",
+        nsfw: false,
+        random: true,
+        params: &[
+            Param { name: "intensity", min: 0.1, max: 1.0 },
+        ],
+    },
+    Effect {
+        name: "scan",
+        description: "cleans up a photographed document, straightening it and turning it black and white",
+        nsfw: false,
+        random: false,
+        params: &[],
+    },
+    Effect {
+        name: "neon",
+        description: "traces the edges of the image in glowing neon over a darkened copy of it",
+        nsfw: false,
+        random: true,
+        params: &[
+            Param { name: "glow", min: 2.0, max: 24.0 },
+        ],
+    },
+];
+
+/// finds an effect in the registry by its name
+#[must_use]
+pub fn find(name: &str) -> Option<&'static Effect> {
+    EFFECTS.iter()
+        .find(|effect| effect.name.eq_ignore_ascii_case(name))
+}
+
+/// picks a random effect out of those that may be picked by the `random` command
+pub fn choose_random<R: Rng>(rng: &mut R) -> Option<&'static Effect> {
+    EFFECTS.iter()
+        .filter(|effect| effect.random)
+        .collect::<Vec<_>>()
+        .choose(rng)
+        .copied()
+}
\ No newline at end of file
@@ -8,24 +8,55 @@ use serenity::{
         user::User,
         channel::Message,
         guild::{Emoji, Member},
+        sticker::Sticker,
         prelude::{
             Embed,
             StickerItem,
             Attachment,
+            AttachmentId,
             ChannelId,
             GuildId,
+            MessageId,
+        },
+        application::interaction::application_command::{
+            ApplicationCommandInteraction,
+            CommandDataOptionValue,
+            ResolvedTarget,
         },
     },
 };
 
 use regex::{Regex, RegexBuilder};
-use crate::ClientData;
+use reqwest::{
+    header::{HeaderMap, HeaderValue, AUTHORIZATION},
+    Method,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use crate::{ClientData, ConfigData};
 use super::{
     Error,
-    helpers::url_to_bytes,
+    helpers::{
+        url_to_bytes,
+        url_to_bytes_limited,
+        read_limited,
+        read_remaining,
+        probe_url,
+        sniff_image,
+    },
+    policy,
+    assets::ASSETS,
+    entitlements::Limits,
 };
 
 
+/// the directory bundled country flags are loaded from, relative to the assets directory, each named by its lowercase country code
+pub const FLAGS_DIR: &str = "flags";
+/// the CDN country flags are fetched from when they are not bundled, with `{}` replaced by the lowercase country code
+pub const FLAG_CDN_URL: &str = "https://flagcdn.com/w640/{}.png";
+/// discord's endpoint exchanging expired attachment urls for fresh ones
+pub const REFRESH_URLS_ENDPOINT: &str = "https://discord.com/api/v10/attachments/refresh-urls";
+
 lazy_static::lazy_static! {
     /// regex for removing whitespace in a string
     static ref WS_REGEX: Regex = Regex::new(r"\s+").unwrap();
@@ -33,6 +64,11 @@ lazy_static::lazy_static! {
     static ref EMOJI_REGEX: Regex = Regex::new(r"^<(a?):([a-zA-Z0-9_]{1,32}):([0-9]{15,20})>$").unwrap();
     /// regex that matches a discord snowflake (id)
     static ref ID_REGEX: Regex = Regex::new(r"^([0-9]{15,20})$").unwrap();
+    /// regex that matches the url of a file attached to a discord message, which expires some time after it was sent
+    static ref ATTACHMENT_URL_REGEX: Regex = RegexBuilder::new(r"^https?://(cdn\.discordapp\.com|media\.discordapp\.net)/attachments/[0-9]{15,20}/[0-9]{15,20}/\S+$")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
     /// regex that matches a tenor page url
     static ref TENOR_PAGE_REGEX: Regex = RegexBuilder::new(r"^https?://(www\.)?tenor\.com/view/\S+/?$")
         .case_insensitive(true)
@@ -43,8 +79,20 @@ lazy_static::lazy_static! {
         .case_insensitive(true)
         .build()
         .unwrap();
-    /// regex that matches an imgur page url
-    static ref IMGUR_PAGE_REGEX: Regex = RegexBuilder::new(r"^https?://(www\.)?imgur.com/(\S+)/?$")
+    /// regex that matches an imgur image, album or gallery page url,
+    /// capturing the kind of page and the id (which titled urls append to the slug)
+    static ref IMGUR_PAGE_REGEX: Regex = RegexBuilder::new(r"^https?://(?:www\.|m\.)?imgur\.com/(?:(a|gallery|t/\w+)/)?(?:[\w-]*-)?(\w+)/?$")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    /// regex that matches a twitter (or x) status url, capturing the id of the status
+    static ref TWITTER_STATUS_REGEX: Regex = RegexBuilder::new(r"^https?://(?:www\.|mobile\.)?(?:twitter|x|fxtwitter|vxtwitter|fixupx)\.com/\w+/status(?:es)?/(\d+)")
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    /// regex that matches a reddit post or gallery url, capturing the id of the post
+    /// (`v.redd.it` and `redd.it` short links redirect to these)
+    static ref REDDIT_POST_REGEX: Regex = RegexBuilder::new(r"^https?://(?:www\.|old\.|new\.)?reddit\.com/(?:r/\w+/comments|comments|gallery)/(\w+)")
         .case_insensitive(true)
         .build()
         .unwrap();
@@ -52,17 +100,57 @@ lazy_static::lazy_static! {
 
 /// the default max size for resolved images: 16 MB
 pub const DEFAULT_MAX_SIZE: u64 = 16_000_000;
+/// the maximum amount of replies followed up a reply chain when looking for an image
+pub const MAX_REPLY_DEPTH: usize = 5;
+
 
+/// a reference to a file attached to a message, kept to fetch a fresh url of the file as discord's attachment urls expire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AttachmentRef {
+    /// the channel of the message
+    pub channel_id: ChannelId,
+    /// the message the file is attached to
+    pub message_id: MessageId,
+    /// the attached file
+    pub attachment_id: AttachmentId,
+}
+
+impl AttachmentRef {
+    /// returns a reference to the provided file attached to the provided message
+    #[must_use]
+    pub fn new(message: &Message, attachment: &Attachment) -> Self {
+        Self {
+            channel_id: message.channel_id,
+            message_id: message.id,
+            attachment_id: attachment.id,
+        }
+    }
+
+    /// fetches the message over HTTP, returning the attached file with a fresh url
+    ///
+    /// # Errors
+    /// if the message could not be fetched, or the file is no longer attached to it
+    pub async fn refresh(&self, ctx: &Context) -> Result<Attachment, Error> {
+        self.channel_id.message(ctx, self.message_id)
+            .await?
+            .attachments
+            .into_iter()
+            .find(|attachment| attachment.id == self.attachment_id)
+            .ok_or(Error::FetchUrlError)
+    }
+}
 
 /// A struct for resolving a source image from command arguments or references
 /// In order it try's to resolve from:
 ///     - A guild member from the provided argument
 ///     - A discord user from the provided argument
 ///     - A valid discord custom emoji from the provided argument
+///     - A sticker from the provided id, or the name of one of the guild's stickers
 ///     - A valid default emoji from the provided argument
 ///     if all fails or no argument was provided:
 ///     - checks attached files -> stickers -> embeds
-///     - repeats the above for a referenced message if exists.
+///     - repeats the above for a referenced message if exists, following the reply chain up to [`MAX_REPLY_DEPTH`] messages
+///     - checks the channel's pinned messages, if enabled
 ///     - fallbacks to command author
 #[derive(Debug, Clone)]
 #[allow(clippy::module_name_repetitions)]
@@ -71,6 +159,10 @@ pub struct ImageResolver {
     pub max_size: u64,
     /// indicates whether or not the image was resolved from an argument
     pub arg_resolved: bool,
+    /// the client id used to access the imgur API, without which imgur pages are guessed to be gifs
+    pub imgur_client_id: Option<String>,
+    /// indicates whether or not to fallback to the images of the channel's pinned messages
+    pub resolve_pins: bool,
 }
 
 impl Default for ImageResolver {
@@ -87,10 +179,16 @@ impl ImageResolver {
         Self {
             max_size: DEFAULT_MAX_SIZE,
             arg_resolved: true,
+            imgur_client_id: None,
+            resolve_pins: false,
         }
     }
 
     /// a method to resolve a user inputted URL, with many checks
+    ///
+    /// the url is first probed to reject oversized images before downloading them,
+    /// and the first chunk of the content is sniffed to determine whether or not it is actually an image,
+    /// as the reported `Content-Type` is not always reliable
     pub async fn resolve_url<T>(&self, client: Option<&reqwest::Client>, arg: T) -> Result<Vec<u8>, Error>
     where
         T: AsRef<str> + Send
@@ -101,86 +199,312 @@ impl ImageResolver {
             .trim_end_matches('>')
             .trim();
 
-        let response = if let Some(client) = client {
-            client.get(arg)
-                .send()
-                .await
-        } else {
-            reqwest::get(arg)
-                .await
+        if let Some(uri) = arg.strip_prefix("data:") {
+            return self.decode_data_uri(uri);
         }
-        .map_err(|_| Error::FetchUrlError)?;
 
-        if response.status().is_success() {
-            if response.headers()
-                .get("Content-Type")
-                .map_or("unknown", |v| v.to_str().unwrap_or("unknown"))
-                .starts_with("image/")
-            {
-                let content_length = response.content_length()
-                    .unwrap_or(0);
+        // twitter's pages are rendered client-side, so there is nothing to be found by fetching them directly
+        if let Some(captures) = TWITTER_STATUS_REGEX.captures(arg) {
+            let status_id = captures.get(1)
+                .ok_or(Error::InvalidContentType)?
+                .as_str();
 
-                let bytes = response.bytes()
-                    .await?;
+            return self.resolve_tweet(client, status_id)
+                .await;
+        }
 
-                let size = content_length.max(bytes.len() as u64);
-                if size >= self.max_size {
-                    Err(Error::ImageTooLarge(size, self.max_size))
-                } else {
-                    Ok(bytes.to_vec())
-                }
-            } else if TENOR_PAGE_REGEX.is_match(arg) {
-                let asset = TENOR_ASSET_URL.find(response.text().await?.as_str())
-                    .map(|mat| mat.as_str().to_string())
-                    .ok_or(Error::InvalidContentType)?;
+        if let Some(probe) = probe_url(client, arg).await {
+            let is_image = probe.content_type
+                .as_deref()
+                .map_or(true, |content_type| content_type.starts_with("image/"));
 
-                url_to_bytes(client, asset)
-                    .await
-            } else if let Some(captures) =
-                IMGUR_PAGE_REGEX.captures(arg)
+            if let Some(size) = probe.content_length
+                .filter(|size| is_image && *size >= self.max_size)
             {
-                let imgur_id = captures.get(2)
-                    .ok_or(Error::InvalidContentType)?
-                    .as_str();
+                return Err(Error::ImageTooLarge(size, self.max_size));
+            }
+        }
+
+        let mut response = policy::get(client, arg)
+            .await
+            .map_err(|err| match err {
+                err @ Error::BlockedUrl(_) => err,
+                _ => Error::FetchUrlError,
+            })?;
+
+        if !response.status().is_success() {
+            return Err(Error::FetchUrlError);
+        }
+
+        let labeled_image = response.headers()
+            .get("Content-Type")
+            .map_or("unknown", |v| v.to_str().unwrap_or("unknown"))
+            .starts_with("image/");
+
+        let first_chunk = response.chunk()
+            .await?
+            .map(|chunk| chunk.to_vec())
+            .unwrap_or_default();
+
+        if sniff_image(&first_chunk).is_some() {
+            read_remaining(response, first_chunk, self.max_size)
+                .await
+        } else if labeled_image {
+            Err(Error::InvalidContentType)
+        } else if let Some(captures) =
+            REDDIT_POST_REGEX.captures(response.url().as_str())
+        {
+            let post_id = captures.get(1)
+                .ok_or(Error::InvalidContentType)?
+                .as_str();
+
+            self.resolve_reddit_post(client, post_id)
+                .await
+        } else if TENOR_PAGE_REGEX.is_match(arg) {
+            let page = read_remaining(response, first_chunk, self.max_size)
+                .await?;
 
-                url_to_bytes(client, format!("https://i.imgur.com/{imgur_id}.gif"))
+            let asset = TENOR_ASSET_URL.find(String::from_utf8_lossy(&page).as_ref())
+                .map(|mat| mat.as_str().to_string())
+                .ok_or(Error::InvalidContentType)?;
+
+            url_to_bytes_limited(client, asset, self.max_size)
+                .await
+        } else if let Some(captures) =
+            IMGUR_PAGE_REGEX.captures(arg)
+        {
+            let imgur_id = captures.get(2)
+                .ok_or(Error::InvalidContentType)?
+                .as_str();
+
+            if let Some(client_id) = &self.imgur_client_id {
+                let kind = captures.get(1)
+                    .map_or("image", |mat|
+                        if mat.as_str() == "a" { "album" } else { "gallery" }
+                    );
+
+                self.resolve_imgur(client, client_id, kind, imgur_id)
                     .await
             } else {
-                Err(Error::InvalidContentType)
+                url_to_bytes_limited(client, format!("https://i.imgur.com/{imgur_id}.gif"), self.max_size)
+                    .await
             }
         } else {
-            Err(Error::FetchUrlError)
+            Err(Error::InvalidContentType)
         }
     }
 
-    /// called by [`Self::get_attachments`], tries to resolve an image from message files
-    async fn get_file_image(&self, attachments: &Vec<Attachment>) -> Result<Option<Vec<u8>>, Error> {
-        for file in attachments {
-            if file.content_type
-                .clone()
-                .unwrap_or_else(|| "unknown".to_string())
-                .starts_with("image/")
-            {
-                if file.size < self.max_size {
-                    let bytes = file.download().await?;
+    /// resolves the first image of a reddit post through its `.json` endpoint,
+    /// supporting galleries, direct image links and the preview images of videos and link posts
+    async fn resolve_reddit_post(&self, client: Option<&reqwest::Client>, post_id: &str) -> Result<Vec<u8>, Error> {
+        let response = policy::get(client, format!("https://www.reddit.com/comments/{post_id}.json").as_str())
+            .await?;
 
-                    let size = bytes.len() as u64;
-                    if size < self.max_size {
-                        return Ok(Some(bytes));
-                    }
-                    return Err(
-                        Error::ImageTooLarge(size, self.max_size)
-                    );
+        if !response.status().is_success() {
+            return Err(Error::FetchUrlError);
+        }
+
+        let listing: Value = serde_json::from_slice(
+            &read_limited(response, self.max_size).await?
+        )
+            .map_err(|_| Error::FetchUrlError)?;
+        let post = &listing[0]["data"]["children"][0]["data"];
+
+        let gallery_item = post["gallery_data"]["items"][0]["media_id"]
+            .as_str()
+            .map(|media_id| &post["media_metadata"][media_id]["s"]);
+
+        // urls in reddit's api responses are html escaped
+        let url = [
+            gallery_item.and_then(|item| item["gif"].as_str()),
+            gallery_item.and_then(|item| item["u"].as_str()),
+            post["url_overridden_by_dest"]
+                .as_str()
+                .filter(|url| url.starts_with("https://i.redd.it/")),
+            post["preview"]["images"][0]["variants"]["gif"]["source"]["url"].as_str(),
+            post["preview"]["images"][0]["source"]["url"].as_str(),
+        ]
+            .into_iter()
+            .flatten()
+            .next()
+            .ok_or(Error::InvalidContentType)?
+            .replace("&amp;", "&");
+
+        url_to_bytes_limited(client, url, self.max_size)
+            .await
+    }
+
+    /// resolves an imgur image through the imgur API, taking the first image of albums and galleries
+    ///
+    /// `kind` is the endpoint of the API to query: `image`, `album` or `gallery`
+    async fn resolve_imgur(
+        &self,
+        client: Option<&reqwest::Client>,
+        client_id: &str,
+        kind: &str,
+        imgur_id: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(format!("Client-ID {client_id}").as_str())
+                .map_err(|_| Error::FetchUrlError)?,
+        );
+
+        let response = policy::send(
+            client,
+            Method::GET,
+            format!("https://api.imgur.com/3/{kind}/{imgur_id}").as_str(),
+            headers,
+        )
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::FetchUrlError);
+        }
+
+        let body: Value = serde_json::from_slice(
+            &read_limited(response, self.max_size).await?
+        )
+            .map_err(|_| Error::FetchUrlError)?;
+
+        // gallery posts are either a single image or an album, with the images of the latter nested under `images`
+        let data = &body["data"];
+        let image = if data["images"].is_array() {
+            &data["images"][0]
+        } else {
+            data
+        };
+
+        // videos can not be decoded, but imgur serves a gif of them as long as they are short enough
+        let url = image["link"]
+            .as_str()
+            .ok_or(Error::InvalidContentType)?
+            .replace(".mp4", ".gif");
+
+        url_to_bytes_limited(client, url, self.max_size)
+            .await
+    }
+
+    /// resolves the first image of a tweet through the fxtwitter API,
+    /// falling back to the thumbnail of the first video or gif
+    async fn resolve_tweet(&self, client: Option<&reqwest::Client>, status_id: &str) -> Result<Vec<u8>, Error> {
+        let response = policy::get(client, format!("https://api.fxtwitter.com/status/{status_id}").as_str())
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(Error::FetchUrlError);
+        }
+
+        let body: Value = serde_json::from_slice(
+            &read_limited(response, self.max_size).await?
+        )
+            .map_err(|_| Error::FetchUrlError)?;
+        let media = &body["tweet"]["media"];
+
+        let url = media["photos"][0]["url"]
+            .as_str()
+            .or_else(|| media["videos"][0]["thumbnail_url"].as_str())
+            .ok_or(Error::InvalidContentType)?;
+
+        url_to_bytes_limited(client, url, self.max_size)
+            .await
+    }
+
+    /// decodes the content of a base64 `data:image/...` URI, with the `data:` scheme already stripped
+    fn decode_data_uri(&self, uri: &str) -> Result<Vec<u8>, Error> {
+        let (mime, payload) = uri.split_once(',')
+            .ok_or(Error::InvalidDataUri)?;
+        let mime = mime.strip_suffix(";base64")
+            .ok_or(Error::InvalidDataUri)?;
+
+        if !mime.starts_with("image/") {
+            return Err(Error::InvalidContentType);
+        }
+
+        // checks the length of the encoded payload first, which is 4/3 the size of the decoded content
+        let estimated_size = payload.len() as u64 / 4 * 3;
+        if estimated_size >= self.max_size {
+            return Err(Error::ImageTooLarge(estimated_size, self.max_size));
+        }
+
+        let bytes = STANDARD.decode(payload.trim())
+            .map_err(|_| Error::InvalidDataUri)?;
+
+        if sniff_image(&bytes).is_some() {
+            Ok(bytes)
+        } else {
+            Err(Error::InvalidContentType)
+        }
+    }
+
+    /// downloads a single attached file if it is an image, checking it against [`Self::max_size`]
+    async fn download_file(&self, file: &Attachment) -> Result<Option<Vec<u8>>, Error> {
+        if file.content_type
+            .clone()
+            .unwrap_or_else(|| "unknown".to_string())
+            .starts_with("image/")
+        {
+            if file.size < self.max_size {
+                let bytes = file.download().await?;
+
+                let size = bytes.len() as u64;
+                if size < self.max_size {
+                    return Ok(Some(bytes));
                 }
                 return Err(
-                    Error::ImageTooLarge(file.size, self.max_size)
+                    Error::ImageTooLarge(size, self.max_size)
                 );
             }
+            return Err(
+                Error::ImageTooLarge(file.size, self.max_size)
+            );
         }
 
         Ok(None)
     }
 
+    /// same as [`Self::download_file`] for a file attached to the provided message,
+    /// but if the download fails, such as from its url having expired since the message was cached,
+    /// the message is fetched again and the file downloaded from its fresh url
+    async fn download_message_file(&self, ctx: &Context, message: &Message, file: &Attachment) -> Result<Option<Vec<u8>>, Error> {
+        match self.download_file(file).await {
+            Err(Error::SerenityError(_) | Error::RequestError(_)) => {
+                let fresh = AttachmentRef::new(message, file)
+                    .refresh(ctx)
+                    .await?;
+
+                self.download_file(&fresh)
+                    .await
+            },
+            other => other,
+        }
+    }
+
+    /// called by [`Self::get_attachments`], tries to resolve an image from message files
+    async fn get_file_image(&self, ctx: &Context, message: &Message) -> Result<Option<Vec<u8>>, Error> {
+        for file in &message.attachments {
+            if let Some(bytes) = self.download_message_file(ctx, message, file).await? {
+                return Ok(Some(bytes));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// similar to [`Self::get_file_image`], but resolves every image file that was attached instead of just the first
+    async fn get_file_images(&self, ctx: &Context, message: &Message) -> Result<Vec<Vec<u8>>, Error> {
+        let mut images = Vec::new();
+
+        for file in &message.attachments {
+            if let Some(bytes) = self.download_message_file(ctx, message, file).await? {
+                images.push(bytes);
+            }
+        }
+
+        Ok(images)
+    }
+
     /// called by [`Self::get_attachments`], tries to resolve an image from message stickers
     async fn get_sticker_image(
         &self,
@@ -217,12 +541,13 @@ impl ImageResolver {
     async fn get_attachments(
         &self,
         client: Option<&reqwest::Client>,
+        ctx: &Context,
         message: &Message,
     ) -> Result<Option<Vec<u8>>, Error> {
         let mut source: Option<Vec<u8>> = None;
 
         if !message.attachments.is_empty() {
-            source = self.get_file_image(&message.attachments).await?;
+            source = self.get_file_image(ctx, message).await?;
         }
 
         if source.is_none() && !message.sticker_items.is_empty() {
@@ -238,7 +563,7 @@ impl ImageResolver {
 
     /// fetches the member's face but fallbacks to `png` format instead of `webp`
     #[must_use]
-    fn member_avatar_url(member: &Member) -> String {
+    pub fn member_avatar_url(member: &Member) -> String {
         let is_gif = member.avatar.as_ref()
             .or(member.user.avatar.as_ref())
             .map_or(false, |av| av.starts_with("a_"));
@@ -257,15 +582,42 @@ impl ImageResolver {
             .replace(".webp", if is_gif { ".gif" } else { ".png" })
     }
 
-    /// a method to fetch the emoji image from a `<:name:id>` formatted emoji or simply an `id`
+    /// fetches the avatar of the command author, the final fallback when no other image could be resolved
+    async fn author_avatar(
+        client: Option<&reqwest::Client>,
+        ctx: &Context,
+        guild_id: Option<GuildId>,
+        author: &User,
+    ) -> Result<Vec<u8>, Error> {
+        // in direct messages, or if the member could not be fetched, we fallback to the global avatar
+        let member = if let Some(guild) = guild_id {
+            guild.member(ctx, author.id)
+                .await
+                .ok()
+        } else {
+            None
+        };
+
+        let avatar = member.as_ref()
+            .map_or_else(
+                || Self::user_avatar_url(author),
+                Self::member_avatar_url,
+            );
+
+        url_to_bytes(client, avatar)
+            .await
+    }
+
+    /// parses the CDN url of a custom emoji from a `<:name:id>` formatted emoji or simply an `id`
+    #[must_use]
     #[allow(clippy::option_if_let_else)]
-    pub async fn convert_emoji(client: Option<&reqwest::Client>, argument: &str) -> Result<Vec<u8>, Error> {
+    pub fn emoji_url(argument: &str) -> Option<String> {
         let (animated, id) =
             if let Some(captures) = EMOJI_REGEX.captures(argument)
         {
             (
                 captures.get(1)
-                    .is_some(),
+                    .map_or(false, |mat| !mat.as_str().is_empty()),
                 captures.get(3)
                     .map(|id| id.as_str().to_string()),
             )
@@ -275,17 +627,170 @@ impl ImageResolver {
             (false, None)
         };
 
-        let id = id.ok_or_else(||
-            Error::EmojiParseError(argument.to_string())
-        )?;
-
         let fmt = if animated { "gif" } else { "png" };
-        let url = format!("https://cdn.discordapp.com/emojis/{id}.{fmt}");
+        id.map(|id| format!("https://cdn.discordapp.com/emojis/{id}.{fmt}"))
+    }
+
+    /// returns the url of the twemoji image for a default (unicode) emoji
+    #[must_use]
+    pub fn unicode_emoji_url(argument: &str) -> String {
+        format!("https://emojicdn.elk.sh/{argument}?style=twitter")
+    }
+
+    /// a method to fetch the emoji image from a `<:name:id>` formatted emoji or simply an `id`
+    pub async fn convert_emoji(client: Option<&reqwest::Client>, argument: &str) -> Result<Vec<u8>, Error> {
+        let url = Self::emoji_url(argument)
+            .ok_or_else(|| Error::EmojiParseError(argument.to_string()))?;
+
+        url_to_bytes(client, url)
+            .await
+    }
+
+    /// same as [`Self::resolve_url`], but if the url of a file attached to a discord message could not be fetched
+    /// as it has expired, it is exchanged for a fresh url through discord's API and fetched again
+    async fn resolve_attachment_url(&self, client: Option<&reqwest::Client>, ctx: &Context, arg: &str) -> Result<Vec<u8>, Error> {
+        match self.resolve_url(client, arg).await {
+            Err(Error::FetchUrlError) if ATTACHMENT_URL_REGEX.is_match(arg.trim_start_matches('<').trim_end_matches('>')) => {
+                let url = Self::refresh_attachment_url(client, ctx, arg)
+                    .await?;
+
+                self.resolve_url(client, url)
+                    .await
+            },
+            other => other,
+        }
+    }
+
+    /// exchanges the url of a file attached to a discord message, which may have expired, for a fresh one
+    ///
+    /// # Errors
+    /// if discord refused to refresh the url, such as if the message has since been deleted
+    pub async fn refresh_attachment_url(client: Option<&reqwest::Client>, ctx: &Context, url: &str) -> Result<String, Error> {
+        let url = url.trim_start_matches('<')
+            .trim_end_matches('>');
+        let client = client.cloned()
+            .unwrap_or_else(policy::build_client);
+
+        let response = client.post(REFRESH_URLS_ENDPOINT)
+            .header(AUTHORIZATION, ctx.http.token.as_str())
+            .json(&serde_json::json!({ "attachment_urls": [url] }))
+            .send()
+            .await?
+            .error_for_status()
+            .map_err(|_| Error::FetchUrlError)?
+            .json::<Value>()
+            .await?;
+
+        response["refreshed_urls"][0]["refreshed"]
+            .as_str()
+            .map(ToString::to_string)
+            .ok_or(Error::FetchUrlError)
+    }
+
+    /// resolves the image url of a sticker from its id or, within the guild, its name
+    ///
+    /// the stickers of the guild are looked up in the cache, or fetched over HTTP if it is not cached,
+    /// while any other sticker can only be found by its id
+    pub async fn sticker_url(ctx: &Context, guild: Option<GuildId>, argument: &str) -> Option<String> {
+        let id = ID_REGEX.is_match(argument)
+            .then(|| argument.parse::<u64>().ok())
+            .flatten();
+        let matches = |sticker: &Sticker| id.map_or_else(
+            || sticker.name.eq_ignore_ascii_case(argument),
+            |id| sticker.id.0 == id,
+        );
+
+        if let Some(guild_id) = guild {
+            let sticker = match ctx.cache.guild_field(guild_id, |guild| guild.stickers.values().find(|sticker| matches(sticker)).cloned()) {
+                Some(sticker) => sticker,
+                None => guild_id.stickers(&ctx.http)
+                    .await
+                    .ok()
+                    .and_then(|stickers| stickers.into_iter().find(|sticker| matches(sticker))),
+            };
+
+            // lottie stickers have no image to resolve
+            if let Some(url) = sticker.and_then(|sticker| sticker.image_url()) {
+                return Some(url);
+            }
+        }
+
+        ctx.http.get_sticker(id?)
+            .await
+            .ok()?
+            .image_url()
+    }
+
+    /// resolves strictly an emoji from the argument, trying in order:
+    /// a guild emoji by name, a formatted custom emoji or id, and finally a default emoji
+    pub async fn resolve_emoji(
+        &self,
+        ctx: &Context,
+        message: &Message,
+        argument: &str,
+    ) -> Result<Vec<u8>, Error> {
+        let client_data = ctx.data.read()
+            .await;
+
+        let client = client_data
+            .get::<ClientData>();
+
+        let url = if let Ok(emoji) =
+            Emoji::convert(ctx, message.guild_id, Some(message.channel_id), argument)
+                .await
+        {
+            emoji.url()
+        } else if let Some(url) = Self::emoji_url(argument) {
+            url
+        } else {
+            return url_to_bytes(client, Self::unicode_emoji_url(argument))
+                .await
+                .map_err(|_| Error::EmojiParseError(argument.to_string()));
+        };
 
         url_to_bytes(client, url)
             .await
     }
 
+    /// parses an ISO 3166-1 alpha-2 country code, such as `ca`, or a flag emoji such as 🇨🇦, into a lowercase country code
+    #[must_use]
+    pub fn country_code(argument: &str) -> Option<String> {
+        let argument = argument.trim();
+
+        // flag emojis are made of a pair of regional indicator symbols, each standing for a letter
+        let letters = if argument.chars().all(|c| ('🇦'..='🇿').contains(&c)) {
+            argument.chars()
+                .filter_map(|c| char::from_u32(u32::from(c) - u32::from('🇦') + u32::from('a')))
+                .collect::<String>()
+        } else {
+            argument.to_lowercase()
+        };
+
+        (letters.len() == 2 && letters.chars().all(|c| c.is_ascii_lowercase()))
+            .then_some(letters)
+    }
+
+    /// resolves the flag of the country of the provided code or flag emoji,
+    /// from the bundled flags in [`FLAGS_DIR`] if there is one, otherwise from [`FLAG_CDN_URL`]
+    pub async fn resolve_flag(&self, ctx: &Context, argument: &str) -> Result<Vec<u8>, Error> {
+        let code = Self::country_code(argument)
+            .ok_or_else(|| Error::InvalidCountryCode(argument.to_string()))?;
+
+        if let Some(bytes) = ASSETS.get(format!("{FLAGS_DIR}/{code}.png")) {
+            return Ok(bytes.to_vec());
+        }
+
+        let client_data = ctx.data.read()
+            .await;
+
+        let client = client_data
+            .get::<ClientData>();
+
+        url_to_bytes(client, FLAG_CDN_URL.replace("{}", &code))
+            .await
+            .map_err(|_| Error::InvalidCountryCode(argument.to_string()))
+    }
+
     /// run's conversions on the argument and referenced message's content
     pub async fn try_conversions(
         &self,
@@ -295,6 +800,12 @@ impl ImageResolver {
         channel: Option<ChannelId>,
         arg: &str,
     ) -> Result<Option<Vec<u8>>, Error> {
+        if arg.starts_with("data:") {
+            return self.resolve_url(client, arg)
+                .await
+                .map(Some);
+        }
+
         Ok(if let Ok(out) =
             Member::convert(ctx, guild, channel, arg)
                 .await
@@ -318,13 +829,19 @@ impl ImageResolver {
                 .await
         {
             Some(out)
+        } else if let Some(url) =
+            Self::sticker_url(ctx, guild, arg)
+                .await
+        {
+            Some(url_to_bytes(client, url)
+                .await?)
         } else if let Ok(out) =
-            url_to_bytes(client, format!("https://emojicdn.elk.sh/{arg}?style=twitter"))
+            url_to_bytes(client, Self::unicode_emoji_url(arg))
                 .await
         {
             Some(out)
         } else if let Ok(out) =
-            match self.resolve_url(client, arg)
+            match self.resolve_attachment_url(client, ctx, arg)
                 .await
             {
                 Err(err @ Error::ImageTooLarge(..)) => return Err(err),
@@ -337,8 +854,65 @@ impl ImageResolver {
         })
     }
 
+    /// raises [`Self::max_size`] to the limit of the invoking user's tier
+    /// and picks up the imgur client id and pinned messages fallback from the configuration
+    async fn configure(&mut self, ctx: &Context, user: &User) {
+        self.max_size = self.max_size.max(
+            Limits::lookup(ctx, user)
+                .await
+                .max_size
+        );
+
+        if let Some(config) = ctx.data.read()
+            .await
+            .get::<ConfigData>()
+        {
+            if self.imgur_client_id.is_none() {
+                self.imgur_client_id = config.imgur_client_id.clone();
+            }
+            self.resolve_pins |= config.resolve_pins;
+        }
+    }
+
+    /// fetches the message the provided message replied to, if any,
+    /// from the cache or over HTTP as discord only includes a single level of referenced messages
+    async fn fetch_referenced(ctx: &Context, message: &Message) -> Option<Message> {
+        if let Some(referenced) = &message.referenced_message {
+            return Some(referenced.as_ref().clone());
+        }
+
+        let reference = message.message_reference.as_ref()?;
+
+        reference.channel_id
+            .message(ctx, reference.message_id?)
+            .await
+            .ok()
+    }
+
+    /// tries to resolve the attachments of the channel's pinned messages, starting from the most recent
+    async fn get_pinned_image(
+        &self,
+        client: Option<&reqwest::Client>,
+        ctx: &Context,
+        channel_id: ChannelId,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        for pinned in channel_id.pins(ctx).await? {
+            if let Some(bytes) =
+                self.get_attachments(client, ctx, &pinned)
+                .await?
+            {
+                return Ok(Some(bytes));
+            }
+        }
+
+        Ok(None)
+    }
+
     /// the primary method to call to resolve an image from the provided `context`, `message` and `args`
     pub async fn resolve(&mut self, ctx: &Context, message: &Message, arg: Option<String>) -> Result<Vec<u8>, Error> {
+        self.configure(ctx, &message.author)
+            .await;
+
         let client_data = ctx.data.read()
             .await;
 
@@ -364,31 +938,39 @@ impl ImageResolver {
         }
 
         if let Some(bytes) =
-            self.get_attachments(client, message)
+            self.get_attachments(client, ctx, message)
             .await?
         {
             return Ok(bytes);
         }
 
-        if let Some(referenced) = &message.referenced_message {
+        let mut referenced = message.referenced_message
+            .as_deref()
+            .cloned();
+
+        for _ in 0..MAX_REPLY_DEPTH {
+            let Some(current) = referenced else {
+                break;
+            };
+
             if let Some(bytes) =
-                self.get_attachments(client, referenced)
+                self.get_attachments(client, ctx, &current)
                 .await?
             {
                 return Ok(bytes);
             }
 
-            if !referenced.content.is_empty() {
+            if !current.content.is_empty() {
                 let content = WS_REGEX
-                    .split(referenced.content.as_str())
+                    .split(current.content.as_str())
                     .next();
 
                 if let Some(content) = content {
                     if let Some(bytes) = self.try_conversions(
                             client,
                             ctx,
-                            referenced.guild_id,
-                            Some(referenced.channel_id),
+                            current.guild_id,
+                            Some(current.channel_id),
                             content,
                         )
                         .await
@@ -398,20 +980,219 @@ impl ImageResolver {
                     }
                 }
             }
+
+            referenced = Self::fetch_referenced(ctx, &current)
+                .await;
+        }
+
+        if self.resolve_pins {
+            if let Some(bytes) =
+                self.get_pinned_image(client, ctx, message.channel_id)
+                .await?
+            {
+                return Ok(bytes);
+            }
+        }
+
+        Self::author_avatar(client, ctx, message.guild_id, &message.author)
+            .await
+    }
+
+    /// resolves several source images at once, such as for commands that combine multiple images
+    ///
+    /// each argument is converted in order, with any missing images then taken from the files attached to the message,
+    /// and then from those of the referenced message; if there are still not enough,
+    /// the command author's avatar is inserted as the first image
+    pub async fn resolve_many(
+        &mut self,
+        ctx: &Context,
+        message: &Message,
+        args: Vec<String>,
+        count: usize,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        self.configure(ctx, &message.author)
+            .await;
+
+        let client_data = ctx.data.read()
+            .await;
+
+        let client = client_data
+            .get::<ClientData>();
+
+        let mut images = Vec::with_capacity(count);
+
+        for arg in args {
+            if images.len() >= count {
+                break;
+            }
+
+            if let Some(bytes) = self.try_conversions(
+                    client,
+                    ctx,
+                    message.guild_id,
+                    Some(message.channel_id),
+                    arg.as_str(),
+                )
+                .await?
+            {
+                images.push(bytes);
+            } else {
+                self.arg_resolved = false;
+            }
         }
 
-        let avatar = if let Some(guild) = message.guild_id {
-            Self::member_avatar_url(
-                &guild.member(ctx, message.author.id)
+        if images.len() < count {
+            images.extend(
+                self.get_file_images(ctx, message)
                     .await?
-            )
-        } else {
-            Self::user_avatar_url(&message.author)
-        };
+            );
+        }
+
+        if images.len() < count {
+            if let Some(referenced) = &message.referenced_message {
+                images.extend(
+                    self.get_file_images(ctx, referenced)
+                        .await?
+                );
+            }
+        }
+
+        while images.len() < count {
+            images.insert(0,
+                Self::author_avatar(client, ctx, message.guild_id, &message.author)
+                    .await?
+            );
+        }
+
+        images.truncate(count);
+        Ok(images)
+    }
 
-        let fallback = url_to_bytes(client, avatar)
+    /// resolves every image file attached to the message, or to the referenced message if there are none,
+    /// falling back to [`Self::resolve`] without an argument if neither has any, used by the `--all` batch mode
+    pub async fn resolve_all(&mut self, ctx: &Context, message: &Message) -> Result<Vec<Vec<u8>>, Error> {
+        self.configure(ctx, &message.author)
+            .await;
+        self.arg_resolved = false;
+
+        let mut images = self.get_file_images(ctx, message)
             .await?;
 
-        Ok(fallback)
+        if images.is_empty() {
+            if let Some(referenced) = &message.referenced_message {
+                images = self.get_file_images(ctx, referenced)
+                    .await?;
+            }
+        }
+
+        if images.is_empty() {
+            images.push(
+                self.resolve(ctx, message, None)
+                    .await?
+            );
+        }
+
+        Ok(images)
+    }
+
+    /// a shortcut that resolves every attached image with [`Self::resolve_all`] if `all` is true,
+    /// or a single image from the argument with [`Self::resolve`] otherwise
+    pub async fn resolve_batch(
+        &mut self,
+        ctx: &Context,
+        message: &Message,
+        arg: Option<String>,
+        all: bool,
+    ) -> Result<Vec<Vec<u8>>, Error> {
+        if all {
+            self.resolve_all(ctx, message)
+                .await
+        } else {
+            Ok(vec![
+                self.resolve(ctx, message, arg)
+                    .await?
+            ])
+        }
+    }
+
+    /// a shortcut for [`Self::resolve_many`] to resolve exactly two source images
+    pub async fn resolve_pair(
+        &mut self,
+        ctx: &Context,
+        message: &Message,
+        first: Option<String>,
+        second: Option<String>,
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let mut images = self.resolve_many(
+            ctx,
+            message,
+            [first, second].into_iter()
+                .flatten()
+                .collect(),
+            2,
+        )
+            .await?
+            .into_iter();
+
+        match (images.next(), images.next()) {
+            (Some(first), Some(second)) => Ok((first, second)),
+            _ => unreachable!("`resolve_many` always returns the requested amount of images"),
+        }
+    }
+
+    /// similar to [`Self::resolve`], but resolves an image from the data of a slash or context menu command,
+    /// used when the bot runs without access to message content
+    ///
+    /// the image is taken from the `image` attachment option, then the `source` option,
+    /// then the target message of a context menu command and finally the avatar of the invoking user
+    pub async fn resolve_interaction(
+        &mut self,
+        ctx: &Context,
+        interaction: &ApplicationCommandInteraction,
+    ) -> Result<Vec<u8>, Error> {
+        self.configure(ctx, &interaction.user)
+            .await;
+
+        let client_data = ctx.data.read()
+            .await;
+
+        let client = client_data
+            .get::<ClientData>();
+
+        for option in &interaction.data.options {
+            match (option.name.as_str(), &option.resolved) {
+                ("image", Some(CommandDataOptionValue::Attachment(attachment))) => {
+                    if let Some(bytes) = self.download_file(attachment).await? {
+                        return Ok(bytes);
+                    }
+                },
+                ("source", Some(CommandDataOptionValue::String(source))) => {
+                    if let Some(bytes) = self.try_conversions(
+                            client,
+                            ctx,
+                            interaction.guild_id,
+                            Some(interaction.channel_id),
+                            source.as_str(),
+                        )
+                        .await?
+                    {
+                        return Ok(bytes);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        if let Some(ResolvedTarget::Message(target)) = interaction.data.target() {
+            if let Some(bytes) =
+                self.get_attachments(client, ctx, &target)
+                .await?
+            {
+                return Ok(bytes);
+            }
+        }
+
+        Self::author_avatar(client, ctx, interaction.guild_id, &interaction.user)
+            .await
     }
 }
\ No newline at end of file
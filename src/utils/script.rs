@@ -0,0 +1,621 @@
+//! contains the scripting language of custom effects, which chains operations as `hue(45) | blur(2) | caption('hi')`
+//!
+//! scripts can only call the operations known to [`Op`], each taking at most one argument clamped to its range,
+//! and are rejected before running if their estimated [`Script::weight`] is above [`MAX_WEIGHT`],
+//! so that no script can do more work than it was admitted for
+//!
+//! the `pipe` command is run as a script of effects as well, so that its chains are bound by the same weight
+
+use std::{
+    fmt,
+    iter::Peekable,
+    str::{CharIndices, FromStr},
+};
+
+use ril::prelude::*;
+
+use super::{
+    filters,
+    functions::caption_func,
+    imaging::{Frames, ImageArguments},
+    jobs::checked,
+    options::{Align, CaptionOptions, CaptionPosition},
+    pipeline::{Pipeline, PipelineContext, Stage, FACES, MAX_STAGES},
+};
+
+
+/// the longest script accepted, in characters
+pub const MAX_SCRIPT_LENGTH: usize = 500;
+/// the highest estimated weight of a script that may be run, see [`Script::weight`]
+pub const MAX_WEIGHT: u64 = 64;
+/// the most custom effects a single user may save
+pub const MAX_SAVED: usize = 25;
+/// the longest name a custom effect may be saved under
+const MAX_NAME_LENGTH: usize = 32;
+/// the longest text that may be passed to `caption`, in characters
+const MAX_TEXT_LENGTH: usize = 200;
+/// the number of frames `huerotate` always produces, however many frames it is given, see [`super::functions::huerotate_func`]
+const HUEROTATE_FRAMES: u64 = 36;
+
+/// the operations a script may call besides the effects of [`Stage::EFFECTS`]
+pub const OPERATIONS: &[&str] = &["hue", "blur", "brightness", "grayscale", "rotate", "caption"];
+/// the words used by the `custom` command that effects can not be saved under
+const RESERVED_NAMES: &[&str] = &["save", "delete", "remove", "list"];
+
+/// an argument passed to an operation
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+/// a call to an operation as written in a script, before it has been checked
+#[derive(Debug, Clone)]
+struct Call {
+    /// the name of the operation called
+    name: String,
+    /// the arguments passed to the operation
+    arguments: Vec<Value>,
+    /// the position of the call within the script, in characters
+    position: usize,
+}
+
+/// a hand-written recursive descent parser of scripts, following the grammar
+///
+/// ```text
+/// script := call ('|' call)*
+/// call   := identifier ['(' [value (',' value)*] ')']
+/// value  := number | '\'' text '\'' | '"' text '"'
+/// ```
+struct Parser<'a> {
+    /// the script being parsed
+    source: &'a str,
+    /// the characters of the script left to parse, along with their byte offsets
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { source, chars: source.char_indices().peekable() }
+    }
+
+    /// the position of the next character, in characters from the start of the script
+    fn position(&mut self) -> usize {
+        let offset = self.chars
+            .peek()
+            .map_or(self.source.len(), |(offset, _)| *offset);
+
+        self.source[..offset].chars().count()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    /// consumes the next character if it is `expected`, returning whether or not it was
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            return Ok(());
+        }
+
+        let position = self.position();
+        Err(match self.chars.peek() {
+            Some((_, found)) => format!("Expected `{expected}` at position {position}, found `{found}`"),
+            None => format!("Expected `{expected}` at position {position}, found the end of the script"),
+        })
+    }
+
+    fn identifier(&mut self) -> Result<String, String> {
+        self.skip_whitespace();
+        let position = self.position();
+
+        let mut identifier = String::new();
+        while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_alphanumeric() || *c == '_') {
+            identifier.push(c);
+        }
+
+        if identifier.is_empty() {
+            return Err(format!("Expected the name of an operation at position {position}"));
+        }
+
+        Ok(identifier)
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let position = self.position();
+
+        match self.chars.peek().map(|(_, c)| *c) {
+            Some(quote @ ('\'' | '"')) => {
+                self.chars.next();
+
+                let mut text = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some((_, '\\')) => match self.chars.next() {
+                            Some((_, c)) => text.push(c),
+                            None => break,
+                        },
+                        Some((_, c)) if c == quote => return Ok(Value::Text(text)),
+                        Some((_, c)) => text.push(c),
+                        None => break,
+                    }
+                }
+
+                Err(format!("The text starting at position {position} is never closed with `{quote}`"))
+            },
+            Some(c) if c.is_ascii_digit() || matches!(c, '-' | '+' | '.') => {
+                let mut number = String::new();
+                while let Some((_, c)) = self.chars.next_if(|(_, c)| c.is_ascii_digit() || matches!(c, '-' | '+' | '.')) {
+                    number.push(c);
+                }
+
+                number.parse::<f64>()
+                    .ok()
+                    .filter(|number| number.is_finite())
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("`{number}` at position {position} is not a valid number"))
+            },
+            _ => Err(format!("Expected a number or quoted text at position {position}")),
+        }
+    }
+
+    fn call(&mut self) -> Result<Call, String> {
+        self.skip_whitespace();
+        let position = self.position();
+        let name = self.identifier()?;
+
+        let mut arguments = Vec::new();
+        if self.eat('(') && !self.eat(')') {
+            loop {
+                arguments.push(self.value()?);
+
+                if !self.eat(',') {
+                    break;
+                }
+            }
+            self.expect(')')?;
+        }
+
+        Ok(Call { name, arguments, position })
+    }
+
+    fn script(&mut self) -> Result<Vec<Call>, String> {
+        let mut calls = vec![self.call()?];
+
+        while self.eat('|') {
+            calls.push(self.call()?);
+        }
+
+        self.skip_whitespace();
+        if let Some((_, c)) = self.chars.peek().copied() {
+            let position = self.position();
+            return Err(format!("Unexpected `{c}` at position {position}, operations are separated by `|`"));
+        }
+
+        Ok(calls)
+    }
+}
+
+/// a checked operation of a script, with its argument clamped to its range
+#[derive(Debug, Clone)]
+pub enum Op {
+    /// applies a chainable effect, see [`Stage`]
+    Effect(Stage),
+    /// shifts the hue of the frames by a number of degrees
+    Hue(i32),
+    /// blurs the frames by a radius in pixels
+    Blur(u32),
+    /// scales the brightness of the frames
+    Brightness(f64),
+    /// removes the colors of the frames
+    Grayscale,
+    /// rotates the frames clockwise by a number of degrees
+    Rotate(f64),
+    /// adds a meme caption above the frames
+    Caption(String),
+}
+
+impl Op {
+    /// checks a call to an operation, clamping its argument to its range
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn from_call(call: &Call) -> Result<Self, String> {
+        let Call { name, arguments, position } = call;
+        let name = name.to_lowercase();
+
+        let max_arguments = usize::from(!matches!(name.as_str(), "grayscale" | "greyscale"));
+        if arguments.len() > max_arguments {
+            return Err(format!("`{name}` at position {position} takes at most {max_arguments} argument(s), not {}", arguments.len()));
+        }
+
+        let number = |default: f64, min: f64, max: f64| match arguments.first() {
+            None => Ok(default),
+            Some(Value::Number(value)) => Ok(value.clamp(min, max)),
+            Some(Value::Text(_)) => Err(format!("`{name}` at position {position} takes a number, not text")),
+        };
+
+        Ok(match name.as_str() {
+            "hue" => Self::Hue(number(180.0, -360.0, 360.0)?.round() as i32),
+            "blur" => Self::Blur(number(4.0, 1.0, 32.0)?.round() as u32),
+            "brightness" => Self::Brightness(number(1.5, 0.0, 3.0)?),
+            "grayscale" | "greyscale" => Self::Grayscale,
+            "rotate" => Self::Rotate(number(90.0, -360.0, 360.0)?),
+            "caption" => match arguments.first() {
+                Some(Value::Text(text)) if text.chars().count() <= MAX_TEXT_LENGTH => Self::Caption(text.clone()),
+                Some(Value::Text(_)) => return Err(format!("The text of `caption` can be at most {MAX_TEXT_LENGTH} characters")),
+                _ => return Err(format!("`caption` at position {position} takes its text in quotes, such as `caption('hello')`")),
+            },
+            _ if Stage::EFFECTS.contains(&name.as_str()) => {
+                let value = arguments.first()
+                    .map(|_| number(0.0, f64::MIN, f64::MAX))
+                    .transpose()?;
+
+                Self::Effect(Stage::new(&name, value)?)
+            },
+            _ => return Err(format!(
+                "Unknown operation `{name}` at position {position}, the operations are: {}, {}",
+                OPERATIONS.join(", "),
+                Stage::EFFECTS.join(", "),
+            )),
+        })
+    }
+
+    /// the estimated cost of this operation on a single frame, relative to inverting its colors
+    #[must_use]
+    pub fn weight(&self) -> u64 {
+        match self {
+            Self::Effect(stage) => match stage.effect.name {
+                "anaglyph" | "tilt" => 2,
+                "eyes" | "glasses" | "clown" => 4,
+                "neon" => 6,
+                _ => 1,
+            },
+            Self::Hue(_) | Self::Brightness(_) | Self::Grayscale => 1,
+            Self::Rotate(_) | Self::Caption(_) => 2,
+            Self::Blur(_) => 3,
+        }
+    }
+
+    /// the number of frames this operation produces when given `frames` frames
+    #[must_use]
+    pub fn frames(&self, frames: u64) -> u64 {
+        match self {
+            Self::Effect(stage) if stage.effect.name == "huerotate" => HUEROTATE_FRAMES,
+            _ => frames,
+        }
+    }
+
+    /// the keys of the data in the [`PipelineContext`] that is still valid after this operation has been applied,
+    /// see [`Stage::keeps`]
+    #[must_use]
+    pub fn keeps(&self) -> &'static [&'static str] {
+        match self {
+            Self::Effect(stage) => stage.keeps(),
+            Self::Hue(_) | Self::Blur(_) | Self::Brightness(_) | Self::Grayscale => &[FACES],
+            Self::Rotate(_) | Self::Caption(_) => &[],
+        }
+    }
+
+    /// applies this operation to the provided frames, reusing the data in `context` that it needs
+    ///
+    /// # Errors
+    /// if the operation fails to apply
+    pub fn apply(&self, frames: Frames, seed: u64, context: &mut PipelineContext) -> ril::Result<Frames> {
+        Ok(match self {
+            Self::Effect(stage) => stage.apply(frames, seed, context)?,
+            Self::Hue(degrees) => map_frames(frames, |mut image| {
+                image.hue_rotate(*degrees);
+                image
+            }),
+            Self::Blur(radius) => map_frames(frames, |image| filters::soft_blur(image, *radius)),
+            Self::Brightness(factor) => map_frames(frames, |image| filters::brightness(image, *factor)),
+            Self::Grayscale => map_frames(frames, filters::grayscale),
+            Self::Rotate(degrees) => map_frames(frames, |image| filters::rotate(&image, *degrees, Rgba::transparent())),
+            Self::Caption(text) => caption_func(ImageArguments {
+                frames,
+                arguments: vec![CaptionOptions {
+                    text: text.clone(),
+                    color: Rgba::black(),
+                    stroke: None,
+                    size: 30.0,
+                    align: Align::Center,
+                    position: CaptionPosition::Top,
+                    transparent: false,
+                }],
+                seed,
+            })?,
+        })
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Effect(stage) => match stage.value {
+                Some(value) => write!(f, "{}({value})", stage.effect.name),
+                None => f.write_str(stage.effect.name),
+            },
+            Self::Hue(degrees) => write!(f, "hue({degrees})"),
+            Self::Blur(radius) => write!(f, "blur({radius})"),
+            Self::Brightness(factor) => write!(f, "brightness({factor})"),
+            Self::Grayscale => f.write_str("grayscale"),
+            Self::Rotate(degrees) => write!(f, "rotate({degrees})"),
+            Self::Caption(text) => write!(f, "caption('{}')", text.replace('\\', "\\\\").replace('\'', "\\'")),
+        }
+    }
+}
+
+/// applies `function` to the image of each frame, keeping their delays and disposals
+fn map_frames(frames: Frames, function: impl Fn(Image<Rgba>) -> Image<Rgba>) -> Frames {
+    let mut sequence =
+        ImageSequence::<Rgba>::new();
+
+    for frame in checked(frames) {
+        let (delay, disposal) = (frame.delay(), frame.disposal());
+        let frame = Frame::from_image(function(frame.into_image()))
+            .with_delay(delay)
+            .with_disposal(disposal);
+        sequence.push_frame(frame);
+    }
+
+    sequence
+}
+
+/// the operations of a script, applied in order
+#[derive(Debug, Clone)]
+pub struct Script(pub Vec<Op>);
+
+impl Script {
+    /// creates the script running the provided operations, rejecting it if it is too costly to run
+    ///
+    /// # Errors
+    /// if the [`Script::weight`] of the script is above [`MAX_WEIGHT`]
+    pub fn new(ops: Vec<Op>) -> Result<Self, String> {
+        let script = Self(ops);
+
+        match script.weight() {
+            weight if weight > MAX_WEIGHT => Err(format!(
+                "This chain is too costly to run, with a cost of `{weight}` where at most `{MAX_WEIGHT}` is allowed"
+            )),
+            _ => Ok(script),
+        }
+    }
+
+    /// the estimated cost of running this script on a still image, relative to inverting its colors
+    ///
+    /// operations after one that changes the number of frames, such as `huerotate`, are weighted by the frames it produces
+    #[must_use]
+    pub fn weight(&self) -> u64 {
+        let mut frames = 1_u64;
+        let mut weight = 0_u64;
+
+        for op in &self.0 {
+            weight = weight.saturating_add(op.weight().saturating_mul(frames));
+            frames = op.frames(frames);
+        }
+
+        weight
+    }
+}
+
+impl TryFrom<Pipeline> for Script {
+    type Error = String;
+
+    /// runs the stages of a pipeline as a script of effects, rejecting it if it is too costly to run
+    fn try_from(pipeline: Pipeline) -> Result<Self, Self::Error> {
+        Self::new(
+            pipeline.0
+                .into_iter()
+                .map(Op::Effect)
+                .collect()
+        )
+    }
+}
+
+impl FromStr for Script {
+    type Err = String;
+
+    /// parses and checks a script, rejecting it if it is too long or too costly to run
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("No operations were provided".to_string());
+        }
+
+        let length = s.chars().count();
+        if length > MAX_SCRIPT_LENGTH {
+            return Err(format!("Scripts can be at most {MAX_SCRIPT_LENGTH} characters long, not {length}"));
+        }
+
+        let calls = Parser::new(s).script()?;
+        if calls.len() > MAX_STAGES {
+            return Err(format!("Scripts can chain at most {MAX_STAGES} operations, not {}", calls.len()));
+        }
+
+        Self::new(
+            calls.iter()
+                .map(Op::from_call)
+                .collect::<Result<_, _>>()?
+        )
+    }
+}
+
+impl fmt::Display for Script {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, op) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" | ")?;
+            }
+            write!(f, "{op}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// checks that a custom effect may be saved under `name`
+///
+/// # Errors
+/// if the name is empty, too long, contains characters other than letters, digits, `_` and `-`, or is reserved
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(format!("Names of custom effects must be between 1 and {MAX_NAME_LENGTH} characters long"));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')) {
+        return Err("Names of custom effects can only contain letters, digits, `_` and `-`".to_string());
+    }
+    if RESERVED_NAMES.contains(&name) {
+        return Err(format!("`{name}` can not be used as the name of a custom effect"));
+    }
+
+    Ok(())
+}
+
+/// applies each operation of the script to the frames in order, passing them between operations without re-encoding them
+///
+/// each operation is seeded with its own offset from `seed`, so that repeating an effect does not repeat its randomness
+pub fn run(data: ImageArguments<Script>) -> ril::Result<Frames> {
+    let mut context = PipelineContext::default();
+    let mut frames = data.frames;
+
+    for (i, op) in data.arguments[0].0.iter().enumerate() {
+        frames = op.apply(frames, data.seed.wrapping_add(i as u64), &mut context)?;
+        context.advance(op.keeps());
+    }
+
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// parses a script, returning the message of the error it fails with
+    fn error(source: &str) -> String {
+        source.parse::<Script>()
+            .expect_err("the script should not parse")
+    }
+
+    /// parses a script made of a single operation, returning that operation
+    fn op(source: &str) -> Op {
+        let mut script = source.parse::<Script>()
+            .expect("the script should parse");
+
+        script.0.remove(0)
+    }
+
+    #[test]
+    fn reports_the_position_of_parse_errors() {
+        assert_eq!(error("hue(45) | blur(2"), "Expected `)` at position 16, found the end of the script");
+        assert_eq!(error("hue(45) blur"), "Unexpected `b` at position 8, operations are separated by `|`");
+        assert_eq!(error("caption('hi"), "The text starting at position 8 is never closed with `'`");
+        assert_eq!(error("blur(1-2)"), "`1-2` at position 5 is not a valid number");
+        assert_eq!(error("hue(45) | | blur"), "Expected the name of an operation at position 10");
+        assert!(error("hue(45) | sharpen").starts_with("Unknown operation `sharpen` at position 10"));
+    }
+
+    #[test]
+    fn counts_positions_in_characters() {
+        assert!(error("caption('é') | nope").starts_with("Unknown operation `nope` at position 15"));
+    }
+
+    #[test]
+    fn checks_arguments() {
+        assert_eq!(error("blur(1, 2)"), "`blur` at position 0 takes at most 1 argument(s), not 2");
+        assert_eq!(error("grayscale(1)"), "`grayscale` at position 0 takes at most 0 argument(s), not 1");
+        assert_eq!(error("blur('far')"), "`blur` at position 0 takes a number, not text");
+        assert!(error("caption(12)").starts_with("`caption` at position 0 takes its text in quotes"));
+        assert!(error("noise:0.5").starts_with("Unexpected `:`"));
+    }
+
+    #[test]
+    fn clamps_arguments_to_their_range() {
+        assert!(matches!(op("blur(100)"), Op::Blur(32)));
+        assert!(matches!(op("blur(0)"), Op::Blur(1)));
+        assert!(matches!(op("hue(-1000)"), Op::Hue(-360)));
+        assert!(matches!(op("brightness(9)"), Op::Brightness(factor) if (factor - 3.0).abs() < f64::EPSILON));
+        assert_eq!(op("neon(100)").to_string(), "neon(24)");
+    }
+
+    #[test]
+    fn falls_back_to_defaults() {
+        assert!(matches!(op("blur"), Op::Blur(4)));
+        assert!(matches!(op("hue()"), Op::Hue(180)));
+        assert!(matches!(op("neon"), Op::Effect(stage) if stage.value.is_none()));
+    }
+
+    #[test]
+    fn unescapes_quoted_text() {
+        assert!(matches!(op(r"caption('it\'s a \\ test')"), Op::Caption(text) if text == r"it's a \ test"));
+        assert!(matches!(op(r#"caption("say 'hi'")"#), Op::Caption(text) if text == "say 'hi'"));
+    }
+
+    #[test]
+    fn escapes_quoted_text() {
+        assert_eq!(op(r"caption('it\'s a \\ test')").to_string(), r"caption('it\'s a \\ test')");
+        assert_eq!(op(r#"caption("say 'hi'")"#).to_string(), r"caption('say \'hi\'')");
+    }
+
+    #[test]
+    fn round_trips_through_display() {
+        let source = r"hue(45) | blur(2) | grayscale | rotate(-90) | neon(12) | caption('a \\ \'b\'')";
+        let script = source.parse::<Script>()
+            .unwrap();
+
+        assert_eq!(script.to_string(), source);
+        assert_eq!(script.to_string().parse::<Script>().unwrap().to_string(), source);
+    }
+
+    #[test]
+    fn displays_clamped_and_aliased_operations() {
+        let script = "HUE(1000)|greyscale|blur(0.4)"
+            .parse::<Script>()
+            .unwrap();
+
+        assert_eq!(script.to_string(), "hue(360) | grayscale | blur(1)");
+    }
+
+    #[test]
+    fn weighs_operations_after_huerotate_by_its_frames() {
+        assert_eq!("invert | neon".parse::<Script>().unwrap().weight(), 7);
+        assert_eq!("huerotate".parse::<Script>().unwrap().weight(), 1);
+        assert_eq!("huerotate | invert".parse::<Script>().unwrap().weight(), 1 + HUEROTATE_FRAMES);
+        // huerotate always produces the same number of frames, rather than multiplying those it is given
+        assert_eq!("huerotate | huerotate".parse::<Script>().unwrap().weight(), 1 + HUEROTATE_FRAMES);
+    }
+
+    #[test]
+    fn rejects_costly_scripts() {
+        assert!(error("huerotate | neon").starts_with("This chain is too costly to run, with a cost of `217`"));
+        assert!(error("huerotate | huerotate | huerotate").starts_with("This chain is too costly to run"));
+    }
+
+    #[test]
+    fn rejects_costly_pipelines() {
+        let pipeline = |source: &str| Script::try_from(source.parse::<Pipeline>().unwrap());
+
+        assert_eq!(pipeline("eyes | neon:12 | tilt").unwrap().weight(), 12);
+        assert!(pipeline("huerotate | neon").is_err());
+    }
+
+    #[test]
+    fn rejects_long_scripts() {
+        assert_eq!(error("  "), "No operations were provided");
+        assert!(error(&"a".repeat(MAX_SCRIPT_LENGTH + 1)).starts_with("Scripts can be at most"));
+        assert!(error(&["invert"; MAX_STAGES + 1].join(" | ")).starts_with("Scripts can chain at most"));
+    }
+
+    #[test]
+    fn validates_names() {
+        assert!(validate_name("my-effect_1").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name(&"a".repeat(MAX_NAME_LENGTH + 1)).is_err());
+        assert!(validate_name("two words").is_err());
+        assert!(validate_name("list").is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,250 @@
+//! contains the registration of the slash and context menu commands used when the bot runs in slash-only mode,
+//! where it can not read the content of messages to parse prefix commands
+
+use serenity::{
+    prelude::Context,
+    builder::CreateApplicationCommand,
+    model::application::{
+        command::{Command, CommandOptionType, CommandType},
+        interaction::autocomplete::AutocompleteInteraction,
+    },
+};
+
+use super::{
+    Error,
+    assets::ASSETS,
+    pipeline::Stage,
+    registry::{self, Effect},
+    templates::Template,
+};
+
+
+/// the effects of the registry that are not available as slash commands, as they combine several images or take an emoji
+const UNSUPPORTED_EFFECTS: &[&str] = &["overlay", "enlarge"];
+/// the slash commands that are not a single effect, each taking a name that is autocompleted as it is typed
+pub const SLASH_COMMANDS: &[&str] = &["pipe", "template", "text"];
+/// the most suggestions discord accepts in response to an autocomplete interaction
+const MAX_SUGGESTIONS: usize = 25;
+/// the longest name and value discord accepts for a suggestion, in characters,
+/// any longer suggestion failing the whole response
+const MAX_SUGGESTION_LENGTH: usize = 100;
+
+
+/// returns every effect of the registry available as a slash command
+pub fn slash_effects() -> impl Iterator<Item = &'static Effect> {
+    registry::EFFECTS.iter()
+        .filter(|effect| !UNSUPPORTED_EFFECTS.contains(&effect.name))
+}
+
+/// builds the slash command applying the provided effect
+fn create_slash_command<'a>(command: &'a mut CreateApplicationCommand, effect: &Effect) -> &'a mut CreateApplicationCommand {
+    command.name(effect.name)
+        .description(effect.description)
+        .dm_permission(true);
+
+    // caption is the only effect taking text, which discord requires to be placed before any optional options
+    if effect.name == "caption" {
+        command.create_option(|option|
+            option.name("text")
+                .description("the text of the caption")
+                .kind(CommandOptionType::String)
+                .required(true)
+        );
+    }
+
+    add_image_options(command);
+
+    for param in effect.params {
+        command.create_option(|option|
+            option.name(param.name)
+                .description(format!("the {} of the effect", param.name))
+                .kind(CommandOptionType::Number)
+                .min_number_value(param.min)
+                .max_number_value(param.max)
+        );
+    }
+
+    if effect.name == "facecrop" {
+        command.create_option(|option|
+            option.name("smart")
+                .description("whether to center the crop on the most prominent face")
+                .kind(CommandOptionType::Boolean)
+        );
+        command.create_option(|option|
+            option.name("circle")
+                .description("whether to crop the image into a circle")
+                .kind(CommandOptionType::Boolean)
+        );
+    }
+
+    command
+}
+
+/// adds the options taking the source image to a slash command
+fn add_image_options(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command.create_option(|option|
+        option.name("image")
+            .description("the image to apply the effect to")
+            .kind(CommandOptionType::Attachment)
+    )
+        .create_option(|option|
+            option.name("source")
+                .description("a link, emoji or user to take the image from")
+                .kind(CommandOptionType::String)
+        )
+}
+
+/// builds the slash command named `name`, one of [`SLASH_COMMANDS`]
+fn create_named_command<'a>(command: &'a mut CreateApplicationCommand, name: &str) -> &'a mut CreateApplicationCommand {
+    command.name(name)
+        .dm_permission(true);
+
+    match name {
+        "pipe" => add_image_options(
+            command.description("applies several effects one after another")
+                .create_option(|option|
+                    option.name("effects")
+                        .description("the effects to apply, separated by `|` such as `eyes | neon:12`")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                )
+        ),
+        "template" => add_image_options(
+            command.description("composites the image into an animated template")
+                .create_option(|option|
+                    option.name("name")
+                        .description("the name of the template")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                )
+        ),
+        _ => command.description("renders text as an image")
+            .create_option(|option|
+                option.name("text")
+                    .description("the text to render")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            )
+            .create_option(|option|
+                option.name("font")
+                    .description("the font to render the text in")
+                    .kind(CommandOptionType::String)
+                    .set_autocomplete(true)
+            ),
+    }
+}
+
+/// picks up to [`MAX_SUGGESTIONS`] of the provided names matching what has been typed so far, ignoring case,
+/// those starting with it being suggested before those merely containing it
+fn matching<I>(names: I, typed: &str) -> Vec<String>
+where
+    I: IntoIterator<Item = String>,
+{
+    let typed = typed.trim().to_lowercase();
+    let (mut starting, containing) = names.into_iter()
+        .filter(|name| name.to_lowercase().contains(typed.as_str()))
+        .partition::<Vec<_>, _>(|name| name.to_lowercase().starts_with(typed.as_str()));
+
+    starting.extend(containing);
+    starting.truncate(MAX_SUGGESTIONS);
+
+    starting
+}
+
+/// returns the suggestions for the option named `option` of the slash command named `command`,
+/// given what has been typed into it so far
+///
+/// suggestions longer than [`MAX_SUGGESTION_LENGTH`] are left out, such as those completing long chains of effects
+#[must_use]
+pub fn suggestions(command: &str, option: &str, typed: &str) -> Vec<String> {
+    let suggestions = match (command, option) {
+        // only the stage being typed is completed, keeping those before it as they are
+        ("pipe", "effects") => {
+            let (before, current) = typed.rsplit_once('|')
+                .unwrap_or(("", typed));
+            let prefix = if before.is_empty() { String::new() } else { format!("{} | ", before.trim_end()) };
+            let effects = registry::EFFECTS.iter()
+                .filter(|effect| Stage::EFFECTS.contains(&effect.name))
+                .map(|effect| effect.name.to_string());
+
+            matching(effects, current)
+                .into_iter()
+                .map(|name| format!("{prefix}{name}"))
+                .collect()
+        },
+        ("template", "name") => matching(Template::names(), typed),
+        ("text", "font") => ASSETS.fonts()
+            .map(|fonts| matching(fonts.names().map(ToString::to_string), typed))
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    suggestions
+        .into_iter()
+        .filter(|suggestion| suggestion.chars().count() <= MAX_SUGGESTION_LENGTH)
+        .collect()
+}
+
+/// responds to an autocomplete interaction with the [`suggestions`] for the option being typed in
+///
+/// # Errors
+/// if the response could not be sent
+pub async fn autocomplete(ctx: &Context, interaction: &AutocompleteInteraction) -> Result<(), Error> {
+    let Some(focused) = interaction.data.options
+        .iter()
+        .find(|option| option.focused)
+    else {
+        return Ok(());
+    };
+
+    let typed = focused.value
+        .as_ref()
+        .and_then(|value| value.as_str())
+        .unwrap_or_default();
+    let suggestions = suggestions(interaction.data.name.as_str(), focused.name.as_str(), typed);
+
+    interaction.create_autocomplete_response(ctx, |response| {
+        for suggestion in suggestions {
+            response.add_string_choice(suggestion.clone(), suggestion);
+        }
+
+        response
+    })
+        .await?;
+
+    Ok(())
+}
+
+/// registers a slash command for each of the [`slash_effects`] and [`SLASH_COMMANDS`],
+/// along with a message context menu command for the effects that do not require any options
+///
+/// # Errors
+/// if the commands could not be registered
+pub async fn register(ctx: &Context) -> Result<Vec<Command>, Error> {
+    let effects = slash_effects()
+        .collect::<Vec<_>>();
+
+    Ok(
+        Command::set_global_application_commands(ctx, |commands| {
+            for effect in &effects {
+                commands.create_application_command(|command| create_slash_command(command, effect));
+
+                if effect.name != "caption" {
+                    commands.create_application_command(|command|
+                        command.name(effect.name)
+                            .kind(CommandType::Message)
+                    );
+                }
+            }
+
+            for name in SLASH_COMMANDS {
+                commands.create_application_command(|command| create_named_command(command, name));
+            }
+
+            commands
+        })
+            .await?
+    )
+}
\ No newline at end of file
@@ -0,0 +1,170 @@
+//! contains the animated templates the reaction commands composite an image into, such as `dance`, `party` and `vibe`
+//!
+//! each template is an animation in `assets/templates/<name>.gif`, along with a manifest in `assets/templates/<name>.json`
+//! describing where the image is placed on each of its frames, so that new templates can be added without code changes
+
+use std::{fs, path::Path, sync::Arc};
+
+use ril::prelude::*;
+use serde::Deserialize;
+
+use super::{Error, assets::{ASSETS, ASSETS_DIR}, filters, imaging::{decode_secondary, Frames}, jobs::checked};
+
+
+/// the directory the templates are loaded from, relative to the assets directory
+pub const TEMPLATES_DIR: &str = "templates";
+
+
+/// where the image is placed on a single frame of a template, in the template's pixels
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct Placement {
+    /// the x coordinate of the left edge of the image, which may be negative to place it partially off the frame
+    pub x: i64,
+    /// the y coordinate of the top edge of the image
+    pub y: i64,
+    /// the width the image is resized to
+    pub width: u32,
+    /// the height the image is resized to, squashing or stretching it if it differs from the image's aspect ratio
+    pub height: u32,
+}
+
+/// the manifest of a template, describing how an image is composited into it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    /// whether the image is drawn beneath the template, showing through its transparent areas, rather than on top of it
+    #[serde(default)]
+    pub behind: bool,
+    /// whether the image is masked to a circle, as avatars are displayed
+    #[serde(default)]
+    pub circle: bool,
+    /// where the image is placed on each frame of the template, repeating if there are fewer than there are frames,
+    /// with `null` hiding the image on that frame
+    pub placements: Vec<Option<Placement>>,
+}
+
+/// a template that has been read, but whose animation has not been decoded yet,
+/// so that it can be read on the async side and decoded on the worker pool
+#[derive(Debug, Clone)]
+pub struct Template {
+    /// the raw bytes of the template's animation
+    pub bytes: Arc<Vec<u8>>,
+    /// the template's manifest
+    pub manifest: Manifest,
+}
+
+impl Template {
+    /// reads the template named `name` and parses its manifest
+    ///
+    /// # Errors
+    /// if the template or its manifest is missing, or if the manifest is invalid
+    pub fn read(name: &str) -> Result<Self, Error> {
+        let missing = || Error::MissingTemplate(name.to_string());
+
+        let bytes = ASSETS.get(format!("{TEMPLATES_DIR}/{name}.gif"))
+            .ok_or_else(missing)?;
+        let manifest = ASSETS.get(format!("{TEMPLATES_DIR}/{name}.json"))
+            .ok_or_else(missing)?;
+        let manifest = serde_json::from_slice::<Manifest>(&manifest)
+            .map_err(|err| {
+                tracing::warn!("the manifest of the template `{name}` is invalid: {err}");
+                missing()
+            })?;
+
+        if manifest.placements.is_empty() {
+            tracing::warn!("the manifest of the template `{name}` has no placements");
+            return Err(missing());
+        }
+
+        Ok(Self { bytes, manifest })
+    }
+
+    /// the names of every template, in alphabetical order, as found by their manifests in [`TEMPLATES_DIR`]
+    #[must_use]
+    pub fn names() -> Vec<String> {
+        let mut names = fs::read_dir(Path::new(ASSETS_DIR).join(TEMPLATES_DIR))
+            .map(|entries| entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path| path.extension().map_or(false, |ext| ext == "json"))
+                .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+                .collect::<Vec<_>>()
+            )
+            .unwrap_or_default();
+        names.sort();
+
+        names
+    }
+
+    /// composites the provided image into every frame of this template, cycling through its frames if it is animated too
+    ///
+    /// # Errors
+    /// if the template's animation could not be decoded
+    pub fn render(&self, image: &Frames) -> ril::Result<Frames> {
+        let template = decode_secondary(&self.bytes[..])?;
+        let layers = image.iter()
+            .map(|frame| frame.image().clone())
+            .collect::<Vec<_>>();
+
+        let mut sequence =
+            ImageSequence::<Rgba>::new();
+
+        for (i, frame) in checked(template.into_iter().enumerate()) {
+            let (delay, disposal) = (frame.delay(), frame.disposal());
+            let placement = self.manifest.placements[i % self.manifest.placements.len()];
+            let background = frame.into_image();
+
+            let composited = match (placement, layers.get(i % layers.len().max(1))) {
+                (Some(placement), Some(layer)) => self.composite(background, layer, placement),
+                _ => background,
+            };
+
+            sequence.push_frame(
+                Frame::from_image(composited)
+                    .with_delay(delay)
+                    .with_disposal(disposal)
+            );
+        }
+
+        Ok(sequence)
+    }
+
+    /// draws `layer` onto the template frame at `placement`, beneath or on top of the frame as set in the manifest
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+    fn composite(&self, frame: Image<Rgba>, layer: &Image<Rgba>, placement: Placement) -> Image<Rgba> {
+        let (width, height) = (placement.width.max(1), placement.height.max(1));
+        let mut layer = layer.clone()
+            .resized(width, height, ResizeAlgorithm::Lanczos3);
+
+        if self.manifest.circle {
+            layer = filters::circle_mask(layer);
+        }
+
+        let (frame_width, frame_height) = frame.dimensions();
+        let (mut bottom, top) = if self.manifest.behind {
+            (Image::<Rgba>::new(frame_width, frame_height, Rgba::transparent()), Some(frame))
+        } else {
+            (frame, None)
+        };
+
+        // the layer may reach past the edges of the frame, so only the part of it within the frame is pasted
+        let (left, top_edge) = (placement.x.max(0), placement.y.max(0));
+        let (right, bottom_edge) = (
+            (placement.x + i64::from(width)).min(i64::from(frame_width)),
+            (placement.y + i64::from(height)).min(i64::from(frame_height)),
+        );
+        if left < right && top_edge < bottom_edge {
+            let visible = layer.cropped(
+                (left - placement.x) as u32,
+                (top_edge - placement.y) as u32,
+                (right - placement.x) as u32,
+                (bottom_edge - placement.y) as u32,
+            );
+            bottom.paste(left as u32, top_edge as u32, &visible);
+        }
+
+        if let Some(top) = top {
+            bottom.paste(0, 0, &top);
+        }
+
+        bottom
+    }
+}
\ No newline at end of file
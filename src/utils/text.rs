@@ -0,0 +1,727 @@
+//! contains the text rendering pipeline used by the captioning functions,
+//! along with a markdown-aware layout for rendering discord messages
+//!
+//! text is split into bidirectional runs, each run is split again by which font in the [`FontChain`] supports it,
+//! shaped with `rustybuzz` and finally rasterized with `fontdue`,
+//! allowing non-latin scripts such as Arabic, Hebrew and Devanagari to render correctly.
+
+use std::{
+    fs,
+    path::Path,
+    ops::Range,
+};
+
+use ril::prelude::*;
+use rustybuzz::{Direction, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
+
+use super::{
+    filters::soft_blur,
+    options::Align,
+};
+
+
+/// the path of the primary font
+pub const PRIMARY_FONT: &str = "./assets/impact.ttf";
+/// the directory containing the fallback fonts, which are tried in alphabetical order
+pub const FALLBACK_FONT_DIR: &str = "./assets/fonts";
+
+
+/// a single font face, holding both the raw font data used for shaping and the parsed font used for rasterizing
+#[derive(Clone)]
+pub struct FontFace {
+    /// the name of the font, the stem of the file it was loaded from
+    name: String,
+    /// the raw bytes of the font file
+    data: Vec<u8>,
+    /// the parsed font used to rasterize glyphs
+    font: fontdue::Font,
+}
+
+impl FontFace {
+    /// parses a font face named `name` from the raw bytes of a font file
+    pub fn from_bytes(name: String, data: Vec<u8>) -> Result<Self, String> {
+        let font = fontdue::Font::from_bytes(
+            data.as_slice(),
+            fontdue::FontSettings::default(),
+        )?;
+
+        Ok(Self { name, data, font })
+    }
+
+    /// reads and parses the font file at `path`, named after the stem of the file
+    fn read(path: &Path) -> Result<Self, String> {
+        let name = path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        Self::from_bytes(name, fs::read(path).map_err(|err| err.to_string())?)
+    }
+
+    /// returns whether or not this face has a glyph for the provided character
+    fn supports(&self, c: char) -> bool {
+        self.font.lookup_glyph_index(c) != 0
+    }
+}
+
+/// an ordered list of fonts, where each character is rendered using the first font that supports it
+#[derive(Clone)]
+pub struct FontChain {
+    /// the faces in this chain, the first being the primary font
+    faces: Vec<FontFace>,
+}
+
+impl FontChain {
+    /// loads the primary font from `primary` and every `.ttf` and `.otf` file in `fallback_dir`, if it exists
+    pub fn load<P, D>(primary: P, fallback_dir: D) -> Result<Self, String>
+    where
+        P: AsRef<Path>,
+        D: AsRef<Path>,
+    {
+        let mut faces = vec![FontFace::read(primary.as_ref())?];
+
+        if let Ok(entries) = fs::read_dir(fallback_dir) {
+            let mut paths = entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .filter(|path|
+                    path.extension()
+                        .map_or(false, |ext| ext == "ttf" || ext == "otf")
+                )
+                .collect::<Vec<_>>();
+            paths.sort();
+
+            for path in paths {
+                faces.push(FontFace::read(&path)?);
+            }
+        }
+
+        Ok(Self { faces })
+    }
+
+    /// the names of the fonts in this chain, in order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.faces.iter()
+            .map(|face| face.name.as_str())
+    }
+
+    /// returns a copy of this chain with the font named `name`, ignoring case, moved to the front as the primary font,
+    /// or `None` if there is no font by that name
+    #[must_use]
+    pub fn preferring(&self, name: &str) -> Option<Self> {
+        let index = self.faces.iter()
+            .position(|face| face.name.eq_ignore_ascii_case(name))?;
+
+        let mut faces = self.faces.clone();
+        let face = faces.remove(index);
+        faces.insert(0, face);
+
+        Some(Self { faces })
+    }
+
+    /// returns the index of the first face that supports the provided character,
+    /// defaulting to the primary font so that a `.notdef` glyph is still drawn
+    fn face_for(&self, c: char) -> usize {
+        if c.is_whitespace() || c.is_control() {
+            return 0;
+        }
+
+        self.faces.iter()
+            .position(|face| face.supports(c))
+            .unwrap_or(0)
+    }
+
+    /// returns the `(ascent, line height)` of the primary font at the provided size
+    fn line_metrics(&self, size: f32) -> (f32, f32) {
+        self.faces[0].font
+            .horizontal_line_metrics(size)
+            .map_or(
+                (size, size * 1.2),
+                |metrics| (metrics.ascent, metrics.new_line_size),
+            )
+    }
+}
+
+/// a single shaped glyph positioned along a line
+#[derive(Debug, Clone, Copy)]
+struct PositionedGlyph {
+    /// the index of the face in the [`FontChain`] this glyph belongs to
+    face: usize,
+    /// the glyph id within the face
+    id: u16,
+    /// the horizontal position of the glyph's origin from the start of the line
+    x: f32,
+    /// the vertical offset of the glyph from the baseline
+    y: f32,
+}
+
+/// a single line of shaped glyphs, in visual order
+#[derive(Debug, Clone, Default)]
+pub struct ShapedLine {
+    /// the glyphs of this line
+    glyphs: Vec<PositionedGlyph>,
+    /// the total advance width of this line
+    pub width: f32,
+}
+
+impl ShapedLine {
+    /// shapes a single line of text at the provided size
+    #[must_use]
+    pub fn shape(fonts: &FontChain, text: &str, size: f32) -> Self {
+        let mut line = Self::default();
+
+        if text.is_empty() {
+            return line;
+        }
+
+        let bidi = BidiInfo::new(text, None);
+        let Some(paragraph) = bidi.paragraphs.first() else {
+            return line;
+        };
+        let (levels, runs) = bidi.visual_runs(paragraph, paragraph.range.clone());
+
+        for run in runs {
+            let rtl = levels[run.start].is_rtl();
+            let mut pieces = Self::split_by_face(fonts, text, run);
+
+            if rtl {
+                pieces.reverse();
+            }
+
+            for (face, range) in pieces {
+                line.push_run(fonts, face, &text[range], size, rtl);
+            }
+        }
+
+        line
+    }
+
+    /// splits the provided range of `text` into sub-ranges that are each supported by a single face
+    fn split_by_face(fonts: &FontChain, text: &str, range: Range<usize>) -> Vec<(usize, Range<usize>)> {
+        let mut pieces: Vec<(usize, Range<usize>)> = Vec::new();
+
+        for (offset, c) in text[range.clone()].char_indices() {
+            let start = range.start + offset;
+            let end = start + c.len_utf8();
+
+            // combining marks, joiners and variation selectors stay with the preceding character
+            let face = match pieces.last() {
+                Some((face, _)) if c.is_whitespace() || is_joining(c) => *face,
+                _ => fonts.face_for(c),
+            };
+
+            match pieces.last_mut() {
+                Some((last, last_range)) if *last == face => last_range.end = end,
+                _ => pieces.push((face, start..end)),
+            }
+        }
+
+        pieces
+    }
+
+    /// shapes a run of text with a single face and appends the glyphs to this line
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    fn push_run(&mut self, fonts: &FontChain, face: usize, text: &str, size: f32, rtl: bool) {
+        let font_face = &fonts.faces[face];
+        let Some(shaper) = rustybuzz::Face::from_slice(&font_face.data, 0) else {
+            return;
+        };
+
+        let scale = size / shaper.units_per_em() as f32;
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        buffer.guess_segment_properties();
+        buffer.set_direction(
+            if rtl { Direction::RightToLeft } else { Direction::LeftToRight }
+        );
+
+        let shaped = rustybuzz::shape(&shaper, &[], buffer);
+
+        for (info, position) in shaped.glyph_infos()
+            .iter()
+            .zip(shaped.glyph_positions())
+        {
+            self.glyphs.push(PositionedGlyph {
+                face,
+                id: info.glyph_id as u16,
+                x: self.width + position.x_offset as f32 * scale,
+                y: position.y_offset as f32 * scale,
+            });
+
+            self.width += position.x_advance as f32 * scale;
+        }
+    }
+}
+
+/// returns whether or not the character should be shaped together with the character before it
+fn is_joining(c: char) -> bool {
+    matches!(c,
+        '\u{0300}'..='\u{036F}'
+        | '\u{200C}'..='\u{200D}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}'
+    )
+}
+
+/// a block of text wrapped into several shaped lines
+#[derive(Debug, Clone)]
+pub struct TextBlock {
+    /// the shaped lines of this block
+    lines: Vec<ShapedLine>,
+    /// the font size used
+    size: f32,
+    /// the distance from the top of a line to its baseline
+    ascent: f32,
+    /// the height of a single line
+    line_height: f32,
+}
+
+impl TextBlock {
+    /// shapes the provided text, wrapping words onto new lines to fit within `max_width`
+    #[must_use]
+    pub fn new(fonts: &FontChain, text: &str, size: f32, max_width: f32) -> Self {
+        let mut lines = Vec::new();
+
+        for paragraph in text.lines() {
+            let mut current = String::new();
+
+            for word in paragraph.split_whitespace() {
+                let candidate = if current.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{current} {word}")
+                };
+
+                if current.is_empty()
+                    || ShapedLine::shape(fonts, &candidate, size).width <= max_width
+                {
+                    current = candidate;
+                } else {
+                    lines.push(ShapedLine::shape(fonts, &current, size));
+                    current = word.to_string();
+                }
+            }
+
+            lines.push(ShapedLine::shape(fonts, &current, size));
+        }
+
+        let (ascent, line_height) = fonts.line_metrics(size);
+
+        Self { lines, size, ascent, line_height }
+    }
+
+    /// the width of the widest line in this block
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        self.lines.iter()
+            .map(|line| line.width)
+            .fold(0.0, f32::max)
+    }
+
+    /// the total height of this block
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn height(&self) -> f32 {
+        self.line_height * self.lines.len() as f32
+    }
+
+    /// draws this block onto `image` in the provided color,
+    /// aligning each line within the box of `width` whose top-left corner is at `(x, y)`
+    #[allow(clippy::cast_precision_loss)]
+    pub fn draw(&self, image: &mut Image<Rgba>, fonts: &FontChain, (x, y): (i64, i64), width: f32, align: Align, color: Rgba) {
+        for (i, line) in self.lines.iter().enumerate() {
+            let offset = match align {
+                Align::Left => 0.0,
+                Align::Center => (width - line.width) / 2.0,
+                Align::Right => width - line.width,
+            };
+            let baseline = y as f32 + self.line_height * i as f32 + self.ascent;
+
+            draw_line(image, fonts, line, self.size, (x as f32 + offset, baseline), color, 0.0);
+        }
+    }
+
+    /// draws an outline of this block onto `image` in the provided color, reaching `radius` pixels past the edges of its glyphs,
+    /// to be drawn under the block itself
+    ///
+    /// the coverage of the block is rasterized once and dilated, rather than drawing the block again at every offset within the radius
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_stroke(&self, image: &mut Image<Rgba>, fonts: &FontChain, origin: (i64, i64), width: f32, align: Align, color: Rgba, radius: u32) {
+        let mut layer = Image::<Rgba>::new(image.width(), image.height(), Rgba::transparent());
+        self.draw(&mut layer, fonts, origin, width, align, Rgba::white());
+
+        let coverage = layer.data
+            .iter()
+            .map(|px| px.a)
+            .collect::<Vec<_>>();
+        let coverage = dilate(&coverage, image.width() as usize, radius as usize);
+
+        for (pixel, alpha) in image.data.iter_mut().zip(coverage) {
+            if alpha > 0 {
+                *pixel = blend(*pixel, color, alpha);
+            }
+        }
+    }
+}
+
+/// grows the provided coverage of an image `width` pixels wide by `radius` pixels in every direction,
+/// each pixel taking the highest coverage within the square around it, found in a horizontal and then a vertical pass
+fn dilate(coverage: &[u8], width: usize, radius: usize) -> Vec<u8> {
+    if width == 0 || radius == 0 {
+        return coverage.to_vec();
+    }
+    let height = coverage.len() / width;
+
+    let horizontal = (0..coverage.len())
+        .map(|i| {
+            let (x, row) = (i % width, i - i % width);
+
+            coverage[row + x.saturating_sub(radius)..=row + (x + radius).min(width - 1)]
+                .iter()
+                .copied()
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    (0..coverage.len())
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+
+            (y.saturating_sub(radius)..=(y + radius).min(height - 1))
+                .map(|y| horizontal[y * width + x])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// rasterizes the glyphs of a single shaped line onto `image`, with the start of its baseline at `origin`,
+/// shearing each glyph to the right by `slant` pixels per pixel above the baseline to fake an italic
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+fn draw_line(image: &mut Image<Rgba>, fonts: &FontChain, line: &ShapedLine, size: f32, (x, baseline): (f32, f32), color: Rgba, slant: f32) {
+    for glyph in &line.glyphs {
+        let (metrics, coverage) = fonts.faces[glyph.face].font
+            .rasterize_indexed(glyph.id, size);
+
+        let left = (x + glyph.x).round() as i64 + i64::from(metrics.xmin);
+        let top = (baseline - glyph.y).round() as i64
+            - i64::from(metrics.ymin)
+            - metrics.height as i64;
+
+        for (j, alpha) in coverage.into_iter().enumerate() {
+            let py = top + (j / metrics.width) as i64;
+            let shear = ((baseline - py as f32) * slant).round() as i64;
+            let px = left + (j % metrics.width) as i64 + shear;
+
+            if alpha == 0
+                || px < 0 || py < 0
+                || px >= i64::from(image.width())
+                || py >= i64::from(image.height())
+            {
+                continue;
+            }
+
+            let pixel = image.pixel_mut(px as u32, py as u32);
+            *pixel = blend(*pixel, color, alpha);
+        }
+    }
+}
+
+/// the style of a span of text, toggled by discord markdown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct TextStyle {
+    /// `**bold**`, drawn twice with a slight offset
+    pub bold: bool,
+    /// `*italics*` or `_italics_`, sheared to the right
+    pub italic: bool,
+    /// `~~strikethrough~~`, struck through the middle
+    pub strike: bool,
+    /// `||spoiler||`, blurred beyond recognition
+    pub spoiler: bool,
+    /// `` `inline code` ``, drawn over a darker box and left unparsed
+    pub code: bool,
+}
+
+impl TextStyle {
+    /// the flag toggled by the provided markdown marker, one of [`MARKERS`]
+    fn flag(&mut self, marker: &str) -> &mut bool {
+        match marker {
+            "**" => &mut self.bold,
+            "~~" => &mut self.strike,
+            "||" => &mut self.spoiler,
+            _ => &mut self.italic,
+        }
+    }
+}
+
+/// a span of text sharing a single style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextSegment {
+    /// the text of this segment, stripped of its markdown
+    pub text: String,
+    /// the style of this segment
+    pub style: TextStyle,
+}
+
+/// the markdown markers toggling a style, longest first so that `**` is not mistaken for two `*`
+const MARKERS: [&str; 5] = ["**", "~~", "||", "*", "_"];
+
+/// parses the basic discord markdown of the provided text into segments of differently styled text
+///
+/// a marker only toggles a style on if it is closed later on, otherwise it is kept as text,
+/// and characters can be escaped with a backslash
+#[must_use]
+pub fn parse_markdown(text: &str) -> Vec<TextSegment> {
+    let mut segments = Vec::new();
+    let mut style = TextStyle::default();
+    let mut current = String::new();
+    let mut rest = text;
+
+    let mut flush = |current: &mut String, style: TextStyle| {
+        if !current.is_empty() {
+            segments.push(TextSegment { text: std::mem::take(current), style });
+        }
+    };
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            let mut chars = rest[1..].chars();
+            if let Some(escaped) = chars.next() {
+                current.push(escaped);
+                rest = chars.as_str();
+            } else {
+                current.push(c);
+                rest = "";
+            }
+            continue;
+        }
+
+        if c == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                flush(&mut current, style);
+                current.push_str(&rest[1..=end]);
+                flush(&mut current, TextStyle { code: true, ..style });
+                rest = &rest[end + 2..];
+                continue;
+            }
+        }
+
+        // an opening marker must be closed later on to count
+        let marker = MARKERS.into_iter()
+            .find(|marker| rest.starts_with(marker))
+            .filter(|marker| *style.flag(marker) || rest[marker.len()..].contains(marker));
+
+        if let Some(marker) = marker {
+            flush(&mut current, style);
+
+            let flag = style.flag(marker);
+            *flag = !*flag;
+            rest = &rest[marker.len()..];
+        } else {
+            current.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    flush(&mut current, style);
+
+    segments
+}
+
+/// escapes every markdown marker in the provided text with a backslash, so that [`parse_markdown`] keeps it as is
+#[must_use]
+pub fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| match c {
+            '\\' | '*' | '_' | '~' | '|' | '`' => vec!['\\', c],
+            _ => vec![c],
+        })
+        .collect()
+}
+
+/// a single shaped piece of styled text, the smallest unit a [`MarkdownBlock`] wraps
+#[derive(Debug, Clone)]
+struct StyledPiece {
+    /// the shaped text of this piece
+    line: ShapedLine,
+    /// the style of this piece
+    style: TextStyle,
+    /// the horizontal position of this piece from the start of its line
+    x: f32,
+}
+
+/// a block of markdown formatted text, wrapped into several lines of styled pieces
+#[derive(Debug, Clone)]
+pub struct MarkdownBlock {
+    /// the pieces of each line
+    lines: Vec<Vec<StyledPiece>>,
+    /// the widths of each line
+    widths: Vec<f32>,
+    /// the font size used
+    size: f32,
+    /// the distance from the top of a line to its baseline
+    ascent: f32,
+    /// the height of a single line
+    line_height: f32,
+}
+
+impl MarkdownBlock {
+    /// parses the markdown of the provided text with [`parse_markdown`] and shapes it,
+    /// wrapping words onto new lines to fit within `max_width`
+    #[must_use]
+    pub fn new(fonts: &FontChain, text: &str, size: f32, max_width: f32) -> Self {
+        let space = ShapedLine::shape(fonts, " ", size).width;
+        let mut lines = Vec::new();
+        let mut widths = Vec::new();
+
+        for paragraph in text.lines() {
+            let mut line: Vec<StyledPiece> = Vec::new();
+            let mut width = 0.0;
+            let mut trailing = false;
+
+            for segment in parse_markdown(paragraph) {
+                // a segment may start or end in the middle of a word, which is then kept together
+                let mut spaced = trailing || segment.text.starts_with(char::is_whitespace);
+
+                for word in segment.text.split_whitespace() {
+                    let shaped = ShapedLine::shape(fonts, word, size);
+                    let gap = if spaced && !line.is_empty() { space } else { 0.0 };
+
+                    if spaced && !line.is_empty() && width + gap + shaped.width > max_width {
+                        lines.push(std::mem::take(&mut line));
+                        widths.push(width);
+                        width = 0.0;
+                    }
+
+                    let x = if line.is_empty() { 0.0 } else { width + gap };
+                    width = x + shaped.width;
+                    line.push(StyledPiece { line: shaped, style: segment.style, x });
+                    spaced = true;
+                }
+
+                trailing = segment.text.ends_with(char::is_whitespace)
+                    || (trailing && segment.text.trim().is_empty());
+            }
+
+            lines.push(line);
+            widths.push(width);
+        }
+
+        let (ascent, line_height) = fonts.line_metrics(size);
+
+        Self { lines, widths, size, ascent, line_height }
+    }
+
+    /// the width of the widest line in this block
+    #[must_use]
+    pub fn width(&self) -> f32 {
+        self.widths.iter()
+            .copied()
+            .fold(0.0, f32::max)
+    }
+
+    /// the total height of this block
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn height(&self) -> f32 {
+        self.line_height * self.lines.len() as f32
+    }
+
+    /// draws this block onto `image` in the provided color,
+    /// aligning each line within the box of `width` whose top-left corner is at `(x, y)`
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    pub fn draw(&self, image: &mut Image<Rgba>, fonts: &FontChain, (x, y): (i64, i64), width: f32, align: Align, color: Rgba) {
+        let bold_offset = (self.size / 24.0).max(1.0);
+
+        for (i, (line, line_width)) in self.lines.iter().zip(&self.widths).enumerate() {
+            let offset = match align {
+                Align::Left => 0.0,
+                Align::Center => (width - line_width) / 2.0,
+                Align::Right => width - line_width,
+            };
+            let top = y as f32 + self.line_height * i as f32;
+            let baseline = top + self.ascent;
+
+            for piece in line {
+                let left = x as f32 + offset + piece.x;
+                let right = left + piece.line.width;
+
+                if piece.style.code {
+                    let padding = self.size / 8.0;
+                    fill(image, (left - padding, top), (right + padding, top + self.line_height), Rgba::new(0, 0, 0, 80));
+                }
+
+                let slant = if piece.style.italic { 0.2 } else { 0.0 };
+                draw_line(image, fonts, &piece.line, self.size, (left, baseline), color, slant);
+                if piece.style.bold {
+                    draw_line(image, fonts, &piece.line, self.size, (left + bold_offset, baseline), color, slant);
+                }
+
+                if piece.style.strike {
+                    let middle = baseline - self.ascent * 0.3;
+                    let thickness = (self.size / 14.0).max(1.0);
+                    fill(image, (left, middle - thickness / 2.0), (right, middle + thickness / 2.0), color);
+                }
+
+                if piece.style.spoiler {
+                    blur_region(image, (left, top), (right + bold_offset, top + self.line_height), (self.size / 4.0) as u32);
+                }
+            }
+        }
+    }
+}
+
+/// fills the box between the provided corners of `image` with `color`, clipped to the image
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fill(image: &mut Image<Rgba>, (x1, y1): (f32, f32), (x2, y2): (f32, f32), color: Rgba) {
+    let clip = |value: f32, max: u32| (value.round().max(0.0) as u32).min(max);
+
+    for py in clip(y1, image.height())..clip(y2, image.height()) {
+        for px in clip(x1, image.width())..clip(x2, image.width()) {
+            let pixel = image.pixel_mut(px, py);
+            *pixel = blend(*pixel, color, 255);
+        }
+    }
+}
+
+/// blurs the box between the provided corners of `image` in place, clipped to the image
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn blur_region(image: &mut Image<Rgba>, (x1, y1): (f32, f32), (x2, y2): (f32, f32), radius: u32) {
+    let clip = |value: f32, max: u32| (value.round().max(0.0) as u32).min(max);
+    let (x1, y1) = (clip(x1, image.width()), clip(y1, image.height()));
+    let (x2, y2) = (clip(x2, image.width()), clip(y2, image.height()));
+
+    if x2 <= x1 || y2 <= y1 {
+        return;
+    }
+
+    let region = image.clone()
+        .cropped(x1, y1, x2, y2);
+    image.paste(x1, y1, &soft_blur(region, radius));
+}
+
+/// alpha-composites `src` over `dst`, with `src`'s alpha scaled by `coverage`
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn blend(dst: Rgba, src: Rgba, coverage: u8) -> Rgba {
+    let alpha = f32::from(src.a) / 255.0 * f32::from(coverage) / 255.0;
+    let dst_alpha = f32::from(dst.a) / 255.0;
+    let out_alpha = alpha + dst_alpha * (1.0 - alpha);
+
+    if out_alpha <= 0.0 {
+        return Rgba::transparent();
+    }
+
+    let channel = |s: u8, d: u8| {
+        ((f32::from(s) * alpha + f32::from(d) * dst_alpha * (1.0 - alpha)) / out_alpha)
+            .round() as u8
+    };
+
+    Rgba::new(
+        channel(src.r, dst.r),
+        channel(src.g, dst.g),
+        channel(src.b, dst.b),
+        (out_alpha * 255.0).round() as u8,
+    )
+}
\ No newline at end of file
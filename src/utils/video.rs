@@ -0,0 +1,226 @@
+//! contains the encoding of animations into `mp4` and `webm` videos, by piping their frames to `ffmpeg`,
+//! which discord plays inline and which are far smaller than `gif`s for long or large animations
+//!
+//! each frame is written once along with its own duration, so the videos have a variable frame rate
+//! and a frame shown for a long time costs no more to encode than any other
+//!
+//! `ffmpeg` is looked up on the path, or at the path in the `FFMPEG_PATH` environment variable,
+//! and must be built with `libx264` for `mp4`s and `libvpx-vp9` for `webm`s
+
+use std::{
+    env,
+    fmt::Write as _,
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{Error, functions::letterbox, imaging::{frame_delay, Frames}, jobs::CancelToken};
+
+
+/// the environment variable overriding the path of the `ffmpeg` executable
+pub const FFMPEG_PATH_VAR: &str = "FFMPEG_PATH";
+/// the longest an animation encoded into a video may last
+pub const MAX_DURATION: Duration = Duration::from_secs(300);
+/// the longest `ffmpeg` may take to encode a video before it is killed
+pub const ENCODE_TIMEOUT: Duration = Duration::from_secs(120);
+/// how often `ffmpeg` is checked on while it encodes, to enforce the [`ENCODE_TIMEOUT`] and cancellation
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// the name of the list of frames passed to `ffmpeg`'s concat demuxer
+const FRAME_LIST: &str = "frames.ffconcat";
+
+
+/// the video container, and the codec encoded into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    /// `h.264` in an `mp4`, which is the most widely supported but can not be transparent
+    Mp4,
+    /// `vp9` in a `webm`, which keeps the transparency of the frames
+    Webm,
+}
+
+impl Container {
+    /// the extension of files in this container
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    /// the arguments passed to `ffmpeg` to encode in this container
+    const fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            // h.264 requires even dimensions, so odd ones are padded by a pixel
+            Self::Mp4 => &[
+                "-c:v", "libx264", "-pix_fmt", "yuv420p", "-preset", "veryfast", "-crf", "23",
+                "-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-movflags", "+faststart",
+            ],
+            Self::Webm => &["-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-b:v", "0", "-crf", "33", "-row-mt", "1"],
+        }
+    }
+}
+
+/// writes the provided frames into `dir` as uncompressed `pam` images, scaling any that differ from `width` and `height` to fit them,
+/// along with the list of frames and their durations read by `ffmpeg`'s concat demuxer
+fn write_frames(dir: &Path, frames: &Frames, (width, height): (u32, u32), token: Option<&CancelToken>) -> Result<(), Error> {
+    let io_error = |err: std::io::Error| Error::VideoError(err.to_string());
+    let total = frames.len();
+    let mut list = String::from("ffconcat version 1.0\n");
+
+    for (i, frame) in frames.iter().enumerate() {
+        if let Some(token) = token {
+            token.check()?;
+        }
+
+        let name = format!("{i:06}.pam");
+        let image = if frame.dimensions() == (width, height) {
+            None
+        } else {
+            Some(letterbox(frame.image().clone(), width, height))
+        };
+
+        let mut file = BufWriter::new(
+            File::create(dir.join(&name))
+                .map_err(io_error)?
+        );
+        write!(file, "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n")
+            .map_err(io_error)?;
+        for px in &image.as_ref().unwrap_or_else(|| frame.image()).data {
+            file.write_all(&[px.r, px.g, px.b, px.a])
+                .map_err(io_error)?;
+        }
+        file.flush()
+            .map_err(io_error)?;
+
+        writeln!(list, "file '{name}'\nduration {:.3}", frame_delay(frame).as_secs_f64())
+            .ok();
+
+        if let Some(token) = token {
+            token.report_progress(i + 1, total);
+        }
+    }
+
+    // the concat demuxer ignores the duration of the last entry unless it is followed by another,
+    // so the last frame is listed again to hold it for its full duration
+    if let Some(last) = total.checked_sub(1) {
+        writeln!(list, "file '{last:06}.pam'")
+            .ok();
+    }
+
+    fs::write(dir.join(FRAME_LIST), list)
+        .map_err(io_error)
+}
+
+/// encodes the provided frames into a video in `container`, reporting the frames written for `ffmpeg` to `token` if provided
+///
+/// frames whose dimensions differ from those of the first frame are scaled to fit them,
+/// and `ffmpeg` is killed if it takes longer than [`ENCODE_TIMEOUT`] or if the job is cancelled
+///
+/// # Errors
+/// if the animation lasts longer than [`MAX_DURATION`], if `ffmpeg` could not be run or failed to encode the video in time,
+/// or if the job was cancelled
+pub fn encode(frames: &Frames, container: Container, token: Option<&CancelToken>) -> Result<Vec<u8>, Error> {
+    let Some(dimensions) = frames.first_frame()
+        .map(|frame| frame.dimensions())
+    else {
+        return Err(Error::VideoError(String::from("there are no frames to encode")));
+    };
+
+    let duration = frames.iter()
+        .map(frame_delay)
+        .sum::<Duration>();
+    if duration > MAX_DURATION {
+        return Err(Error::VideoError(format!(
+            "the animation lasts {:.1}s, longer than the limit of {}s",
+            duration.as_secs_f64(),
+            MAX_DURATION.as_secs(),
+        )));
+    }
+
+    let dir = env::temp_dir()
+        .join(format!("ril-bot-{}", rand::random::<u64>()));
+    fs::create_dir_all(&dir)
+        .map_err(|err| Error::VideoError(err.to_string()))?;
+
+    let result = write_frames(&dir, frames, dimensions, token)
+        .and_then(|()| run_ffmpeg(&dir, container, token));
+
+    fs::remove_dir_all(&dir).ok();
+    result
+}
+
+/// runs `ffmpeg` over the frames written into `dir` by [`write_frames`], returning the encoded video
+fn run_ffmpeg(dir: &Path, container: Container, token: Option<&CancelToken>) -> Result<Vec<u8>, Error> {
+    // the video is written to a file rather than to stdout, as moving the index of `mp4`s to the start requires seeking
+    let path = dir.join(format!("output.{}", container.extension()));
+    let ffmpeg = env::var_os(FFMPEG_PATH_VAR)
+        .map_or_else(|| PathBuf::from("ffmpeg"), PathBuf::from);
+
+    let mut child = Command::new(ffmpeg)
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-f", "concat", "-safe", "0", "-i"])
+        .arg(dir.join(FRAME_LIST))
+        .args(["-an", "-vsync", "vfr"])
+        .args(container.codec_args())
+        .arg(&path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| Error::VideoError(format!("ffmpeg could not be run: {err}")))?;
+
+    let (status, errors) = thread::scope(|scope| {
+        // stderr is drained on another thread, so that ffmpeg never blocks on it while it is being waited on
+        let stderr = child.stderr.take();
+        let errors = scope.spawn(move || {
+            let mut output = String::new();
+            if let Some(mut stderr) = stderr {
+                std::io::Read::read_to_string(&mut stderr, &mut output).ok();
+            }
+
+            output
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break Ok(status),
+                Ok(None) => (),
+                Err(err) => break Err(Error::VideoError(err.to_string())),
+            }
+
+            let cancelled = token.map_or(Ok(()), CancelToken::check);
+            let timed_out = started.elapsed() > ENCODE_TIMEOUT;
+            if cancelled.is_err() || timed_out {
+                child.kill().ok();
+                child.wait().ok();
+
+                break cancelled.and(Err(Error::VideoError(format!(
+                    "ffmpeg took longer than {}s to encode the video",
+                    ENCODE_TIMEOUT.as_secs(),
+                ))));
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        (status, errors.join().unwrap_or_default())
+    });
+
+    status.and_then(|status| if status.success() {
+        fs::read(&path)
+            .map_err(|err| Error::VideoError(err.to_string()))
+    } else {
+        Err(Error::VideoError(
+            errors.lines()
+                .last()
+                .unwrap_or("ffmpeg failed to encode the video")
+                .to_string()
+        ))
+    })
+}
\ No newline at end of file
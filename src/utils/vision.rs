@@ -0,0 +1,164 @@
+//! contains the face detection used to place accessories onto faces and to center crops on them,
+//! powered by `rustface` and the SeetaFace frontal model in the assets directory
+//!
+//! detection is best-effort: if the model is missing or no face is found, callers fall back to the center of the image
+
+use ril::prelude::*;
+use rustface::{model::{self, Model}, Detector, ImageData};
+
+use super::assets::ASSETS;
+
+
+/// the path of the face detection model, relative to the assets directory
+pub const FACE_MODEL: &str = "seeta_fd_frontal_v1.0.bin";
+/// the smallest size of a face that is detected, in pixels
+pub const MIN_FACE_SIZE: u32 = 20;
+
+lazy_static::lazy_static! {
+    /// the face detection model read from [`FACE_MODEL`] the first time it is needed, or `None` if it is missing or invalid,
+    /// which is kept for the lifetime of the process as parsing it takes longer than detecting the faces of most images
+    static ref MODEL: Option<Model> = ASSETS.get(FACE_MODEL)
+        .and_then(|bytes|
+            model::read_model(bytes.as_slice())
+                .map_err(|err| tracing::warn!("failed to read the face detection model: {err}"))
+                .ok()
+        );
+}
+
+
+/// the bounding box of a detected face
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Face {
+    /// the x coordinate of the left edge
+    pub x: u32,
+    /// the y coordinate of the top edge
+    pub y: u32,
+    /// the width of the face
+    pub width: u32,
+    /// the height of the face
+    pub height: u32,
+}
+
+impl Face {
+    /// a face-sized box in the center of an image of the provided dimensions, used when no face could be detected
+    #[must_use]
+    pub const fn centered(width: u32, height: u32) -> Self {
+        let size = if width < height { width / 2 } else { height / 2 };
+
+        Self {
+            x: (width - size) / 2,
+            y: (height - size) / 2,
+            width: size,
+            height: size,
+        }
+    }
+
+    /// the center of the face
+    #[must_use]
+    pub const fn center(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    /// this box grown by `fraction` of its size on every side, clamped within an image of the provided dimensions
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn grown(&self, fraction: f64, width: u32, height: u32) -> Self {
+        let (dx, dy) = (
+            (f64::from(self.width) * fraction) as u32,
+            (f64::from(self.height) * fraction) as u32,
+        );
+        let (x, y) = (self.x.saturating_sub(dx).min(width), self.y.saturating_sub(dy).min(height));
+
+        Self {
+            x,
+            y,
+            width: (self.x + self.width + dx).min(width).saturating_sub(x),
+            height: (self.y + self.height + dy).min(height).saturating_sub(y),
+        }
+    }
+
+    /// the approximate positions of the left and right eyes, from the usual proportions of a frontal face
+    #[must_use]
+    pub const fn eyes(&self) -> ((u32, u32), (u32, u32)) {
+        let y = self.y + self.height * 2 / 5;
+
+        (
+            (self.x + self.width * 3 / 10, y),
+            (self.x + self.width * 7 / 10, y),
+        )
+    }
+
+    /// the approximate position of the tip of the nose
+    #[must_use]
+    pub const fn nose(&self) -> (u32, u32) {
+        (self.x + self.width / 2, self.y + self.height * 3 / 5)
+    }
+}
+
+/// creates a face detector from the [`MODEL`], or `None` if it is missing or invalid
+///
+/// detectors keep state between detections and can not be shared between threads, so only the parsed model is cached
+fn detector() -> Option<Box<dyn Detector>> {
+    let mut detector = rustface::create_detector_with_model(MODEL.clone()?);
+    detector.set_min_face_size(MIN_FACE_SIZE);
+    detector.set_score_thresh(2.0);
+    detector.set_pyramid_scale_factor(0.8);
+    detector.set_slide_window_step(4, 4);
+
+    Some(detector)
+}
+
+/// whether or not faces can be detected, which requires the [`FACE_MODEL`] to be installed
+#[must_use]
+pub fn is_available() -> bool {
+    MODEL.is_some()
+}
+
+/// detects the faces in the provided image, largest first
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+pub fn detect_faces(image: &Image<Rgba>) -> Vec<Face> {
+    let Some(mut detector) = detector() else {
+        return Vec::new();
+    };
+
+    // the detector works on grayscale images
+    let gray = image.data
+        .iter()
+        .map(|px| ((u32::from(px.r) * 299 + u32::from(px.g) * 587 + u32::from(px.b) * 114) / 1000) as u8)
+        .collect::<Vec<_>>();
+
+    let mut faces = detector.detect(&ImageData::new(&gray, image.width(), image.height()))
+        .into_iter()
+        .map(|info| {
+            let bbox = info.bbox();
+
+            // boxes may reach past the edges of the image, so they are clamped within it
+            let x = bbox.x().clamp(0, image.width() as i32) as u32;
+            let y = bbox.y().clamp(0, image.height() as i32) as u32;
+
+            Face {
+                x,
+                y,
+                width: bbox.width().min(image.width() - x),
+                height: bbox.height().min(image.height() - y),
+            }
+        })
+        .filter(|face| face.width > 0 && face.height > 0)
+        .collect::<Vec<_>>();
+
+    faces.sort_by_key(|face| std::cmp::Reverse(face.width * face.height));
+    faces
+}
+
+/// detects the faces in the provided image, falling back to [`Face::centered`] if none were found
+#[must_use]
+pub fn faces_or_center(image: &Image<Rgba>) -> Vec<Face> {
+    let faces = detect_faces(image);
+
+    if faces.is_empty() {
+        vec![Face::centered(image.width(), image.height())]
+    } else {
+        faces
+    }
+}
\ No newline at end of file
@@ -0,0 +1,96 @@
+//! contains the welcome cards, rendered with [`welcome_func`] for each member joining a guild that enabled them
+//! and posted to the channel it configured
+
+use std::borrow::Cow;
+
+use serenity::{
+    prelude::{Context, Mentionable},
+    model::{channel::AttachmentType, guild::Member},
+};
+
+use crate::ClientData;
+use super::{
+    Error,
+    ImageResolver,
+    assets::ASSETS,
+    database::Database,
+    functions::welcome_func,
+    helpers::url_to_bytes,
+    imaging::{decode_frames, ImageArguments, Output, DEFAULT_MAX_FRAMES},
+    options::{OutputFormat, WelcomeOptions},
+    pool::{estimate_cost, run_job},
+};
+
+
+/// the directory the backgrounds of welcome cards are loaded from, relative to the assets directory,
+/// each a `<name>.png` selected by its name
+pub const BACKGROUNDS_DIR: &str = "welcome";
+
+/// returns the bytes of the background named `name`, or `None` if there is none by that name
+#[must_use]
+pub fn background(name: &str) -> Option<Vec<u8>> {
+    // only plain names are accepted, so that a background can not point outside of its directory
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        return None;
+    }
+
+    ASSETS.get(format!("{BACKGROUNDS_DIR}/{}.png", name.to_lowercase()))
+        .map(|bytes| bytes.as_ref().clone())
+}
+
+/// renders and posts a welcome card for the provided member, if their guild has enabled them
+///
+/// # Errors
+/// if the settings of the guild could not be read, the avatar could not be fetched,
+/// or the card could not be rendered or sent
+pub async fn greet(ctx: &Context, member: &Member) -> Result<(), Error> {
+    if member.user.bot {
+        return Ok(());
+    }
+
+    let Some((channel_id, background_name)) = Database::get(ctx)
+        .await?
+        .welcome(member.guild_id)
+        .await?
+    else {
+        return Ok(());
+    };
+
+    let client = ctx.data.read()
+        .await
+        .get::<ClientData>()
+        .cloned();
+    let avatar = url_to_bytes(client.as_ref(), ImageResolver::member_avatar_url(member))
+        .await?;
+
+    let options = WelcomeOptions {
+        name: member.display_name().to_string(),
+        member_count: ctx.cache.guild_field(member.guild_id, |guild| guild.member_count)
+            .unwrap_or_default(),
+        background: background_name.as_deref()
+            .and_then(background),
+    };
+
+    let output = run_job(ctx, estimate_cost(&avatar), move || -> Result<Output, Error> {
+        let frames = welcome_func(ImageArguments {
+            frames: decode_frames(&avatar, DEFAULT_MAX_FRAMES)?,
+            arguments: vec![options],
+            seed: 0,
+        })?;
+
+        Output::encode(frames, OutputFormat::Png, None)
+    })
+        .await??;
+
+    channel_id.send_files(
+        ctx,
+        vec![AttachmentType::Bytes {
+            filename: format!("welcome.{}", output.extension),
+            data: Cow::Owned(output.bytes),
+        }],
+        |msg| msg.content(format!("Welcome to the server, {}!", member.mention())),
+    )
+        .await?;
+
+    Ok(())
+}
\ No newline at end of file